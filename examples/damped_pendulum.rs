@@ -0,0 +1,77 @@
+// 示例：用 solver2 的 RungeKuttaSolver 求解与打水漂无关的方程 —— 阻尼单摆。
+// 求解器只依赖 VectorSpace / OdeSystem 两个特征，这里分别演示自定义结构体和闭包两种写法。
+// 运行：cargo run --example damped_pendulum
+
+use dapgame::solver2::{FnOdeSystem, OdeSystem, RkMethod, RungeKuttaSolver, VectorSpace};
+
+/// 单摆状态：摆角 (rad) 与角速度 (rad/s)
+#[derive(Clone, Debug)]
+struct PendulumState {
+    theta: f64,
+    omega: f64,
+}
+
+impl VectorSpace for PendulumState {
+    fn add(&self, other: &Self) -> Self {
+        Self { theta: self.theta + other.theta, omega: self.omega + other.omega }
+    }
+
+    fn scale(&self, scalar: f64) -> Self {
+        Self { theta: self.theta * scalar, omega: self.omega * scalar }
+    }
+
+    fn is_finite(&self) -> bool {
+        self.theta.is_finite() && self.omega.is_finite()
+    }
+}
+
+/// theta'' = -(g/L) sin(theta) - c theta'
+struct DampedPendulum {
+    gravity: f64,
+    length: f64,
+    damping: f64,
+}
+
+impl OdeSystem<PendulumState> for DampedPendulum {
+    fn derivatives(&self, _t: f64, y: &PendulumState) -> PendulumState {
+        PendulumState {
+            theta: y.omega,
+            omega: -self.gravity / self.length * y.theta.sin() - self.damping * y.omega,
+        }
+    }
+}
+
+impl DampedPendulum {
+    /// 机械能 (每单位质量)
+    fn energy(&self, y: &PendulumState) -> f64 {
+        0.5 * (self.length * y.omega).powi(2) + self.gravity * self.length * (1.0 - y.theta.cos())
+    }
+}
+
+fn main() {
+    let dt = 0.01;
+    let steps = 1000;
+
+    // 1. 自定义结构体：不同积分方法的比较
+    let pendulum = DampedPendulum { gravity: 9.81, length: 1.0, damping: 0.2 };
+    let y0 = PendulumState { theta: 1.0, omega: 0.0 };
+    println!("阻尼单摆 (L = 1 m, c = 0.2 1/s)，theta0 = 1 rad，dt = {} s", dt);
+    for method in [RkMethod::Euler, RkMethod::Midpoint, RkMethod::Rk4] {
+        let mut solver = RungeKuttaSolver::new(0.0, y0.clone());
+        solver.method = method;
+        for _ in 0..steps {
+            solver.step(&pendulum, dt);
+        }
+        println!("  {:<8} t = {:.1} s  theta = {:+.6} rad  E = {:.6} J/kg  ({} 次导数求值)",
+                 method.name(), solver.t, solver.state.theta, pendulum.energy(&solver.state), solver.derivative_calls);
+    }
+
+    // 2. 闭包：同一个方程写成 Vec<f64>，无需定义结构体
+    let (g, l, c) = (pendulum.gravity, pendulum.length, pendulum.damping);
+    let system = FnOdeSystem(move |_t: f64, y: &Vec<f64>| vec![y[1], -g / l * y[0].sin() - c * y[1]]);
+    let mut solver = RungeKuttaSolver::new(0.0, vec![y0.theta, y0.omega]);
+    for _ in 0..steps {
+        solver.step(&system, dt);
+    }
+    println!("  闭包 RK4 t = {:.1} s  theta = {:+.6} rad", solver.t, solver.state[0]);
+}
@@ -1,139 +1,7 @@
 
-//参考的基础结构定义。可以更方便的调用三维或者二维向量。四元数是否使用正在考虑
-#[derive(Debug, Clone, Copy)]
-pub struct Vector3D {
-    pub x: f64,
-    pub y: f64,
-    pub z: f64,
-}
-
-impl Vector3D {
-    pub fn new(x: f64, y: f64, z: f64) -> Self {
-        Self{
-            x: x,
-            y: y,
-            z: z,
-        }
-    }
-}
-
-impl Vector3D {
-    fn length_squared(self) -> f64 { self.x * self.x + self.y * self.y + self.z * self.z}
-    fn length(self) -> f64 { self.length_squared().sqrt() }
-}
-impl std::ops::Add for Vector3D {
-    type Output = Self;
-    fn add(self, rhs: Self) -> Self::Output { Self { x: self.x + rhs.x, y: self.y + rhs.y, z: self.z + rhs.z } }
-}
-impl std::ops::Sub for Vector3D {
-    type Output = Self;
-    fn sub(self, rhs: Self) -> Self::Output { Self { x: self.x - rhs.x, y: self.y - rhs.y, z: self.z - rhs.z } }
-}
-impl std::ops::Mul<f64> for Vector3D {
-    type Output = Self;
-    fn mul(self, rhs: f64) -> Self::Output { Self { x: self.x * rhs, y: self.y * rhs, z: self.z * rhs } }
-}
-impl std::ops::Div<f64> for Vector3D {
-    type Output = Self;
-    fn div(self, rhs: f64) -> Self::Output { Self { x: self.x / rhs, y: self.y / rhs, z: self.z / rhs } }
-}
-
-impl std::ops::Mul<Vector3D> for Vector3D {
-    type Output = Self;
-    fn mul(self, rhs: Vector3D) -> Self::Output { Self { x: self.x * rhs.x, y: self.y * rhs.y, z: self.z * rhs.z } }
-}
-impl std::ops::Div<Vector3D> for Vector3D {
-    type Output = Self;
-    fn div(self, rhs: Vector3D) -> Self::Output { Self { x: self.x / rhs.x, y: self.y / rhs.y, z: self.z / rhs.z } }
-}
-
-impl Vector3D {
-    fn dot(self, rhs: Vector3D) -> f64 {
-    self.x * rhs.x + self.y * rhs.y + self.z * rhs.z
-    }
-    fn times(self, rhs: Vector3D) -> Self {
-        Self{
-            x: self.y * rhs.z - self.z * rhs.y,
-            y: - self.x * rhs.z + self.z * rhs.x,
-            z: self.x * rhs.y - self.y * rhs.x,
-        }
-    }
-}
-
-#[derive(Debug, Clone, Copy)]
-pub struct Quaternion {
-    pub w: f64,
-    pub x: f64,
-    pub y: f64,
-    pub z: f64,
-}
-
-impl Quaternion {
-    pub fn identity() -> Self {
-        Self { w: 1.0, x: 0.0, y: 0.0, z: 0.0 }
-    }
-
-    // 从角速度向量创建一个四元数 (0, vx, vy, vz)
-    pub fn from_vector(v: Vector3D) -> Self {
-        Self { w: 0.0, x: v.x, y: v.y, z: v.z }
-    }
-
-    // 归一化
-    pub fn normalize(self) -> Self {
-        let mag = (self.w*self.w + self.x*self.x + self.y*self.y + self.z*self.z).sqrt();
-        if mag.abs() < 1e-10 {
-            return Self::identity();
-        }
-        Self {
-            w: self.w / mag,
-            x: self.x / mag,
-            y: self.y / mag,
-            z: self.z / mag,
-        }
-    }
-}
-
-// 四元数乘法
-impl std::ops::Mul for Quaternion {
-    type Output = Self;
-    fn mul(self, rhs: Self) -> Self::Output {
-        Self {
-            w: self.w * rhs.w - self.x * rhs.x - self.y * rhs.y - self.z * rhs.z,
-            x: self.w * rhs.x + self.x * rhs.w + self.y * rhs.z - self.z * rhs.y,
-            y: self.w * rhs.y - self.x * rhs.z + self.y * rhs.w + self.z * rhs.x,
-            z: self.w * rhs.z + self.x * rhs.y - self.y * rhs.x + self.z * rhs.w,
-        }
-    }
-}
-
-impl std::ops::Mul<f64> for Quaternion {
-    type Output = Self;
-    fn mul(self, rhs: f64) -> Self::Output {
-        Self { w: self.w * rhs, x: self.x * rhs, y: self.y * rhs, z: self.z * rhs }
-    }
-}
-impl std::ops::Add for Quaternion {
-    type Output = Self;
-    fn add(self, rhs: Self) -> Self::Output {
-        Self { w: self.w + rhs.w, x: self.x + rhs.x, y: self.y + rhs.y, z: self.z + rhs.z }
-    }
-}
-
-// (用于转动惯量张量)
-#[derive(Debug, Clone, Copy)]
-pub struct Tensor3d {
-    pub x: Vector3D,
-    pub y: Vector3D,
-    pub z: Vector3D
-}
-impl Tensor3d{
-    fn new(x: Vector3D, y: Vector3D, z: Vector3D) -> Self{
-        Self { x: x, y: y, z: z }
-    }
-}
-
+//参考的基础结构定义：二维向量与多边形工具
 // 石片（二维）存储点采用另一个结构处理
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Vector2D{
     pub x: f64,
     pub y: f64,
@@ -142,8 +10,8 @@ pub struct Vector2D{
 impl Vector2D{
     pub(crate) fn new(x: f64, y: f64) -> Self {
         Self{
-            x: x,
-            y: y
+            x,
+            y
         }
     }
 }
@@ -167,7 +35,7 @@ impl std::ops::Mul<f64> for Vector2D {
     }
 }
 impl Vector2D {
-    fn dot(self, other: Self) -> f64 {
+    pub(crate) fn dot(self, other: Self) -> f64 {
         self.x * other.x + self.y * other.y
     }
     pub(crate) fn length_squared(self) -> f64 {
@@ -180,4 +48,104 @@ impl Vector2D {
         let len = self.length();
         if len > 0.0 { self * (1.0 / len) } else { self }
     }
-}
\ No newline at end of file
+    /// 把两个分量都当作角度 (rad) 归一化到 [-π, π)，见 wrap_angle
+    pub fn wrap_angles(self) -> Self {
+        Self { x: wrap_angle(self.x), y: wrap_angle(self.y) }
+    }
+    /// 两个分量的差都不超过 eps (测试中比较浮点几何结果)
+    pub fn approx_eq(self, other: Self, eps: f64) -> bool {
+        (self.x - other.x).abs() <= eps && (self.y - other.y).abs() <= eps
+    }
+}
+
+/// 多边形面积 (鞋带公式)，与顶点顺序无关
+pub fn polygon_area(poly: &[Vector2D]) -> f64 {
+    signed_polygon_area(poly).abs()
+}
+
+/// 多边形的有向面积，逆时针为正 (方向判断、形心计算使用)
+pub fn signed_polygon_area(poly: &[Vector2D]) -> f64 {
+    if poly.len() < 3 {
+        return 0.0;
+    }
+
+    let mut area = 0.0;
+    let n = poly.len();
+    for i in 0..n {
+        let j = (i + 1) % n; // 环绕到第一个点
+        area += poly[i].x * poly[j].y - poly[j].x * poly[i].y;
+    }
+
+    area * 0.5
+}
+
+/// 多边形形心 (解析法，与顶点顺序无关)；面积过小 (例如退化为线段) 时返回顶点的平均
+pub fn polygon_centroid(poly: &[Vector2D]) -> Vector2D {
+    if poly.len() < 3 { return Vector2D::new(0.0, 0.0); }
+
+    let area = signed_polygon_area(poly);
+    // 防止面积过小导致除以零
+    if area.abs() < 1e-9 {
+        let sum = poly.iter().fold(Vector2D::new(0.0, 0.0), |acc, &p| acc + p);
+        return sum * (1.0 / poly.len() as f64);
+    }
+
+    let mut cx = 0.0;
+    let mut cy = 0.0;
+    for i in 0..poly.len() {
+        let p1 = poly[i];
+        let p2 = poly[(i + 1) % poly.len()];
+        let cross = p1.x * p2.y - p2.x * p1.y;
+        cx += (p1.x + p2.x) * cross;
+        cy += (p1.y + p2.y) * cross;
+    }
+
+    let factor = 1.0 / (6.0 * area);
+    Vector2D::new(cx * factor, cy * factor)
+}
+
+/// 角度 (rad) 归一化到 [-π, π)
+/// 积分中的角度允许累积 (保证角速度积分连续)，只在显示时归一化
+pub fn wrap_angle(angle: f64) -> f64 {
+    use std::f64::consts::{PI, TAU};
+    // 已在范围内的角度原样返回，避免引入舍入误差
+    if (-PI..PI).contains(&angle) {
+        return angle;
+    }
+    (angle + PI).rem_euclid(TAU) - PI
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::PI;
+
+    #[test]
+    fn angles_wrap_into_half_open_range() {
+        assert_eq!(wrap_angle(3.0 * PI), -PI);
+        assert_eq!(wrap_angle(PI), -PI);
+        assert!((wrap_angle(-3.5 * PI) - 0.5 * PI).abs() < 1e-12);
+        assert!((wrap_angle(4.0 * PI + 0.1) - 0.1).abs() < 1e-12);
+        assert_eq!(wrap_angle(0.3), 0.3);
+
+        let wrapped = Vector2D::new(3.0 * PI, -0.2).wrap_angles();
+        assert!(wrapped.approx_eq(Vector2D::new(-PI, -0.2), 1e-12));
+    }
+
+    #[test]
+    fn polygon_area_ignores_winding() {
+        let ccw = vec![
+            Vector2D::new(0.0, 0.0),
+            Vector2D::new(2.0, 0.0),
+            Vector2D::new(2.0, 1.0),
+            Vector2D::new(0.0, 1.0),
+        ];
+        let cw: Vec<Vector2D> = ccw.iter().rev().copied().collect();
+        assert_eq!(signed_polygon_area(&ccw), 2.0);
+        assert_eq!(signed_polygon_area(&cw), -2.0);
+        assert_eq!(polygon_area(&cw), 2.0);
+        assert_eq!(polygon_area(&ccw[..2]), 0.0);
+        assert_eq!(polygon_centroid(&ccw), Vector2D::new(1.0, 0.5));
+        assert_eq!(polygon_centroid(&cw), Vector2D::new(1.0, 0.5));
+    }
+}
@@ -0,0 +1,108 @@
+// 职责：单步物理开销的基准测试 (`--bench [步数]`)。
+// 构建一块典型石片 (椭圆, 默认碰撞网格)，放在半浸没的位置并固定为 Bouncing 相位，
+// 计时 N 次 solver.step，报告每步耗时与每秒导数求值次数，作为优化缓存与受力模型时的基线。
+// 每一步前都把积分器重置到同一初始状态，保证每次计时的都是浸水段的完整开销 (裁剪、分布力)。
+
+use std::cell::Cell;
+use std::time::{Duration, Instant};
+
+use crate::basic_structs::Vector2D;
+use crate::physics::parameters::{CustomSettings, CustomSettingsBuilder, Phase};
+use crate::physics::simulation::StoneInfo;
+use crate::rng::{Rng, DEFAULT_SEED};
+use crate::solver2::{OdeSystem, RungeKuttaSolver};
+use crate::stone_editor::{generate_preset, PresetKind, PresetParams, StoneBlueprint};
+use crate::stone_phy::{StoneError, StoneProperties};
+
+/// 默认计时的步数
+pub const BENCH_STEPS: usize = 2_000;
+/// 计时前的预热步数 (不计入结果)
+const WARMUP_STEPS: usize = 50;
+
+/// 一次基准测试的结果
+pub struct BenchResult {
+    pub steps: usize,
+    pub elapsed: Duration,
+    /// 计时期间导数函数的调用次数
+    pub derivative_calls: u64,
+}
+
+impl BenchResult {
+    pub fn ns_per_step(&self) -> f64 {
+        self.elapsed.as_nanos() as f64 / self.steps.max(1) as f64
+    }
+
+    pub fn evals_per_second(&self) -> f64 {
+        self.derivative_calls as f64 / self.elapsed.as_secs_f64().max(1e-12)
+    }
+}
+
+// 统计导数求值次数的包装
+struct CountingSystem<'a> {
+    inner: &'a CustomSettings,
+    calls: Cell<u64>,
+}
+
+impl OdeSystem<StoneInfo> for CountingSystem<'_> {
+    fn derivatives(&self, t: f64, y: &StoneInfo) -> StoneInfo {
+        self.calls.set(self.calls.get() + 1);
+        self.inner.derivatives(t, y)
+    }
+}
+
+/// 基准测试使用的典型石片：6 cm x 4.5 cm 的椭圆，厚 8 mm
+pub fn bench_blueprint() -> StoneBlueprint {
+    StoneBlueprint {
+        points: generate_preset(PresetKind::Ellipse, &PresetParams { a: 0.03, b: 0.0225, sides: 0 }),
+        thickness: 0.008,
+        name: "bench ellipse".to_string(),
+        density_fn: None,
+    }
+}
+
+/// 计时 `steps` 次浸水段的 solver.step (步长取 Bouncing 相位的推荐步长)
+pub fn bench_bouncing_steps(blueprint: &StoneBlueprint, mesh_points: usize, steps: usize) -> Result<BenchResult, StoneError> {
+    // 1. 构建石片与参数，质心略低于水面，前缘抬起 10°
+    let mut rng = Rng::new(DEFAULT_SEED);
+    let stone = StoneProperties::with_mesh_points(blueprint, mesh_points, &mut rng)?;
+    let y0 = StoneInfo {
+        position: Vector2D::new(0.0, -0.002),
+        velocity: Vector2D::new(8.0, -1.0),
+        angle: Vector2D::new(10f64.to_radians(), 0.0),
+        angle_velocity: Vector2D::new(0.0, 30.0),
+    };
+    let mut system = CustomSettingsBuilder::new().rng(rng.fork()).build(stone).with_initial_state(&y0);
+    system.phase = Phase::Bouncing;
+    let dt = system.recommended_dt();
+
+    // 2. 预热后计时
+    let counting = CountingSystem { inner: &system, calls: Cell::new(0) };
+    let mut solver = RungeKuttaSolver::new(0.0, y0.clone());
+    for _ in 0..WARMUP_STEPS {
+        solver.reset(0.0, y0.clone());
+        solver.step(&counting, dt);
+    }
+    counting.calls.set(0);
+    let start = Instant::now();
+    for _ in 0..steps {
+        solver.reset(0.0, y0.clone());
+        solver.step(&counting, dt);
+    }
+    let elapsed = start.elapsed();
+
+    Ok(BenchResult { steps, elapsed, derivative_calls: counting.calls.get() })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bench_counts_one_derivative_call_per_stage() {
+        let result = bench_bouncing_steps(&bench_blueprint(), 2_000, 10).unwrap();
+        assert_eq!(result.steps, 10);
+        // 默认 RK4：每步 4 次求值
+        assert_eq!(result.derivative_calls, 40);
+        assert!(result.ns_per_step() > 0.0);
+    }
+}
@@ -4,39 +4,225 @@ use crate::basic_structs::Vector2D;
 
 // 总计算点数
 pub const CALCULATE_POINTS: usize = 1000;
+// 分段曲线中每段的最少计算点数
+const MIN_SEGMENT_POINTS: usize = 16;
+// 单条曲线允许的最高阶数；更高时改用分段三次曲线
+// (de Casteljau 每个采样点 O(n^2)，整条曲线 O(CALCULATE_POINTS * n^2)，
+//  上千个控制点的手绘输入会让编辑器卡住；阶数过高时数值上也不稳定)
+pub const MAX_SINGLE_ORDER: usize = 64;
 
 pub struct BezierInfo{
     name: String,
     control_points: Vec<Vector2D>,
-    order: usize,
+    // 各段的控制点 (单条曲线时只有一段；分段三次曲线相邻段共享端点)
+    segments: Vec<Vec<Vector2D>>,
     pub polyline_points: Vec<Vector2D>,
 }
 
 impl BezierInfo {
+    /// 单条 n 阶曲线；n 超过 MAX_SINGLE_ORDER 时退化为分段三次曲线 (见 new_piecewise)，
+    /// 此时曲线经过每隔三个的控制点，形状与单条高阶曲线不同
     pub(crate) fn new(name: String, points: Vec<Vector2D>) -> Self {
-        let order = if points.len() > 0 { points.len() - 1 } else { 0 };
+        let order = if !points.is_empty() { points.len() - 1 } else { 0 };
+        if order > MAX_SINGLE_ORDER {
+            println!("Warning: 贝塞尔曲线阶数 {} 超过 {}，改用分段三次曲线", order, MAX_SINGLE_ORDER);
+            return Self::new_piecewise(name, points);
+        }
 
         let resolution = CALCULATE_POINTS; //计算的点数
-        let mut polyline_points = Vec::with_capacity(resolution + 1);
-        if !points.is_empty() {
-            for i in 0..=resolution {
-                let t = i as f64 / resolution as f64;
-                if let Some(point) = de_iterative(&points, t) {
-                    polyline_points.push(point);
-                }
-            }
+        let polyline_points = tessellate(&points, resolution);
+        Self {
+            name,
+            segments: vec![points.clone()],
+            control_points: points,
+            polyline_points, // 存储结果
+        }
+    }
+
+    /// 分段三次贝塞尔曲线
+    /// 控制点按 P0..P3, P3..P6, ... 切分，相邻段共享端点；
+    /// 末尾不足一段的点组成一条低阶的尾段
+    pub(crate) fn new_piecewise(name: String, points: Vec<Vector2D>) -> Self {
+        let mut segments = Vec::new();
+        let mut start = 0;
+        while start + 1 < points.len() {
+            let end = (start + 3).min(points.len() - 1);
+            segments.push(points[start..=end].to_vec());
+            start = end;
+        }
+        if segments.is_empty() && !points.is_empty() {
+            segments.push(points.clone());
+        }
+
+        // 各段独立细分后首尾相接 (跳过后续段重复的起点)
+        let resolution = (CALCULATE_POINTS / segments.len().max(1)).max(MIN_SEGMENT_POINTS);
+        let mut polyline_points = Vec::new();
+        for (k, segment) in segments.iter().enumerate() {
+            let samples = tessellate(segment, resolution);
+            let skip = if k == 0 { 0 } else { 1 };
+            polyline_points.extend(samples.into_iter().skip(skip));
         }
+
         Self {
-            name: name,
-            order: order,
+            name,
+            segments,
             control_points: points,
-            polyline_points: polyline_points, // 存储结果
+            polyline_points,
         }
     }
 
+    pub fn segments(&self) -> &[Vec<Vector2D>] {
+        &self.segments
+    }
+
     pub(crate) fn get_polyline_points(&self) -> &Vec<Vector2D> {
         &self.polyline_points
     }
+
+    pub(crate) fn control_points(&self) -> &[Vector2D] {
+        &self.control_points
+    }
+
+    /// 参数 t 处的单位切向量
+    /// 一阶导数由导矢曲线 (hodograph) 计算: B'(t) = n Σ (P_{i+1} - P_i) B_{i,n-1}(t)
+    /// 控制点少于 2 个或导数为零时返回 None
+    pub fn tangent_at(&self, t: f64) -> Option<Vector2D> {
+        let p = &self.control_points;
+        if p.len() < 2 { return None; }
+
+        let n = (p.len() - 1) as f64;
+        let hodograph: Vec<Vector2D> = p.windows(2)
+            .map(|w| (w[1] - w[0]) * n)
+            .collect();
+
+        let derivative = de_iterative(&hodograph, t)?;
+        if derivative.length() < 1e-12 { return None; }
+        Some(derivative.normalize())
+    }
+
+    /// 参数 t 处的单位法向量 (切向量逆时针旋转 90°)
+    pub fn normal_at(&self, t: f64) -> Option<Vector2D> {
+        let tangent = self.tangent_at(t)?;
+        Some(Vector2D::new(-tangent.y, tangent.x))
+    }
+
+    /// 升阶：n 阶曲线变为 n+1 阶，曲线形状完全不变
+    /// Q_i = (i/(n+1)) P_{i-1} + (1 - i/(n+1)) P_i,  i = 0..=n+1
+    pub fn elevate_degree(&self) -> BezierInfo {
+        let p = &self.control_points;
+        if p.is_empty() {
+            return BezierInfo::new(self.name.clone(), Vec::new());
+        }
+
+        let n = p.len() - 1;
+        let mut q = Vec::with_capacity(n + 2);
+        q.push(p[0]);
+        for i in 1..=n {
+            let a = i as f64 / (n + 1) as f64;
+            q.push(p[i - 1] * a + p[i] * (1.0 - a));
+        }
+        q.push(p[n]);
+
+        BezierInfo::new(self.name.clone(), q)
+    }
+
+    /// 降阶：n 阶曲线近似为 n-1 阶 (最小二乘拟合)
+    /// 固定两个端点，求内部控制点使其升阶后的控制点与原控制点的距离平方和最小
+    pub fn reduce_degree(&self) -> BezierInfo {
+        let p = &self.control_points;
+        // 至少 3 个控制点才能降阶
+        if p.len() < 3 {
+            return BezierInfo::new(self.name.clone(), p.clone());
+        }
+
+        let n = p.len() - 1; // 原阶数
+        let m = n - 1;       // 目标阶数
+        let unknowns = m.saturating_sub(1); // 内部控制点 Q_1..Q_{m-1}
+
+        // 升阶关系: R_i = a_i Q_{i-1} + (1 - a_i) Q_i, a_i = i / n
+        // 对 i = 1..n-1 建立残差 R_i - P_i，端点 Q_0 = P_0, Q_m = P_n 已知
+        let mut ata = vec![vec![0.0; unknowns]; unknowns];
+        let mut atb_x = vec![0.0; unknowns];
+        let mut atb_y = vec![0.0; unknowns];
+
+        for i in 1..n {
+            let a = i as f64 / n as f64;
+            // 该行对 Q_{i-1} 与 Q_i 的系数
+            let terms = [(i - 1, a), (i, 1.0 - a)];
+
+            // 已知端点移到右侧
+            let mut rhs = p[i];
+            let mut row: Vec<(usize, f64)> = Vec::new();
+            for (k, coeff) in terms {
+                if k == 0 {
+                    rhs = rhs - p[0] * coeff;
+                } else if k == m {
+                    rhs = rhs - p[n] * coeff;
+                } else {
+                    row.push((k - 1, coeff));
+                }
+            }
+
+            for &(r, cr) in &row {
+                atb_x[r] += cr * rhs.x;
+                atb_y[r] += cr * rhs.y;
+                for &(c, cc) in &row {
+                    ata[r][c] += cr * cc;
+                }
+            }
+        }
+
+        let xs = solve_linear(ata.clone(), atb_x);
+        let ys = solve_linear(ata, atb_y);
+
+        let mut q = Vec::with_capacity(m + 1);
+        q.push(p[0]);
+        match (xs, ys) {
+            (Some(xs), Some(ys)) => {
+                for (x, y) in xs.into_iter().zip(ys) {
+                    q.push(Vector2D::new(x, y));
+                }
+            }
+            // 方程奇异 (理论上不会发生)：退化为均匀取点
+            _ => {
+                for k in 1..m {
+                    q.push(p[k * n / m]);
+                }
+            }
+        }
+        q.push(p[n]);
+
+        BezierInfo::new(self.name.clone(), q)
+    }
+}
+
+// 高斯消元 (部分主元) 求解 A x = b，矩阵奇异时返回 None
+fn solve_linear(mut a: Vec<Vec<f64>>, mut b: Vec<f64>) -> Option<Vec<f64>> {
+    let n = b.len();
+    for col in 0..n {
+        let pivot = (col..n).max_by(|&i, &j| a[i][col].abs().total_cmp(&a[j][col].abs()))?;
+        if a[pivot][col].abs() < 1e-12 {
+            return None;
+        }
+        a.swap(col, pivot);
+        b.swap(col, pivot);
+
+        for row in (col + 1)..n {
+            let factor = a[row][col] / a[col][col];
+            let (upper, lower) = a.split_at_mut(row);
+            for (dst, src) in lower[0][col..].iter_mut().zip(&upper[col][col..]) {
+                *dst -= factor * src;
+            }
+            b[row] -= factor * b[col];
+        }
+    }
+
+    let mut x = vec![0.0; n];
+    for row in (0..n).rev() {
+        let sum: f64 = ((row + 1)..n).map(|k| a[row][k] * x[k]).sum();
+        x[row] = (b[row] - sum) / a[row][row];
+    }
+    Some(x)
 }
 
 pub fn lerp(a: Vector2D, b: Vector2D, t: f64) -> Vector2D {
@@ -50,6 +236,19 @@ pub fn lerp(a: Vector2D, b: Vector2D, t: f64) -> Vector2D {
 //`points`: 包含 n+1 个控制点的切片。
 //`t`: 参数，通常在 [0.0, 1.0] 范围内。
 
+// 在 [0, 1] 上均匀取 resolution + 1 个参数点
+fn tessellate(points: &[Vector2D], resolution: usize) -> Vec<Vector2D> {
+    if points.is_empty() { return Vec::new(); }
+    (0..=resolution)
+        .filter_map(|i| de_iterative(points, i as f64 / resolution as f64))
+        .collect()
+}
+
+/// C1 连续：把上一段的第二个手柄关于连接点镜像，得到下一段的第一个手柄
+pub fn mirror_handle(handle: Vector2D, joint: Vector2D) -> Vector2D {
+    joint * 2.0 - handle
+}
+
 pub fn de_iterative(points: &[Vector2D], t: f64) -> Option<Vector2D> {
 
     if points.is_empty() {
@@ -72,4 +271,74 @@ pub fn de_iterative(points: &[Vector2D], t: f64) -> Option<Vector2D> {
     }
 
     Some(buffer[0])
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tangent_at_start_points_toward_second_control_point() {
+        let info = BezierInfo::new("test".to_string(), vec![
+            Vector2D::new(0.0, 0.0),
+            Vector2D::new(1.0, 2.0),
+            Vector2D::new(3.0, 0.0),
+        ]);
+
+        let tangent = info.tangent_at(0.0).unwrap();
+        let expected = Vector2D::new(1.0, 2.0).normalize();
+        assert!((tangent.x - expected.x).abs() < 1e-12);
+        assert!((tangent.y - expected.y).abs() < 1e-12);
+
+        let normal = info.normal_at(0.0).unwrap();
+        assert!((normal.x * tangent.x + normal.y * tangent.y).abs() < 1e-12);
+    }
+
+    #[test]
+    fn piecewise_segments_share_endpoints() {
+        let points: Vec<Vector2D> = (0..8).map(|i| Vector2D::new(i as f64, (i % 2) as f64)).collect();
+        let info = BezierInfo::new_piecewise("test".to_string(), points.clone());
+
+        // 0..3, 3..6, 6..7 (尾段)
+        assert_eq!(info.segments().len(), 3);
+        assert_eq!(info.segments()[1][0].x, points[3].x);
+        assert_eq!(info.segments()[2].len(), 2);
+
+        let polyline = info.get_polyline_points();
+        assert_eq!(polyline.first().map(|p| p.x), Some(0.0));
+        assert_eq!(polyline.last().map(|p| p.x), Some(7.0));
+    }
+
+    #[test]
+    fn dense_control_points_fall_back_to_piecewise_cubics() {
+        let circle = |n: usize| -> Vec<Vector2D> {
+            (0..n)
+                .map(|i| {
+                    let a = i as f64 / n as f64 * std::f64::consts::TAU;
+                    Vector2D::new(a.cos(), a.sin())
+                })
+                .collect()
+        };
+
+        // 阈值处：恰好 MAX_SINGLE_ORDER 阶仍是单条曲线，再多一个控制点就分段
+        assert_eq!(BezierInfo::new("limit".to_string(), circle(MAX_SINGLE_ORDER + 1)).segments().len(), 1);
+        assert!(BezierInfo::new("over".to_string(), circle(MAX_SINGLE_ORDER + 2)).segments().len() > 1);
+
+        // 分段后每段至多三阶，细分的总点数与控制点数无关 (耗时不随阶数平方增长)
+        let points = circle(500);
+        let info = BezierInfo::new("dense".to_string(), points.clone());
+        assert!(info.segments().iter().all(|segment| segment.len() <= 4));
+        assert!(info.get_polyline_points().len() <= CALCULATE_POINTS.max(MIN_SEGMENT_POINTS * info.segments().len()) + 1,
+                "{}", info.get_polyline_points().len());
+        assert_eq!(info.control_points().len(), 500);
+        assert_eq!(info.get_polyline_points()[0], points[0]);
+        assert_eq!(*info.get_polyline_points().last().unwrap(), points[499]);
+    }
+
+    #[test]
+    fn tangent_needs_two_control_points() {
+        let info = BezierInfo::new("test".to_string(), vec![Vector2D::new(0.0, 0.0)]);
+        assert!(info.tangent_at(0.5).is_none());
+        assert!(info.normal_at(0.5).is_none());
+    }
+}
@@ -0,0 +1,110 @@
+// 职责：在多次运行之间持久化排行榜 (scores.json)。
+// 文件格式是一个简单的 JSON 数组，不引入额外依赖，手写读写。
+
+use std::fs;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// 排行榜文件路径 (相对于工作目录)
+pub const SCORE_FILE: &str = "scores.json";
+/// 最多保留的记录条数
+pub const MAX_ENTRIES: usize = 10;
+
+/// 一条成绩记录
+#[derive(Clone, Debug, PartialEq)]
+pub struct ScoreEntry {
+    pub score: f64,     // 有效滞空时间 (s)
+    pub skips: i32,     // 水漂次数
+    pub timestamp: u64, // Unix 时间戳 (s)
+}
+
+pub struct HighScoreTable {
+    pub entries: Vec<ScoreEntry>,
+}
+
+impl HighScoreTable {
+    /// 从文件读取排行榜；文件缺失或损坏时返回空表，而不是 panic
+    pub fn load() -> Self {
+        let entries = fs::read_to_string(SCORE_FILE)
+            .ok()
+            .and_then(|text| parse_entries(&text))
+            .unwrap_or_default();
+
+        let mut table = Self { entries };
+        table.normalize();
+        table
+    }
+
+    /// 记录一次成绩，排序、去重、截断后写回文件
+    pub fn record(&mut self, score: f64, skips: i32) {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+
+        self.entries.push(ScoreEntry { score, skips, timestamp });
+        self.normalize();
+
+        if let Err(e) = fs::write(SCORE_FILE, self.to_json()) {
+            println!("Warning: 无法写入排行榜文件 {}: {}", SCORE_FILE, e);
+        }
+    }
+
+    /// 按分数降序排序，去掉相邻的相同成绩，只保留前 MAX_ENTRIES 条
+    fn normalize(&mut self) {
+        self.entries.sort_by(|a, b| {
+            b.score.total_cmp(&a.score).then(b.skips.cmp(&a.skips))
+        });
+        self.entries.dedup_by(|a, b| a.score == b.score && a.skips == b.skips);
+        self.entries.truncate(MAX_ENTRIES);
+    }
+
+    fn to_json(&self) -> String {
+        let rows: Vec<String> = self.entries.iter()
+            .map(|e| format!(
+                "  {{\"score\": {}, \"skips\": {}, \"timestamp\": {}}}",
+                e.score, e.skips, e.timestamp
+            ))
+            .collect();
+        format!("[\n{}\n]\n", rows.join(",\n"))
+    }
+}
+
+// 解析形如 [{"score": 1.0, "skips": 2, "timestamp": 3}, ...] 的文本
+// 任意一处格式不符都返回 None
+fn parse_entries(text: &str) -> Option<Vec<ScoreEntry>> {
+    let body = text.trim().strip_prefix('[')?.strip_suffix(']')?.trim();
+    let mut entries = Vec::new();
+    if body.is_empty() {
+        return Some(entries);
+    }
+
+    for object in body.split('}') {
+        let object = object.trim().trim_start_matches(',').trim();
+        if object.is_empty() {
+            continue;
+        }
+        let fields = object.strip_prefix('{')?;
+
+        let mut score = None;
+        let mut skips = None;
+        let mut timestamp = None;
+        for pair in fields.split(',') {
+            let (key, value) = pair.split_once(':')?;
+            let value = value.trim();
+            match key.trim().trim_matches('"') {
+                "score" => score = value.parse::<f64>().ok().filter(|s| s.is_finite()),
+                "skips" => skips = value.parse::<i32>().ok(),
+                "timestamp" => timestamp = value.parse::<u64>().ok(),
+                _ => {}
+            }
+        }
+
+        entries.push(ScoreEntry {
+            score: score?,
+            skips: skips?,
+            timestamp: timestamp?,
+        });
+    }
+
+    Some(entries)
+}
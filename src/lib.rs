@@ -0,0 +1,20 @@
+// 职责：库目标。物理、求解器与界面模块都在这里，main.rs (游戏) 与 examples/ 通过 `dapgame::` 使用
+
+pub mod bezier;
+pub mod basic_structs;
+pub mod bench;
+pub mod high_score;
+pub mod pause_menu;
+pub mod prefs;
+pub mod print;
+pub mod replay;
+pub mod rng;
+pub mod run_record;
+pub mod solver2;
+pub mod stone_editor;
+pub mod stone_phy;
+pub mod sweep;
+pub mod training;
+pub mod ui;
+// 引用 physics 模块 (对应 src/physics/mod.rs)
+pub mod physics;
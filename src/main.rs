@@ -1,37 +1,169 @@
 //备注：角度、角速度.x均为与水平面角度,
 //另外一个是自传.
 
-mod bezier;
-mod basic_structs;
-mod print;
-mod solver2;
-mod stone_editor;
-mod stone_phy;
-// 引用 physics 模块 (对应 src/physics/mod.rs)
-mod physics;
-
 use macroquad::prelude::*;
-use crate::stone_editor::StoneEditor;
-use crate::print::SimulationRenderer;
-use crate::solver2::RungeKuttaSolver;
-use crate::stone_phy::StoneProperties;
+use dapgame::stone_editor::{StoneBlueprint, StoneEditor};
+use dapgame::ui::game_ui_scale;
+use dapgame::print::{SimulationRenderer, PIXELS_PER_METER};
+use dapgame::solver2::{RkMethod, RungeKuttaSolver};
+use dapgame::stone_phy::{StoneProperties, DEFAULT_MESH_POINTS};
+use dapgame::high_score::HighScoreTable;
+use dapgame::pause_menu::PauseMenu;
+use dapgame::replay::{load_replay, save_replay, Replay, REPLAY_EXTENSION, REPLAY_FILE};
+use dapgame::rng::{Rng, DEFAULT_SEED};
+use dapgame::run_record::{RunRecord, SettingsSnapshot, RUN_FILE};
+use dapgame::{bench, sweep};
+use dapgame::sweep::{SweepParam, SWEEP_FILE};
 
 // [修正] 根据提供的文件结构引入模块
-use crate::physics::parameters::{CustomSettings, Phase};
-use crate::physics::simulation::StoneInfo;
+use dapgame::physics::parameters::{BounceModel, CustomSettings, GravityPreset, WallMode};
+use dapgame::physics::simulation::{advance_tracked_with, segment_skips, skips_to_csv, OPTIMAL_IMPACT_ANGLE, SKIPS_FILE, simulate, ScoreMode, SimSummary, SkipArc, Stamp, StepAccumulator, StoneInfo, DEFAULT_SAMPLE_STRIDE};
+
+// 窗口配置：`--width <px> --height <px>` 指定窗口大小，`--fullscreen` 全屏
+// 未指定时使用 macroquad 的默认大小；界面布局随窗口高度缩放 (见 ui::ui_scale)
+fn window_conf() -> Conf {
+    let args: Vec<String> = std::env::args().collect();
+    let value_of = |flag: &str| -> Option<i32> {
+        let index = args.iter().position(|a| a == flag)?;
+        match args.get(index + 1).and_then(|v| v.parse::<i32>().ok()) {
+            Some(px) if px > 0 => Some(px),
+            _ => {
+                println!("错误: {} 需要一个正整数像素值，已忽略", flag);
+                None
+            }
+        }
+    };
+    let defaults = Conf::default();
+    Conf {
+        window_title: "2D 水漂模拟 (Skipping Stone Simulation)".to_string(),
+        window_width: value_of("--width").unwrap_or(defaults.window_width),
+        window_height: value_of("--height").unwrap_or(defaults.window_height),
+        fullscreen: args.iter().any(|a| a == "--fullscreen"),
+        ..defaults
+    }
+}
 
-#[macroquad::main("2D 水漂模拟 (Skipping Stone Simulation)")]
+#[macroquad::main(window_conf)]
 async fn main() {
+    // 排行榜在多次运行之间持久化
+    let mut high_scores = HighScoreTable::load();
+
+    // 关闭窗口时先保存偏好再退出 (见各循环中的 is_quit_requested)
+    prevent_quit();
+
+    // 在结束画面按 K 保留的石片，下一局作为对手一起模拟
+    let mut kept_competitor: Option<(StoneBlueprint, StoneInfo)> = None;
+
+    // 轨迹模式的开关与历史发射轨迹 (渲染器每局新建)，在多局之间保持
+    let mut trace_state: (bool, Vec<Vec<Stamp>>) = (false, Vec::new());
+
+    // 计分方式 (在初始条件界面选择)，在多局之间保持
+    let mut score_mode = ScoreMode::AirTime;
+
+    // 命令行 `--sweep <angle|speed|spin> <start> <end> <step>`：无窗口批量扫描后退出
+    if std::env::args().any(|a| a == "--sweep") {
+        run_sweep_arg();
+        return;
+    }
+
+    // 命令行 `--bench [步数]`：计时浸水段的单步物理开销后退出
+    if std::env::args().any(|a| a == "--bench") {
+        run_bench_arg();
+        return;
+    }
+
+    // 命令行 `--replay run.json` 或 `--replay last.dapreplay`：第一局跳过编辑器，直接使用记录的输入
+    let mut replay = parse_replay_arg();
+
+    // 命令行 `--time-scale <倍率>`：每秒真实时间推进的模拟时间 (1 为实时)
+    let time_scale = parse_time_scale_arg();
+
+    // 命令行 `--wall <x> [bounce]`：在 x (m) 处放置对岸墙壁，到达即结束 (或反弹)
+    let wall_arg = parse_wall_arg();
+
+    // 命令行 `--verbose`：打印相位切换、结束原因等调试信息
+    let verbose = std::env::args().any(|a| a == "--verbose");
+
+    // 命令行 `--seed <n>`：石片网格与受力模型的随机数种子 (缺省 DEFAULT_SEED)
+    let seed_arg = parse_seed_arg();
+
+    // 命令行 `--drop-at-rest`：忽略输入的发射，每块石片以平衡漂浮姿态静止放在水面上 ("静置入水" 实验)
+    let drop_at_rest = std::env::args().any(|a| a == "--drop-at-rest");
+
+    // 命令行 `--trail <帧数>`：每条轨迹只保留最近的若干帧 (长时间实验时限制内存与绘制开销)
+    let trail_arg = parse_trail_arg();
+
+    // 命令行 `--playback`：回放文件带有轨迹时直接播放，不重新模拟；Restart 后回到编辑器
+    if std::env::args().any(|a| a == "--playback") {
+        match replay.take_if(|r| r.trajectory.is_some()) {
+            Some(r) => if !play_back(r, trail_arg).await { return; },
+            None => println!("错误: --playback 需要带轨迹的 {} 回放文件，改为重新模拟", REPLAY_EXTENSION),
+        }
+    }
+
     // [新增] 外层循环，用于支持 Restart 功能
     loop {
         // --- 阶段 1: 参数获取 (通过 StoneEditor) ---
         let mut editor = StoneEditor::new();
+        editor.score_mode = score_mode;
+        editor.seed = seed_arg;
+
+        // 回放记录中的参数快照 (仅回放局)
+        let mut replay_settings: Option<SettingsSnapshot> = None;
+        let mut seed = seed_arg;
+        // 碰撞网格采样点数 (编辑器中设置，回放局取自记录)
+        let mut mesh_points = editor.mesh_points;
+
+        let mut primary = None;
+        // 编辑器中选择 "Compare Run" 时的设计 B，与主石片从相同初始条件出发
+        let mut compare_design: Option<StoneBlueprint> = None;
+        if let Some(record) = replay.take().map(|r| r.record) {
+            println!("正在回放运行记录...");
+            let mut rng = Rng::new(record.seed);
+            match StoneProperties::with_mesh_points(&record.blueprint, record.mesh_points, &mut rng) {
+                Ok(stone_props) => {
+                    editor.gravity = GravityPreset::Custom(record.settings.gravity);
+                    editor.water_level = record.settings.water_level;
+                    editor.slope = record.settings.slope;
+                    editor.bounce_model = record.settings.bounce_model;
+                    seed = record.seed;
+                    mesh_points = record.mesh_points;
+                    replay_settings = Some(record.settings);
+                    primary = Some((record.blueprint, record.y0, stone_props, rng));
+                }
+                Err(e) => println!("错误: 运行记录中的石片无效，改为打开编辑器: {}", e),
+            }
+        }
+
+        if primary.is_none() {
+            println!("正在启动参数编辑器...");
+        }
 
-        println!("正在启动参数编辑器...");
-        editor.run().await;
+        // 获取编辑器结果；石片无法构建时回到预览界面让用户修改
+        while primary.is_none() {
+            editor.run().await;
 
-        // 获取编辑器结果
-        if let Some((blueprint, y0)) = editor.result {
+            score_mode = editor.score_mode;
+            let Some((blueprint, y0)) = editor.result.take() else { break; };
+
+            // 0. 随机数源 (每块石片使用相同的种子，保证可复现)
+            let mut rng = Rng::new(seed);
+
+            // 1. 计算石片物理属性
+            mesh_points = editor.mesh_points;
+            match StoneProperties::with_mesh_points(&blueprint, mesh_points, &mut rng) {
+                Ok(stone_props) => {
+                    primary = Some((blueprint, y0, stone_props, rng));
+                    compare_design = editor.compare_result.take();
+                }
+                Err(e) => {
+                    println!("错误: 石片无效，无法模拟: {}", e);
+                    editor.show_build_error(blueprint, e);
+                }
+            }
+        }
+
+        if let Some((blueprint, y0, stone_props, rng)) = primary {
             println!("\n--- 参数获取成功 ---");
             println!("  > 石片名称: {}", blueprint.name);
             println!("  > 初始状态 Pos: ({:.2}, {:.2})", y0.position.x, y0.position.y);
@@ -39,110 +171,280 @@ async fn main() {
             println!("正在启动物理模拟...");
 
             // --- 阶段 2: 初始化 ---
+            // 第 0 块是刚设计的石片，其后是对比的设计 B 与保留的对手
+            let is_compare_run = compare_design.is_some();
+            let mut labels = vec![if is_compare_run { "Design A" } else { "Stone #1" }.to_string()];
+            let mut designs = vec![(blueprint, y0.clone(), stone_props, rng)];
+            // 设计 B 与对手使用与主石片相同的种子，对比只反映形状与发射的差别
+            if let Some(blueprint) = compare_design.take() {
+                let mut rng = Rng::new(seed);
+                match StoneProperties::with_mesh_points(&blueprint, mesh_points, &mut rng) {
+                    Ok(stone_props) => {
+                        designs.push((blueprint, y0.clone(), stone_props, rng));
+                        labels.push("Design B".to_string());
+                    }
+                    Err(e) => println!("错误: 设计 B 无效，已忽略: {}", e),
+                }
+            }
+            if let Some((blueprint, y0)) = kept_competitor.take() {
+                let mut rng = Rng::new(seed);
+                match StoneProperties::with_mesh_points(&blueprint, mesh_points, &mut rng) {
+                    Ok(stone_props) => {
+                        designs.push((blueprint, y0, stone_props, rng));
+                        labels.push(if is_compare_run { "Rival".to_string() } else { format!("Stone #{}", designs.len()) });
+                    }
+                    Err(e) => println!("错误: 对手石片无效，已忽略: {}", e),
+                }
+            }
 
-            // 1. 计算石片物理属性
-            let stone_props = StoneProperties::new(&blueprint);
+            let mut runs: Vec<(CustomSettings, RungeKuttaSolver<StoneInfo>)> = Vec::new();
+            let mut renderer: Option<SimulationRenderer> = None;
 
-            if stone_props.mass <= 1e-9 {
-                println!("错误: 石片质量无效，无法模拟");
-                return;
-            }
+            // 每块石片传给 CustomSettings::new 的随机数源 (抽取阵风之前)，重放时恢复，保证结果可复现
+            let mut initial_rngs: Vec<Rng> = Vec::new();
 
-            // 2. 初始化物理环境
-            let mut system = CustomSettings::new(9.81, stone_props.clone());
+            for (_, y0, stone_props, rng) in designs.iter_mut() {
+                let stone_props = stone_props.clone();
 
-            // 3. 初始化渲染器
-            let mut renderer = SimulationRenderer::new(stone_props, 8000.0);
+                // 2. 初始化物理环境
+                let run_rng = rng.fork();
+                initial_rngs.push(run_rng.clone());
+                let mut system = CustomSettings::new(editor.gravity.value(), stone_props.clone(), run_rng);
+                system.bounce_model = editor.bounce_model;
+                system.verbose = verbose;
+                if let Some((x, mode)) = wall_arg {
+                    system.wall_x = Some(x);
+                    system.wall_mode = mode;
+                }
+                system.water_level = editor.water_level;
+                system.slope = editor.slope;
+                system.set_water_temperature(editor.water_temp_c);
+                if let Some(snapshot) = &replay_settings {
+                    snapshot.apply(&mut system);
+                }
+                if drop_at_rest {
+                    match system.rest_state() {
+                        Some(rest) => *y0 = rest,
+                        None => println!("错误: 石片比水重，没有漂浮姿态，--drop-at-rest 已忽略"),
+                    }
+                }
+                // 初始状态已有部分浸在水中时直接从 Bouncing 开始
+                system.set_initial_phase(y0);
+                // 浸水步长相对弹跳周期过大时提示 (回放中的参数快照可能改过步长)
+                if let Some(warning) = system.bounce_dt_warning(system.dt_bouncing) {
+                    println!("{}", warning);
+                }
 
-            // 4. 初始化求解器
-            let mut solver = RungeKuttaSolver::new(0.0, y0);
+                // 3. 初始化渲染器 (每块石片一条轨迹)
+                match renderer.as_mut() {
+                    Some(r) => { r.add_track(stone_props); },
+                    None => {
+                        let mut r = SimulationRenderer::new(stone_props, PIXELS_PER_METER);
+                        r.set_water_surface(&system);
+                        r.set_gravity_label(&editor.gravity);
+                        r.set_angle_unit(editor.angle_unit);
+                        r.set_max_trajectory_len(trail_arg);
+                        let (trace_mode, traces) = std::mem::take(&mut trace_state);
+                        r.set_traces(trace_mode, traces);
+                        renderer = Some(r);
+                    }
+                }
 
-            // 记录初始帧
-            renderer.add_state(solver.state.clone());
+                // 4. 初始化求解器
+                runs.push((system, RungeKuttaSolver::new(0.0, y0.clone())));
+            }
+            let mut renderer = renderer.expect("至少有一块石片");
+
+            // 训练模式：编辑器中搜索到的最佳发射作为参考轨迹
+            if let Some(target) = &editor.training {
+                let unit = editor.angle_unit;
+                let label = format!("{} {} at {:.1} m/s, {} skips", unit.format(unit.in_unit(target.angle_deg.to_radians())),
+                                    unit.name(), target.speed, target.summary.skips);
+                renderer.set_guide(label, target.trajectory.clone());
+            }
 
-            let simulation_dt: f64 = 0.001;
-            let steps_per_frame: usize = 1;
+            // 保存主石片的全部输入，便于用 --replay 复现
+            let (blueprint, y0, _, _) = &designs[0];
+            primary_record(blueprint, y0, seed, mesh_points, &runs[0].0).save(RUN_FILE);
 
-            // --- 游戏/评分状态变量 ---
-            let mut skip_count = 0;          // 水漂次数
-            let mut score_air_time = 0.0;    // 得分 (有效滞空时间)
-            let mut has_touched_water = false; // 是否已经开始接触水面 (用于开始计分)
-            let mut is_game_over = false;    // 游戏结束标志 (沉没)
+            // 记录初始帧
+            record_initial_frames(&mut renderer, &runs);
 
-            // --- 阶段 3: 主循环 ---
-            let mut restart_requested = false;
-            loop {
+            // 每推进 N 个时间块向渲染器记录一帧 (计分仍在每个子步进行)
+            // 本帧没有推进任何时间块时不记录，避免出现时刻重复的帧
+            let sample_stride: usize = DEFAULT_SAMPLE_STRIDE;
+            let mut stepped_blocks: usize = 0;
+            let mut record_due = false;
 
-                // 1. 物理计算子步 (仅当游戏未结束时进行)
-                if !is_game_over {
-                    for _ in 0..steps_per_frame {
+            // 把真实时间折算为固定的模拟时间块 (暂停时不累计)
+            let mut clock = StepAccumulator::new(FRAME_SIM_TIME, time_scale, MAX_STEPS_PER_FRAME);
 
-                        // [评分逻辑] 记录这一步之前的 Y 坐标
-                        let y_prev = solver.state.position.y;
+            // --- 游戏/评分状态变量 ---
+            let mut scores: Vec<SimSummary> = runs.iter().map(|(_, solver)| SimSummary::new(&solver.state)).collect();
+            let mut is_game_over = false;    // 游戏结束标志 (全部沉没)
+            let mut skip_arcs: Vec<SkipArc> = Vec::new(); // 主石片每一跳的统计 (结束时计算)
 
-                        // (A) 更新浸没面积
-                        if system.phase == Phase::Bouncing {
-                            system.update_submerged_area(&solver.state);
-                        }
+            // 暂停菜单 (P 键)，暂停期间不推进物理
+            let mut pause_menu = PauseMenu::new();
 
-                        // (B) 更新相位
-                        system.update_phase(&solver.state);
+            // 本设计目前最佳一次的 (得分, 轨迹)，作为幽灵轨迹显示；换新设计时清空
+            let mut ghost: Option<(f64, Vec<Stamp>)> = None;
 
-                        // (C) 检查是否沉没 (游戏结束)
-                        if system.phase == Phase::Sinking {
-                            is_game_over = true;
-                            println!("Game Over! Final Score: {:.3}s, Skips: {}", score_air_time, skip_count);
-                            break; // 停止物理步进
-                        }
+            // 上一次用于对比的积分器 (I 键循环切换)，主模拟始终使用 RK4
+            let mut compare_method = RkMethod::Rk4;
 
-                        // (D) 执行一步积分 (更新 solver.state)
-                        solver.step(&system, simulation_dt);
+            // --- 阶段 3: 主循环 ---
+            loop {
+                if pause_menu.check_toggle(runs.iter_mut().map(|(system, _)| system)) {
+                    // 暂停菜单可能改了水深，池底随之更新
+                    renderer.set_water_surface(&runs[0].0);
+                }
 
-                        // [评分逻辑] 记录这一步之后的 Y 坐标
-                        let y_curr = solver.state.position.y;
+                // B 键：在连续水动力与冲量弹跳之间切换
+                if is_key_pressed(KeyCode::B) {
+                    editor.bounce_model = match editor.bounce_model {
+                        BounceModel::Continuous => BounceModel::Impulse,
+                        BounceModel::Impulse => BounceModel::Continuous,
+                    };
+                    for (system, _) in runs.iter_mut() {
+                        system.bounce_model = editor.bounce_model;
+                    }
+                }
 
-                        // 1. 检测首次入水
-                        if !has_touched_water && y_curr <= 0.0 {
-                            has_touched_water = true;
+                // I 键：用下一种积分器重新模拟主石片，叠加显示以比较精度
+                if is_key_pressed(KeyCode::I) && !pause_menu.is_open {
+                    compare_method = compare_method.next();
+                    let (_, y0, stone_props, _) = &designs[0];
+                    let trajectory = simulate_with_method(
+                        &runs[0].0, stone_props, y0, initial_rngs[0].clone(), compare_method, COMPARE_DT,
+                    );
+                    renderer.add_overlay(format!("{} (dt = {} s)", compare_method.name(), COMPARE_DT), trajectory);
+                }
+                let is_stepping = !is_game_over && !pause_menu.is_open;
+
+                // 1. 物理计算子步 (仅当游戏未结束且未暂停时进行)
+                if is_stepping {
+                    // 按真实经过的时间决定本帧推进几个 FRAME_SIM_TIME 时间块，模拟速度与帧率无关
+                    for _ in 0..clock.steps(get_frame_time() as f64) {
+                        for ((system, solver), score) in runs.iter_mut().zip(scores.iter_mut()) {
+                            if is_sunk(system, solver, score) { continue; }
+
+                            // 每块推进 FRAME_SIM_TIME：按相位的推荐步长细分子步，最后一步截到块末
+                            // 更新浸没面积与相位并积分，同时按实际步长计分；沉没时停止物理步进
+                            let mut remaining = FRAME_SIM_TIME;
+                            while remaining > 1e-12 {
+                                let step_dt = |s: &CustomSettings| s.recommended_dt().min(remaining);
+                                match advance_tracked_with(system, solver, step_dt, score) {
+                                    Some(dt) => remaining -= dt,
+                                    None => break,
+                                }
+                            }
                         }
+                        stepped_blocks += 1;
+                        record_due |= stepped_blocks.is_multiple_of(sample_stride);
+                    }
 
-                        // 2. 如果已经入过水，开始处理计分
-                        if has_touched_water {
-                            // 检测水漂：上一步在水下 (或刚好在水面)，这一步在水上
-                            if y_prev <= 0.0 && y_curr > 0.0 {
-                                skip_count += 1;
+                    // 全部沉没 -> 游戏结束 (排行榜只记录主石片)
+                    if runs.iter().zip(&scores).all(|((system, solver), score)| is_sunk(system, solver, score)) {
+                        is_game_over = true;
+                        let primary = &scores[0];
+                        println!("Game Over! Final Score: {:.3}s, Skips: {}", primary.air_time, primary.skips);
+                        for (label, (_, solver)) in labels.iter().zip(&runs) {
+                            if let Some(e) = &solver.error {
+                                println!("错误: {} 的模拟中止: {}", label, e);
                             }
+                        }
+                        // 调试统计只在 --verbose 时打印
+                        let (system, solver) = &runs[0];
+                        system.log_event(format_args!("Derivative calls: {}", solver.derivative_calls));
+                        system.log_event(format_args!("Peak acceleration: {:.1} g{}", primary.peak_g,
+                                                      if primary.pitch_acc_clamped { " (pitch acceleration clamped)" } else { "" }));
+                        high_scores.record(primary.air_time, primary.skips as i32);
+                        renderer.fit_trajectory_to_view();
+                        renderer.start_last_bounce_loop();
+                        renderer.record_trace();
+                        skip_arcs = segment_skips(renderer.trajectory(0));
+                        if let Err(e) = std::fs::write(SKIPS_FILE, skips_to_csv(&skip_arcs)) {
+                            println!("Warning: cannot write {}: {}", SKIPS_FILE, e);
+                        }
 
-                            // 累加滞空时间 (作为分数)
-                            if y_curr > 0.0 {
-                                score_air_time += simulation_dt;
-                            }
+                        // 输入连同主石片轨迹一起保存为回放文件
+                        let (blueprint, y0, _, _) = &designs[0];
+                        let record = primary_record(blueprint, y0, seed, mesh_points, &runs[0].0);
+                        if let Err(e) = save_replay(REPLAY_FILE, &Replay::new(record, Some(renderer.trajectory(0).to_vec()))) {
+                            println!("Warning: {}", e);
+                        }
+
+                        // 新的最佳成绩 -> 更新幽灵轨迹
+                        let final_score = score_mode.score(primary);
+                        if ghost.as_ref().is_none_or(|(best, _)| final_score > *best) {
+                            ghost = Some((final_score, renderer.trajectory(0).to_vec()));
                         }
                     }
                 }
 
                 // 2. 渲染与交互
                 // 即使游戏结束，也可以继续绘制轨迹和操作视角，只是不再添加新状态
-                if !is_game_over {
-                    renderer.add_state(solver.state.clone());
+                if std::mem::take(&mut record_due) && !is_game_over {
+                    for (i, ((system, solver), score)) in runs.iter().zip(&scores).enumerate() {
+                        if !is_sunk(system, solver, score) {
+                            renderer.add_state(i, Stamp::capture(system, solver));
+                        }
+                    }
                 }
+                // 所有石片激起的涟漪 (各石片同步步进，取最晚的时间)
+                let latest_t = runs.iter().map(|(_, solver)| solver.t).fold(0.0, f64::max);
+                renderer.set_ripples(runs.iter().flat_map(|(system, _)| system.ripples.iter().copied()).collect(), latest_t);
                 renderer.check_input();
-                renderer.draw_and_update();
+                renderer.draw_and_update(ghost.as_ref().map(|(_, trajectory)| trajectory.as_slice()));
 
                 // 3. 绘制 UI (分数与游戏状态)
-                draw_game_ui(skip_count, score_air_time, is_game_over);
+                let colors: Vec<Color> = (0..scores.len()).map(|i| renderer.track_color(i)).collect();
+                draw_game_ui(&scores, &colors, &labels, score_mode, is_game_over, &high_scores, &skip_arcs);
+                let sc = game_ui_scale();
+                draw_text(&format!("Bounce model (B): {:?}", editor.bounce_model),
+                          20.0 * sc, screen_height() - 55.0 * sc, 20.0 * sc, GRAY);
+                pause_menu.draw_and_update();
+
+                // 结束画面按 K：保留本局主石片作为下一局的对手
+                if is_game_over && kept_competitor.is_none() && is_key_pressed(KeyCode::K) {
+                    let (blueprint, y0, _, _) = &designs[0];
+                    kept_competitor = Some((blueprint.clone(), y0.clone()));
+                }
 
-                // 4. 检查重启
+                // 录制中则保存本帧画面 (包含所有 UI)
+                renderer.capture_if_recording();
+
+                // 4. 检查重启 (回到外层循环重新打开编辑器) 与关闭窗口；
+                // 两种情况都保存偏好，模拟中切换的弹跳模型下一局与下次启动时仍然有效
+                if is_quit_requested() {
+                    editor.save_prefs();
+                    return;
+                }
                 if renderer.should_restart {
-                    restart_requested = true;
+                    editor.save_prefs();
+                    trace_state = renderer.take_traces();
                     break;
                 }
 
-                next_frame().await
-            }
+                // 5. 同石片重放：原地重置求解器与运行状态，保留 (可能在暂停菜单中修改过的) 参数
+                if renderer.should_replay {
+                    for (((system, solver), (_, y0, _, _)), rng) in runs.iter_mut().zip(&designs).zip(&initial_rngs) {
+                        system.reset_state(rng.clone());
+                        system.set_initial_phase(y0);
+                        solver.reset(0.0, y0.clone());
+                    }
+                    scores = runs.iter().map(|(_, solver)| SimSummary::new(&solver.state)).collect();
+                    is_game_over = false;
+                    stepped_blocks = 0;
+                    record_due = false;
+                    clock.reset();
+
+                    renderer.clear_trajectories();
+                    record_initial_frames(&mut renderer, &runs);
+                }
 
-            if restart_requested {
-                continue;
+                next_frame().await
             }
 
         } else {
@@ -152,51 +454,403 @@ async fn main() {
     }
 }
 
+// 主石片的全部输入 (用于 run.json 与回放文件)
+fn primary_record(blueprint: &StoneBlueprint, y0: &StoneInfo, seed: u64, mesh_points: usize,
+                  system: &CustomSettings) -> RunRecord {
+    RunRecord {
+        blueprint: blueprint.clone(),
+        y0: y0.clone(),
+        seed,
+        mesh_points,
+        settings: SettingsSnapshot::capture(system),
+    }
+}
+
+// 解析 `--replay <path>` 参数并读取运行记录或回放文件 (按扩展名区分)；
+// 缺少路径或读取失败时打印错误并返回 None。run.json 没有轨迹
+fn parse_replay_arg() -> Option<Replay> {
+    let args: Vec<String> = std::env::args().collect();
+    let index = args.iter().position(|a| a == "--replay")?;
+    let Some(path) = args.get(index + 1) else {
+        println!("错误: --replay 需要一个文件路径");
+        return None;
+    };
+    let loaded = if path.ends_with(REPLAY_EXTENSION) {
+        load_replay(path)
+    } else {
+        RunRecord::load(path).map(|record| Replay::new(record, None))
+    };
+    match loaded {
+        Ok(replay) => Some(replay),
+        Err(e) => {
+            println!("错误: {}", e);
+            None
+        }
+    }
+}
+
+// 直接播放回放文件中保存的主石片轨迹 (不重新模拟)
+// Replay Same 从头播放，Restart 结束播放；关闭窗口时返回 false
+async fn play_back(replay: Replay, trail: Option<usize>) -> bool {
+    let record = replay.record;
+    let mut rng = Rng::new(record.seed);
+    let stone_props = match StoneProperties::with_mesh_points(&record.blueprint, record.mesh_points, &mut rng) {
+        Ok(stone_props) => stone_props,
+        Err(e) => {
+            println!("错误: 回放文件中的石片无效: {}", e);
+            return true;
+        }
+    };
+    println!("正在播放回放文件中保存的轨迹...");
+
+    // 水面与重力标签取自保存的参数
+    let gravity = GravityPreset::Custom(record.settings.gravity);
+    let mut system = CustomSettings::new(gravity.value(), stone_props.clone(), rng.fork());
+    record.settings.apply(&mut system);
+
+    let mut renderer = SimulationRenderer::new(stone_props, PIXELS_PER_METER);
+    renderer.set_water_surface(&system);
+    renderer.set_gravity_label(&gravity);
+    renderer.set_max_trajectory_len(trail);
+    for stamp in replay.trajectory.unwrap_or_default() {
+        renderer.add_state(0, stamp);
+    }
+    renderer.fit_trajectory_to_view();
+
+    loop {
+        renderer.check_input();
+        renderer.draw_and_update(None);
+        let sc = game_ui_scale();
+        draw_text(&format!("Playback: {}", record.blueprint.name), 20.0 * sc, screen_height() - 55.0 * sc, 20.0 * sc, GRAY);
+        renderer.capture_if_recording();
+
+        if is_quit_requested() {
+            return false;
+        }
+        if renderer.should_restart {
+            return true;
+        }
+        if renderer.should_replay {
+            renderer.should_replay = false;
+            renderer.reset();
+        }
+        next_frame().await
+    }
+}
+
+// 解析 `--seed <n>` 参数；缺省或无效时为 DEFAULT_SEED
+fn parse_seed_arg() -> u64 {
+    let args: Vec<String> = std::env::args().collect();
+    let Some(index) = args.iter().position(|a| a == "--seed") else { return DEFAULT_SEED; };
+    match args.get(index + 1).and_then(|v| v.parse::<u64>().ok()) {
+        Some(seed) => seed,
+        None => {
+            println!("错误: --seed 需要一个非负整数，使用默认种子 {}", DEFAULT_SEED);
+            DEFAULT_SEED
+        }
+    }
+}
+
+// 解析 `--trail <frames>` 参数；缺省或无效时为 None (不限帧数)
+fn parse_trail_arg() -> Option<usize> {
+    let args: Vec<String> = std::env::args().collect();
+    let index = args.iter().position(|a| a == "--trail")?;
+    match args.get(index + 1).and_then(|v| v.parse::<usize>().ok()) {
+        Some(frames) if frames > 0 => Some(frames),
+        _ => {
+            println!("错误: --trail 需要一个正整数帧数，已忽略");
+            None
+        }
+    }
+}
+
+// 解析 `--wall <x> [bounce]` 参数；缺省或无效时为 None (没有墙壁)
+fn parse_wall_arg() -> Option<(f64, WallMode)> {
+    let args: Vec<String> = std::env::args().collect();
+    let index = args.iter().position(|a| a == "--wall")?;
+    let Some(x) = args.get(index + 1).and_then(|v| v.parse::<f64>().ok()).filter(|x| x.is_finite()) else {
+        println!("错误: --wall 需要墙壁的 x 坐标 (m)，已忽略");
+        return None;
+    };
+    let mode = if args.get(index + 2).is_some_and(|m| m == "bounce") { WallMode::Bounce } else { WallMode::Stop };
+    Some((x, mode))
+}
+
+// 解析 `--time-scale <倍率>` 参数；缺省或无效时为 DEFAULT_TIME_SCALE
+fn parse_time_scale_arg() -> f64 {
+    let args: Vec<String> = std::env::args().collect();
+    let Some(index) = args.iter().position(|a| a == "--time-scale") else { return DEFAULT_TIME_SCALE; };
+    match args.get(index + 1).and_then(|v| v.parse::<f64>().ok()) {
+        Some(scale) if scale > 0.0 && scale.is_finite() => scale,
+        _ => {
+            println!("错误: --time-scale 需要一个正数倍率，使用默认值 {}", DEFAULT_TIME_SCALE);
+            DEFAULT_TIME_SCALE
+        }
+    }
+}
+
+// 解析 `--sweep <param> <start> <end> <step>` 并执行参数扫描
+// 石片、其余初始条件、全部物理参数 (水面、弹跳模型等) 与种子取自上一局的运行记录 (run.json)，结果写入 sweep.csv
+// 每次运行的进度只在同时给出 `--verbose` 时打印
+fn run_sweep_arg() {
+    let args: Vec<String> = std::env::args().collect();
+    let index = args.iter().position(|a| a == "--sweep").unwrap_or(0);
+    let rest = &args[index + 1..];
+    let param = rest.first().and_then(|name| SweepParam::parse(name));
+    let numbers: Option<Vec<f64>> = rest.iter().skip(1).take(3).map(|v| v.parse::<f64>().ok()).collect();
+    let (Some(param), Some(range)) = (param, numbers.filter(|n| n.len() == 3)) else {
+        println!("用法: --sweep <angle|speed|spin> <start> <end> <step>");
+        return;
+    };
+
+    let record = match RunRecord::load(RUN_FILE) {
+        Ok(record) => record,
+        Err(e) => {
+            println!("错误: 参数扫描需要运行记录 ({}): {}", RUN_FILE, e);
+            return;
+        }
+    };
+
+    println!("正在扫描 {} 从 {} 到 {} (步长 {})...", param.name(), range[0], range[1], range[2]);
+    let verbose = args.iter().any(|a| a == "--verbose");
+    let rows = match sweep::sweep(&record, param, (range[0], range[1], range[2]), verbose) {
+        Ok(rows) => rows,
+        Err(e) => {
+            println!("错误: 运行记录中的石片无效: {}", e);
+            return;
+        }
+    };
+    // 各次运行的石片与步长相同，步长提示只打印一次
+    if let Some(warning) = rows.first().and_then(|row| row.summary.dt_warning.as_ref()) {
+        println!("{}", warning);
+    }
+    match sweep::write_csv(SWEEP_FILE, param, &rows) {
+        Ok(()) => println!("已写入 {} ({} 次运行)", SWEEP_FILE, rows.len()),
+        Err(e) => println!("错误: {}", e),
+    }
+    for row in sweep::unfinished(&rows) {
+        println!("警告: {} = {} 的运行未正常结束 ({})，结果不可信", param.name(), row.value, row.summary.end_reason.name());
+    }
+}
+
+// 解析 `--bench [steps]` 并计时浸水段的单步开销 (缺省 BENCH_STEPS 步)
+fn run_bench_arg() {
+    let args: Vec<String> = std::env::args().collect();
+    let index = args.iter().position(|a| a == "--bench").unwrap_or(0);
+    let steps = args.get(index + 1).and_then(|v| v.parse::<usize>().ok()).filter(|&n| n > 0).unwrap_or(bench::BENCH_STEPS);
+
+    println!("正在计时 {} 步 (Bouncing, {} 网格点)...", steps, DEFAULT_MESH_POINTS);
+    match bench::bench_bouncing_steps(&bench::bench_blueprint(), DEFAULT_MESH_POINTS, steps) {
+        Ok(result) => println!("{:.0} ns/step, {:.0} derivative evals/s ({} 次求值, 共 {:.3} s)",
+                               result.ns_per_step(), result.evals_per_second(),
+                               result.derivative_calls, result.elapsed.as_secs_f64()),
+        Err(e) => println!("错误: 基准石片无效: {}", e),
+    }
+}
+
+// 固定的模拟时间块 (s)；飞行段约一个子步，浸水段细分为多个子步
+// 取飞行段步长 DEFAULT_DT_FLYING (原来每帧固定推进 0.001 s)，块的大小只影响采样粒度，
+// 播放速度由 DEFAULT_TIME_SCALE 决定，不随块的大小变化
+const FRAME_SIM_TIME: f64 = 0.002;
+// 默认时间倍率：与真实时间同步；慢动作观察弹跳用 `--time-scale 0.1` 之类
+const DEFAULT_TIME_SCALE: f64 = 1.0;
+// 每个渲染帧最多追赶的真实时间 (s)，慢机器上超出的积压直接丢弃 (避免越积越多)
+const MAX_FRAME_LAG: f64 = 0.05;
+// 每个渲染帧最多推进的时间块数 (实时倍率下 MAX_FRAME_LAG 对应的块数)
+const MAX_STEPS_PER_FRAME: usize = (MAX_FRAME_LAG / FRAME_SIM_TIME) as usize;
+
+// 对比积分器时使用固定步长，各方法之间才可比较
+const COMPARE_DT: f64 = 0.001;
+// 对比轨迹的最大步数 (低阶积分器可能发散，始终无法沉没)
+const COMPARE_MAX_STEPS: usize = 60_000;
+
+// 用指定积分器从 y0 重新模拟一块石片，参数复制自 `template` (含暂停菜单中的修改)
+fn simulate_with_method(template: &CustomSettings, stone_props: &StoneProperties, y0: &StoneInfo,
+                        rng: Rng, method: RkMethod, dt: f64) -> Vec<Stamp> {
+    let mut system = CustomSettings::new(template.gravity, stone_props.clone(), rng);
+    SettingsSnapshot::capture(template).apply(&mut system);
+    system.set_initial_phase(y0);
+
+    let mut solver = RungeKuttaSolver::new(0.0, y0.clone());
+    solver.method = method;
+    simulate(&mut system, &mut solver, dt, COMPARE_MAX_STEPS, DEFAULT_SAMPLE_STRIDE).0
+}
+
+// 把每块石片的当前状态作为第一帧写入渲染器
+fn record_initial_frames(renderer: &mut SimulationRenderer, runs: &[(CustomSettings, RungeKuttaSolver<StoneInfo>)]) {
+    for (i, (system, solver)) in runs.iter().enumerate() {
+        renderer.add_state(i, Stamp::capture(system, solver));
+    }
+}
+
+// 结束画面每跳统计表最多显示的行数
+const MAX_SKIP_ROWS: usize = 8;
+
+// 石片是否已经停止 (沉没，有池底时沉到池底；到达对岸；或积分发散而中止)
+fn is_sunk(system: &CustomSettings, solver: &RungeKuttaSolver<StoneInfo>, score: &SimSummary) -> bool {
+    system.sinking_done() || score.reached_wall || solver.error.is_some()
+}
+
+// 入水角与经验最佳值的比较 (相差 5° 以内视为接近)
+fn impact_angle_hint(angle: f64) -> (&'static str, Color) {
+    let diff = angle - OPTIMAL_IMPACT_ANGLE;
+    if diff.abs() <= 5.0 {
+        ("near optimal", GREEN)
+    } else if diff > 0.0 {
+        ("too steep", ORANGE)
+    } else {
+        ("too shallow", ORANGE)
+    }
+}
+
 // 辅助函数：绘制游戏UI
-fn draw_game_ui(skip_count: i32, score_time: f64, is_game_over: bool) {
-    let font_size = 30.0;
-    let padding = 20.0;
+// `scores`、`colors` 与 `labels` 一一对应，第 0 块为主石片
+// 得分按 `score_mode` 计算；排行榜始终记录滞空时间
+fn draw_game_ui(scores: &[SimSummary], colors: &[Color], labels: &[String], score_mode: ScoreMode,
+                is_game_over: bool, high_scores: &HighScoreTable, skip_arcs: &[SkipArc]) {
+    // 布局按设计高度绘制，整体乘以缩放 (矮窗口中结束画面不超出屏幕)
+    let sc = game_ui_scale();
+    let font_size = 30.0 * sc;
+    let padding = 20.0 * sc;
+    let primary = &scores[0];
 
     // 左上角实时数据
-    draw_text(&format!("Skips: {}", skip_count), padding, 40.0, font_size, WHITE);
-    draw_text(&format!("Score: {:.3}s", score_time), padding, 75.0, font_size, WHITE);
+    if scores.len() == 1 {
+        draw_text(&format!("Skips: {}", primary.skips), padding, 40.0 * sc, font_size, WHITE);
+        draw_text(&format!("Score: {:.3}s", score_mode.score(primary)), padding, 75.0 * sc, font_size, WHITE);
+        // 连击模式：实时显示当前倍率
+        if score_mode == ScoreMode::Streak {
+            draw_text(&format!("Streak x{:.1}", primary.streak_multiplier()), padding, 110.0 * sc, font_size, ORANGE);
+        }
+    } else {
+        for (i, ((score, color), label)) in scores.iter().zip(colors).zip(labels).enumerate() {
+            let multiplier = if score_mode == ScoreMode::Streak { format!("  x{:.1}", score.streak_multiplier()) } else { String::new() };
+            draw_text(
+                &format!("{} Skips: {}  Score: {:.3}s{}", label, score.skips, score_mode.score(score), multiplier),
+                padding, (40.0 + 35.0 * i as f32) * sc, font_size, *color,
+            );
+        }
+    }
 
     // 游戏结束画面
     if is_game_over {
         let center_x = screen_width() / 2.0;
         let center_y = screen_height() / 2.0;
 
+        // 多石片时额外显示对比表 (表头 + 每块一行 + 胜者两行)
+        let compare_lines = if scores.len() > 1 { scores.len() + 3 } else { 0 };
+        // 每跳统计表 (表头 + 最多 MAX_SKIP_ROWS 行)
+        let shown_arcs = &skip_arcs[..skip_arcs.len().min(MAX_SKIP_ROWS)];
+        let arc_lines = if skip_arcs.is_empty() { 0 } else { shown_arcs.len() + 2 };
+
         // 半透明背景板
-        let panel_w = 400.0;
-        let panel_h = 250.0;
+        let panel_w = 480.0 * sc;
+        let panel_h = (285.0 + 25.0 * ((high_scores.entries.len() + compare_lines + arc_lines) as f32 + 1.0)) * sc;
+        // 面板顶部固定，排行榜向下扩展；过长时整体上移，不超出屏幕底部
+        let panel_top = (center_y - 125.0 * sc).min(screen_height() - panel_h).max(0.0);
+        let center_y = panel_top + 125.0 * sc;
         draw_rectangle(
             center_x - panel_w/2.0,
-            center_y - panel_h/2.0,
+            panel_top,
             panel_w, panel_h,
             Color::from_rgba(0, 0, 0, 200)
         );
         draw_rectangle_lines(
             center_x - panel_w/2.0,
-            center_y - panel_h/2.0,
+            panel_top,
             panel_w, panel_h,
             3.0, RED
         );
 
         // 文字
-        let title = "GAME OVER";
-        let title_dims = measure_text(title, None, 50, 1.0);
-        draw_text(title, center_x - title_dims.width/2.0, center_y - 50.0, 50.0, RED);
+        // 到达对岸时显示为成功
+        let (title, title_color) = if primary.reached_wall { ("CROSSED THE POND!", GREEN) } else { ("GAME OVER", RED) };
+        let title_dims = measure_text(title, None, (50.0 * sc) as u16, 1.0);
+        draw_text(title, center_x - title_dims.width/2.0, center_y - 50.0 * sc, 50.0 * sc, title_color);
+
+        let score_text = format!("Final Score ({}): {:.3}s", score_mode.name(), score_mode.score(primary));
+        let score_dims = measure_text(&score_text, None, (30.0 * sc) as u16, 1.0);
+        draw_text(&score_text, center_x - score_dims.width/2.0, center_y + 10.0 * sc, 30.0 * sc, WHITE);
+
+        let skip_text = format!("Total Skips: {}", primary.skips);
+        let skip_dims = measure_text(&skip_text, None, (30.0 * sc) as u16, 1.0);
+        draw_text(&skip_text, center_x - skip_dims.width/2.0, center_y + 50.0 * sc, 30.0 * sc, WHITE);
+
+        // 首次入水角，与经验最佳值 (~20°) 比较
+        if let Some(angle) = primary.impact_angle {
+            let (advice, color) = impact_angle_hint(angle);
+            let angle_text = format!("Entry {:.1} deg: {} (best ~{})", angle, advice, OPTIMAL_IMPACT_ANGLE);
+            let angle_dims = measure_text(&angle_text, None, (22.0 * sc) as u16, 1.0);
+            draw_text(&angle_text, center_x - angle_dims.width/2.0, center_y + 85.0 * sc, 22.0 * sc, color);
+        }
 
-        let score_text = format!("Final Score: {:.3}s", score_time);
-        let score_dims = measure_text(&score_text, None, 30, 1.0);
-        draw_text(&score_text, center_x - score_dims.width/2.0, center_y + 10.0, 30.0, WHITE);
+        let hint = if scores.len() == 1 {
+            "Press 'Restart' to try again | K: keep as rival"
+        } else {
+            "Press 'Restart' to try again"
+        };
+        let hint_dims = measure_text(hint, None, (20.0 * sc) as u16, 1.0);
+        draw_text(hint, center_x - hint_dims.width/2.0, center_y + 120.0 * sc, 20.0 * sc, GRAY);
+
+        let board_x = center_x - panel_w/2.0 + 40.0 * sc;
+        let mut board_y = center_y + 165.0 * sc;
+
+        // 多石片对比：并排列出得分、弹跳次数与水平距离
+        if scores.len() > 1 {
+            draw_text(&format!("{:<10} {:>8} {:>6} {:>9}", "STONE", "SCORE", "SKIPS", "DISTANCE"), board_x, board_y, 22.0 * sc, YELLOW);
+            board_y += 25.0 * sc;
+            for ((score, color), label) in scores.iter().zip(colors).zip(labels) {
+                draw_text(
+                    &format!("{:<10} {:>7.3}s {:>6} {:>7.2} m", label, score_mode.score(score), score.skips, score.distance),
+                    board_x, board_y, 22.0 * sc, *color,
+                );
+                board_y += 25.0 * sc;
+            }
+            let best_by = |key: fn(&SimSummary) -> f64| scores.iter().enumerate()
+                .max_by(|a, b| key(a.1).total_cmp(&key(b.1)))
+                .map(|(i, _)| i)
+                .unwrap_or(0);
+            let winner = scores.iter().enumerate()
+                .max_by(|a, b| score_mode.score(a.1).total_cmp(&score_mode.score(b.1)))
+                .map(|(i, _)| i)
+                .unwrap_or(0);
+            let farthest = best_by(|s| s.distance);
+            draw_text(&format!("Winner: {}", labels[winner]), board_x, board_y, 24.0 * sc, colors[winner]);
+            board_y += 25.0 * sc;
+            draw_text(&format!("Farthest: {}", labels[farthest]), board_x, board_y, 24.0 * sc, colors[farthest]);
+            board_y += 35.0 * sc;
+        }
 
-        let skip_text = format!("Total Skips: {}", skip_count);
-        let skip_dims = measure_text(&skip_text, None, 30, 1.0);
-        draw_text(&skip_text, center_x - skip_dims.width/2.0, center_y + 50.0, 30.0, WHITE);
+        // 每跳统计：弧段最高点、水平距离与起跳前触水耗散的能量，最后一段 (之后沉没) 单独标出
+        // 能量反常增加 (损失为负) 的一跳标红
+        if !skip_arcs.is_empty() {
+            draw_text("SKIP   APEX      DISTANCE   E LOSS", board_x, board_y, 22.0 * sc, YELLOW);
+            for (i, arc) in shown_arcs.iter().enumerate() {
+                board_y += 25.0 * sc;
+                let flag = if arc.complete { "" } else { "  (sank)" };
+                let color = if arc.energy_loss < 0.0 { RED } else if arc.complete { WHITE } else { GRAY };
+                draw_text(
+                    &format!("{:>3}   {:>6.3} m  {:>6.3} m  {:>6.3} J{}", i + 1, arc.apex, arc.distance, arc.energy_loss, flag),
+                    board_x, board_y, 22.0 * sc, color,
+                );
+            }
+            if skip_arcs.len() > shown_arcs.len() {
+                draw_text(&format!("... {} more", skip_arcs.len() - shown_arcs.len()), board_x + 240.0 * sc, board_y, 20.0 * sc, GRAY);
+            }
+            board_y += 35.0 * sc;
+        }
 
-        let hint = "Press 'Restart' to try again";
-        let hint_dims = measure_text(hint, None, 20, 1.0);
-        draw_text(hint, center_x - hint_dims.width/2.0, center_y + 100.0, 20.0, GRAY);
+        // 排行榜 (已按分数降序排列)
+        let board_title = if score_mode == ScoreMode::AirTime { "HIGH SCORES" } else { "HIGH SCORES (air time)" };
+        draw_text(board_title, board_x, board_y, 24.0 * sc, YELLOW);
+        for (rank, entry) in high_scores.entries.iter().enumerate() {
+            board_y += 25.0 * sc;
+            let is_current = entry.score == primary.air_time && entry.skips == primary.skips as i32;
+            draw_text(
+                &format!("{:>2}. {:>8.3}s   Skips: {}", rank + 1, entry.score, entry.skips),
+                board_x, board_y, 22.0 * sc,
+                if is_current { GREEN } else { WHITE },
+            );
+        }
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,145 @@
+// 职责：模拟过程中的暂停菜单 (按 P 开关)。
+// 打开时暂停物理步进，并允许直接修改 CustomSettings 中的环境参数；
+// 关闭时把输入的新值写回，后续积分立即使用新参数，已记录的轨迹保持不变。
+
+use macroquad::prelude::*;
+use crate::physics::parameters::CustomSettings;
+use crate::stone_editor::{draw_text_input_box, read_numeric_chars, validate_input, TextCursor};
+use crate::ui::game_ui_scale;
+
+/// 可调参数的 (输入框 ID, 显示标签)
+const FIELDS: [(&str, &str); 8] = [
+    ("gravity", "gravity (m/s^2)"),
+    ("rho", "rho (kg/m^3)"),
+    ("Cl", "Cl"),
+    ("Cf", "Cf"),
+    ("beta", "beta"),
+    ("spin_lift", "spin lift"),
+    ("water_depth", "depth (m, 0=none)"),
+    ("gust", "gust (m/s)"),
+];
+
+pub struct PauseMenu {
+    pub is_open: bool,
+    active_input_id: Option<String>,
+    cursor: TextCursor,
+    // 与 FIELDS 一一对应的输入文本
+    inputs: Vec<String>,
+}
+
+impl Default for PauseMenu {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PauseMenu {
+    pub fn new() -> Self {
+        Self {
+            is_open: false,
+            active_input_id: None,
+            cursor: TextCursor::new(),
+            inputs: vec![String::new(); FIELDS.len()],
+        }
+    }
+
+    /// (主循环调用) 处理 P 键开关
+    /// 打开时从第一组参数读取初值；关闭时把输入应用到所有参数，此时返回 true
+    pub fn check_toggle<'a>(&mut self, settings: impl IntoIterator<Item = &'a mut CustomSettings>) -> bool {
+        if !is_key_pressed(KeyCode::P) { return false; }
+        // 正在输入时 P 不是合法字符，不会和输入冲突
+
+        if self.is_open {
+            for s in settings {
+                self.apply(s);
+            }
+            self.is_open = false;
+            self.active_input_id = None;
+            true
+        } else {
+            if let Some(first) = settings.into_iter().next() {
+                self.load(first);
+            }
+            self.is_open = true;
+            false
+        }
+    }
+
+    /// 用当前参数填充输入框
+    fn load(&mut self, settings: &CustomSettings) {
+        let values = [settings.gravity, settings.rho, settings.Cl, settings.Cf, settings.beta, settings.spin_lift_coeff, settings.water_depth,
+                      settings.gust_strength];
+        for (input, value) in self.inputs.iter_mut().zip(values) {
+            *input = format!("{}", value);
+        }
+    }
+
+    /// 把输入写回参数 (无法解析的输入保持原值)
+    fn apply(&self, settings: &mut CustomSettings) {
+        let targets = [
+            &mut settings.gravity,
+            &mut settings.rho,
+            &mut settings.Cl,
+            &mut settings.Cf,
+            &mut settings.beta,
+            &mut settings.spin_lift_coeff,
+            &mut settings.water_depth,
+            &mut settings.gust_strength,
+        ];
+        for ((target, input), (id, _)) in targets.into_iter().zip(&self.inputs).zip(FIELDS) {
+            if let Ok(v) = validate_input(id, input) {
+                *target = v;
+            }
+        }
+    }
+
+    /// (主循环调用) 绘制菜单并处理输入
+    pub fn draw_and_update(&mut self) {
+        if !self.is_open { return; }
+
+        // 1. 键盘输入写入当前激活的输入框
+        if let Some(index) = self.active_index() {
+            self.cursor.sync(&self.active_input_id, &self.inputs[index]);
+            if read_numeric_chars(&mut self.inputs[index], &mut self.cursor.pos) {
+                self.active_input_id = None;
+            }
+        }
+
+        // 2. 背景板
+        let sc = game_ui_scale();
+        let font_size = (24.0 * sc) as u16;
+        let row_height = 45.0 * sc;
+        let panel_w = 420.0 * sc;
+        let panel_h = 110.0 * sc + row_height * FIELDS.len() as f32;
+        let panel_x = screen_width() / 2.0 - panel_w / 2.0;
+        let panel_y = screen_height() / 2.0 - panel_h / 2.0;
+
+        draw_rectangle(panel_x, panel_y, panel_w, panel_h, Color::from_rgba(0, 0, 0, 220));
+        draw_rectangle_lines(panel_x, panel_y, panel_w, panel_h, 3.0, YELLOW);
+        draw_text("PAUSED", panel_x + 20.0 * sc, panel_y + 40.0 * sc, 36.0 * sc, YELLOW);
+
+        // 3. 输入行 (点击空白处取消焦点)
+        if is_mouse_button_pressed(MouseButton::Left) {
+            self.active_input_id = None;
+        }
+
+        let mut y = panel_y + 60.0 * sc;
+        for (i, (id, label)) in FIELDS.iter().enumerate() {
+            draw_text(label, panel_x + 20.0 * sc, y + 28.0 * sc, font_size as f32, WHITE);
+
+            let input_rect = Rect::new(panel_x + 200.0 * sc, y, 200.0 * sc, 38.0 * sc);
+            let is_invalid = validate_input(id, &self.inputs[i]).is_err();
+            if draw_text_input_box(&self.inputs[i], input_rect, id, &self.active_input_id, font_size, is_invalid, self.cursor.pos) {
+                self.active_input_id = Some(id.to_string());
+            }
+            y += row_height;
+        }
+
+        draw_text("P: resume and apply", panel_x + 20.0 * sc, panel_y + panel_h - 15.0 * sc, 20.0 * sc, GRAY);
+    }
+
+    fn active_index(&self) -> Option<usize> {
+        let active = self.active_input_id.as_deref()?;
+        FIELDS.iter().position(|(id, _)| *id == active)
+    }
+}
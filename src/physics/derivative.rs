@@ -2,7 +2,8 @@ use crate::basic_structs::Vector2D;
 use crate::physics::parameters::*;
 use crate::solver2::{OdeSystem, VectorSpace};
 use crate::physics::simulation::*;
-use crate::physics::simulation::polygon_area;
+use crate::basic_structs::{polygon_centroid, signed_polygon_area};
+use crate::physics::simulation::regions_area;
 
 impl VectorSpace for StoneInfo {
     fn add(&self, other: &Self) -> Self {
@@ -22,6 +23,10 @@ impl VectorSpace for StoneInfo {
             angle_velocity: self.angle_velocity * scalar,
         }
     }
+
+    fn is_finite(&self) -> bool {
+        StoneInfo::is_finite(self)
+    }
 }
 
 impl OdeSystem<StoneInfo> for CustomSettings {
@@ -35,34 +40,110 @@ impl OdeSystem<StoneInfo> for CustomSettings {
 }
 
 impl CustomSettings {
-    pub fn deriv_flying(&self, _t: f64, stone: &StoneInfo) -> StoneInfo {
+    pub fn deriv_flying(&self, t: f64, stone: &StoneInfo) -> StoneInfo {
+        let gust = self.gust_acceleration(t, stone);
+        let drag = self.air_drag_acceleration(stone);
         StoneInfo {
             position: stone.velocity,
             velocity: Vector2D {
-                x: 0.0,
-                y: - self.gravity,
+                x: gust.x + drag.x,
+                y: - self.gravity + gust.y + drag.y,
             },
             angle: stone.angle_velocity,
             angle_velocity: Vector2D { x: 0.0, y: 0.0 },
         }
     }
 
+    /// 静止空气的阻力加速度 -0.5 rho_air Cd_air A |v| v / M (未开启 air_drag 时为零)
+    fn air_drag_acceleration(&self, stone: &StoneInfo) -> Vector2D {
+        let speed = stone.velocity.length();
+        if !self.air_drag || speed < 1e-9 {
+            return Vector2D::new(0.0, 0.0);
+        }
+        let area = self.frontal_area(stone);
+        stone.velocity * (-0.5 * self.rho_air * self.Cd_air * area * speed / self.M)
+    }
+
+    /// 石片垂直于飞行方向的投影面积 (m^2)：
+    /// 世界坐标轮廓在速度法向上的展宽 * 厚度，随俯仰和自转姿态变化
+    pub fn frontal_area(&self, stone: &StoneInfo) -> f64 {
+        let speed = stone.velocity.length();
+        if speed < 1e-9 {
+            return 0.0;
+        }
+        let normal = Vector2D::new(-stone.velocity.y / speed, stone.velocity.x / speed);
+        let (lo, hi) = self.outline_to_world(stone).iter()
+            .map(|&p| (p - stone.position).dot(normal))
+            .fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), d| (lo.min(d), hi.max(d)));
+        (hi - lo).max(0.0) * self.stone.thickness
+    }
+
+    /// 阵风带来的附加加速度
+    /// 只取有风与无风两种相对速度下阻力之差 (静止空气的阻力见 air_drag_acceleration)，
+    /// 强度为 0 时严格为零。迎风面积按侧飞估算：直径 * 厚度
+    fn gust_acceleration(&self, t: f64, stone: &StoneInfo) -> Vector2D {
+        if self.gust_strength == 0.0 {
+            return Vector2D::new(0.0, 0.0);
+        }
+        let relative = self.wind_velocity(t) - stone.velocity;
+        let still = stone.velocity * -1.0;
+        let area = 2.0 * self.stone.d_max.sqrt() * self.stone.thickness;
+        let k = 0.5 * AIR_DENSITY * GUST_DRAG_COEFF * area / self.M;
+        (relative * relative.length() - still * still.length()) * k
+    }
+
+    /// 无副作用的导数入口，用于外部分析 (如有限差分求雅可比矩阵与特征值)
+    /// 相位取 self.phase (不做相位切换)，不改写浸没状态，也不记录 force_regions；
+    /// 唯一的内部写入是世界坐标轮廓缓存，不影响任何结果
+    pub fn deriv_at(&self, t: f64, state: &StoneInfo) -> StoneInfo {
+        match self.phase {
+            Phase::Flying => self.deriv_flying(t, state),
+            Phase::Bouncing => self.bouncing_derivative(t, state).0,
+            Phase::Sinking => self.deriv_sinking(t, state),
+        }
+    }
+
     pub fn deriv_bouncing(&self, _t: f64, stone: &StoneInfo) -> StoneInfo {
+        let (derivative, regions) = self.bouncing_derivative(_t, stone);
+        // 记下水动力实际使用的浸没多边形 (每个子步覆盖，一步结束时为最后一个子步的)
+        *self.force_regions.borrow_mut() = regions;
+        // 诊断：峰值线加速度与俯仰角加速度截断
+        let acceleration = derivative.velocity.length();
+        if acceleration > self.peak_acceleration.get() {
+            self.peak_acceleration.set(acceleration);
+        }
+        if derivative.angle_velocity.x.abs() >= MAX_PITCH_ACC {
+            self.pitch_acc_clamped.set(true);
+        }
+        derivative
+    }
+
+    // 浸水阶段的导数，同时返回水动力使用的浸没多边形
+    fn bouncing_derivative(&self, t: f64, stone: &StoneInfo) -> (StoneInfo, Vec<Vec<Vector2D>>) {
         // 实时计算当前 RK4 子步的浸没状态
-        let (sim, clipped) = self.calculate_instant_submerged(stone);
+        let (sim, regions) = self.calculate_instant_submerged(stone);
 
-        // 1. 计算水动力 (不含重力)
-        let f_hydro = self.compute_hydro_force(stone, sim);
+        // 1. 计算水动力 (不含重力) 与关于质心的俯仰力矩
+        // 由可替换的力模型给出，默认为内置的集中力/分布式模型；入水初期按 force_ramp_factor 渐变
+        let (f_hydro, hydro_torque) = self.force_model.force_and_torque(self, stone, &regions, sim);
+        let ramp = self.force_ramp_factor(t);
+        let (f_hydro, hydro_torque) = (f_hydro * ramp, hydro_torque * ramp);
 
         // 2. 计算总合力
-        let f_gravity = Vector2D { x: 0.0, y: self.M * self.gravity };
-        let f_total = f_hydro + f_gravity;
+        // Y 轴向上，重力与飞行阶段一致指向 -y
+        let f_gravity = Vector2D { x: 0.0, y: -self.M * self.gravity };
+        // 静水浮力 (可选)：排开水的重量，浸没体积 = 浸没面积 * 石片厚度
+        let f_buoyancy = if self.buoyancy {
+            Vector2D { x: 0.0, y: self.rho * self.gravity * sim * self.stone.thickness }
+        } else {
+            Vector2D::new(0.0, 0.0)
+        };
+        let f_total = f_hydro + f_gravity + f_buoyancy;
 
         // 引入附加质量 (Added Mass)
         // 当石片在水中时，它必须带动周围的水运动。这增加了有效惯性。
-        // 估算公式：M_added = rho * Volume_ref * Coeff
-        let estimated_thickness = 0.02;
-        let added_mass = self.rho * sim * estimated_thickness * 5.0;
+        // 估算公式：M_added = rho * Volume_ref * Coeff (参考体积 = 浸没面积 * 石片厚度)
+        let added_mass = self.rho * sim * self.stone.thickness * self.added_mass_coeff;
 
         // 有效质量 = 自身质量 + 附加质量
         let effective_mass = self.M + added_mass;
@@ -76,48 +157,181 @@ impl CustomSettings {
         //let acceleration = f_total * (1.0 / mass);
 
         // 4. 计算角加速度
-        let angular_acc = self.compute_angular_acceleration(stone, sim, &clipped, f_hydro);
+        let angular_acc = self.angular_acceleration(stone, sim, hydro_torque);
 
-        StoneInfo {
+        let derivative = StoneInfo {
             position: stone.velocity,
             velocity: acceleration,
             angle: stone.angle_velocity,
             angle_velocity: angular_acc,
-        }
+        };
+        (derivative, regions)
     }
 
+    /// 沉没阶段：与浸水阶段使用同一套阻力、浮力与附加质量，只是不再计算升力等弹跳相关的力
+    /// 两个方向都受与速度平方成正比的阻力，竖直方向的净力为重力减浮力；姿态保持不变
     pub fn deriv_sinking(&self, _t:f64, stone: &StoneInfo) -> StoneInfo {
+        let (sim, _) = self.calculate_instant_submerged(stone);
+        let f_drag = self.drag_force(stone, sim);
+        let f_gravity = Vector2D { x: 0.0, y: -self.M * self.gravity };
+        let f_buoyancy = if self.buoyancy {
+            Vector2D { x: 0.0, y: self.rho * self.gravity * sim * self.stone.thickness }
+        } else {
+            Vector2D::new(0.0, 0.0)
+        };
+        let effective_mass = self.M + self.rho * sim * self.stone.thickness * self.added_mass_coeff;
         StoneInfo {
             position: stone.velocity,
-            velocity: Vector2D { x: -10.0 * stone.velocity.x, y: -self.gravity},
+            velocity: (f_drag + f_gravity + f_buoyancy) * (1.0 / effective_mass),
             angle: Vector2D { x: 0.0, y: 0.0 },
             angle_velocity: Vector2D { x: 0.0, y: 0.0 },
         }
     }
 }
 
+/// 集中力模型中各水动力分量 (N)，用于显示与单项测试；积分器使用 total()
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HydroForces {
+    pub drag: Vector2D,
+    pub lift: Vector2D,
+    pub vertical_damping: Vector2D,
+    pub suction: Vector2D,
+    pub wave_radiation: Vector2D,
+    pub horizontal_resist: Vector2D,
+}
+
+impl HydroForces {
+    pub fn zero() -> Self {
+        let zero = Vector2D { x: 0.0, y: 0.0 };
+        Self { drag: zero, lift: zero, vertical_damping: zero, suction: zero, wave_radiation: zero, horizontal_resist: zero }
+    }
+
+    /// 合力
+    pub fn total(&self) -> Vector2D {
+        self.drag + self.lift + self.vertical_damping + self.suction + self.wave_radiation + self.horizontal_resist
+    }
+
+    /// (名称, 大小) 列表，按显示顺序
+    pub fn magnitudes(&self) -> [(&'static str, f64); 6] {
+        [
+            ("drag", self.drag.length()),
+            ("lift", self.lift.length()),
+            ("damping", self.vertical_damping.length()),
+            ("suction", self.suction.length()),
+            ("wave", self.wave_radiation.length()),
+            ("horizontal", self.horizontal_resist.length()),
+        ]
+    }
+}
+
+/// 攻角 α = 俯仰角 - 速度方向角 (rad)
+/// 石片平面与来流的夹角：平拍入水时大，贴着速度方向切入时接近 0
+pub fn angle_of_attack(stone: &StoneInfo) -> f64 {
+    stone.angle.x - stone.velocity.y.atan2(stone.velocity.x)
+}
+
+/// 多边形在垂直于 `dir` (单位向量) 方向上的总展宽 (m)
+/// 每个多边形投影成一个区间，合并重叠的区间后求总长，互相遮挡的部分不重复计算
+fn projected_width(regions: &[Vec<Vector2D>], dir: Vector2D) -> f64 {
+    let normal = Vector2D::new(-dir.y, dir.x);
+    let mut spans: Vec<(f64, f64)> = regions.iter()
+        .filter(|region| !region.is_empty())
+        .map(|region| region.iter()
+            .map(|&p| p.dot(normal))
+            .fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), d| (lo.min(d), hi.max(d))))
+        .collect();
+    spans.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    let mut width = 0.0;
+    let mut covered = f64::NEG_INFINITY; // 已计入的区间右端
+    for (lo, hi) in spans {
+        if hi > covered {
+            width += hi - lo.max(covered);
+            covered = hi;
+        }
+    }
+    width
+}
+
 impl CustomSettings {
-    // 根据传入的 StoneInfo 实时计算浸没多边形
-    fn calculate_instant_submerged(&self, stone: &StoneInfo) -> (f64, Vec<Vector2D>) {
+    // 根据传入的 StoneInfo 实时计算浸没区域
+    fn calculate_instant_submerged(&self, stone: &StoneInfo) -> (f64, Vec<Vec<Vector2D>>) {
         // 调用 simulation.rs 中的逻辑
         let outline_world = self.outline_to_world(stone);
-        let clipped = clip_polygon_below_line(&outline_world, self.water_level);
+        let regions = clip_polygon_below_surface(&outline_world, self.effective_water_level(), self.slope);
+        (regions_area(&regions), regions)
+    }
 
-        let sim = if clipped.len() < 3 {
-            0.0
-        } else {
-            polygon_area(&clipped)
-        };
-        (sim, clipped)
+    /// 浸没部分垂直于 `dir` (单位向量) 的投影面积 (m^2)，即阻力的参考面积：
+    /// 与 frontal_area 相同，取浸没多边形在来流法向上的展宽 * 厚度；浮力仍使用浸没面积本身
+    pub fn projected_area(&self, stone: &StoneInfo, dir: Vector2D) -> f64 {
+        let (_, regions) = self.calculate_instant_submerged(stone);
+        projected_width(&regions, dir) * self.stone.thickness
+    }
+
+    // 有效阻力系数
+    // 开启 use_reynolds_drag 时，根据 Re = v L / nu 在低雷诺数 (高阻力) 与
+    // 高雷诺数 (Cf) 两个区间之间平滑插值；特征长度取 sqrt(浸没面积)
+    pub fn effective_drag_coefficient(&self, speed: f64, sim: f64) -> f64 {
+        if !self.use_reynolds_drag || self.nu <= 0.0 {
+            return self.Cf;
+        }
+
+        // 低雷诺数区的阻力放大倍数
+        let low_re_factor = 3.0;
+        // 转捩雷诺数 (对数坐标中心) 与过渡宽度 (数量级)
+        let re_transition: f64 = 5.0e5;
+        let transition_width = 0.5;
+
+        let reynolds = speed * sim.max(0.0).sqrt() / self.nu;
+        if reynolds <= 1e-9 {
+            return self.Cf * low_re_factor;
+        }
+
+        // 在 log10(Re) 上做 logistic 过渡: w=0 为低雷诺数区, w=1 为高雷诺数区
+        let x = (reynolds.log10() - re_transition.log10()) / transition_width;
+        let w = 1.0 / (1.0 + (-x).exp());
+
+        self.Cf * (low_re_factor * (1.0 - w) + w)
+    }
+
+    /// 浸没部分的阻力 (N)，方向与速度相反，大小 0.5 * rho * A * Cf * v^2
+    /// 参考面积取垂直于来流的投影面积 (见 projected_area)，侧缘切水时阻力很小
+    fn drag_force(&self, stone: &StoneInfo, sim: f64) -> Vector2D {
+        let speed_sq = stone.velocity.length_squared();
+        if sim <= 1e-9 || speed_sq <= 1e-9 {
+            return Vector2D::new(0.0, 0.0);
+        }
+        let speed = speed_sq.sqrt();
+        let dir_v = if speed > 1e-6 { stone.velocity * (1.0 / speed) } else { Vector2D { x: 0.0, y: 0.0 } };
+        let cf = self.effective_drag_coefficient(speed, sim);
+        let frontal = self.projected_area(stone, dir_v);
+        dir_v * -(0.5 * self.rho * frontal * cf * speed_sq)
+    }
+
+    // 水动力合力 (各分量之和，见 compute_hydro_components)
+    pub fn compute_hydro_force(&self, stone: &StoneInfo, sim: f64, perimeter: f64) -> Vector2D {
+        self.compute_hydro_components(stone, sim, perimeter).total()
+    }
+
+    /// 当前状态下水动力的各分量，供渲染器显示
+    /// 石片未浸没，或所用的模型没有分量 (分布式受力、自定义模型) 时返回 None，不显示与实际受力不符的分解
+    pub fn hydro_breakdown(&self, stone: &StoneInfo) -> Option<HydroForces> {
+        let (sim, regions) = self.calculate_instant_submerged(stone);
+        if sim <= 1e-9 {
+            return None;
+        }
+        self.force_model.breakdown(self, stone, &regions, sim)
     }
 
     // 水动力计算：增加表面张力和波辐射耗散
-    pub fn compute_hydro_force(&self, stone: &StoneInfo, sim: f64) -> Vector2D {
+    // `perimeter`: 湿周 (浸没部分与水接触的边长，不含水线)
+    pub fn compute_hydro_components(&self, stone: &StoneInfo, sim: f64, perimeter: f64) -> HydroForces {
         let velocity = stone.velocity;
         let speed_sq = velocity.length_squared();
 
         if sim <= 1e-9 {
-            return Vector2D { x: 0.0, y: 0.0 };
+            return HydroForces::zero();
         }
 
         let speed = if speed_sq > 1e-9 { speed_sq.sqrt() } else { 0.0 };
@@ -125,71 +339,76 @@ impl CustomSettings {
 
         // --- 1. 流体动力项 (Hydrodynamic Forces) ---
 
-        // 阻力 (Drag)
-        let f_drag_mag = 0.5 * self.rho * sim * self.Cf * speed_sq;
-        let f_drag = dir_v * -f_drag_mag;
+        // 阻力 (Drag)：见 drag_force
+        let f_drag = self.drag_force(stone, sim);
 
         // 升力 (Lift)
-        let mut dir_lift = Vector2D { x: -dir_v.y, y: dir_v.x };
-        if dir_lift.y < 0.0 { dir_lift = dir_lift * -1.0; } // 总是向上
+        // 方向为速度逆时针转 90°；大小与符号取决于真实攻角 (薄平板近似 Cl * sin(2α))，
+        // 不强制向上：负攻角、向后运动或翻过竖直位置 (α > 90°) 时升力可以向下，把石片压入水中
+        let dir_lift = Vector2D { x: -dir_v.y, y: dir_v.x };
 
-        let f_lift_mag = 0.5 * self.rho * sim * self.Cl * speed_sq;
-        let f_lift = dir_lift * f_lift_mag;
+        let alpha = angle_of_attack(stone);
+        let f_lift_mag = 0.5 * self.rho * sim * self.Cl * (2.0 * alpha).sin() * speed_sq;
+
+        // 自转升力 (Spin Lift)：旋转的下表面带动水流，附加一个与 |自转| * 半径 * 速度 成正比的升力，
+        // 与自转方向无关，总是指向离开水面的一侧；spin_lift_coeff 为 0 时关闭
+        let spin_lift_mag = 0.5 * self.rho * sim * self.spin_lift_coeff
+            * stone.angle_velocity.y.abs() * self.stone.d_max.sqrt() * speed;
+        let spin_lift_dir = if dir_lift.y < 0.0 { dir_lift * -1.0 } else { dir_lift };
+        let f_lift = dir_lift * f_lift_mag + spin_lift_dir * spin_lift_mag;
 
         // --- 2. 垂直混合阻尼 (Vertical Damping) ---
         // 包括压差阻力 (Slamming) 和 粘性摩擦
         let vy = velocity.y;
-        let damping_quad = 20.0;
-        let damping_lin = 10.0;
-        let damp_mag = 0.5 * self.rho * sim * (damping_quad * vy.abs() + damping_lin);
+        let damp_mag = 0.5 * self.rho * sim * (self.damping_quad * vy.abs() + self.damping_lin);
         let f_vertical_damp = Vector2D { x: 0.0, y: -damp_mag * vy };
 
         // --- 3. [新增] 表面张力与吸附 (Surface Tension / Suction) ---
         // 只有当物体试图离开水面 (vy > 0) 且浸没较浅时生效
+        // 完全浸没时没有水线，不受吸附 (否则浮力无法让水下的石片上浮)
+        let fully_submerged = self.stone.area > 0.0 && sim >= self.stone.area * (1.0 - 1e-9);
         let mut f_suction_y = 0.0;
-        if vy > 0.0 {
-            // 估算接触周长 (Contact Perimeter) 近似为 sqrt(Area) * 4 (假设正方形)
-            let perimeter = sim.sqrt() * 4.0;
+        if vy > 0.0 && !fully_submerged {
+            // 接触周长 (Contact Perimeter) 使用真实的湿周
 
             // 水的表面张力系数 sigma ≈ 0.072 N/m，但在宏观模拟中，
-            // 我们使用一个放大的等效系数来模拟 "粘滞吸附" 效应。
-            // 系数 5.0 是调节参数：值越大，石头越难脱离水面，飘荡次数越少。
-            let tension_coeff = 8.0;
+            // 我们使用一个放大的等效系数 (tension_coeff) 来模拟 "粘滞吸附" 效应。
+            // 值越大，石头越难脱离水面，飘荡次数越少。
 
             // 吸附力 = -系数 * 周长
             // 这是一个向下的力
-            f_suction_y = -tension_coeff * perimeter;
+            f_suction_y = -self.tension_coeff * perimeter;
         }
         let f_suction = Vector2D { x: 0.0, y: f_suction_y };
 
         // --- 4. [新增] 波辐射耗散 (Wave Radiation Damping) ---
         // 模拟石头上下震荡时激发水波带走的能量
         // 这本质上是一个额外的垂直阻尼，但专门针对高频小振幅震荡
-        let wave_damping_coeff = 15.0; // 辐射阻尼系数
         // 力的大小与排开水的体积变化率 (≈ Area * vy) 成正比
-        let f_wave_radiation = Vector2D { x: 0.0, y: -wave_damping_coeff * sim * vy };
+        let f_wave_radiation = Vector2D { x: 0.0, y: -self.wave_damping_coeff * sim * vy };
 
-        let horizontal_resist_coeff = 2.0;
         let f_horizontal_resist = Vector2D {
-            x: -0.5 * self.rho * sim * horizontal_resist_coeff * velocity.x,
+            x: -0.5 * self.rho * sim * self.horizontal_resist_coeff * velocity.x,
             y: 0.0
         };
 
-        // 总力合成
-        f_drag + f_lift + f_vertical_damp + f_suction + f_wave_radiation + f_horizontal_resist
+        HydroForces {
+            drag: f_drag,
+            lift: f_lift,
+            vertical_damping: f_vertical_damp,
+            suction: f_suction,
+            wave_radiation: f_wave_radiation,
+            horizontal_resist: f_horizontal_resist,
+        }
     }
-    // [重构] 角加速度计算：增强稳定性
-    pub fn compute_angular_acceleration(&self, stone: &StoneInfo, sim: f64, clipped: &Vec<Vector2D>, f_hydro: Vector2D) -> Vector2D {
-        // 1. 自转阻尼 (Spin Damping)
-        // 这是一个纯耗散项
-        let spin_damping = -self.beta * stone.angle_velocity.y * stone.angle_velocity.y;
-
+    // 集中力模型的俯仰力矩：合力作用在压力中心，外加俯仰阻尼
+    pub fn lumped_pitch_torque(&self, stone: &StoneInfo, sim: f64, regions: &[Vec<Vector2D>], f_hydro: Vector2D) -> f64 {
         if sim <= 1e-9 {
-            return Vector2D { x: 0.0, y: spin_damping };
+            return 0.0;
         }
 
-        // 2. 计算压力中心 (Center of Pressure)
-        let force_point = pressure_center(clipped);
+        // 1. 计算压力中心 (Center of Pressure)
+        let force_point = pressure_center(regions);
 
         // 力臂 r = 压力中心 - 质心
         let mut r = force_point - stone.position;
@@ -201,57 +420,551 @@ impl CustomSettings {
             r = r.normalize() * max_arm;
         }
 
-        // 3. 水动力力矩 Torque = r x F_hydro
+        // 2. 水动力力矩 Torque = r x F_hydro
         let torque = r.x * f_hydro.y - r.y * f_hydro.x;
 
-        // 4. [关键] 俯仰阻尼 (Pitch Damping)
+        // 3. [关键] 俯仰阻尼 (Pitch Damping)
         // 水对石片翻转有巨大的抵抗力 (Added Mass Inertia / Viscosity)
         // 系数需要足够大以抑制“点头”震荡
         let pitch_damping_coeff = 5.0;
         // 阻尼力矩与 浸没面积 和 角速度 成正比
         let pitch_damping_torque = -0.5 * self.rho * sim * pitch_damping_coeff * stone.angle_velocity.x;
 
-        let total_torque_x = torque + pitch_damping_torque;
+        torque + pitch_damping_torque
+    }
 
-        let inertia = if self.stone.inertia_tensor_x > 1e-9 { self.stone.inertia_tensor_x } else { 0.1 };
+    // [重构] 角加速度计算：增强稳定性
+    // `torque`: 力模型给出的水动力俯仰力矩
+    pub fn angular_acceleration(&self, stone: &StoneInfo, sim: f64, torque: f64) -> Vector2D {
+        if sim <= 1e-9 {
+            // 离开水面时自转守恒
+            return Vector2D { x: 0.0, y: 0.0 };
+        }
+
+        // 1. 自转阻尼 (Spin Damping)
+        // 这是一个纯耗散项：带符号的平方阻尼 -beta * ω|ω|，始终与自转方向相反
+        let spin = stone.angle_velocity.y;
+        let spin_damping = -self.beta * spin * spin.abs();
 
-        // 5. [安全修正] 限制最大角加速度
-        let pitch_acc = total_torque_x / inertia;
-        let max_acc = 500.0;
-        let pitch_acc_clamped = pitch_acc.clamp(-max_acc, max_acc);
+        // 2. 陀螺稳定 + 3. 限制最大角加速度
+        let pitch_acc = clamp_pitch_acc(torque / self.effective_pitch_inertia(stone));
 
-        Vector2D { x: pitch_acc_clamped, y: spin_damping }
+        Vector2D { x: pitch_acc, y: spin_damping }
+    }
+
+    // 俯仰的等效惯量
+    // 陀螺稳定 (Gyroscopic Stabilization)：高速自转的石片抵抗俯仰变化，
+    // 等效俯仰惯性随 |自转角速度| 和自转惯量增大
+    fn effective_pitch_inertia(&self, stone: &StoneInfo) -> f64 {
+        // 惯量退化的石片在 StoneProperties 构建时已被拒绝 (StoneError::DegenerateInertia)
+        let inertia = self.stone.inertia_tensor_x;
+        let gyro_inertia = self.gyro_coeff * self.stone.inertia_tensor_y * stone.angle_velocity.y.abs();
+        inertia + gyro_inertia
+    }
+
+    // 分布式水动力：对各浸没区域的每条湿边积分压力与切向摩擦
+    // 返回 (合力, 关于质心的合力矩)；水线上的边不受力
+    //
+    // 每条边取中点的局部速度 v + ω × r，外法向分量 vn > 0 (迎流面) 时受压力
+    //   F_p = -0.5 * rho * Cp * A * vn^2 * n,  A = 边长 * 厚度
+    // 背流面视为通气空腔，不受压力；切向分量受摩擦 -0.5 * rho * Cf * A * |vt| * vt
+    // 力矩由各边的 r × F 自然产生 (已包含转动引起的阻尼)，因此不再需要额外的俯仰阻尼
+    pub fn compute_distributed_force(&self, stone: &StoneInfo, regions: &[Vec<Vector2D>]) -> (Vector2D, f64) {
+        let zero = Vector2D { x: 0.0, y: 0.0 };
+        let mut force = zero;
+        let mut torque = 0.0;
+        // 裁剪保持轮廓的顶点顺序，各区域的外法向按整个轮廓的环绕方向确定
+        // (退化或细长的区域自身的有向面积可能接近 0 甚至反号)
+        let orientation = if signed_polygon_area(&self.stone.outline_com) >= 0.0 { 1.0 } else { -1.0 };
+        for clipped in regions {
+            let (f, t) = self.region_distributed_force(stone, clipped, orientation);
+            force = force + f;
+            torque += t;
+        }
+        (force, torque)
+    }
+
+    fn region_distributed_force(&self, stone: &StoneInfo, clipped: &[Vector2D], orientation: f64) -> (Vector2D, f64) {
+        let zero = Vector2D { x: 0.0, y: 0.0 };
+        if clipped.len() < 3 {
+            return (zero, 0.0);
+        }
+
+        // 压力系数 (平板迎流面的驻点压力量级)
+        let pressure_coeff = 1.0;
+        let thickness = self.stone.thickness;
+
+        let n = clipped.len();
+
+        let on_surface = |p: Vector2D| (p.y - self.surface_height(p.x)).abs() < 1e-9;
+
+        let mut force = zero;
+        let mut torque = 0.0;
+        for i in 0..n {
+            let (p1, p2) = (clipped[i], clipped[(i + 1) % n]);
+            if on_surface(p1) && on_surface(p2) { continue; }
+
+            let edge = p2 - p1;
+            let length = edge.length();
+            if length < 1e-12 { continue; }
+
+            // 逆时针多边形的外法向为边向量顺时针旋转 90°
+            let tangent = edge * (1.0 / length);
+            let normal = Vector2D { x: tangent.y, y: -tangent.x } * orientation;
+
+            // 边中点的局部速度 (平动 + 俯仰转动)
+            let mid = (p1 + p2) * 0.5;
+            let r = mid - stone.position;
+            let omega = stone.angle_velocity.x;
+            let v_local = stone.velocity + Vector2D { x: -omega * r.y, y: omega * r.x };
+
+            let panel_area = length * thickness;
+            let vn = v_local.dot(normal);
+            let vt = v_local.dot(tangent);
+
+            let mut f_edge = tangent * (-0.5 * self.rho * self.Cf * panel_area * vt.abs() * vt);
+            if vn > 0.0 {
+                f_edge = f_edge + normal * (-0.5 * self.rho * pressure_coeff * panel_area * vn * vn);
+            }
+
+            force = force + f_edge;
+            torque += r.x * f_edge.y - r.y * f_edge.x;
+        }
+
+        (force, torque)
     }
 }
 
-// 压力中心计算
-fn pressure_center(clipped: &Vec<Vector2D>) -> Vector2D {
-    if clipped.len() < 3 { return Vector2D::new(0.0, 0.0); }
-
-    let area = polygon_area(clipped);
-    // 防止面积过小导致除以零
-    if area.abs() < 1e-9 {
-        let mut sum_x = 0.0;
-        let mut sum_y = 0.0;
-        for p in clipped {
-            sum_x += p.x;
-            sum_y += p.y;
+/// 俯仰角加速度的截断值 (rad/s^2)
+pub const MAX_PITCH_ACC: f64 = 500.0;
+
+// [安全修正] 限制最大俯仰角加速度
+fn clamp_pitch_acc(pitch_acc: f64) -> f64 {
+    pitch_acc.clamp(-MAX_PITCH_ACC, MAX_PITCH_ACC)
+}
+
+// 压力中心计算：各浸没区域的形心按面积加权
+// 使用有向面积，使嵌套区域 (方向相反) 被正确扣除
+pub(crate) fn pressure_center(regions: &[Vec<Vector2D>]) -> Vector2D {
+    let (mut sum, mut total) = (Vector2D::new(0.0, 0.0), 0.0);
+    for region in regions {
+        let area = signed_polygon_area(region);
+        sum = sum + polygon_centroid(region) * area;
+        total += area;
+    }
+    if total.abs() < 1e-9 {
+        // 面积过小：退化为各区域形心的平均
+        let centers: Vec<Vector2D> = regions.iter().filter(|r| r.len() >= 3).map(|r| polygon_centroid(r)).collect();
+        if centers.is_empty() { return Vector2D::new(0.0, 0.0); }
+        let n = centers.len() as f64;
+        return centers.iter().fold(Vector2D::new(0.0, 0.0), |acc, &c| acc + c) * (1.0 / n);
+    }
+    sum * (1.0 / total)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physics::force_model::ForceModel;
+    use crate::rng::Rng;
+    use crate::solver2::RungeKuttaSolver;
+    use crate::stone_editor::StoneBlueprint;
+    use crate::stone_phy::StoneProperties;
+
+    fn square_settings() -> CustomSettings {
+        let blueprint = StoneBlueprint {
+            points: vec![
+                Vector2D::new(-0.03, -0.03),
+                Vector2D::new(0.03, -0.03),
+                Vector2D::new(0.03, 0.03),
+                Vector2D::new(-0.03, 0.03),
+            ],
+            thickness: 0.01,
+            name: "square".to_string(),
+            density_fn: None,
+        };
+        let mut rng = Rng::new(7);
+        let stone = StoneProperties::new(&blueprint, &mut rng).unwrap();
+        CustomSettings::new(9.81, stone, rng.fork())
+    }
+
+    #[test]
+    fn hydro_components_sum_to_the_integrated_force() {
+        let system = square_settings();
+        // 上升中：吸附力向下，大小为 系数 * 湿周
+        let stone = StoneInfo {
+            position: Vector2D::new(0.0, 0.0),
+            velocity: Vector2D::new(4.0, 0.5),
+            angle: Vector2D::new(0.1, 0.0),
+            angle_velocity: Vector2D::new(0.0, 0.0),
+        };
+        let (sim, regions) = system.calculate_instant_submerged(&stone);
+        let perimeter = wetted_perimeter(&regions, 0.0, 0.0);
+        let parts = system.compute_hydro_components(&stone, sim, perimeter);
+
+        assert_eq!(parts.total(), system.compute_hydro_force(&stone, sim, perimeter));
+        assert!((parts.suction.y + 8.0 * perimeter).abs() < 1e-12);
+        assert!(parts.drag.x < 0.0 && parts.horizontal_resist.x < 0.0);
+        assert_eq!(system.hydro_breakdown(&stone), Some(parts));
+
+        // 未浸没时没有分量
+        let airborne = StoneInfo { position: Vector2D::new(0.0, 1.0), ..stone };
+        assert_eq!(system.hydro_breakdown(&airborne), None);
+
+        // 分布式受力时集中力模型的分量不是实际受力，不显示
+        let mut system = system;
+        system.distributed_forces = true;
+        assert_eq!(system.hydro_breakdown(&stone), None);
+    }
+
+    #[test]
+    fn empirical_coefficients_are_tunable_and_never_negative() {
+        let mut system = square_settings();
+        let stone = StoneInfo {
+            position: Vector2D::new(0.0, 0.0),
+            velocity: Vector2D::new(4.0, 0.5),
+            angle: Vector2D::new(0.1, 0.0),
+            angle_velocity: Vector2D::new(0.0, 0.0),
+        };
+        let (sim, regions) = system.calculate_instant_submerged(&stone);
+        let perimeter = wetted_perimeter(&regions, 0.0, 0.0);
+        let before = system.compute_hydro_components(&stone, sim, perimeter);
+        system.wave_damping_coeff *= 2.0;
+        system.tension_coeff = 0.0;
+        let after = system.compute_hydro_components(&stone, sim, perimeter);
+        assert!((after.wave_radiation.y - 2.0 * before.wave_radiation.y).abs() < 1e-12);
+        assert_eq!(after.suction.y, 0.0);
+        assert_eq!(after.drag, before.drag);
+
+        // 构建器把负的阻尼系数截为 0
+        let built = CustomSettingsBuilder::new().damping_lin(-3.0).damping_quad(4.0).build(system.stone.clone());
+        assert_eq!((built.damping_lin, built.damping_quad), (0.0, 4.0));
+    }
+
+    #[test]
+    fn lift_follows_the_angle_of_attack_in_every_orientation() {
+        let system = square_settings();
+        let lift = |velocity: Vector2D, pitch: f64| {
+            let stone = StoneInfo {
+                position: Vector2D::new(0.0, 0.0),
+                velocity,
+                angle: Vector2D::new(pitch, 0.0),
+                angle_velocity: Vector2D::new(0.0, 0.0),
+            };
+            let (sim, regions) = system.calculate_instant_submerged(&stone);
+            system.compute_hydro_components(&stone, sim, wetted_perimeter(&regions, 0.0, 0.0)).lift
+        };
+
+        // 前缘抬起：向上；向左运动的镜像状态给出镜像的升力
+        let forward = lift(Vector2D::new(5.0, -1.0), 0.3);
+        let mirrored = lift(Vector2D::new(-5.0, -1.0), -0.3);
+        assert!(forward.y > 0.0);
+        assert!((mirrored.x + forward.x).abs() < 1e-9 && (mirrored.y - forward.y).abs() < 1e-9);
+
+        // 翻过竖直位置 (俯仰 120°)：升力向下
+        assert!(lift(Vector2D::new(5.0, -1.0), 120f64.to_radians()).y < 0.0);
+    }
+
+    #[test]
+    fn over_rotated_stone_is_driven_under() {
+        // 已翻过竖直位置 (约 109°) 且继续高速翻滚的石片：升力向下，比不计升力时潜得更深
+        let run = |cl: f64| {
+            let mut system = square_settings();
+            system.Cl = cl;
+            let y0 = StoneInfo {
+                position: Vector2D::new(0.0, 0.06),
+                velocity: Vector2D::new(6.0, -1.0),
+                angle: Vector2D::new(1.9, 0.0),
+                angle_velocity: Vector2D::new(20.0, 0.0),
+            };
+            system.set_initial_phase(&y0);
+            let mut solver = RungeKuttaSolver::new(0.0, y0);
+            crate::physics::simulation::simulate(&mut system, &mut solver, 1e-4, 3_000, 1).0
+        };
+        let deepest = |trajectory: &[Stamp]| trajectory.iter().map(|s| s.state.position.y).fold(f64::INFINITY, f64::min);
+
+        let with_lift = run(square_settings().Cl);
+        let pushed_down = with_lift.iter()
+            .filter_map(|s| s.forces.as_ref())
+            .any(|f| f.lift.y < 0.0);
+        assert!(pushed_down, "翻转后的石片应受到向下的升力");
+        let (lift_depth, no_lift_depth) = (deepest(&with_lift), deepest(&run(0.0)));
+        assert!(lift_depth < no_lift_depth, "升力应把翻转的石片压得更深: {} vs {}", lift_depth, no_lift_depth);
+    }
+
+    #[test]
+    fn drag_uses_the_area_projected_across_the_flow() {
+        // 6 cm 见方、厚 1 cm 的石片，水平放置，一半浸在水中
+        let system = square_settings();
+        let level = StoneInfo {
+            position: Vector2D::new(0.0, 0.0),
+            velocity: Vector2D::new(5.0, 0.0),
+            angle: Vector2D::new(0.0, 0.0),
+            angle_velocity: Vector2D::new(0.0, 0.0),
+        };
+
+        // 水平来流看到的是 3 cm 的浸没深度，竖直来流看到的是 6 cm 的整个宽度
+        let area = |dir: Vector2D| system.projected_area(&level, dir);
+        assert!((area(Vector2D::new(1.0, 0.0)) - 0.03 * 0.01).abs() < 1e-9);
+        assert!((area(Vector2D::new(0.0, -1.0)) - 0.06 * 0.01).abs() < 1e-9);
+
+        // 侧缘迎流时阻力变小，但不为零
+        let (sim, regions) = system.calculate_instant_submerged(&level);
+        let drag = system.compute_hydro_components(&level, sim, wetted_perimeter(&regions, 0.0, 0.0)).drag.x;
+        assert!(drag < 0.0);
+
+        // 在来流法向上互相遮挡的区域只计一次
+        let square = |x: f64, y: f64| vec![
+            Vector2D::new(x, y), Vector2D::new(x + 1.0, y), Vector2D::new(x + 1.0, y + 1.0), Vector2D::new(x, y + 1.0),
+        ];
+        let flow = Vector2D::new(1.0, 0.0);
+        assert!((projected_width(&[square(0.0, 0.0), square(2.0, 0.5)], flow) - 1.5).abs() < 1e-12);
+        assert!((projected_width(&[square(0.0, 0.0), square(2.0, 3.0)], flow) - 2.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn deriv_at_is_side_effect_free_and_supports_a_jacobian() {
+        let mut system = square_settings();
+        system.phase = Phase::Bouncing;
+        let state = StoneInfo {
+            position: Vector2D::new(0.0, 0.01),
+            velocity: Vector2D::new(6.0, -1.0),
+            angle: Vector2D::new(0.2, 0.0),
+            angle_velocity: Vector2D::new(0.0, 20.0),
+        };
+
+        // 与积分器使用的导数一致，但不记录浸没多边形
+        let d = system.deriv_at(0.0, &state);
+        assert!(system.force_regions().is_empty());
+        assert_eq!(system.current_submerged_regions.len(), 0);
+        let integrated = system.derivatives(0.0, &state);
+        assert_eq!((d.velocity, d.angle_velocity), (integrated.velocity, integrated.angle_velocity));
+        assert!(!system.force_regions().is_empty());
+
+        // 中心差分雅可比矩阵的一列：对 velocity.x 求导，位置导数那一行应为单位向量
+        let h = 1e-6;
+        let mut plus = state.clone();
+        let mut minus = state.clone();
+        plus.velocity.x += h;
+        minus.velocity.x -= h;
+        let column = system.deriv_at(0.0, &plus).add(&system.deriv_at(0.0, &minus).scale(-1.0)).scale(0.5 / h);
+        assert!((column.position.x - 1.0).abs() < 1e-9 && column.position.y.abs() < 1e-9);
+        assert!(column.velocity.x < 0.0, "阻力应随水平速度增大");
+    }
+
+    #[test]
+    fn sinking_uses_the_same_drag_and_buoyancy_as_bouncing() {
+        let mut system = square_settings();
+        system.phase = Phase::Sinking;
+        system.buoyancy = true;
+        // 完全没入水中、平放 (俯仰 90°，法向水平) 的石片
+        let at_rest = StoneInfo {
+            position: Vector2D::new(0.0, -0.1),
+            velocity: Vector2D::new(0.0, 0.0),
+            angle: Vector2D::new(std::f64::consts::FRAC_PI_2, 0.0),
+            angle_velocity: Vector2D::new(0.0, 0.0),
+        };
+
+        // 静止时只有重力减浮力，除以含附加质量的有效质量
+        let (sim, _) = system.calculate_instant_submerged(&at_rest);
+        assert!((sim - system.stone.area).abs() < 1e-9);
+        let volume = sim * system.stone.thickness;
+        let expected = -(system.M - system.rho * volume) * system.gravity / (system.M + system.rho * volume * system.added_mass_coeff);
+        let d = system.deriv_at(0.0, &at_rest);
+        assert!((d.velocity.y - expected).abs() < 1e-9);
+        assert_eq!(d.velocity.x, 0.0);
+
+        // 水平运动受到与浸水阶段相同的阻力
+        let moving = StoneInfo { velocity: Vector2D::new(2.0, 0.0), ..at_rest.clone() };
+        let d = system.deriv_at(0.0, &moving);
+        let effective_mass = system.M + system.rho * volume * system.added_mass_coeff;
+        assert!(d.velocity.x < 0.0);
+        assert!((d.velocity.x * effective_mass - system.drag_force(&moving, sim).x).abs() < 1e-12);
+    }
+
+    #[test]
+    fn hydro_forces_ramp_in_after_water_entry() {
+        let mut system = square_settings();
+        system.phase = Phase::Bouncing;
+        let state = StoneInfo {
+            position: Vector2D::new(0.0, 0.01),
+            velocity: Vector2D::new(6.0, -1.0),
+            angle: Vector2D::new(0.2, 0.0),
+            angle_velocity: Vector2D::new(0.0, 0.0),
+        };
+        let full = system.deriv_at(0.0, &state);
+
+        // 渐变时间为 0 时与原来完全相同
+        system.bouncing_entry_t = Some(0.0);
+        assert_eq!(system.deriv_at(0.0, &state).velocity, full.velocity);
+
+        system.force_ramp_time = 0.004;
+        assert_eq!(system.force_ramp_factor(0.002), 0.5);
+        // 入水瞬间只剩重力 (仍计附加质量)，渐变结束后恢复全部水动力
+        let (sim, _) = system.calculate_instant_submerged(&state);
+        let effective_mass = system.M + system.rho * sim * system.stone.thickness * system.added_mass_coeff;
+        let at_entry = system.deriv_at(0.0, &state);
+        assert_eq!(at_entry.velocity.x, 0.0);
+        assert!((at_entry.velocity.y + system.M * system.gravity / effective_mass).abs() < 1e-12);
+        assert_eq!(system.deriv_at(0.004, &state).velocity, full.velocity);
+    }
+
+    #[test]
+    fn gravity_pulls_down_while_bouncing() {
+        let mut system = square_settings();
+        system.phase = Phase::Bouncing;
+
+        // 静止释放，底边刚好没入水面 2 mm
+        let stone = StoneInfo {
+            position: Vector2D::new(0.0, 0.028),
+            velocity: Vector2D::new(0.0, 0.0),
+            angle: Vector2D::new(0.0, 0.0),
+            angle_velocity: Vector2D::new(0.0, 0.0),
+        };
+        let (sim, _) = system.calculate_instant_submerged(&stone);
+        assert!(sim > 0.0);
+
+        let d = system.deriv_bouncing(0.0, &stone);
+        assert!(d.velocity.y < 0.0);
+        assert!(d.velocity.x.abs() < 1e-12);
+        // 静止时水动力为零，加速度为重力被附加质量稀释后的值
+        assert!(d.velocity.y > -system.gravity);
+    }
+
+    #[test]
+    fn spin_magnitude_decreases_while_submerged() {
+        for initial_spin in [20.0, -20.0] {
+            let mut system = square_settings();
+            system.phase = Phase::Bouncing;
+
+            let y0 = StoneInfo {
+                position: Vector2D::new(0.0, -0.01),
+                velocity: Vector2D::new(5.0, 0.0),
+                angle: Vector2D::new(0.0, 0.0),
+                angle_velocity: Vector2D::new(0.0, initial_spin),
+            };
+            let mut solver = RungeKuttaSolver::new(0.0, y0);
+
+            let mut last = f64::abs(initial_spin);
+            for _ in 0..50 {
+                solver.step(&system, 0.001);
+                let spin = solver.state.angle_velocity.y.abs();
+                assert!(spin < last, "spin grew from {} to {}", last, spin);
+                last = spin;
+            }
+            // 阻尼不会让自转反向
+            assert_eq!(solver.state.angle_velocity.y.signum(), f64::signum(initial_spin));
         }
-        let n = clipped.len() as f64;
-        return Vector2D { x: sum_x / n, y: sum_y / n };
     }
 
-    let mut cx = 0.0;
-    let mut cy = 0.0;
+    #[test]
+    fn gusts_are_off_by_default_and_reproducible() {
+        let stone = StoneInfo {
+            position: Vector2D::new(0.0, 1.0),
+            velocity: Vector2D::new(10.0, 1.0),
+            angle: Vector2D::new(0.1, 0.0),
+            angle_velocity: Vector2D::new(0.0, 20.0),
+        };
+
+        // 默认强度 0：只有重力
+        let calm = square_settings();
+        let d = calm.deriv_flying(1.3, &stone);
+        assert_eq!(d.velocity, Vector2D::new(0.0, -9.81));
+
+        // 相同种子得到相同的阵风，且确实改变了加速度
+        let mut a = square_settings();
+        let mut b = square_settings();
+        a.gust_strength = 5.0;
+        b.gust_strength = 5.0;
+        for t in [0.0, 0.4, 1.3] {
+            let da = a.deriv_flying(t, &stone);
+            assert_eq!(da.velocity, b.deriv_flying(t, &stone).velocity);
+            assert!(a.wind_velocity(t).length() <= 5.0 * std::f64::consts::SQRT_2);
+        }
+        assert_ne!(a.deriv_flying(1.3, &stone).velocity, d.velocity);
+    }
+
+    #[test]
+    fn air_drag_is_opt_in_and_opposes_motion() {
+        let stone = StoneInfo {
+            position: Vector2D::new(0.0, 1.0),
+            velocity: Vector2D::new(10.0, 0.0),
+            angle: Vector2D::new(0.0, 0.0),
+            angle_velocity: Vector2D::new(0.0, 20.0),
+        };
+
+        // 默认关闭：理想抛体
+        let mut system = square_settings();
+        assert_eq!(system.deriv_flying(0.0, &stone).velocity, Vector2D::new(0.0, -9.81));
+
+        // 6 cm 方片水平飞行：迎风面 0.06 * 0.01
+        system.air_drag = true;
+        let area = system.frontal_area(&stone);
+        assert!((area - 0.06 * 0.01).abs() < 1e-9, "area = {}", area);
+        let d = system.deriv_flying(0.0, &stone);
+        let expected = 0.5 * AIR_DENSITY * DEFAULT_AIR_DRAG_COEFF * area * 100.0 / system.M;
+        assert!((d.velocity.x + expected).abs() < 1e-9);
+        assert_eq!(d.velocity.y, -9.81);
+    }
+
+    #[test]
+    fn distributed_pressure_pushes_a_falling_stone_up() {
+        let mut system = square_settings();
+        system.distributed_forces = true;
+
+        // 方块下半部分浸没，竖直下落
+        let stone = StoneInfo {
+            position: Vector2D::new(0.0, 0.0),
+            velocity: Vector2D::new(0.0, -2.0),
+            angle: Vector2D::new(0.0, 0.0),
+            angle_velocity: Vector2D::new(0.0, 0.0),
+        };
+        let (_, regions) = system.calculate_instant_submerged(&stone);
+        let (force, torque) = system.compute_distributed_force(&stone, &regions);
 
-    for i in 0..clipped.len() {
-        let p1 = clipped[i];
-        let p2 = clipped[(i + 1) % clipped.len()];
-        let cross = p1.x * p2.y - p2.x * p1.y;
-        cx += (p1.x + p2.x) * cross;
-        cy += (p1.y + p2.y) * cross;
+        assert!(force.y > 0.0);
+        assert!(force.x.abs() < 1e-9);
+        // 对称浸没不产生力矩
+        assert!(torque.abs() < 1e-9);
     }
 
-    let factor = 1.0 / (6.0 * area);
-    Vector2D { x: cx * factor, y: cy * factor }
-}
\ No newline at end of file
+    #[test]
+    fn spin_is_conserved_out_of_water() {
+        let system = square_settings();
+        let stone = StoneInfo {
+            position: Vector2D::new(0.0, 1.0),
+            velocity: Vector2D::new(5.0, 0.0),
+            angle: Vector2D::new(0.0, 0.0),
+            angle_velocity: Vector2D::new(0.0, -20.0),
+        };
+        let acc = system.angular_acceleration(&stone, 0.0, 0.0);
+        assert_eq!(acc.y, 0.0);
+    }
+
+    #[test]
+    fn custom_force_model_replaces_hydrodynamics() {
+        // 只施加固定水平阻力、不产生力矩的模型
+        struct ConstantDrag;
+        impl ForceModel for ConstantDrag {
+            fn force_and_torque(&self, _: &CustomSettings, _: &StoneInfo, _: &[Vec<Vector2D>], _: f64) -> (Vector2D, f64) {
+                (Vector2D::new(-1.0, 0.0), 0.0)
+            }
+        }
+
+        let mut system = square_settings();
+        system.force_model = Box::new(ConstantDrag);
+        let stone = StoneInfo {
+            position: Vector2D::new(0.0, 0.0),
+            velocity: Vector2D::new(5.0, -1.0),
+            angle: Vector2D::new(0.1, 0.0),
+            angle_velocity: Vector2D::new(3.0, 0.0),
+        };
+
+        let (sim, _) = system.calculate_instant_submerged(&stone);
+        let effective_mass = system.M + system.rho * sim * system.stone.thickness * system.added_mass_coeff;
+        let d = system.deriv_bouncing(0.0, &stone);
+        assert!((d.velocity.x + 1.0 / effective_mass).abs() < 1e-12);
+        // 没有水动力力矩，也就没有俯仰阻尼
+        assert_eq!(d.angle_velocity.x, 0.0);
+    }
+}
@@ -0,0 +1,189 @@
+// 职责：浸没阶段水动力的扩展点。
+// deriv_bouncing 通过 CustomSettings::force_model 调用，研究者可以实现 ForceModel
+// 换入自己的水动力模型而无需修改本 crate；重力、附加质量、自转阻尼与陀螺稳定仍由求导部分处理。
+
+use crate::basic_structs::Vector2D;
+use crate::physics::derivative::HydroForces;
+use crate::physics::parameters::CustomSettings;
+use crate::physics::simulation::{wetted_perimeter, StoneInfo};
+
+/// 水动力模型
+pub trait ForceModel {
+    /// 返回 (水动力合力 (N，不含重力), 关于质心的俯仰力矩 (N m))
+    /// `regions`: 水面以下的各浸没区域 (世界坐标)，`sim`: 总面积 (m^2)
+    /// `settings` 提供水的参数与石片属性
+    fn force_and_torque(&self, settings: &CustomSettings, stone: &StoneInfo,
+                        regions: &[Vec<Vector2D>], sim: f64) -> (Vector2D, f64);
+
+    /// 合力按物理来源的分解 (渲染器信息面板显示)；合力不是由这些分量相加得到的模型返回 None
+    fn breakdown(&self, _settings: &CustomSettings, _stone: &StoneInfo,
+                 _regions: &[Vec<Vector2D>], _sim: f64) -> Option<HydroForces> {
+        None
+    }
+}
+
+/// 内置模型：按 `distributed_forces` 选择
+/// 集中力模型 (阻力、升力、垂直阻尼、表面吸附、波辐射，力矩由压力中心计算) 或
+/// 分布式模型 (逐边积分压力与摩擦)
+pub struct DefaultForceModel;
+
+impl ForceModel for DefaultForceModel {
+    fn force_and_torque(&self, settings: &CustomSettings, stone: &StoneInfo,
+                        regions: &[Vec<Vector2D>], sim: f64) -> (Vector2D, f64) {
+        if settings.distributed_forces {
+            return settings.compute_distributed_force(stone, regions);
+        }
+
+        let perimeter = wetted_perimeter(regions, settings.effective_water_level(), settings.slope);
+        let force = settings.compute_hydro_force(stone, sim, perimeter);
+        (force, settings.lumped_pitch_torque(stone, sim, regions, force))
+    }
+
+    /// 只有集中力模型有分量；分布式模型逐边积分，没有对应的分解
+    fn breakdown(&self, settings: &CustomSettings, stone: &StoneInfo,
+                 regions: &[Vec<Vector2D>], sim: f64) -> Option<HydroForces> {
+        if settings.distributed_forces { return None; }
+        let perimeter = wetted_perimeter(regions, settings.effective_water_level(), settings.slope);
+        Some(settings.compute_hydro_components(stone, sim, perimeter))
+    }
+}
+
+/// 滑行 (planing) 模型：沿石片弦向积分与浸没深度成正比的压力 (可选，默认不使用)
+///
+/// 把弦长分成若干站位，每个站位取石片下表面在水面以下的深度 d(s)，压力
+///   p(s) = (rho * g + 0.5 * rho * Cl * U^2 / c) * d_eff(s)
+/// 垂直于石片平面作用 (c 为弦长，U 为速度大小)。运动时水在前缘堆起，
+/// 有效深度 d_eff 从后缘到前缘按 bow_wave 比例线性增加，前缘受力更大，
+/// 这一压力差正是滑行时抬头力矩的来源；力矩直接由各站位的 s * f(s) 积分得到。
+/// 另加与湿长度成正比的摩擦阻力和与集中力模型相同的俯仰阻尼。
+///
+/// ```ignore
+/// settings.force_model = Box::new(PlaningForceModel::default());
+/// ```
+pub struct PlaningForceModel {
+    /// 沿弦向的积分站位数
+    pub stations: usize,
+    /// 前缘堆水：前缘有效深度比静水深度多出的比例 (0 为不考虑)
+    pub bow_wave: f64,
+}
+
+impl Default for PlaningForceModel {
+    fn default() -> Self {
+        Self { stations: 32, bow_wave: 0.5 }
+    }
+}
+
+impl PlaningForceModel {
+    /// 各站位 (弦向坐标 s (m)，下表面的有效浸没深度 (m))，以及站位间距 ds
+    /// s 从质心沿石片平面量起，指向运动方向为正
+    fn depth_profile(&self, settings: &CustomSettings, stone: &StoneInfo) -> (Vec<(f64, f64)>, f64) {
+        let outline = settings.outline_to_world(stone);
+        let pitch = stone.angle.x;
+        let dir = Vector2D::new(pitch.cos(), pitch.sin());    // 弦向
+        let normal = Vector2D::new(-pitch.sin(), pitch.cos()); // 石片上表面法向
+        let local: Vec<(f64, f64)> = outline.iter()
+            .map(|&p| { let r = p - stone.position; (r.dot(dir), r.dot(normal)) })
+            .collect();
+        let (u_min, u_max) = local.iter().fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), &(u, _)| (lo.min(u), hi.max(u)));
+        let n = self.stations.max(1);
+        let ds = (u_max - u_min) / n as f64;
+        if local.len() < 3 || ds <= 0.0 {
+            return (Vec::new(), 0.0);
+        }
+
+        // 运动方向上的前缘：水平速度向 -x 时前后缘对调
+        let forward = if stone.velocity.dot(dir) >= 0.0 { 1.0 } else { -1.0 };
+
+        let profile = (0..n).map(|i| {
+            let u = u_min + ds * (i as f64 + 0.5);
+            // 下表面：该站位处轮廓边的最低交点
+            let w_low = (0..local.len())
+                .filter_map(|k| {
+                    let ((u1, w1), (u2, w2)) = (local[k], local[(k + 1) % local.len()]);
+                    if (u1 - u) * (u2 - u) > 0.0 || u1 == u2 { return None; }
+                    Some(w1 + (w2 - w1) * (u - u1) / (u2 - u1))
+                })
+                .fold(f64::INFINITY, f64::min);
+            if !w_low.is_finite() {
+                return (u, 0.0);
+            }
+            let p = stone.position + dir * u + normal * w_low;
+            let depth = (settings.surface_height(p.x) - p.y).max(0.0);
+            // 从后缘 (0) 到前缘 (1) 的位置
+            let xi = if forward > 0.0 { (u - u_min) / (u_max - u_min) } else { (u_max - u) / (u_max - u_min) };
+            (u, depth * (1.0 + self.bow_wave * xi))
+        }).collect();
+        (profile, ds)
+    }
+}
+
+impl ForceModel for PlaningForceModel {
+    fn force_and_torque(&self, settings: &CustomSettings, stone: &StoneInfo,
+                        _regions: &[Vec<Vector2D>], sim: f64) -> (Vector2D, f64) {
+        let zero = Vector2D::new(0.0, 0.0);
+        let (profile, ds) = self.depth_profile(settings, stone);
+        if profile.is_empty() || sim <= 1e-12 {
+            return (zero, 0.0);
+        }
+
+        let pitch = stone.angle.x;
+        let normal = Vector2D::new(-pitch.sin(), pitch.cos());
+        let chord = ds * profile.len() as f64;
+        let speed = stone.velocity.length();
+        let thickness = settings.stone.thickness;
+        let k = settings.rho * settings.gravity + 0.5 * settings.rho * settings.Cl * speed * speed / chord;
+
+        // 1. 压力：f(s) = p(s) * 厚度 * ds，沿法向；力矩 r x F = s * f(s)
+        let (mut lift, mut torque, mut wetted) = (0.0, 0.0, 0.0);
+        for &(s, depth) in &profile {
+            let f = k * depth * thickness * ds;
+            lift += f;
+            torque += s * f;
+            if depth > 0.0 { wetted += ds; }
+        }
+
+        // 2. 湿长度上的摩擦阻力，与速度反向
+        let drag = stone.velocity * (-0.5 * settings.rho * settings.Cf * speed * wetted * thickness);
+
+        // 3. 俯仰阻尼 (与集中力模型相同)
+        let pitch_damping = -0.5 * settings.rho * sim * 5.0 * stone.angle_velocity.x;
+
+        (normal * lift + drag, torque + pitch_damping)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::Rng;
+    use crate::stone_phy::{slab_outline, StoneProperties};
+
+    #[test]
+    fn flat_planing_stone_gets_a_nose_up_torque() {
+        let stone = StoneProperties::from_outline(&slab_outline(0.01), 0.01, 2500.0).unwrap();
+        let settings = CustomSettings::new(9.81, stone, Rng::new(1));
+        let model = PlaningForceModel::default();
+        // 水平放置、下表面浸入 2 mm，水平向 +x 滑行
+        let mut state = StoneInfo {
+            position: Vector2D::new(0.0, 0.003),
+            velocity: Vector2D::new(5.0, 0.0),
+            angle: Vector2D::new(0.0, 0.0),
+            angle_velocity: Vector2D::new(0.0, 0.0),
+        };
+        let sim = 0.06 * 0.002;
+
+        let (force, torque) = model.force_and_torque(&settings, &state, &[], sim);
+        assert!(force.y > 0.0, "lift {:?}", force);
+        assert!(force.x < 0.0, "drag {:?}", force);
+        assert!(torque > 0.0, "前缘更深，应当抬头: {}", torque);
+
+        // 反向滑行时前缘在 -x 一侧，力矩反号
+        state.velocity.x = -5.0;
+        let (_, reverse) = model.force_and_torque(&settings, &state, &[], sim);
+        assert!((reverse + torque).abs() < 1e-9 * torque.abs());
+
+        // 不考虑堆水时深度处处相同，没有力矩
+        let still = PlaningForceModel { bow_wave: 0.0, ..PlaningForceModel::default() };
+        assert!(still.force_and_torque(&settings, &state, &[], sim).1.abs() < 1e-12);
+    }
+}
@@ -0,0 +1,82 @@
+// 职责："黄金轨迹" 回归测试。固定石片、初始条件、种子和步长跑一次无窗口模拟，
+// 把终点位置、弹跳次数和滞空时间与 tests/golden/ 下保存的参考值比较，
+// 防止重构悄悄改变物理结果。
+// 与其他单元测试一样放在 crate 内部 (需要用到 crate 内可见的接口)，
+// 参考值文件仍放在 tests/golden/。
+// 有意修改物理时用 `BLESS=1 cargo test golden` 重新生成参考值。
+
+use std::fs;
+use std::path::PathBuf;
+
+use crate::basic_structs::Vector2D;
+use crate::physics::parameters::CustomSettings;
+use crate::physics::simulation::{simulate, SimSummary, StoneInfo, DEFAULT_SAMPLE_STRIDE};
+use crate::rng::Rng;
+use crate::solver2::RungeKuttaSolver;
+use crate::stone_phy::{slab_outline, StoneProperties};
+
+const SEED: u64 = 2024;
+const DT: f64 = 0.0005;
+const MAX_STEPS: usize = 20_000;
+const DENSITY: f64 = 2700.0;
+/// 比较容差 (同一平台上结果应逐位一致，留一点余量给不同编译器版本)
+const TOLERANCE: f64 = 1e-6;
+
+fn reference_path() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/golden/rectangle.txt")
+}
+
+// 6 cm x 2 cm 的矩形石片，15 m/s、0.35 rad 攻角、15 rad/s 自转
+fn run_reference() -> (StoneInfo, SimSummary) {
+    let outline = slab_outline(0.02);
+    let stone = StoneProperties::from_outline(&outline, 0.01, DENSITY).unwrap();
+    let y0 = StoneInfo {
+        position: Vector2D::new(0.0, 0.05),
+        velocity: Vector2D::new(15.0, -1.0),
+        angle: Vector2D::new(0.35, 0.0),
+        angle_velocity: Vector2D::new(0.0, 15.0),
+    };
+    let mut rng = Rng::new(SEED);
+    let mut system = CustomSettings::new(9.81, stone, rng.fork()).with_initial_state(&y0);
+    let mut solver = RungeKuttaSolver::new(0.0, y0);
+    let (trajectory, summary) = simulate(&mut system, &mut solver, DT, MAX_STEPS, DEFAULT_SAMPLE_STRIDE);
+    (trajectory.last().unwrap().state.clone(), summary)
+}
+
+// 参考值文件：每行 `键 = 值`
+fn to_reference(last: &StoneInfo, summary: &SimSummary) -> String {
+    format!("final_x = {:e}\nfinal_y = {:e}\nskips = {}\nair_time = {:e}\n",
+            last.position.x, last.position.y, summary.skips, summary.air_time)
+}
+
+fn parse_reference(text: &str) -> Vec<(String, f64)> {
+    text.lines()
+        .filter_map(|line| line.split_once('='))
+        .map(|(k, v)| (k.trim().to_string(), v.trim().parse().expect("参考值不是数字")))
+        .collect()
+}
+
+#[test]
+fn golden_trajectory_matches_reference() {
+    let (last, summary) = run_reference();
+    let actual = to_reference(&last, &summary);
+    let path = reference_path();
+
+    if std::env::var("BLESS").is_ok_and(|v| v == "1") {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, &actual).unwrap();
+        println!("已重新生成参考值: {}", path.display());
+        return;
+    }
+
+    let expected = fs::read_to_string(&path)
+        .unwrap_or_else(|e| panic!("无法读取 {} ({})，用 BLESS=1 生成", path.display(), e));
+    let expected = parse_reference(&expected);
+    let actual = parse_reference(&actual);
+    assert_eq!(expected.len(), actual.len(), "参考值字段不一致，用 BLESS=1 重新生成");
+    assert!(summary.skips > 0, "参考运行应当至少弹跳一次");
+    for ((key, want), (_, got)) in expected.iter().zip(&actual) {
+        assert!((want - got).abs() <= TOLERANCE * want.abs().max(1.0),
+                "{} 偏离参考值: 期望 {}，实际 {} (有意修改物理时用 BLESS=1 重新生成)", key, want, got);
+    }
+}
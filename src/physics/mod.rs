@@ -1,3 +1,8 @@
 pub mod simulation; 
 pub mod parameters;
-pub mod derivative; 
\ No newline at end of file
+pub mod derivative;
+pub mod force_model; 
+pub mod snapshot;
+
+#[cfg(test)]
+mod golden;
@@ -1,6 +1,10 @@
 
 use crate::stone_phy::StoneProperties; 
 use crate::basic_structs::Vector2D; 
+use crate::rng::{Rng, DEFAULT_SEED};
+use crate::physics::force_model::{DefaultForceModel, ForceModel};
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
 #[derive(PartialEq, Eq, Debug, Clone, Copy)]
 pub enum Phase {
     Flying, 
@@ -8,6 +12,182 @@ pub enum Phase {
     Sinking
 }
 
+// 弹跳模型：连续水动力 或 恢复系数冲量 (街机风格，更可预测)
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum BounceModel {
+    Continuous,
+    Impulse,
+}
+
+// 冲量模型中恢复系数随弹跳次数 n 衰减的曲线 (衰减率 d 见 restitution_decay)
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum DecayCurve {
+    Geometric, // e_n = e * (1 - d)^n
+    Linear,    // e_n = e * max(1 - d * n, 0)
+}
+
+impl DecayCurve {
+    /// 第 n 次弹跳 (从 0 起) 时恢复系数的倍率
+    pub fn factor(self, decay: f64, n: u32) -> f64 {
+        match self {
+            DecayCurve::Geometric => (1.0 - decay).clamp(0.0, 1.0).powi(n as i32),
+            DecayCurve::Linear => (1.0 - decay * n as f64).max(0.0),
+        }
+    }
+}
+
+// 石片到达对岸墙壁时的处理方式
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum WallMode {
+    Stop,   // 到达对岸，结束模拟
+    Bounce, // 弹性反弹：水平速度反向
+}
+
+// 重力预设 (初始条件界面选择)
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum GravityPreset {
+    Earth,
+    Moon,
+    Mars,
+    Custom(f64),
+}
+
+impl GravityPreset {
+    /// 重力加速度 (m/s^2)
+    pub fn value(&self) -> f64 {
+        match *self {
+            GravityPreset::Earth => 9.81,
+            GravityPreset::Moon => 1.62,
+            GravityPreset::Mars => 3.71,
+            GravityPreset::Custom(g) => g,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            GravityPreset::Earth => "Earth",
+            GravityPreset::Moon => "Moon",
+            GravityPreset::Mars => "Mars",
+            GravityPreset::Custom(_) => "Custom",
+        }
+    }
+}
+
+/// 默认水温 (°C)
+pub const DEFAULT_WATER_TEMP_C: f64 = 20.0;
+
+/// 纯水密度 (kg/m^3)，Thiesen 经验公式，约 4°C 时最大 (≈ 999.97)
+pub fn water_density(temp_c: f64) -> f64 {
+    let t = temp_c;
+    1000.0 * (1.0 - (t + 288.9414) / (508929.2 * (t + 68.12963)) * (t - 3.9863).powi(2))
+}
+
+/// 纯水运动粘度 (m^2/s)
+/// 动力粘度用 Vogel 公式 mu = A * 10^(B / (T - C))，随温度升高而降低
+pub fn water_kinematic_viscosity(temp_c: f64) -> f64 {
+    let kelvin = temp_c + 273.15;
+    let mu = 2.414e-5 * 10f64.powf(247.8 / (kelvin - 140.0));
+    mu / water_density(temp_c)
+}
+
+/// 默认最小法向入水速度 (m/s)
+pub const DEFAULT_MIN_SKIP_SPEED: f64 = 0.25;
+
+/// 默认积分步长 (s)：飞行段只有重力与阵风，用粗步长；浸水段受力剧烈变化，用细步长
+pub const DEFAULT_DT_FLYING: f64 = 0.002;
+pub const DEFAULT_DT_BOUNCING: f64 = 0.0005;
+/// 浸水步长不应超过弹跳振荡周期的这一比例，否则 RK4 可能失稳
+/// (静水刚度只是下限，高速触水时升力更硬，所以取得较保守)
+pub const SAFE_DT_FRACTION: f64 = 0.02;
+
+/// 空气密度 (kg/m^3)，用于阵风对飞行石片的作用力
+pub const AIR_DENSITY: f64 = 1.225;
+/// 阵风作用的阻力系数 (钝体量级)
+pub const GUST_DRAG_COEFF: f64 = 1.0;
+/// 飞行段空气阻力系数的默认值 (薄板侧飞与钝体之间)
+pub const DEFAULT_AIR_DRAG_COEFF: f64 = 1.0;
+/// 阵风由若干正弦分量叠加而成 (带限噪声)
+const GUST_MODES: usize = 4;
+
+/// 阵风的一个正弦分量；频率相对 gust_frequency 给出，便于运行时调整频率
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct GustMode {
+    pub(crate) freq_ratio: f64,
+    pub(crate) phase_x: f64,
+    pub(crate) phase_y: f64,
+    pub(crate) weight: f64,
+}
+
+/// 从随机数源抽取阵风分量 (频率在基频的 0.5~2 倍之间)
+fn sample_gust_modes(rng: &mut Rng) -> Vec<GustMode> {
+    let tau = std::f64::consts::TAU;
+    (0..GUST_MODES)
+        .map(|_| GustMode {
+            freq_ratio: rng.range(0.5, 2.0),
+            phase_x: rng.range(0.0, tau),
+            phase_y: rng.range(0.0, tau),
+            weight: rng.range(0.5, 1.0),
+        })
+        .collect()
+}
+
+/// 静止判定：在最近 window 秒内质心位置的方差 (m^2，x 与 y 之和) 不超过 max_variance，
+/// 且速度始终不超过 max_speed 时，认为石片已经停稳 (见 simulation::SettleTracker)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SettleCriteria {
+    pub window: f64,       // 观察窗口 (s)，0 为关闭
+    pub max_variance: f64, // 位置方差上限 (m^2)
+    pub max_speed: f64,    // 速度上限 (m/s)
+}
+
+impl Default for SettleCriteria {
+    fn default() -> Self {
+        // 2 s 内偏离平均位置不超过约 2 mm，速度不超过 2 cm/s
+        Self { window: 2.0, max_variance: 4.0e-6, max_speed: 0.02 }
+    }
+}
+
+/// 同时存在的涟漪数量上限，超出时丢弃最早的一个
+pub const MAX_RIPPLES: usize = 8;
+/// 涟漪波包的传播速度 (m/s)、波长 (m)、包络宽度 (m)、衰减率 (1/s) 与寿命 (s)
+const RIPPLE_SPEED: f64 = 0.6;
+const RIPPLE_WAVELENGTH: f64 = 0.04;
+const RIPPLE_WIDTH: f64 = 0.05;
+const RIPPLE_DECAY: f64 = 1.5;
+const RIPPLE_LIFETIME: f64 = 3.0;
+/// 法向入水速度达到该值 (m/s) 时涟漪取满幅 ripple_amplitude
+const RIPPLE_REFERENCE_SPEED: f64 = 5.0;
+
+/// 触水点产生的一圈涟漪：从 x0 向两侧传播的波包，随时间指数衰减
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Ripple {
+    pub x0: f64,        // 触水点 (m)
+    pub t0: f64,        // 产生时刻 (s)
+    pub amplitude: f64, // 初始幅值 (m)
+}
+
+impl Ripple {
+    /// t 时刻水平位置 x 处的水面起伏 (m)
+    pub fn height(&self, x: f64, t: f64) -> f64 {
+        let age = t - self.t0;
+        if !(0.0..RIPPLE_LIFETIME).contains(&age) {
+            return 0.0;
+        }
+        // 相对波前的距离
+        let s = (x - self.x0).abs() - RIPPLE_SPEED * age;
+        let envelope = (-RIPPLE_DECAY * age).exp() * (-(s / RIPPLE_WIDTH).powi(2)).exp();
+        self.amplitude * envelope * (std::f64::consts::TAU * s / RIPPLE_WAVELENGTH).cos()
+    }
+
+    fn expired(&self, t: f64) -> bool {
+        t - self.t0 >= RIPPLE_LIFETIME
+    }
+}
+
+/// 世界坐标轮廓的缓存项：(位置, 姿态, 轮廓)
+pub(crate) type OutlineCache = Option<(Vector2D, Vector2D, Rc<[Vector2D]>)>;
+
+#[allow(non_snake_case)]
 pub struct CustomSettings {
     pub gravity: f64,
     pub rho: f64,
@@ -16,31 +196,597 @@ pub struct CustomSettings {
     pub Sim: f64,
     pub M: f64,
     pub beta: f64,
+    pub gyro_coeff: f64, // 自转对俯仰的陀螺稳定系数 (s)
+    pub spin_lift_coeff: f64, // 自转产生的附加升力系数 (0 为关闭，见 compute_hydro_components)
+    pub added_mass_coeff: f64, // 附加质量系数: M_added = rho * Sim * 厚度 * 系数
+
+    // 集中力模型的经验系数 (见 compute_hydro_components)，不是由物理推导的，按需调节
+    pub damping_quad: f64,            // 垂直阻尼的平方项 (压差/拍击)
+    pub damping_lin: f64,             // 垂直阻尼的线性项 (粘性)
+    pub tension_coeff: f64,           // 离水时的吸附力 (N/m，乘以湿周)
+    pub wave_damping_coeff: f64,      // 波辐射阻尼 (乘以 浸没面积 * vy)
+    pub horizontal_resist_coeff: f64, // 水平附加阻力
+
+    // 角速度上限 (rad/s)，每步积分后截断，防止剧烈入水时数值发散
+    pub max_pitch_rate: f64,
+    pub max_spin_rate: f64,
+    pub angular_clamp_warned: bool, // 截断是否已经触发过 (只警告一次)
+    pub verbose: bool, // 打印相位切换、结束原因等调试信息 (默认关闭，批量运行保持安静)
     pub phase: Phase,
     pub water_level: f64,
+    pub slope: f64, // 水面坡度 dy/dx，水面高度为 water_level + slope * x
+
+    // 水深 (m)：池底在 y = water_level - water_depth；0 为无底 (石片一开始沉没模拟即结束)
+    // 有底时沉没的石片继续下沉，触底后停在池底 (hit_bottom)
+    pub water_depth: f64,
+    pub hit_bottom: bool, // 是否已沉到池底 (运行状态，reset_state 清空)
+
+    // 对岸墙壁 (默认没有)：石片轮廓越过 x = wall_x 时按 wall_mode 结束模拟或反弹
+    pub wall_x: Option<f64>,
+    pub wall_mode: WallMode,
+    pub reached_wall: bool, // 是否已到达对岸 (运行状态，reset_state 清空)
+
+    // 水温 (°C)，rho 与 nu 由它推导 (见 set_water_temperature)
+    pub water_temp_c: f64,
+
+    // 雷诺数相关阻力模型 (默认关闭，使用常数 Cf)
+    pub use_reynolds_drag: bool,
+    pub nu: f64, // 水的运动粘度 (m^2/s)
+
+    // 分布式水动力：按浸没多边形逐边积分压力与摩擦，代替单点集中力 (默认关闭)
+    pub distributed_forces: bool,
+
+    // 静水浮力 (默认关闭)：开启后比水轻的石片可以漂浮，用于研究静置与沉浮
+    pub buoyancy: bool,
+
+    // 飞行段空气阻力 (默认关闭，保持理想抛体)：F = -0.5 rho_air Cd_air A |v| v，
+    // A 为石片垂直于速度方向的投影面积 (见 frontal_area)
+    pub air_drag: bool,
+    pub rho_air: f64,
+    pub Cd_air: f64,
+
+    // 入水后水动力从 0 平滑增加到全值所用的时间 (s)，模拟流场建立需要的有限时间，
+    // 削弱入水瞬间的冲击；0 为关闭 (入水即施加全部水动力)
+    pub force_ramp_time: f64,
+    pub bouncing_entry_t: Option<f64>, // 本次入水 (Flying -> Bouncing) 的时刻 (运行状态，reset_state 清空)
+
+    // 入水时的最小法向速度 (m/s)，低于该值石片不再弹起而直接沉没
+    pub min_skip_speed: f64,
+
+    // 入水时俯仰角速度转为自转的比例系数 (0 为不耦合，见 apply_spin_coupling)
+    pub spin_coupling: f64,
+
+    // 无窗口模拟的静止判定 (原地晃动既不沉没也不前进时提前结束)
+    pub settle: SettleCriteria,
+
+    // 涟漪：每次入水在触水点产生一圈 (最大幅值 m，0 为关闭)
+    // ripple_feedback 开启时物理在每步开始采样石片所在处的起伏，后续弹跳受之前涟漪影响
+    pub ripple_amplitude: f64,
+    pub ripple_feedback: bool,
+    pub ripples: Vec<Ripple>,
+    pub ripple_offset: f64, // 本步采样到的水面起伏 (m)，未开启反馈时恒为 0
+
+    // 按相位选择的积分步长 (s)，见 recommended_dt
+    pub dt_flying: f64,
+    pub dt_bouncing: f64,
+
+    // 冲量弹跳模型参数
+    pub bounce_model: BounceModel,
+    pub restitution: f64,      // 法向恢复系数
+    pub tangent_friction: f64, // 每次弹跳损失的切向速度比例
+    // 恢复系数随弹跳次数衰减 (0 为不衰减)，模拟一跳比一跳弱、最终贴水沉没
+    pub restitution_decay: f64,
+    pub restitution_decay_curve: DecayCurve,
+    pub bounce_count: u32, // 本次运行已发生的冲量弹跳次数 (运行状态，reset_state 清零)
 
     pub stone: StoneProperties, 
-    pub current_submerged_polygon: Vec<Vector2D>,
+    pub current_submerged_regions: Vec<Vec<Vector2D>>, // 浸没区域 (凹石片可能同时有多处入水)
+    pub wetted_perimeter: f64, // 当前浸没部分的湿周 (m)
+    // 最近一次导数求值中水动力实际作用的浸没多边形 (见 force_regions())
+    pub(crate) force_regions: RefCell<Vec<Vec<Vector2D>>>,
+
+    // 世界坐标轮廓的缓存，键为 (位置, 姿态)；同一状态重复变换时直接复用
+    // (update_submerged_area 与 RK4 的 k1 使用同一状态)
+    pub(crate) outline_cache: RefCell<OutlineCache>,
+    pub outline_transforms: Cell<u64>, // 实际执行的轮廓变换次数 (性能统计)
+    // 浸水阶段各子步中出现过的最大线加速度 (m/s^2) 与俯仰角加速度是否触及截断
+    // 诊断用：数百 g 说明入水瞬态对当前步长过于刚性 (reset_state 清零)
+    pub peak_acceleration: Cell<f64>,
+    pub pitch_acc_clamped: Cell<bool>,
+
+    // 阵风：强度 (m/s，风速分量的最大幅值) 与基频 (Hz)，强度为 0 时无风
+    pub gust_strength: f64,
+    pub gust_frequency: f64,
+    pub(crate) gust_modes: Vec<GustMode>,
+
+    // 随机受力模型 (如阵风) 使用的随机数源，保证可复现
+    pub rng: Rng,
+
+    // 浸没阶段的水动力模型，可替换为自定义实现 (见 force_model.rs)
+    pub force_model: Box<dyn ForceModel>,
 }
 
 
 impl CustomSettings{
-    pub(crate) fn new(g:f64, stone: StoneProperties ) -> Self{
+    pub fn new(g:f64, stone: StoneProperties, mut rng: Rng) -> Self{
+        let gust_modes = sample_gust_modes(&mut rng);
         CustomSettings{
         gravity: g, 
-        rho: 1000.0,         // 水的密度 (kg/m^3)
+        rho: water_density(DEFAULT_WATER_TEMP_C), // 水的密度 (kg/m^3)
         Cl: 0.2,             // 默认升力系数
         Cf: 0.05,            // 默认摩擦/阻力系数
         Sim: 0.01,           // 石头横截面积 (m^2)            
         M: stone.mass,              // 石头质量 (kg)
         beta: 0.02,          // 旋转阻尼
+        gyro_coeff: 0.1,     // 陀螺稳定: 15 rad/s 自转约使俯仰惯性增大数倍
+        spin_lift_coeff: 0.0,
+        added_mass_coeff: 5.0,
+        damping_quad: 20.0,
+        damping_lin: 10.0,
+        tension_coeff: 8.0,
+        wave_damping_coeff: 15.0,
+        horizontal_resist_coeff: 2.0,
+        max_pitch_rate: 50.0,
+        max_spin_rate: 300.0,
+        angular_clamp_warned: false,
+        verbose: false,
         phase: Phase::Flying, 
         water_level: 0.0,
+        slope: 0.0,
+        wall_x: None,
+        wall_mode: WallMode::Stop,
+        reached_wall: false,
+        water_depth: 0.0,
+        hit_bottom: false,
+        use_reynolds_drag: false,
+        water_temp_c: DEFAULT_WATER_TEMP_C,
+        nu: water_kinematic_viscosity(DEFAULT_WATER_TEMP_C), // 水的运动粘度
+        distributed_forces: false,
+        buoyancy: false,
+        air_drag: false,
+        rho_air: AIR_DENSITY,
+        Cd_air: DEFAULT_AIR_DRAG_COEFF,
+        force_ramp_time: 0.0,
+        bouncing_entry_t: None,
+        min_skip_speed: DEFAULT_MIN_SKIP_SPEED,
+        spin_coupling: 0.0,
+        settle: SettleCriteria::default(),
+        ripple_amplitude: 0.005,
+        ripple_feedback: false,
+        ripples: Vec::new(),
+        ripple_offset: 0.0,
+        dt_flying: DEFAULT_DT_FLYING,
+        dt_bouncing: DEFAULT_DT_BOUNCING,
+        bounce_model: BounceModel::Continuous,
+        restitution: 0.6,
+        tangent_friction: 0.1,
+        restitution_decay: 0.0,
+        restitution_decay_curve: DecayCurve::Geometric,
+        bounce_count: 0,
 
-        stone: stone, 
-        current_submerged_polygon: Vec::new(), 
+        stone, 
+        current_submerged_regions: Vec::new(), 
+        wetted_perimeter: 0.0,
+        force_regions: RefCell::new(Vec::new()),
+        outline_cache: RefCell::new(None),
+        outline_transforms: Cell::new(0),
+        peak_acceleration: Cell::new(0.0),
+        pitch_acc_clamped: Cell::new(false),
+        gust_strength: 0.0,
+        gust_frequency: 0.5,
+        gust_modes,
+        rng,
+        force_model: Box::new(DefaultForceModel),
 
         }
     }
+
+    /// 调试信息：只在 verbose 打开时打印 (警告与错误不经过这里)
+    pub fn log_event(&self, message: std::fmt::Arguments) {
+        if self.verbose {
+            println!("{}", message);
+        }
+    }
+
+    /// 重新模拟前恢复运行状态 (相位、浸没多边形、随机数源)，保留所有可调参数
+    /// `rng` 应为当初传给 CustomSettings::new 的随机数源：阵风从它重新抽取，与第一次运行相同
+    pub fn reset_state(&mut self, rng: Rng) {
+        self.phase = Phase::Flying;
+        self.current_submerged_regions.clear();
+        self.wetted_perimeter = 0.0;
+        self.force_regions.borrow_mut().clear();
+        self.angular_clamp_warned = false;
+        self.peak_acceleration.set(0.0);
+        self.pitch_acc_clamped.set(false);
+        self.bounce_count = 0;
+        self.bouncing_entry_t = None;
+        self.reached_wall = false;
+        self.hit_bottom = false;
+        self.ripples.clear();
+        self.ripple_offset = 0.0;
+        self.rng = rng;
+        self.gust_modes = sample_gust_modes(&mut self.rng);
+    }
+
+    /// t 时刻水动力的缩放比例：入水后 force_ramp_time 内按 smoothstep 从 0 增加到 1
+    /// 未开启、初始即在水中 (没有入水时刻) 或已超过渐变时间时为 1
+    pub fn force_ramp_factor(&self, t: f64) -> f64 {
+        let Some(entry) = self.bouncing_entry_t else { return 1.0; };
+        if self.force_ramp_time <= 0.0 {
+            return 1.0;
+        }
+        let x = ((t - entry) / self.force_ramp_time).clamp(0.0, 1.0);
+        x * x * (3.0 - 2.0 * x)
+    }
+
+    /// 上一步中水动力实际作用的浸没多边形 (世界坐标)
+    /// 取该步最后一次导数求值 (RK4 的 k4，接近步末状态)；未浸水的一步为空
+    /// 与 current_submerged_regions (步开始时单独计算) 在入水/出水瞬间可能不同
+    pub fn force_regions(&self) -> Vec<Vec<Vector2D>> {
+        self.force_regions.borrow().clone()
+    }
+
+    /// 当前相位推荐的积分步长 (s)；界面与无窗口运行共用这一策略
+    /// 飞行段用粗步长，浸水 (及沉没) 时用细步长以分辨短促的触水过程
+    pub fn recommended_dt(&self) -> f64 {
+        match self.phase {
+            Phase::Flying => self.dt_flying,
+            Phase::Bouncing | Phase::Sinking => self.dt_bouncing,
+        }
+    }
+
+    /// 石片在水面上的固有振荡频率 (Hz)，由静水刚度与质量估计
+    /// 水线宽度 w (石片平放时的水平跨度) 对应刚度 k = rho * g * w * 厚度，f = sqrt(k / M) / 2pi
+    pub fn estimate_bounce_frequency(&self) -> f64 {
+        let (min_x, max_x) = self.stone.outline_com.iter()
+            .fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), p| (lo.min(p.x), hi.max(p.x)));
+        let width = (max_x - min_x).max(0.0);
+        let stiffness = self.rho * self.gravity * width * self.stone.thickness;
+        if self.M <= 0.0 || stiffness <= 0.0 {
+            return 0.0;
+        }
+        (stiffness / self.M).sqrt() / std::f64::consts::TAU
+    }
+
+    /// 浸水步长 dt 相对弹跳周期过大时返回提示文字，否则 None
+    pub fn bounce_dt_warning(&self, dt: f64) -> Option<String> {
+        let frequency = self.estimate_bounce_frequency();
+        if frequency <= 0.0 {
+            return None;
+        }
+        let safe_dt = SAFE_DT_FRACTION / frequency;
+        (dt > safe_dt).then(|| format!(
+            "警告: 浸水步长 {} s 超过弹跳周期 ({:.3} s) 的安全比例，石片可能抖动或发散；建议 dt <= {:.2e} s 或使用按相位自适应的步长",
+            dt, 1.0 / frequency, safe_dt))
+    }
+
+    /// t 时刻的风速 (m/s)；各分量按权重归一化，幅值不超过 gust_strength
+    pub fn wind_velocity(&self, t: f64) -> Vector2D {
+        if self.gust_strength == 0.0 || self.gust_modes.is_empty() {
+            return Vector2D::new(0.0, 0.0);
+        }
+        let total_weight: f64 = self.gust_modes.iter().map(|m| m.weight).sum();
+        let omega = std::f64::consts::TAU * self.gust_frequency;
+        let (mut wx, mut wy) = (0.0, 0.0);
+        for m in &self.gust_modes {
+            let arg = omega * m.freq_ratio * t;
+            wx += m.weight * (arg + m.phase_x).sin();
+            wy += m.weight * (arg + m.phase_y).sin();
+        }
+        Vector2D::new(wx, wy) * (self.gust_strength / total_weight)
+    }
+
+    /// 设置水温，并据此更新水的密度与运动粘度
+    pub fn set_water_temperature(&mut self, temp_c: f64) {
+        self.water_temp_c = temp_c;
+        self.rho = water_density(temp_c);
+        self.nu = water_kinematic_viscosity(temp_c);
+    }
+
+    /// 物理使用的水面基准高度 (m)：静水面加上本步采样到的涟漪起伏
+    pub fn effective_water_level(&self) -> f64 {
+        self.water_level + self.ripple_offset
+    }
+
+    /// 水平位置 x 处的水面高度 (m)，物理判断与裁剪使用
+    pub fn surface_height(&self, x: f64) -> f64 {
+        self.effective_water_level() + self.slope * x
+    }
+
+    /// t 时刻 x 处包含所有涟漪的水面高度 (m)，用于绘制
+    pub fn surface_height_at(&self, x: f64, t: f64) -> f64 {
+        self.water_level + self.slope * x + self.ripple_height(x, t)
+    }
+
+    /// 所有涟漪在 (x, t) 处的起伏之和 (m)
+    pub fn ripple_height(&self, x: f64, t: f64) -> f64 {
+        self.ripples.iter().map(|r| r.height(x, t)).sum()
+    }
+
+    /// 在触水点产生涟漪，幅值随法向入水速度增大；超过上限时丢弃最早的
+    pub fn spawn_ripple(&mut self, x: f64, t: f64, normal_speed: f64) {
+        if self.ripple_amplitude <= 0.0 {
+            return;
+        }
+        if self.ripples.len() >= MAX_RIPPLES {
+            self.ripples.remove(0);
+        }
+        let strength = (normal_speed / RIPPLE_REFERENCE_SPEED).clamp(0.0, 1.0);
+        self.ripples.push(Ripple { x0: x, t0: t, amplitude: self.ripple_amplitude * strength });
+    }
+
+    /// 每步开始时调用：移除过期涟漪，开启反馈时采样 x 处的起伏 (本步内保持不变)
+    pub fn sample_ripples(&mut self, x: f64, t: f64) {
+        self.ripples.retain(|r| !r.expired(t));
+        self.ripple_offset = if self.ripple_feedback { self.ripple_height(x, t) } else { 0.0 };
+    }
 }
 
+/// CustomSettings 的链式构建器；未设置的参数沿用 CustomSettings::new 的默认值
+///
+/// ```
+/// use dapgame::physics::parameters::{water_density, BounceModel, CustomSettings, CustomSettingsBuilder, GravityPreset};
+/// use dapgame::rng::{Rng, DEFAULT_SEED};
+/// use dapgame::stone_phy::StoneProperties;
+///
+/// let settings = CustomSettingsBuilder::new()
+///     .gravity(GravityPreset::Moon.value())
+///     .water_temperature(4.0)
+///     .lift(0.35)
+///     .bounce_model(BounceModel::Impulse)
+///     .restitution(0.8)
+///     .build(StoneProperties::default());
+/// assert_eq!(settings.gravity, 1.62);
+/// assert_eq!(settings.rho, water_density(4.0));
+/// assert_eq!(settings.Cl, 0.35);
+/// assert_eq!(settings.bounce_model, BounceModel::Impulse);
+/// assert_eq!(settings.restitution, 0.8);
+///
+/// // 未设置的参数与 CustomSettings::new 相同
+/// let defaults = CustomSettings::new(9.81, StoneProperties::default(), Rng::new(DEFAULT_SEED));
+/// assert_eq!(settings.Cf, defaults.Cf);
+/// assert_eq!(settings.min_skip_speed, defaults.min_skip_speed);
+/// ```
+#[derive(Default)]
+pub struct CustomSettingsBuilder {
+    gravity: Option<f64>,
+    rho: Option<f64>,
+    lift: Option<f64>,
+    friction: Option<f64>,
+    beta: Option<f64>,
+    gyro_coeff: Option<f64>,
+    spin_lift_coeff: Option<f64>,
+    added_mass_coeff: Option<f64>,
+    damping_quad: Option<f64>,
+    damping_lin: Option<f64>,
+    tension_coeff: Option<f64>,
+    wave_damping_coeff: Option<f64>,
+    horizontal_resist_coeff: Option<f64>,
+    water_level: Option<f64>,
+    slope: Option<f64>,
+    water_depth: Option<f64>,
+    wall: Option<(f64, WallMode)>,
+    water_temp_c: Option<f64>,
+    min_skip_speed: Option<f64>,
+    force_ramp_time: Option<f64>,
+    spin_coupling: Option<f64>,
+    settle: Option<SettleCriteria>,
+    bounce_model: Option<BounceModel>,
+    restitution: Option<f64>,
+    tangent_friction: Option<f64>,
+    restitution_decay: Option<(f64, DecayCurve)>,
+    dt_flying: Option<f64>,
+    dt_bouncing: Option<f64>,
+    buoyancy: Option<bool>,
+    verbose: Option<bool>,
+    air_drag: Option<bool>,
+    rho_air: Option<f64>,
+    cd_air: Option<f64>,
+    rng: Option<Rng>,
+}
+
+impl CustomSettingsBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 重力加速度 (m/s^2)，默认地球
+    pub fn gravity(mut self, g: f64) -> Self { self.gravity = Some(g); self }
+    /// 水的密度 (kg/m^3)；与 water_temperature 同时设置时以此为准
+    pub fn rho(mut self, rho: f64) -> Self { self.rho = Some(rho); self }
+    /// 升力系数 Cl
+    pub fn lift(mut self, cl: f64) -> Self { self.lift = Some(cl); self }
+    /// 摩擦/阻力系数 Cf
+    pub fn friction(mut self, cf: f64) -> Self { self.friction = Some(cf); self }
+    /// 自转阻尼
+    pub fn beta(mut self, beta: f64) -> Self { self.beta = Some(beta); self }
+    /// 陀螺稳定系数 (s)
+    pub fn gyro_coeff(mut self, k: f64) -> Self { self.gyro_coeff = Some(k); self }
+    /// 自转升力系数 (负值按 0 处理)
+    pub fn spin_lift_coeff(mut self, k: f64) -> Self { self.spin_lift_coeff = Some(k); self }
+    /// 附加质量系数
+    pub fn added_mass_coeff(mut self, k: f64) -> Self { self.added_mass_coeff = Some(k); self }
+    /// 垂直阻尼的平方项系数 (负值按 0 处理，下同：负的阻尼会向系统注入能量)
+    pub fn damping_quad(mut self, k: f64) -> Self { self.damping_quad = Some(k); self }
+    /// 垂直阻尼的线性项系数
+    pub fn damping_lin(mut self, k: f64) -> Self { self.damping_lin = Some(k); self }
+    /// 离水吸附力系数 (N/m)
+    pub fn tension_coeff(mut self, k: f64) -> Self { self.tension_coeff = Some(k); self }
+    /// 波辐射阻尼系数
+    pub fn wave_damping_coeff(mut self, k: f64) -> Self { self.wave_damping_coeff = Some(k); self }
+    /// 水平附加阻力系数
+    pub fn horizontal_resist_coeff(mut self, k: f64) -> Self { self.horizontal_resist_coeff = Some(k); self }
+    /// 水面高度 (m)
+    pub fn water_level(mut self, level: f64) -> Self { self.water_level = Some(level); self }
+    /// 水面坡度 dy/dx
+    pub fn slope(mut self, slope: f64) -> Self { self.slope = Some(slope); self }
+    /// 水深 (m)，0 为无底 (负值按 0 处理)
+    pub fn water_depth(mut self, depth: f64) -> Self { self.water_depth = Some(depth); self }
+    /// 对岸墙壁的位置 (m) 与处理方式，默认没有墙壁
+    pub fn wall(mut self, x: f64, mode: WallMode) -> Self { self.wall = Some((x, mode)); self }
+    /// 水温 (°C)，同时决定 rho 与 nu
+    pub fn water_temperature(mut self, temp_c: f64) -> Self { self.water_temp_c = Some(temp_c); self }
+    /// 最小法向入水速度 (m/s)
+    pub fn min_skip_speed(mut self, speed: f64) -> Self { self.min_skip_speed = Some(speed); self }
+    /// 入水后水动力的渐变时间 (s)，0 为关闭
+    pub fn force_ramp_time(mut self, t: f64) -> Self { self.force_ramp_time = Some(t); self }
+    /// 入水时俯仰转自转的耦合系数
+    pub fn spin_coupling(mut self, k: f64) -> Self { self.spin_coupling = Some(k); self }
+    /// 静止判定的窗口与阈值
+    pub fn settle(mut self, criteria: SettleCriteria) -> Self { self.settle = Some(criteria); self }
+    /// 弹跳模型
+    pub fn bounce_model(mut self, model: BounceModel) -> Self { self.bounce_model = Some(model); self }
+    /// 冲量模型的法向恢复系数
+    pub fn restitution(mut self, e: f64) -> Self { self.restitution = Some(e); self }
+    /// 冲量模型每次弹跳损失的切向速度比例
+    pub fn tangent_friction(mut self, k: f64) -> Self { self.tangent_friction = Some(k); self }
+    /// 冲量模型恢复系数随弹跳次数的衰减率与曲线
+    pub fn restitution_decay(mut self, decay: f64, curve: DecayCurve) -> Self { self.restitution_decay = Some((decay, curve)); self }
+    /// 飞行段积分步长 (s)
+    pub fn dt_flying(mut self, dt: f64) -> Self { self.dt_flying = Some(dt); self }
+    /// 浸水段积分步长 (s)
+    pub fn dt_bouncing(mut self, dt: f64) -> Self { self.dt_bouncing = Some(dt); self }
+    /// 是否计入静水浮力
+    pub fn buoyancy(mut self, enabled: bool) -> Self { self.buoyancy = Some(enabled); self }
+    pub fn verbose(mut self, enabled: bool) -> Self { self.verbose = Some(enabled); self }
+    /// 是否计入飞行段空气阻力
+    pub fn air_drag(mut self, enabled: bool) -> Self { self.air_drag = Some(enabled); self }
+    /// 空气密度 (kg/m^3)
+    pub fn rho_air(mut self, rho: f64) -> Self { self.rho_air = Some(rho); self }
+    /// 空气阻力系数 Cd_air
+    pub fn air_drag_coeff(mut self, cd: f64) -> Self { self.cd_air = Some(cd); self }
+    /// 随机数源，默认 Rng::new(DEFAULT_SEED)
+    pub fn rng(mut self, rng: Rng) -> Self { self.rng = Some(rng); self }
+
+    /// 以给定石片完成构建
+    pub fn build(self, stone: StoneProperties) -> CustomSettings {
+        let rng = self.rng.unwrap_or_else(|| Rng::new(DEFAULT_SEED));
+        let mut s = CustomSettings::new(self.gravity.unwrap_or(GravityPreset::Earth.value()), stone, rng);
+
+        // 水温先于 rho，显式给出的密度覆盖由水温推导的值
+        if let Some(t) = self.water_temp_c { s.set_water_temperature(t); }
+        if let Some(v) = self.rho { s.rho = v; }
+        if let Some(v) = self.lift { s.Cl = v; }
+        if let Some(v) = self.friction { s.Cf = v; }
+        if let Some(v) = self.beta { s.beta = v; }
+        if let Some(v) = self.gyro_coeff { s.gyro_coeff = v; }
+        if let Some(v) = self.spin_lift_coeff { s.spin_lift_coeff = v.max(0.0); }
+        if let Some(v) = self.added_mass_coeff { s.added_mass_coeff = v; }
+        if let Some(v) = self.damping_quad { s.damping_quad = v.max(0.0); }
+        if let Some(v) = self.damping_lin { s.damping_lin = v.max(0.0); }
+        if let Some(v) = self.tension_coeff { s.tension_coeff = v.max(0.0); }
+        if let Some(v) = self.wave_damping_coeff { s.wave_damping_coeff = v.max(0.0); }
+        if let Some(v) = self.horizontal_resist_coeff { s.horizontal_resist_coeff = v.max(0.0); }
+        if let Some(v) = self.water_level { s.water_level = v; }
+        if let Some(v) = self.slope { s.slope = v; }
+        if let Some(v) = self.water_depth { s.water_depth = v.max(0.0); }
+        if let Some((x, mode)) = self.wall {
+            s.wall_x = Some(x);
+            s.wall_mode = mode;
+        }
+        if let Some(v) = self.min_skip_speed { s.min_skip_speed = v; }
+        if let Some(v) = self.force_ramp_time { s.force_ramp_time = v.max(0.0); }
+        if let Some(v) = self.spin_coupling { s.spin_coupling = v; }
+        if let Some(v) = self.settle { s.settle = v; }
+        if let Some(v) = self.bounce_model { s.bounce_model = v; }
+        if let Some(v) = self.restitution { s.restitution = v; }
+        if let Some(v) = self.tangent_friction { s.tangent_friction = v; }
+        if let Some((decay, curve)) = self.restitution_decay {
+            s.restitution_decay = decay;
+            s.restitution_decay_curve = curve;
+        }
+        if let Some(v) = self.dt_flying { s.dt_flying = v; }
+        if let Some(v) = self.dt_bouncing { s.dt_bouncing = v; }
+        if let Some(v) = self.buoyancy { s.buoyancy = v; }
+        if let Some(v) = self.verbose { s.verbose = v; }
+        if let Some(v) = self.air_drag { s.air_drag = v; }
+        if let Some(v) = self.rho_air { s.rho_air = v; }
+        if let Some(v) = self.cd_air { s.Cd_air = v; }
+        s
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stone_phy::slab_outline;
+
+    #[test]
+    fn water_properties_follow_temperature() {
+        // 密度在 4°C 附近最大
+        assert!(water_density(4.0) > water_density(0.0));
+        assert!(water_density(4.0) > water_density(20.0));
+        assert!((water_density(20.0) - 998.2).abs() < 0.1);
+
+        // 粘度随温度降低，20°C 约 1.0e-6 m^2/s
+        assert!(water_kinematic_viscosity(30.0) < water_kinematic_viscosity(10.0));
+        assert!((water_kinematic_viscosity(20.0) - 1.0e-6).abs() < 0.05e-6);
+    }
+
+    #[test]
+    fn builder_overrides_only_what_is_set() {
+        // 文档示例之外的部分：粘度跟随水温，未设置重力时与 new 相同
+        let settings = CustomSettingsBuilder::new().water_temperature(4.0).build(StoneProperties::default());
+        assert_eq!(settings.nu, water_kinematic_viscosity(4.0));
+
+        let defaults = CustomSettings::new(9.81, StoneProperties::default(), Rng::new(DEFAULT_SEED));
+        assert_eq!(settings.beta, defaults.beta);
+        assert_eq!(settings.gravity, defaults.gravity);
+    }
+
+    #[test]
+    fn reset_with_the_initial_rng_replays_the_same_gusts() {
+        let initial = Rng::new(5);
+        let mut settings = CustomSettings::new(9.81, StoneProperties::default(), initial.clone());
+        settings.gust_strength = 2.0;
+        let gusts: Vec<Vector2D> = (0..5).map(|i| settings.wind_velocity(0.3 * i as f64)).collect();
+        let rng_after = settings.rng.state();
+
+        settings.rng.next_f64();
+        settings.reset_state(initial);
+        assert_eq!((0..5).map(|i| settings.wind_velocity(0.3 * i as f64)).collect::<Vec<_>>(), gusts);
+        assert_eq!(settings.rng.state(), rng_after);
+    }
+
+    #[test]
+    fn bounce_frequency_sets_the_safe_step() {
+        // 6 cm x 2 cm x 1 cm 的石板：k = rho * g * 0.06 * 0.01
+        let outline = slab_outline(0.02);
+        let stone = StoneProperties::from_outline(&outline, 0.01, 2700.0).unwrap();
+        let settings = CustomSettings::new(9.81, stone, Rng::new(1));
+        let k = settings.rho * 9.81 * 0.06 * 0.01;
+        let expected = (k / settings.M).sqrt() / std::f64::consts::TAU;
+        assert!((settings.estimate_bounce_frequency() - expected).abs() < 1e-9 * expected);
+
+        // 默认步长远小于周期；粗到周期量级的步长会被提示
+        assert!(settings.bounce_dt_warning(settings.dt_bouncing).is_none());
+        assert!(settings.bounce_dt_warning(0.5 / expected).is_some());
+    }
+
+    #[test]
+    fn ripples_spread_decay_and_are_capped() {
+        let ripple = Ripple { x0: 1.0, t0: 0.0, amplitude: 0.01 };
+        // 刚产生时在触水点处取满幅，随后波前离开触水点
+        assert!((ripple.height(1.0, 0.0) - 0.01).abs() < 1e-12);
+        let front = 1.0 + RIPPLE_SPEED * 0.5;
+        assert!(ripple.height(front, 0.5).abs() > 10.0 * ripple.height(1.0, 0.5).abs());
+        // 对称传播、逐渐衰减、寿命结束后消失
+        assert!((ripple.height(front, 0.5) - ripple.height(2.0 - front, 0.5)).abs() < 1e-15);
+        assert!(ripple.height(1.0 + RIPPLE_SPEED, 1.0).abs() < ripple.height(front, 0.5).abs());
+        assert_eq!(ripple.height(1.0, RIPPLE_LIFETIME), 0.0);
+
+        let mut settings = CustomSettings::new(9.81, StoneProperties::default(), Rng::new(1));
+        for i in 0..MAX_RIPPLES + 3 {
+            settings.spawn_ripple(i as f64, 0.0, 10.0);
+        }
+        assert_eq!(settings.ripples.len(), MAX_RIPPLES);
+        assert_eq!(settings.ripples[0].x0, 3.0);
+
+        // 未开启反馈时物理水面不受影响
+        settings.sample_ripples(3.0, 0.0);
+        assert_eq!(settings.effective_water_level(), settings.water_level);
+        settings.ripple_feedback = true;
+        settings.sample_ripples(3.0, 0.0);
+        assert!((settings.ripple_offset - settings.ripple_amplitude).abs() < 1e-12);
+    }
+}
@@ -1,8 +1,16 @@
 use crate::physics::parameters::CustomSettings;
-use crate::physics::parameters::Phase; 
+use crate::physics::parameters::{BounceModel, GravityPreset, Phase, SettleCriteria, WallMode};
 
-use crate::basic_structs::Vector2D; 
+use crate::basic_structs::{signed_polygon_area, Vector2D};
 use crate::solver2::RungeKuttaSolver; 
+use crate::stone_editor::StoneBlueprint;
+use crate::stone_phy::{StoneError, StoneProperties};
+use crate::rng::Rng;
+use crate::physics::derivative::{pressure_center, HydroForces};
+use std::collections::VecDeque;
+use std::f64::consts::PI;
+use std::rc::Rc;
+use std::time::Instant;
 
 
 //颜子涵负责
@@ -15,6 +23,12 @@ use crate::solver2::RungeKuttaSolver;
 // [3]:角速度
 //Vec<StoneInfo, t>
 
+/// 冲量弹跳后沿水面法向的最小反弹速度 (m/s)，低于该值视为沉没 (与运动方向无关)
+const MIN_REBOUND_SPEED: f64 = 0.05;
+
+/// 事件定位 (如到达对岸墙壁) 的二分次数：步长缩小 2^-40 倍，远小于积分误差
+const EVENT_BISECTIONS: usize = 40;
+
 #[derive(Clone)]
 pub struct StoneInfo {
     pub position: Vector2D,
@@ -24,47 +38,895 @@ pub struct StoneInfo {
     
 }
 
+impl StoneInfo {
+    /// 所有分量是否有限 (非 NaN/Inf)
+    pub fn is_finite(&self) -> bool {
+        [self.position, self.velocity, self.angle, self.angle_velocity]
+            .iter()
+            .all(|v| v.x.is_finite() && v.y.is_finite())
+    }
+}
+
+#[derive(Clone)]
 pub struct Stamp {
     pub t: f64, 
     pub state: StoneInfo, 
+    pub energy: f64, // 该时刻的总机械能 (J)，用于检查积分器漂移
+    pub phase: Phase,
+    pub wetted_perimeter: f64, // 湿周 (m)，未触水时为 0
+    pub froude: f64,           // 以湿长度计的弗劳德数 (见 froude_number)，未触水时为 0
+    pub peak_g: f64,           // 到该时刻为止的峰值线加速度 (g，见 CustomSettings::peak_g)
+    pub forces: Option<HydroForces>, // Bouncing 时集中力模型的各分量，用于信息面板
+    pub submerged: Vec<Vec<Vector2D>>, // 上一步水动力实际作用的浸没多边形 (世界坐标)，用于绘制
+}
+
+impl Stamp {
+    /// 记录求解器当前的状态
+    pub fn capture(system: &CustomSettings, solver: &RungeKuttaSolver<StoneInfo>) -> Self {
+        Self {
+            t: solver.t,
+            state: solver.state.clone(),
+            energy: system.total_energy(&solver.state),
+            phase: system.phase,
+            wetted_perimeter: if system.phase == Phase::Bouncing { system.wetted_perimeter } else { 0.0 },
+            froude: if system.phase == Phase::Bouncing { system.froude_number(&solver.state) } else { 0.0 },
+            peak_g: system.peak_g(),
+            forces: if system.phase == Phase::Bouncing { system.hydro_breakdown(&solver.state) } else { None },
+            submerged: system.force_regions(),
+        }
+    }
+
+    /// 去掉只用于显示的受力分解与浸没多边形 (长期保存的完整记录使用)
+    pub fn without_display_data(&self) -> Self {
+        Self {
+            forces: None,
+            submerged: Vec::new(),
+            state: self.state.clone(),
+            ..*self
+        }
+    }
+}
+
+/// 一次模拟的统计结果 (计分与批量测试共用)
+#[derive(Clone, Debug)]
+pub struct SimSummary {
+    pub skips: u32,        // 水漂次数 (离开水面的次数)
+    pub air_time: f64,     // 首次触水后的滞空时间 (s)，即得分
+    pub streak_score: f64, // 连击计分：每段滞空时间乘以当时的连击倍率 (见 ScoreMode::Streak)
+    pub distance: f64,     // 水平方向跨度 (m)
+    pub max_height: f64,   // 质心最大高度 (m)
+    pub final_phase: Phase,
+    pub reached_wall: bool,        // 是否到达了对岸 (WallMode::Stop 时模拟随之结束)
+    pub impact_angle: Option<f64>, // 首次触水时速度与水面的夹角 (deg)，未触水时为 None
+    pub peak_g: f64,               // 浸水阶段的峰值线加速度 (g)，超过 STIFF_ENTRY_G 说明步长太大
+    pub pitch_acc_clamped: bool,   // 俯仰角加速度是否触及截断 (MAX_PITCH_ACC)
+    pub end_reason: SimEndReason,  // 模拟为何停止 (由 simulate 填写)
+    pub events: Vec<PhaseEvent>,   // 相位切换记录 (按时间顺序，由 advance_tracked_with 填写)
+    pub dt_warning: Option<String>, // 浸水步长相对弹跳周期过大时的提示 (由 run_headless_with 填写，由调用方决定是否打印)
+
+    // 性能统计 (由 simulate 填写)
+    pub derivative_calls: u64, // 导数求值次数
+    pub wall_time: f64,        // 墙钟时间 (s)
+
+    has_touched_water: bool,
+    min_x: f64,
+    max_x: f64,
+}
+
+impl SimSummary {
+    pub fn new(initial: &StoneInfo) -> Self {
+        Self {
+            skips: 0,
+            air_time: 0.0,
+            streak_score: 0.0,
+            distance: 0.0,
+            max_height: initial.position.y,
+            final_phase: Phase::Flying,
+            reached_wall: false,
+            impact_angle: None,
+            peak_g: 0.0,
+            pitch_acc_clamped: false,
+            end_reason: SimEndReason::MaxStepsReached,
+            events: Vec::new(),
+            dt_warning: None,
+            derivative_calls: 0,
+            wall_time: 0.0,
+            has_touched_water: false,
+            min_x: initial.position.x,
+            max_x: initial.position.x,
+        }
+    }
+
+    /// 当前一段滞空的连击倍率：第 2 跳之后 x1.1，第 3 跳之后 x1.2 ...；沉没后归 1
+    pub fn streak_multiplier(&self) -> f64 {
+        if self.final_phase == Phase::Sinking {
+            return 1.0;
+        }
+        1.0 + STREAK_STEP * self.skips.saturating_sub(1) as f64
+    }
+
+    /// 每秒导数求值次数 (墙钟时间为 0 时返回 0)
+    pub fn calls_per_second(&self) -> f64 {
+        if self.wall_time > 0.0 { self.derivative_calls as f64 / self.wall_time } else { 0.0 }
+    }
+
+    /// 每次 advance 之后调用
+    /// `phase_before` / `velocity_before`: advance 之前的相位与速度
+    pub fn record(&mut self, phase_before: Phase, velocity_before: Vector2D, system: &CustomSettings, state: &StoneInfo, dt: f64) {
+        let phase = system.phase;
+        self.final_phase = phase;
+        self.reached_wall = system.reached_wall;
+        self.peak_g = system.peak_g();
+        self.pitch_acc_clamped = system.pitch_acc_clamped.get();
+
+        // 冲量模型在 advance 内部完成反弹，看不到 Bouncing；
+        // Flying 阶段只受重力，竖直速度由负变正只能来自一次反弹
+        let impulse_bounce = phase_before == Phase::Flying && phase == Phase::Flying
+            && velocity_before.y < 0.0 && state.velocity.y >= 0.0;
+
+        // 0. 首次触水 (包括入水太慢直接沉没)：记录入水前的速度与水面的夹角
+        if self.impact_angle.is_none() && phase_before == Phase::Flying && (phase != Phase::Flying || impulse_bounce) {
+            self.impact_angle = Some(incidence_angle(velocity_before, system.slope));
+        }
+        if phase == Phase::Sinking { return; }
+
+        // 1. 水漂: Bouncing -> Flying 的相位切换
+        let left_water = phase_before == Phase::Bouncing && phase == Phase::Flying;
+        if phase == Phase::Bouncing || impulse_bounce {
+            self.has_touched_water = true;
+        }
+        if left_water || impulse_bounce {
+            self.skips += 1;
+        }
+
+        // 2. 首次触水后的滞空时间
+        if self.has_touched_water && phase == Phase::Flying {
+            self.air_time += dt;
+            self.streak_score += dt * self.streak_multiplier();
+        }
+
+        // 3. 水平跨度与最大高度
+        self.min_x = self.min_x.min(state.position.x);
+        self.max_x = self.max_x.max(state.position.x);
+        self.distance = self.max_x - self.min_x;
+        self.max_height = self.max_height.max(state.position.y);
+    }
+}
+
+/// 连击计分中每多一次连续水漂，倍率的增加量
+pub const STREAK_STEP: f64 = 0.1;
+
+/// 计分方式，在初始条件界面选择
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ScoreMode {
+    /// 首次触水后的总滞空时间
+    #[default]
+    AirTime,
+    /// 连续水漂累积倍率，每段滞空时间乘以当时的倍率
+    Streak,
+}
+
+impl ScoreMode {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ScoreMode::AirTime => "Air time",
+            ScoreMode::Streak => "Streak",
+        }
+    }
+
+    pub fn next(&self) -> Self {
+        match self {
+            ScoreMode::AirTime => ScoreMode::Streak,
+            ScoreMode::Streak => ScoreMode::AirTime,
+        }
+    }
+
+    /// 该计分方式下的得分
+    pub fn score(&self, summary: &SimSummary) -> f64 {
+        match self {
+            ScoreMode::AirTime => summary.air_time,
+            ScoreMode::Streak => summary.streak_score,
+        }
+    }
+}
+
+/// 经验上水漂的最佳入水角 (deg)
+pub const OPTIMAL_IMPACT_ANGLE: f64 = 20.0;
+
+/// 速度方向与水面的夹角 (deg)，向下入水为正；`slope` 为水面坡度 dy/dx
+pub fn incidence_angle(velocity: Vector2D, slope: f64) -> f64 {
+    let norm = (1.0 + slope * slope).sqrt();
+    let along = (velocity.x + slope * velocity.y) / norm;   // 沿水面分量
+    let into = (slope * velocity.x - velocity.y) / norm;    // 指向水下的法向分量
+    into.atan2(along.abs()).to_degrees()
+}
+
+/// 一次水漂之后的飞行弧段
+#[derive(Clone, Debug, PartialEq)]
+pub struct SkipArc {
+    pub apex: f64,      // 弧段最高点 (质心高度, m)
+    pub distance: f64,  // 起跳到再次触水的水平距离 (m)
+    pub complete: bool, // false: 最后一段，落水后沉没或模拟在空中结束
+    pub froude: f64,    // 起跳前那次触水中的最大弗劳德数 (冲量模型没有触水帧，为 0)
+
+    // 机械能 (J，见 CustomSettings::total_energy)
+    pub energy_start: f64, // 起跳时
+    pub energy_end: f64,   // 再次触水前的最后一帧 (飞行段只受重力，应与起跳时基本相同)
+    pub energy_loss: f64,  // 这一跳之前的触水过程耗散的能量；为负说明水动力在做正功 (符号错误)
 }
 
+/// 每跳统计的 CSV 文件 (相对于工作目录)，每局结束时写入
+pub const SKIPS_FILE: &str = "skips.csv";
+
+// 相邻两帧之间是否发生了一次水漂 (规则与 SimSummary::record 相同)
+fn is_skip_event(prev: &Stamp, cur: &Stamp) -> bool {
+    let left_water = prev.phase == Phase::Bouncing && cur.phase == Phase::Flying;
+    let impulse_bounce = prev.phase == Phase::Flying && cur.phase == Phase::Flying
+        && prev.state.velocity.y < 0.0 && cur.state.velocity.y >= 0.0;
+    left_water || impulse_bounce
+}
+
+/// 按 Flying <-> Bouncing 切换把轨迹切成水漂弧段
+/// 首次触水前的抛出段不算；每次水漂开始一段，到再次触水 (或下一次冲量反弹) 结束
+pub fn segment_skips(trajectory: &[Stamp]) -> Vec<SkipArc> {
+    let spans = skip_spans(trajectory);
+    let starts: Vec<usize> = spans.iter().map(|&(start, _)| start).collect();
+
+    spans.iter().enumerate().map(|(k, &(start, end))| {
+        let apex = trajectory[start..=end].iter()
+            .map(|stamp| stamp.state.position.y)
+            .fold(f64::MIN, f64::max);
+
+        // 触水前的最后一个飞行帧：起跳前向回找 (冲量模型没有 Bouncing 帧，就是上一帧)
+        let before_contact = (0..start).rev()
+            .find(|&j| trajectory[j].phase == Phase::Flying)
+            .unwrap_or(0);
+        // 本段最后一个飞行帧 (下一次冲量反弹的那一帧已在反弹之后，不属于本段)
+        let last_flying = (start..=end).rev()
+            .find(|&j| trajectory[j].phase == Phase::Flying && starts.get(k + 1) != Some(&j))
+            .unwrap_or(start);
+
+        // 起跳前那次触水的各帧
+        let contact_start = (0..start).rev()
+            .find(|&j| trajectory[j].phase != Phase::Bouncing)
+            .map_or(0, |j| j + 1);
+        let froude = trajectory[contact_start..start].iter()
+            .map(|stamp| stamp.froude)
+            .fold(0.0, f64::max);
+
+        let energy_start = trajectory[start].energy;
+        SkipArc {
+            apex,
+            distance: trajectory[end].state.position.x - trajectory[start].state.position.x,
+            complete: k + 1 < starts.len(),
+            froude,
+            energy_start,
+            energy_end: trajectory[last_flying].energy,
+            energy_loss: trajectory[before_contact].energy - energy_start,
+        }
+    }).collect()
+}
+
+// 每段水漂弧的 (起跳帧, 落点帧) 下标
+// 落点：起跳之后第一个不在飞行的帧，或下一次冲量反弹
+fn skip_spans(trajectory: &[Stamp]) -> Vec<(usize, usize)> {
+    let starts: Vec<usize> = (1..trajectory.len())
+        .filter(|&i| is_skip_event(&trajectory[i - 1], &trajectory[i]))
+        .collect();
+
+    starts.iter().enumerate().map(|(k, &start)| {
+        let end = (start + 1..trajectory.len())
+            .find(|&j| trajectory[j].phase != Phase::Flying || starts.get(k + 1) == Some(&j))
+            .unwrap_or(trajectory.len() - 1);
+        (start, end)
+    }).collect()
+}
+
+/// 最后一次入水 (Flying -> Bouncing) 的回看区间 (帧下标，含两端)：
+/// 从入水前 `lead_time` 秒开始，到开始沉没 (或轨迹结束) 为止；从未入水时返回 None
+pub fn last_bounce_window(trajectory: &[Stamp], lead_time: f64) -> Option<(usize, usize)> {
+    let entry = (1..trajectory.len())
+        .rev()
+        .find(|&i| trajectory[i - 1].phase == Phase::Flying && trajectory[i].phase == Phase::Bouncing)?;
+    let start_t = trajectory[entry].t - lead_time;
+    let start = (0..entry).rev()
+        .take_while(|&i| trajectory[i].t >= start_t)
+        .last()
+        .unwrap_or(entry);
+    let end = (entry..trajectory.len())
+        .find(|&i| trajectory[i].phase == Phase::Sinking)
+        .unwrap_or(trajectory.len() - 1);
+    Some((start, end))
+}
+
+/// 渲染器在轨迹上标注的关键点 (世界坐标)
+#[derive(Clone, Debug, PartialEq)]
+pub struct TrajectoryMarkers {
+    pub apexes: Vec<Vector2D>, // 每段水漂弧的最高点 (与 segment_skips 的弧段一一对应)
+    pub sink: Option<Vector2D>, // 开始沉没的位置；尚未沉没时为 None
+    pub range: f64,             // 沉没点 (或最后一帧) 与出手点的水平距离 (m)
+}
+
+/// 扫描轨迹，找出各弧段最高点与沉没位置
+pub fn trajectory_markers(trajectory: &[Stamp]) -> TrajectoryMarkers {
+    let apexes = skip_spans(trajectory).into_iter()
+        .filter_map(|(start, end)| {
+            trajectory[start..=end].iter()
+                .map(|stamp| stamp.state.position)
+                .max_by(|a, b| a.y.total_cmp(&b.y))
+        })
+        .collect();
+    let sink = trajectory.iter().find(|stamp| stamp.phase == Phase::Sinking).map(|stamp| stamp.state.position);
+    let range = match (trajectory.first(), sink.or(trajectory.last().map(|stamp| stamp.state.position))) {
+        (Some(first), Some(end)) => end.x - first.state.position.x,
+        _ => 0.0,
+    };
+    TrajectoryMarkers { apexes, sink, range }
+}
+
+/// 每跳统计的 CSV 文本：skip, apex, distance, energy_start, energy_end, energy_loss, froude, complete
+pub fn skips_to_csv(arcs: &[SkipArc]) -> String {
+    let mut csv = "skip, apex, distance, energy_start, energy_end, energy_loss, froude, complete\n".to_string();
+    for (i, arc) in arcs.iter().enumerate() {
+        csv += &format!("{}, {}, {}, {}, {}, {}, {}, {}\n",
+                        i + 1, arc.apex, arc.distance, arc.energy_start, arc.energy_end, arc.energy_loss, arc.froude, arc.complete);
+    }
+    csv
+}
+
+// 轨迹在 t 时刻的质心位置，相邻两帧之间线性插值 (t 须在轨迹的时间范围内)
+fn position_at(trajectory: &[Stamp], t: f64) -> Vector2D {
+    let i = trajectory.partition_point(|s| s.t < t);
+    if i == 0 {
+        return trajectory[0].state.position;
+    }
+    if i == trajectory.len() {
+        return trajectory[i - 1].state.position;
+    }
+    let (a, b) = (&trajectory[i - 1], &trajectory[i]);
+    let span = b.t - a.t;
+    if span <= 0.0 {
+        return b.state.position;
+    }
+    let w = (t - a.t) / span;
+    a.state.position * (1.0 - w) + b.state.position * w
+}
+
+/// 两条轨迹按时间对齐后的均方根位置差 (m)
+/// 只在两者时间重叠的区间内比较：取两条轨迹在该区间内的全部时刻，各自线性插值后
+/// 对位置差的平方做梯形积分再除以区间长度；两者记录步长不同也可比较
+/// 没有重叠时返回 NaN
+pub fn trajectory_distance(a: &[Stamp], b: &[Stamp]) -> f64 {
+    let (Some(a0), Some(a1), Some(b0), Some(b1)) = (a.first(), a.last(), b.first(), b.last()) else {
+        return f64::NAN;
+    };
+    let (start, end) = (a0.t.max(b0.t), a1.t.min(b1.t));
+    if start > end {
+        return f64::NAN;
+    }
+
+    // 1. 重叠区间内的公共时刻 (含端点)
+    let mut times: Vec<f64> = a.iter().chain(b).map(|s| s.t).filter(|&t| t > start && t < end).collect();
+    times.push(start);
+    times.push(end);
+    times.sort_by(f64::total_cmp);
+    times.dedup();
+
+    // 2. 各时刻的位置差平方
+    let sq: Vec<f64> = times.iter()
+        .map(|&t| (position_at(a, t) - position_at(b, t)).length_squared())
+        .collect();
+    if times.len() == 1 {
+        return sq[0].sqrt();
+    }
+
+    // 3. 梯形积分并按区间长度平均
+    let integral: f64 = (1..times.len())
+        .map(|i| 0.5 * (sq[i - 1] + sq[i]) * (times[i] - times[i - 1]))
+        .sum();
+    (integral / (end - start)).sqrt()
+}
+
+/// 默认记录间隔：每个积分步都记录
+pub const DEFAULT_SAMPLE_STRIDE: usize = 1;
+
+/// 无窗口运行时进度回调的调用间隔 (积分步数)
+pub const PROGRESS_INTERVAL: usize = 1000;
+
+/// 平衡漂浮姿态：扫描一整圈俯仰角的采样数与每次二分的迭代次数
+const EQUILIBRIUM_SCAN: usize = 72;
+const EQUILIBRIUM_BISECTIONS: usize = 50;
+
+/// 峰值线加速度超过该值 (g) 时，认为入水瞬态对当前步长过于刚性
+pub const STIFF_ENTRY_G: f64 = 100.0;
+/// 离出发点超过该距离 (m) 时停止模拟 (SimEndReason::LeftBounds)
+pub const SIM_BOUNDS: f64 = 1000.0;
+/// 速度低于 STUCK_SPEED (m/s) 持续 STUCK_TIME (s) 视为停住 (SimEndReason::Stuck)
+pub const STUCK_SPEED: f64 = 0.001;
+pub const STUCK_TIME: f64 = 1.0;
+
+/// 固定步长累加器：把真实经过的时间 (乘以时间倍率) 折算成整数个固定的模拟时间块，
+/// 模拟速度与帧率无关，每块的积分步长仍然固定 (结果可复现)
+/// 每帧最多 max_steps 块，超出的积压直接丢弃，避免慢机器上越积越多
+pub struct StepAccumulator {
+    pub step: f64,       // 每块的模拟时间 (s)
+    pub time_scale: f64, // 每秒真实时间对应的模拟时间 (s)
+    pub max_steps: usize,
+    accumulated: f64,    // 尚未推进的模拟时间 (s)
+}
+
+impl StepAccumulator {
+    pub fn new(step: f64, time_scale: f64, max_steps: usize) -> Self {
+        Self { step, time_scale, max_steps, accumulated: 0.0 }
+    }
+
+    /// 经过 `elapsed` 秒真实时间后本帧应推进的块数
+    pub fn steps(&mut self, elapsed: f64) -> usize {
+        self.accumulated += elapsed.max(0.0) * self.time_scale;
+        let n = (self.accumulated / self.step + 1e-9).floor() as usize;
+        if n > self.max_steps {
+            self.accumulated = 0.0;
+            return self.max_steps;
+        }
+        self.accumulated = (self.accumulated - n as f64 * self.step).max(0.0);
+        n
+    }
+
+    pub fn reset(&mut self) {
+        self.accumulated = 0.0;
+    }
+}
+
+/// 一次相位切换：切换时刻与当时的状态
+/// 相位在每步开始时判定，记录的是判定所用的步首状态 (精度为一个积分步长)；
+/// 冲量模型在同一步内完成入水与反弹，记为同一时刻的 Flying -> Bouncing 与 Bouncing -> Flying
+#[derive(Clone, Debug, PartialEq)]
+pub struct PhaseEvent {
+    pub t: f64,
+    pub from: Phase,
+    pub to: Phase,
+    pub position: Vector2D,
+    pub velocity: Vector2D,
+}
+
+/// 无窗口模拟停止的原因
+/// 批量工具据此区分正常结束与未终止的参数组合 (MaxStepsReached 的结果不可信)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SimEndReason {
+    /// 石片沉没
+    Sank,
+    /// 到达对岸 (WallMode::Stop)
+    ReachedWall,
+    /// 沉到池底 (见 CustomSettings::water_depth)
+    HitBottom,
+    /// 离出发点超过 SIM_BOUNDS (参数错误时常见，例如重力为 0)
+    LeftBounds,
+    /// 连续 STUCK_TIME 秒几乎不动 (例如一直漂浮)
+    Stuck,
+    /// 在原地小幅晃动，满足静止判定 (见 SettleCriteria)
+    Settled,
+    /// 积分发散 (见 RungeKuttaSolver::error)
+    Diverged,
+    /// 用完了最大步数仍未结束
+    MaxStepsReached,
+}
+
+impl SimEndReason {
+    pub fn name(&self) -> &'static str {
+        match self {
+            SimEndReason::Sank => "sank",
+            SimEndReason::ReachedWall => "reached_wall",
+            SimEndReason::HitBottom => "hit_bottom",
+            SimEndReason::LeftBounds => "left_bounds",
+            SimEndReason::Stuck => "stuck",
+            SimEndReason::Settled => "settled",
+            SimEndReason::Diverged => "diverged",
+            SimEndReason::MaxStepsReached => "max_steps",
+        }
+    }
+
+    /// 模拟是否自然结束 (沉没、触底、到达对岸或停稳)，其余原因说明结果不完整
+    pub fn is_finished(&self) -> bool {
+        matches!(self, SimEndReason::Sank | SimEndReason::HitBottom | SimEndReason::ReachedWall | SimEndReason::Settled)
+    }
+}
+
+/// 静止判定的滚动窗口：保存最近 window 秒的 (时刻, 位置, 速度是否超限)，
+/// 位置方差用累加和增量维护，每步 O(1)
+pub struct SettleTracker {
+    criteria: SettleCriteria,
+    samples: VecDeque<(f64, Vector2D, bool)>,
+    origin: Option<Vector2D>, // 累加前减去的参考点，减小远离原点时的舍入误差
+    sum: Vector2D,
+    sum_sq: f64,
+    fast: usize, // 窗口内速度超限的样本数
+}
+
+impl SettleTracker {
+    pub fn new(criteria: SettleCriteria) -> Self {
+        Self { criteria, samples: VecDeque::new(), origin: None, sum: Vector2D::new(0.0, 0.0), sum_sq: 0.0, fast: 0 }
+    }
+
+    /// 记录一个样本；窗口已满且满足静止判定时返回 true
+    pub fn push(&mut self, t: f64, state: &StoneInfo) -> bool {
+        if self.criteria.window <= 0.0 {
+            return false;
+        }
+        let p = state.position - *self.origin.get_or_insert(state.position);
+        let fast = state.velocity.length() > self.criteria.max_speed;
+        self.sum = self.sum + p;
+        self.sum_sq += p.length_squared();
+        self.fast += fast as usize;
+        self.samples.push_back((t, p, fast));
+
+        // 窗口只保留最近 window 秒 (保留恰好覆盖整个窗口的最早样本)
+        while self.samples.len() > 1 && t - self.samples[1].0 >= self.criteria.window {
+            let (_, old, old_fast) = self.samples.pop_front().unwrap();
+            self.sum = self.sum - old;
+            self.sum_sq -= old.length_squared();
+            self.fast -= old_fast as usize;
+        }
+        if t - self.samples[0].0 < self.criteria.window || self.fast > 0 {
+            return false;
+        }
+        self.variance() <= self.criteria.max_variance
+    }
+
+    /// 窗口内位置的方差 (m^2，x 与 y 之和)
+    pub fn variance(&self) -> f64 {
+        let n = self.samples.len() as f64;
+        if n == 0.0 {
+            return 0.0;
+        }
+        let mean = self.sum * (1.0 / n);
+        (self.sum_sq / n - mean.length_squared()).max(0.0)
+    }
+}
+
+/// 进度回调：参数为 (已完成的步数, 当前状态)
+pub type ProgressCallback<'a> = &'a mut dyn FnMut(usize, &StoneInfo);
+
+/// 以固定步长模拟直到沉没或达到最大步数
+/// `sample_stride`: 每 N 个积分步记录一帧 (物理与计分仍按每步进行)
 pub fn simulate(system: &mut CustomSettings,
                 solver: &mut RungeKuttaSolver<StoneInfo>,
                 dt: f64,
-                max_steps: usize) -> Vec<Stamp> 
+                max_steps: usize,
+                sample_stride: usize) -> (Vec<Stamp>, SimSummary)
+{
+    simulate_with(system, solver, |_| dt, max_steps, sample_stride, None)
+}
+
+/// 与 simulate 相同，但每步使用当前相位的推荐步长 (CustomSettings::recommended_dt)
+/// 步长随相位变化，记录的帧在时间上不再等间隔，按 Stamp::t 使用
+pub fn simulate_adaptive(system: &mut CustomSettings,
+                         solver: &mut RungeKuttaSolver<StoneInfo>,
+                         max_steps: usize,
+                         sample_stride: usize) -> (Vec<Stamp>, SimSummary)
 {
+    simulate_with(system, solver, CustomSettings::recommended_dt, max_steps, sample_stride, None)
+}
+
+// `on_step`: 每 PROGRESS_INTERVAL 步以 (步数, 当前状态) 调用一次，用于命令行进度输出；None 时不做任何事
+fn simulate_with(system: &mut CustomSettings,
+                 solver: &mut RungeKuttaSolver<StoneInfo>,
+                 step_dt: impl Fn(&CustomSettings) -> f64,
+                 max_steps: usize,
+                 sample_stride: usize,
+                 mut on_step: Option<ProgressCallback>) -> (Vec<Stamp>, SimSummary)
+{
+    let sample_stride = sample_stride.max(1);
     let mut trajectory = Vec::new();
+    let mut summary = SimSummary::new(&solver.state);
+    let calls_before = solver.derivative_calls;
+    let halved_before = solver.halved_steps;
+    let started = Instant::now();
+    let origin = solver.state.position;
+    let mut still_since: Option<f64> = None;
+    let mut settle = SettleTracker::new(system.settle);
 
-    for _ in 0..max_steps {
+    for step in 0..max_steps {
 
-        // (1) 记录当前状态
-        trajectory.push(
-            Stamp{
-                state: solver.state.clone(), 
-                t: solver.t, 
-            }
-        );
+        // (1) 按记录间隔保存当前状态
+        if step.is_multiple_of(sample_stride) {
+            trajectory.push(Stamp::capture(system, solver));
+        }
+        if let Some(callback) = on_step.as_mut() && step.is_multiple_of(PROGRESS_INTERVAL) {
+            callback(step, &solver.state);
+        }
 
-        // (2) 更新浸水面积（必须在 phase 判断前）
-        if system.phase == Phase::Bouncing {
-            
-            system.update_submerged_area(&solver.state);
+        // (2)~(4) 更新状态并积分；若已沉没，停止积分
+        if advance_tracked_with(system, solver, &step_dt, &mut summary).is_none() {
+            summary.end_reason = match &solver.error {
+                Some(e) => {
+                    system.log_event(format_args!("Simulation aborted at t={}: {}", solver.t, e));
+                    SimEndReason::Diverged
+                }
+                None if system.reached_wall => {
+                    system.log_event(format_args!("Reached the other side, simulation finished at t={}", solver.t));
+                    SimEndReason::ReachedWall
+                }
+                None if system.hit_bottom => {
+                    system.log_event(format_args!("Resting on the bottom, simulation finished at t={}", solver.t));
+                    SimEndReason::HitBottom
+                }
+                None => {
+                    system.log_event(format_args!("Phase=Sinking, simulation finished at t={}", solver.t));
+                    SimEndReason::Sank
+                }
+            };
+            break;
         }
-        // (3) 更新 phase
-        system.update_phase(&solver.state);
 
-        // 若已沉没，停止积分
-        if system.phase == Phase::Sinking {
-            println!("Phase=Sinking, simulation finished at t={}", solver.t);
+        // (5) 跑出范围或长时间不动：不会自然结束，提前停止
+        if (solver.state.position - origin).length() > SIM_BOUNDS {
+            system.log_event(format_args!("Left the simulation bounds ({} m) at t={}", SIM_BOUNDS, solver.t));
+            summary.end_reason = SimEndReason::LeftBounds;
+            break;
+        }
+        // 停稳判定在前；浮在水面上一动不动的石片也是停稳 (不必等满滚动窗口)，
+        // 只有不在水中却停住 (例如重力为 0 时悬在空中) 才算卡住
+        if settle.push(solver.t, &solver.state) {
+            system.log_event(format_args!("Stone settled, simulation finished at t={}", solver.t));
+            summary.end_reason = SimEndReason::Settled;
             break;
         }
+        if solver.state.velocity.length() < STUCK_SPEED {
+            let since = *still_since.get_or_insert(solver.t);
+            if solver.t - since >= STUCK_TIME {
+                summary.end_reason = if system.current_submerged_regions.is_empty() {
+                    system.log_event(format_args!("Stone stopped moving, simulation finished at t={}", solver.t));
+                    SimEndReason::Stuck
+                } else {
+                    system.log_event(format_args!("Stone floating still, simulation finished at t={}", solver.t));
+                    SimEndReason::Settled
+                };
+                break;
+            }
+        } else {
+            still_since = None;
+        }
+    }
+    if summary.end_reason == SimEndReason::MaxStepsReached {
+        system.log_event(format_args!("Reached the step limit ({}) at t={} without finishing", max_steps, solver.t));
+    }
+
+    if solver.halved_steps > halved_before {
+        system.log_event(format_args!("Retried {} steps with dt/2 after non-finite states", solver.halved_steps - halved_before));
+    }
+    summary.derivative_calls = solver.derivative_calls - calls_before;
+    summary.wall_time = started.elapsed().as_secs_f64();
+    system.log_event(format_args!("Derivative calls: {} ({:.0} calls/s, {:.3} s)",
+                                  summary.derivative_calls, summary.calls_per_second(), summary.wall_time));
+    system.log_event(format_args!("Peak acceleration: {:.1} g{}{}", summary.peak_g,
+                                  if summary.peak_g > STIFF_ENTRY_G { " (entry transient too stiff for dt)" } else { "" },
+                                  if summary.pitch_acc_clamped { ", pitch acceleration clamped" } else { "" }));
+
+    (trajectory, summary)
+}
+
+/// 推进一个时间步，并把结果计入 `summary`
+pub fn advance_tracked(system: &mut CustomSettings,
+                       solver: &mut RungeKuttaSolver<StoneInfo>,
+                       dt: f64,
+                       summary: &mut SimSummary) -> bool
+{
+    advance_tracked_with(system, solver, |_| dt, summary).is_some()
+}
+
+/// 同 advance_with，并按实际使用的步长计分
+pub fn advance_tracked_with(system: &mut CustomSettings,
+                            solver: &mut RungeKuttaSolver<StoneInfo>,
+                            step_dt: impl Fn(&CustomSettings) -> f64,
+                            summary: &mut SimSummary) -> Option<f64>
+{
+    let phase_before = system.phase;
+    let (t_before, state_before) = (solver.t, solver.state.clone());
+    let skips_before = summary.skips;
+    let used_dt = advance_with(system, solver, step_dt);
+    summary.record(phase_before, state_before.velocity, system, &solver.state, used_dt.unwrap_or(0.0));
+
+    // 相位切换事件；冲量反弹前后都是 Flying，由水漂计数的变化识别
+    let event = |from, to| PhaseEvent { t: t_before, from, to, position: state_before.position, velocity: state_before.velocity };
+    if system.phase != phase_before {
+        summary.events.push(event(phase_before, system.phase));
+    } else if phase_before == Phase::Flying && summary.skips > skips_before {
+        summary.events.push(event(Phase::Flying, Phase::Bouncing));
+        summary.events.push(event(Phase::Bouncing, Phase::Flying));
+    }
+    used_dt
+}
+
+/// 以固定步长推进一个时间步
+/// 返回 false 表示石头已沉没 (此时不再积分)
+pub fn advance(system: &mut CustomSettings,
+               solver: &mut RungeKuttaSolver<StoneInfo>,
+               dt: f64) -> bool
+{
+    advance_with(system, solver, |_| dt).is_some()
+}
+
+/// 推进一个时间步，步长在相位更新之后由 `step_dt` 决定 (入水的这一步即使用浸水步长)
+/// 返回实际使用的步长；None 表示石头已沉没 (有池底时为已触底)、已到达对岸或积分失败 (此时时间不再前进)
+pub fn advance_with(system: &mut CustomSettings,
+                    solver: &mut RungeKuttaSolver<StoneInfo>,
+                    step_dt: impl Fn(&CustomSettings) -> f64) -> Option<f64>
+{
+    if system.reached_wall && system.wall_mode == WallMode::Stop {
+        return None;
+    }
+
+    // (1) 清理过期涟漪，开启反馈时采样石片所在处的水面起伏
+    // 同时清空上一步的受力多边形，本步浸水时由导数求值重新写入
+    system.sample_ripples(solver.state.position.x, solver.t);
+    system.force_regions.borrow_mut().clear();
+
+    // (2) 更新浸水面积（必须在 phase 判断前）
+    if system.phase == Phase::Bouncing {
+        system.update_submerged_area(&solver.state);
+    }
+    // (3) 更新 phase
+    let phase_before = system.phase;
+    system.update_phase(&solver.state);
+
+    // 入水瞬间：按接触几何把部分俯仰角速度转为自转
+    // 同时在触水点激起涟漪
+    if phase_before == Phase::Flying && system.phase == Phase::Bouncing {
+        system.bouncing_entry_t = Some(solver.t);
+        system.apply_spin_coupling(&mut solver.state);
+        let contact_x = pressure_center(&system.current_submerged_regions).x;
+        let normal_speed = system.impact_normal_speed(&solver.state);
+        system.spawn_ripple(contact_x, solver.t, normal_speed);
+    }
+
+    // 冲量模型：触水瞬间直接反弹，不进入连续水动力阶段
+    // 反弹后离开水面的法向速度太小时沉没；只看法向，向 -x 运动 (如被墙壁弹回) 的石片同样可以弹跳
+    if system.bounce_model == BounceModel::Impulse && system.phase == Phase::Bouncing {
+        system.apply_bounce_impulse(&mut solver.state);
+        system.phase = if -system.impact_normal_speed(&solver.state) < MIN_REBOUND_SPEED {
+            Phase::Sinking
+        } else {
+            Phase::Flying
+        };
+    }
+
+    // 沉没后只有设置了池底时才继续下沉，触底后停止
+    if system.sinking_done() {
+        return None;
+    }
+
+    // (4) 使用 RK4 进行一步积分
+    // 减半步长后仍发散时求解器停在发散前的状态，结束本次运行
+    let mut dt = step_dt(system);
+    let (t0, y0) = (solver.t, solver.state.clone());
+    solver.step(system, dt);
+    if solver.error.is_some() {
+        return None;
+    }
+
+    // (5) 池底：本步沉到池底时定位触底时刻，石片静止在池底
+    if system.phase == Phase::Sinking && system.bottom_gap(&solver.state).is_some_and(|gap| gap >= 0.0) {
+        let bottom_gap = |s: &StoneInfo| system.bottom_gap(s).unwrap_or(f64::NEG_INFINITY);
+        dt = locate_event(system, solver, (t0, &y0), dt, bottom_gap);
+        solver.state.velocity = Vector2D::new(0.0, 0.0);
+        solver.state.angle_velocity = Vector2D::new(0.0, 0.0);
+        system.hit_bottom = true;
+        system.log_event(format_args!("Hit the bottom at t={:.4}", solver.t));
+    }
+
+    // (6) 对岸墙壁：本步越过墙面时定位接触时刻并退回到那里，再停止或反弹
+    if solver.state.velocity.x > 0.0 && system.wall_gap(&solver.state).is_some_and(|gap| gap >= 0.0) {
+        let wall_gap = |s: &StoneInfo| system.wall_gap(s).unwrap_or(f64::NEG_INFINITY);
+        dt = locate_event(system, solver, (t0, &y0), dt, wall_gap);
+        match system.wall_mode {
+            WallMode::Stop => {
+                system.reached_wall = true;
+                system.log_event(format_args!("Reached the other side! t={:.4}", solver.t));
+            }
+            WallMode::Bounce => {
+                solver.state.velocity.x = -solver.state.velocity.x;
+                system.log_event(format_args!("Bounced off the far wall at t={:.4}", solver.t));
+            }
+        }
+    }
 
-        // (4) 使用 RK4 进行一步积分
-        solver.step(system, dt);
+    // (7) 截断角速度
+    system.clamp_angular_velocity(&mut solver.state, solver.t);
+    Some(dt)
+}
+
+/// 事件定位：从 (t0, y0) 积分 `dt` 后事件函数 `g` 由负变为非负时，
+/// 二分求出 g 首次达到 0 的步长，并让求解器停在该时刻 (g 刚好非负的一侧)
+/// 起点已满足 g >= 0 时停在起点；返回实际使用的步长
+pub fn locate_event(system: &CustomSettings,
+                    solver: &mut RungeKuttaSolver<StoneInfo>,
+                    (t0, y0): (f64, &StoneInfo),
+                    dt: f64,
+                    g: impl Fn(&StoneInfo) -> f64) -> f64
+{
+    // 回到步首 (不用 reset，保留导数求值计数)
+    let rewind = |solver: &mut RungeKuttaSolver<StoneInfo>| {
+        solver.t = t0;
+        solver.state = y0.clone();
+    };
+    rewind(solver);
+    if g(y0) >= 0.0 {
+        return 0.0;
+    }
+    let (mut lo, mut hi) = (0.0, dt);
+    for _ in 0..EVENT_BISECTIONS {
+        let mid = 0.5 * (lo + hi);
+        rewind(solver);
+        solver.step(system, mid);
+        if g(&solver.state) >= 0.0 { hi = mid; } else { lo = mid; }
+    }
+    rewind(solver);
+    solver.step(system, hi);
+    hi
+}
+
+/// 同时模拟多块石片，各自独立积分，直到全部沉没或达到最大步数
+/// 返回与 `runs` 一一对应的轨迹 (每 `sample_stride` 步记录一帧)
+pub fn simulate_many(runs: &mut [(CustomSettings, RungeKuttaSolver<StoneInfo>)],
+                     dt: f64,
+                     max_steps: usize,
+                     sample_stride: usize) -> Vec<Vec<Stamp>>
+{
+    let sample_stride = sample_stride.max(1);
+    let mut trajectories: Vec<Vec<Stamp>> = runs.iter().map(|_| Vec::new()).collect();
+    let mut running = vec![true; runs.len()];
+
+    for step in 0..max_steps {
+        for (i, (system, solver)) in runs.iter_mut().enumerate() {
+            if !running[i] { continue; }
+
+            if step.is_multiple_of(sample_stride) {
+                trajectories[i].push(Stamp::capture(system, solver));
+            }
+
+            running[i] = advance(system, solver, dt);
+        }
+
+        if running.iter().all(|r| !r) {
+            break;
+        }
     }
 
-    trajectory
+    trajectories
+}
+
+/// 无窗口运行一次完整模拟，步长策略与界面相同 (见 CustomSettings::recommended_dt)
+/// 所有随机性 (网格采样、随机受力) 都来自 `seed`，
+/// 相同参数和种子的两次调用得到逐位相同的轨迹
+/// `on_step`: 可选的进度回调，每 PROGRESS_INTERVAL 步以 (步数, 当前状态) 调用一次
+pub fn run_headless_seeded(blueprint: &StoneBlueprint,
+                           y0: StoneInfo,
+                           gravity: f64,
+                           max_steps: usize,
+                           seed: u64,
+                           on_step: Option<ProgressCallback>) -> Result<(Vec<Stamp>, SimSummary), StoneError>
+{
+    let mut rng = Rng::new(seed);
+    let stone = StoneProperties::new(blueprint, &mut rng)?;
+    let mut system = CustomSettings::new(gravity, stone, rng.fork());
+    Ok(run_headless_with(&mut system, y0, max_steps, on_step))
+}
+
+/// 用已构建好的环境无窗口运行一次 (多次运行同一块石片时避免重复生成网格)
+/// 步长策略与 run_headless_seeded 相同；运行前按 y0 设置初始相位
+/// 不打印任何内容：浸水步长的提示写入 summary.dt_warning，其余调试信息只在 verbose 时输出
+pub fn run_headless_with(system: &mut CustomSettings,
+                         y0: StoneInfo,
+                         max_steps: usize,
+                         on_step: Option<ProgressCallback>) -> (Vec<Stamp>, SimSummary)
+{
+    system.set_initial_phase(&y0);
+    let dt_warning = system.bounce_dt_warning(system.dt_bouncing);
+    let mut solver = RungeKuttaSolver::new(0.0, y0);
+
+    let (trajectory, mut summary) = simulate_with(system, &mut solver, CustomSettings::recommended_dt, max_steps, DEFAULT_SAMPLE_STRIDE, on_step);
+    summary.dt_warning = dt_warning;
+    (trajectory, summary)
 }
 
 
@@ -72,32 +934,47 @@ pub fn simulate(system: &mut CustomSettings,
 impl CustomSettings {
     /// 根据当前 StoneInfo 更新 phase
     pub fn update_phase(&mut self, stone: &StoneInfo) {
-        let _r = self.stone.d_max.sqrt();//石头最大半径
-
         match self.phase {
             Phase::Flying => {
                 // 如果石头触碰到水面，切换到 Bouncing
-                if stone.position.y -_r * stone.angle.x.sin() <= self.water_level {
-                    self.phase = Phase::Bouncing;
-                    println!("Phase switched: Flying -> Bouncing at y={}", stone.position.y);
+                // (轮廓的真实最低点作为预筛选，再用浸没多边形确认，与离水判据一致，避免来回切换)
+                // 非对称石片的最低点不一定是离质心最远的点，不能用最大半径估计
+                let lowest = self.contact_point(stone);
+                if lowest.y <= self.surface_height(lowest.x) {
+                    self.update_submerged_area(stone);
+                    // 冲量反弹较弱时 (恢复系数低或已衰减)，反弹后的几步里石片仍有部分在水面以下，
+                    // 但正在离开水面，不算再次入水
+                    let impact_speed = self.impact_normal_speed(stone);
+                    let rebounding = self.bounce_model == BounceModel::Impulse && impact_speed <= 0.0;
+                    if !self.current_submerged_regions.is_empty() && !rebounding {
+                        // 法向入水速度低于临界值时石片 "粘" 在水面上，不再弹起
+                        if impact_speed < self.min_skip_speed {
+                            self.phase = Phase::Sinking;
+                            self.log_event(format_args!("Phase switched: Flying -> Sinking (impact speed {:.3} m/s) at y={}", impact_speed, stone.position.y));
+                        } else {
+                            self.phase = Phase::Bouncing;
+                            self.log_event(format_args!("Phase switched: Flying -> Bouncing at y={}", stone.position.y));
+                        }
+                    }
                 }
             }
 
             Phase::Bouncing => {
-                // 是否离开水面
-                if stone.position.y - _r * stone.angle.x.sin() > self.water_level && stone.velocity.y > 0.0 {
+                // [修正] 是否离开水面：纯几何判据，浸没多边形为空即离水，与速度方向无关
+                // (调用前 advance 已用当前状态更新了浸没多边形)
+                let surface = self.surface_height(stone.position.x);
+                if self.current_submerged_regions.is_empty() {
                     self.phase = Phase::Flying;
-                    println!("Bouncing → Flying");
+                    self.log_event(format_args!("Bouncing → Flying"));
                     return;
                 }
-                // 判断是否应该沉入水底：
-                // 可以用垂直力小于重力、速度太低等条件
-                let vertical_force = self.compute_vertical_force(stone);
-                let velocity_mag = stone.velocity.length(); // 你需要在 Vector2D/3D 里实现 length()
-
-                if stone.position.y < -0.1 ||stone.velocity.x < 0.2{
+                // 判断是否应该沉入水底：浸没过深，或在水中失速 (合速度低于临界入水速度)
+                // 入水太慢的情形由 Flying 分支的法向速度判据处理
+                // 开启浮力时慢速的石片可以漂浮，只按浸没深度判断
+                let stalled = !self.buoyancy && stone.velocity.length() < self.min_skip_speed;
+                if stone.position.y < surface - 0.1 || stalled {
                     self.phase = Phase::Sinking;
-                    println!("Phase switched: Bouncing -> Sinking at y={}", stone.position.y);
+                    self.log_event(format_args!("Phase switched: Bouncing -> Sinking at y={}", stone.position.y));
                 }
             }
 
@@ -107,101 +984,344 @@ impl CustomSettings {
         }
     }
 
-    /// 可选：计算垂直方向的受力
-    fn compute_vertical_force(&self, stone: &StoneInfo) -> f64 {
-        // 简化：重力 + 升力 + 阻力
-        // Fx = 0.5 * rho * v^2 * area * Cl 之类
-        let v2 = stone.velocity.length_squared();
-        0.5 * self.rho * v2 * self.Sim * self.Cl - self.M * self.gravity
+    /// 沿水面法向 (指向水下) 的入水速度 (m/s)，离开水面方向为负
+    pub fn impact_normal_speed(&self, stone: &StoneInfo) -> f64 {
+        // 水面 y = level + slope * x 的向上法向为 (-slope, 1) / sqrt(1 + slope^2)
+        let norm = (1.0 + self.slope * self.slope).sqrt();
+        (self.slope * stone.velocity.x - stone.velocity.y) / norm
+    }
+
+    /// 入水时的俯仰-自转耦合
+    /// 转移比例 = spin_coupling * |力臂| / 最大半径，力臂为触水区域中心相对质心的水平偏移
+    /// (斜着入水、一侧先触水时耦合强，正下方触水时不耦合)；
+    /// 按 I_x ω_x + I_y ω_y 守恒把减少的俯仰角速度折算到自转上
+    pub fn apply_spin_coupling(&self, stone: &mut StoneInfo) {
+        let contact = &self.current_submerged_regions;
+        if self.spin_coupling == 0.0 || contact.is_empty() || self.stone.inertia_tensor_y < 1e-12 {
+            return;
+        }
+
+        let contact_x = pressure_center(contact).x;
+        let radius = self.stone.d_max.sqrt().max(1e-9);
+        let lever = ((contact_x - stone.position.x) / radius).abs().min(1.0);
+
+        let transferred = (self.spin_coupling * lever).clamp(0.0, 1.0) * stone.angle_velocity.x;
+        stone.angle_velocity.x -= transferred;
+        stone.angle_velocity.y += transferred * self.stone.inertia_tensor_x / self.stone.inertia_tensor_y;
+    }
+
+    /// 冲量弹跳：法向 (竖直) 速度按 (随弹跳次数衰减的) 恢复系数反射，切向速度损失一部分
+    /// 已经向上运动的石头不再反射
+    pub fn apply_bounce_impulse(&mut self, stone: &mut StoneInfo) {
+        if stone.velocity.y < 0.0 {
+            stone.velocity.y *= -self.effective_restitution();
+            stone.velocity.x *= 1.0 - self.tangent_friction;
+            self.bounce_count += 1;
+        }
+    }
+
+    /// 下一次冲量弹跳使用的恢复系数
+    pub fn effective_restitution(&self) -> f64 {
+        self.restitution * self.restitution_decay_curve.factor(self.restitution_decay, self.bounce_count)
+    }
+
+    /// 把俯仰/自转角速度限制在 max_pitch_rate / max_spin_rate 以内
+    /// 第一次触发时打印警告：这说明物理已经到达极限，而不是正常结果
+    pub fn clamp_angular_velocity(&mut self, stone: &mut StoneInfo, t: f64) {
+        let w = stone.angle_velocity;
+        let clamped = Vector2D::new(
+            w.x.clamp(-self.max_pitch_rate, self.max_pitch_rate),
+            w.y.clamp(-self.max_spin_rate, self.max_spin_rate),
+        );
+        if clamped.x == w.x && clamped.y == w.y { return; }
+
+        if !self.angular_clamp_warned {
+            self.angular_clamp_warned = true;
+            println!("Warning: 角速度在 t={:.4}s 超出上限 ({:.1}, {:.1}) rad/s，已截断", t, w.x, w.y);
+        }
+        stone.angle_velocity = clamped;
+    }
+
+    /// 总机械能 = 平动动能 + 转动动能 + 重力势能
+    /// Flying 阶段只有重力做功，该值应守恒，可用于检查积分器
+    pub fn total_energy(&self, stone: &StoneInfo) -> f64 {
+        let kinetic = 0.5 * self.M * stone.velocity.length_squared();
+        let rotational = 0.5 * self.stone.inertia_tensor_x * stone.angle_velocity.x * stone.angle_velocity.x;
+        let potential = self.M * self.gravity * stone.position.y;
+        kinetic + rotational + potential
     }
 }
 
 
 impl CustomSettings {
 
+    /// 按初始状态确定起始相位后返回 (见 set_initial_phase)
+    pub fn with_initial_state(mut self, y0: &StoneInfo) -> Self {
+        self.set_initial_phase(y0);
+        self
+    }
+
+    /// 由初始状态确定起始相位：轮廓已有部分在水面以下时从 Bouncing 开始，否则 Flying
+    /// 应在水面参数 (water_level, slope) 设置完毕之后调用
+    pub fn set_initial_phase(&mut self, y0: &StoneInfo) {
+        self.update_submerged_area(y0);
+        self.phase = if self.current_submerged_regions.is_empty() { Phase::Flying } else { Phase::Bouncing };
+    }
+
+    /// 给定姿态下浸没面积占石片面积的比例 (0 ~ 1)
+    /// 纯查询：不修改 Sim、浸没区域等运行状态，可在不推进模拟时调用 (如发射预览、分析工具)
+    pub fn submerged_fraction(&self, stone: &StoneInfo) -> f64 {
+        if self.stone.area <= 0.0 {
+            return 0.0;
+        }
+        let outline_world = self.outline_to_world(stone);
+        let regions = clip_polygon_below_surface(&outline_world, self.effective_water_level(), self.slope);
+        (regions_area(&regions) / self.stone.area).clamp(0.0, 1.0)
+    }
+
+    /// 湿长度 (m)：浸没部分沿石片弦向 (俯仰方向) 的跨度，未浸没时为 0
+    pub fn wetted_length(&self, stone: &StoneInfo) -> f64 {
+        let outline_world = self.outline_to_world(stone);
+        let regions = clip_polygon_below_surface(&outline_world, self.effective_water_level(), self.slope);
+        let chord = Vector2D::new(stone.angle.x.cos(), stone.angle.x.sin());
+        let (lo, hi) = regions.iter().flatten()
+            .map(|p| p.dot(chord))
+            .fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), u| (lo.min(u), hi.max(u)));
+        if hi > lo { hi - lo } else { 0.0 }
+    }
+
+    /// 峰值线加速度，以标准重力 (地球) 为单位，与当前重力预设无关
+    pub fn peak_g(&self) -> f64 {
+        self.peak_acceleration.get() / GravityPreset::Earth.value()
+    }
+
+    /// 以湿长度 L 为特征长度的弗劳德数 Fr = |vx| / sqrt(g L)，滑行段的行为由它决定
+    /// 纯查询，不改变任何状态；未浸没或重力不为正时为 0
+    pub fn froude_number(&self, stone: &StoneInfo) -> f64 {
+        let length = self.wetted_length(stone);
+        if length <= 0.0 || self.gravity <= 0.0 {
+            return 0.0;
+        }
+        stone.velocity.x.abs() / (self.gravity * length).sqrt()
+    }
+
+    /// 静水中的平衡漂浮姿态 (俯仰角 rad, 吃水深度 m)：浮力与重力平衡，且浮心与质心在同一竖直线上 (力矩为零)
+    /// 吃水深度为轮廓最低点到水面的距离；只取稳定平衡 (偏转后力矩把石片拉回)，有多个时取最接近水平的
+    /// 不比水轻的石片没有漂浮姿态，返回 None。纯查询，不改变任何状态
+    pub fn equilibrium_pose(&self) -> Option<(f64, f64)> {
+        let rest = self.rest_state()?;
+        let lowest = outline_in_world(&self.stone.outline_com, &rest).iter()
+            .map(|p| p.y - self.surface_height(p.x))
+            .fold(f64::INFINITY, f64::min);
+        Some((rest.angle.x, -lowest))
+    }
+
+    /// 以平衡漂浮姿态静止在 x = 0 处的状态 ("静置入水" 实验的初始条件)，石片下沉时为 None
+    pub fn rest_state(&self) -> Option<StoneInfo> {
+        // 1. 平衡时的浸没面积：rho * A_sub * 厚度 = M
+        let target = self.M / (self.rho * self.stone.thickness);
+        if target >= self.stone.area { return None; }
+        let radius = self.stone.outline_com.iter().map(|p| p.length()).fold(0.0, f64::max);
+        let surface = self.surface_height(0.0);
+        let state_at = |pitch: f64, y: f64| StoneInfo {
+            position: Vector2D::new(0.0, y),
+            velocity: Vector2D::new(0.0, 0.0),
+            angle: Vector2D::new(pitch, 0.0),
+            angle_velocity: Vector2D::new(0.0, 0.0),
+        };
+        let submerged = |pitch: f64, y: f64| {
+            let outline = outline_in_world(&self.stone.outline_com, &state_at(pitch, y));
+            clip_polygon_below_surface(&outline, self.effective_water_level(), self.slope)
+        };
+
+        // 2. 给定俯仰角，二分质心高度使浸没面积等于目标值 (面积随高度单调减小)
+        let height_for = |pitch: f64| {
+            let (mut lo, mut hi) = (surface - radius, surface + radius);
+            for _ in 0..EQUILIBRIUM_BISECTIONS {
+                let mid = 0.5 * (lo + hi);
+                if regions_area(&submerged(pitch, mid)) > target { lo = mid; } else { hi = mid; }
+            }
+            0.5 * (lo + hi)
+        };
+        // 浮心相对质心的水平偏移，正值时浮力产生逆时针 (俯仰角增大) 的力矩
+        let offset = |pitch: f64| pressure_center(&submerged(pitch, height_for(pitch))).x;
+
+        // 3. 扫描一整圈，偏移由正变负处是稳定平衡 (避开恰好落在对称姿态上的采样点)
+        let step = 2.0 * PI / EQUILIBRIUM_SCAN as f64;
+        let angles: Vec<f64> = (0..=EQUILIBRIUM_SCAN).map(|i| -PI + (i as f64 + 0.5) * step).collect();
+        let offsets: Vec<f64> = angles.iter().map(|&a| offset(a)).collect();
+        let pitch = (0..EQUILIBRIUM_SCAN)
+            .filter(|&i| offsets[i] > 0.0 && offsets[i + 1] <= 0.0)
+            .map(|i| {
+                let (mut lo, mut hi) = (angles[i], angles[i + 1]);
+                for _ in 0..EQUILIBRIUM_BISECTIONS {
+                    let mid = 0.5 * (lo + hi);
+                    if offset(mid) > 0.0 { lo = mid; } else { hi = mid; }
+                }
+                (0.5 * (lo + hi) + PI).rem_euclid(2.0 * PI) - PI
+            })
+            .min_by(|a, b| a.abs().total_cmp(&b.abs()))?;
+
+        Some(state_at(pitch, height_for(pitch)))
+    }
+
     pub fn update_submerged_area(&mut self, stone_state: &StoneInfo) {
         let outline_world = self.outline_to_world(stone_state);
 
-        let clipped = clip_polygon_below_line(&outline_world, self.water_level);
+        let level = self.effective_water_level();
+        let regions = clip_polygon_below_surface(&outline_world, level, self.slope);
 
         // 保存下来（供 torque 使用）
-        self.current_submerged_polygon = clipped.clone();
+        self.wetted_perimeter = wetted_perimeter(&regions, level, self.slope);
+        self.Sim = regions_area(&regions);
+        self.current_submerged_regions = regions;
+    }
 
-        if clipped.len() < 3 {
-            self.Sim = 0.0;
-        } else {
-            self.Sim = polygon_area(&clipped);
+
+    /// 质心系轮廓 -> 世界坐标轮廓
+    /// 与上一次调用的位置和姿态完全相同时直接返回缓存结果 (共享同一份数据，不复制)
+    pub fn outline_to_world(&self, stone: &StoneInfo) -> Rc<[Vector2D]> {
+        if let Some((position, angle, outline)) = self.outline_cache.borrow().as_ref()
+            && *position == stone.position && *angle == stone.angle {
+            return Rc::clone(outline);
         }
+
+        let outline: Rc<[Vector2D]> = self.transform_outline(stone).into();
+        self.outline_transforms.set(self.outline_transforms.get() + 1);
+        *self.outline_cache.borrow_mut() = Some((stone.position, stone.angle, Rc::clone(&outline)));
+        outline
     }
 
+    /// 石片轮廓最前端越过对岸墙壁的距离 (m)，尚未到达时为负；没有墙壁时为 None
+    pub fn wall_gap(&self, stone: &StoneInfo) -> Option<f64> {
+        let wall_x = self.wall_x?;
+        let front = self.outline_to_world(stone).iter().map(|p| p.x).fold(f64::NEG_INFINITY, f64::max);
+        Some(front - wall_x)
+    }
 
-    pub fn outline_to_world(&self, stone: &StoneInfo) -> Vec<Vector2D> {
-        // 1. 自转角 (Spin / angle.y)
-        // 决定石头在该时刻呈现的“形状”姿态 (在自身坐标系内旋转)
-        let spin = stone.angle.y;
-        let cos_spin = spin.cos();
-        let sin_spin = spin.sin();
+    /// 石片的接触点 (见 contact_point)，使用缓存的世界坐标轮廓；轮廓为空时取质心
+    pub fn contact_point(&self, stone: &StoneInfo) -> Vector2D {
+        lowest_point(&self.outline_to_world(stone)).unwrap_or(stone.position)
+    }
 
-        // 2. 俯仰角 (Pitch / angle.x)
-        // 决定石头整体在世界坐标系中的倾角
-        let pitch = stone.angle.x;
-        let cos_pitch = pitch.cos();
-        let sin_pitch = pitch.sin();
+    /// 池底高度 (m)；没有池底 (water_depth 为 0) 时为 None
+    pub fn bottom_y(&self) -> Option<f64> {
+        (self.water_depth > 0.0).then_some(self.water_level - self.water_depth)
+    }
 
-        self.stone.outline_com.iter()
-            .map(|p| {
-                // A. 先进行自转 (Local Rotation)
-                // 绕石片中心 (0,0) 旋转
-                let x_spun = p.x * cos_spin - p.y * sin_spin;
-                let y_spun = p.x * sin_spin + p.y * cos_spin;
+    /// 石片轮廓最低点低于池底的距离 (m)，尚未触底时为负；没有池底时为 None
+    pub fn bottom_gap(&self, stone: &StoneInfo) -> Option<f64> {
+        let bottom = self.bottom_y()?;
+        Some(bottom - self.contact_point(stone).y)
+    }
 
-                // B. 再进行俯仰 (World Rotation) 并平移
-                // 将自转后的点，应用俯仰角旋转，然后加上质心位置
-                Vector2D {
-                    x: stone.position.x + (x_spun * cos_pitch - y_spun * sin_pitch),
-                    y: stone.position.y + (x_spun * sin_pitch + y_spun * cos_pitch),
-                }
-            })
-            .collect()
+    /// 沉没过程是否已经结束：无底时一开始沉没即结束，有底时触底才结束
+    pub fn sinking_done(&self) -> bool {
+        self.phase == Phase::Sinking && (self.bottom_y().is_none() || self.hit_bottom)
+    }
+
+    fn transform_outline(&self, stone: &StoneInfo) -> Vec<Vector2D> {
+        outline_in_world(&self.stone.outline_com, stone)
     }
 }
 
-pub fn clip_polygon_below_line(poly: &[Vector2D], line_y: f64) -> Vec<Vector2D> {
-    let mut output = Vec::new();
-    let n = poly.len();
+/// 轮廓最低点 (世界坐标)，即石片最先触水的接触点；轮廓为空时为 None
+/// update_phase 用它判断是否触水，再用浸没多边形确认
+pub fn contact_point(outline_com: &[Vector2D], stone: &StoneInfo) -> Option<Vector2D> {
+    lowest_point(&outline_in_world(outline_com, stone))
+}
+
+fn lowest_point(points: &[Vector2D]) -> Option<Vector2D> {
+    points.iter().copied().min_by(|a, b| a.y.total_cmp(&b.y))
+}
+
+/// 质心系轮廓在给定姿态与位置下的世界坐标 (先自转，再俯仰，最后平移)
+pub fn outline_in_world(outline_com: &[Vector2D], stone: &StoneInfo) -> Vec<Vector2D> {
+    // 1. 自转角 (Spin / angle.y)
+    // 决定石头在该时刻呈现的“形状”姿态 (在自身坐标系内旋转)
+    let spin = stone.angle.y;
+    let cos_spin = spin.cos();
+    let sin_spin = spin.sin();
+
+    // 2. 俯仰角 (Pitch / angle.x)
+    // 决定石头整体在世界坐标系中的倾角
+    let pitch = stone.angle.x;
+    let cos_pitch = pitch.cos();
+    let sin_pitch = pitch.sin();
 
+    outline_com.iter()
+        .map(|p| {
+            // A. 先进行自转 (Local Rotation)
+            // 绕石片中心 (0,0) 旋转
+            let x_spun = p.x * cos_spin - p.y * sin_spin;
+            let y_spun = p.x * sin_spin + p.y * cos_spin;
+
+            // B. 再进行俯仰 (World Rotation) 并平移
+            // 将自转后的点，应用俯仰角旋转，然后加上质心位置
+            Vector2D {
+                x: stone.position.x + (x_spun * cos_pitch - y_spun * sin_pitch),
+                y: stone.position.y + (x_spun * sin_pitch + y_spun * cos_pitch),
+            }
+        })
+        .collect()
+}
+
+pub fn clip_polygon_below_line(poly: &[Vector2D], line_y: f64) -> Vec<Vec<Vector2D>> {
+    clip_polygon_below_surface(poly, line_y, 0.0)
+}
+
+/// 裁剪出位于倾斜水面 y = level + slope * x 以下的部分
+/// 凹多边形可能在多处同时入水，因此返回互不相连的若干浸没区域 (每个至少 3 个点)
+pub fn clip_polygon_below_surface(poly: &[Vector2D], level: f64, slope: f64) -> Vec<Vec<Vector2D>> {
+    // 点到水面的竖直距离 (负数表示在水下)
+    let depth = |p: Vector2D| p.y - (level + slope * p.x);
+
+    let n = poly.len();
     if n == 0 {
-        return output;
+        return Vec::new();
     }
 
-    for i in 0..n {
-        let cur = poly[i];
-        let next = poly[(i + 1) % n];
+    // 从一个水面以上的顶点开始遍历，这样每段水下链都以 "入水" 交点开始、"出水" 交点结束
+    // 没有这样的顶点说明整个多边形都在水下
+    let Some(start) = (0..n).find(|&i| depth(poly[i]) >= 0.0) else {
+        return if n >= 3 { vec![poly.to_vec()] } else { Vec::new() };
+    };
 
-        let cur_inside = cur.y < line_y;
-        let next_inside = next.y < line_y;
+    let mut regions = Vec::new();
+    let mut current = Vec::new();
+    for k in 0..n {
+        let cur = poly[(start + k) % n];
+        let next = poly[(start + k + 1) % n];
+
+        let cur_inside = depth(cur) < 0.0;
+        let next_inside = depth(next) < 0.0;
 
         match (cur_inside, next_inside) {
             // Both inside → keep next
             (true, true) => {
-                output.push(next);
+                current.push(next);
             }
 
             // cur inside → next outside
-            // keep intersection only
+            // keep intersection, this region is complete
             (true, false) => {
-                if let Some(inter) = intersect_with_horizontal(cur, next, line_y) {
-                    output.push(inter);
+                if let Some(inter) = intersect_with_surface(cur, next, depth(cur), depth(next)) {
+                    push_distinct(&mut current, inter);
+                }
+                // 恰好落在水面上的顶点既是入水点又是出水点，首尾重复时去掉一个
+                if current.len() > 1 && current.first() == current.last() {
+                    current.pop();
                 }
+                regions.push(std::mem::take(&mut current));
             }
 
             // cur outside → next inside
-            // add intersection + next
+            // start a new region: intersection + next
             (false, true) => {
-                if let Some(inter) = intersect_with_horizontal(cur, next, line_y) {
-                    output.push(inter);
+                if let Some(inter) = intersect_with_surface(cur, next, depth(cur), depth(next)) {
+                    push_distinct(&mut current, inter);
                 }
-                output.push(next);
+                push_distinct(&mut current, next);
             }
 
             // both outside → add nothing
@@ -209,38 +1329,1052 @@ pub fn clip_polygon_below_line(poly: &[Vector2D], line_y: f64) -> Vec<Vector2D>
         }
     }
 
-    output
+    regions.retain(|r: &Vec<Vector2D>| r.len() >= 3);
+    regions
+}
+
+// 与上一个点不同时才加入 (落在水面上的顶点会与交点重合)
+fn push_distinct(region: &mut Vec<Vector2D>, p: Vector2D) {
+    if region.last() != Some(&p) {
+        region.push(p);
+    }
 }
 
-fn intersect_with_horizontal(p1: Vector2D, p2: Vector2D, y: f64) -> Option<Vector2D> {
-    // Line segment p1→p2 intersects horizontal line y?
-    if (p1.y - y) * (p2.y - y) > 0.0 {
+// d1, d2: 两端点到水面的竖直距离
+fn intersect_with_surface(p1: Vector2D, p2: Vector2D, d1: f64, d2: f64) -> Option<Vector2D> {
+    // Line segment p1→p2 crosses the surface?
+    if d1 * d2 > 0.0 {
         return None; // same side, no intersection
     }
-    if (p1.y - p2.y).abs() < 1e-12 {
-        return None; // horizontal segment
+    // 端点恰好在水面上时直接返回端点，避免插值的舍入误差产生几乎重合的点
+    if d1 == 0.0 {
+        return Some(p1);
+    }
+    if d2 == 0.0 {
+        return Some(p2);
+    }
+    if (d1 - d2).abs() < 1e-12 {
+        return None; // parallel to the surface
     }
 
-    let t = (y - p1.y) / (p2.y - p1.y);
+    let t = d1 / (d1 - d2);
 
     Some(Vector2D {
         x: p1.x + t * (p2.x - p1.x),
-        y,
+        y: p1.y + t * (p2.y - p1.y),
     })
 }
 
-pub fn polygon_area(poly: &[Vector2D]) -> f64 {
-    if poly.len() < 3 {
+/// 湿周：所有浸没区域中与水接触的边长之和
+/// 位于水面 y = level + slope * x 上的边 (水线切口) 不计入
+pub fn wetted_perimeter(regions: &[Vec<Vector2D>], level: f64, slope: f64) -> f64 {
+    regions.iter().map(|r| region_wetted_perimeter(r, level, slope)).sum()
+}
+
+fn region_wetted_perimeter(clipped: &[Vector2D], level: f64, slope: f64) -> f64 {
+    if clipped.len() < 3 {
         return 0.0;
     }
 
-    let mut area = 0.0;
-    let n = poly.len();
+    let on_surface = |p: Vector2D| (p.y - (level + slope * p.x)).abs() < 1e-9;
 
+    let n = clipped.len();
+    let mut perimeter = 0.0;
     for i in 0..n {
-        let j = (i + 1) % n;
-        area += poly[i].x * poly[j].y - poly[j].x * poly[i].y;
+        let (p1, p2) = (clipped[i], clipped[(i + 1) % n]);
+        if on_surface(p1) && on_surface(p2) {
+            continue;
+        }
+        perimeter += (p2 - p1).length();
+    }
+    perimeter
+}
+
+/// 多个浸没区域的总面积 (m^2)
+/// 按有向面积求和：嵌套区域 (例如杯状石片内侧) 的方向相反，会被正确扣除
+pub fn regions_area(regions: &[Vec<Vector2D>]) -> f64 {
+    regions.iter().map(|r| signed_polygon_area(r)).sum::<f64>().abs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::basic_structs::polygon_area;
+    use crate::stone_phy::{slab_blueprint, slab_outline};
+    use crate::physics::parameters::{water_density, CustomSettingsBuilder, DecayCurve, DEFAULT_DT_BOUNCING, DEFAULT_DT_FLYING, DEFAULT_WATER_TEMP_C};
+
+    fn stamp(x: f64, y: f64, vy: f64, phase: Phase) -> Stamp {
+        Stamp {
+            t: 0.0,
+            state: StoneInfo {
+                position: Vector2D::new(x, y),
+                velocity: Vector2D::new(1.0, vy),
+                angle: Vector2D::new(0.0, 0.0),
+                angle_velocity: Vector2D::new(0.0, 0.0),
+            },
+            energy: y + 0.5 * (1.0 + vy * vy), // m = g = 1
+            phase,
+            wetted_perimeter: 0.0,
+            froude: 0.0,
+            peak_g: 0.0,
+            forces: None,
+            submerged: Vec::new(),
+        }
+    }
+
+    // 基准：弹跳阶段每步的轮廓变换次数
+    // 不缓存时为 5 次 (update_submerged_area 1 次 + RK4 四个阶段)，缓存后 k1 复用前者
+    #[test]
+    fn outline_transform_is_reused_within_a_step() {
+        let blueprint = slab_blueprint(0.02, "bench");
+        let mut rng = Rng::new(3);
+        let stone = StoneProperties::new(&blueprint, &mut rng).unwrap();
+        let mut system = CustomSettings::new(9.81, stone, rng.fork());
+        system.phase = Phase::Bouncing;
+
+        let mut solver = RungeKuttaSolver::new(0.0, StoneInfo {
+            position: Vector2D::new(0.0, 0.0),
+            velocity: Vector2D::new(5.0, -0.5),
+            angle: Vector2D::new(0.1, 0.0),
+            angle_velocity: Vector2D::new(0.0, 10.0),
+        });
+
+        let steps = 20;
+        for _ in 0..steps {
+            system.update_submerged_area(&solver.state);
+            solver.step(&system, 0.0005);
+        }
+
+        let transforms = system.outline_transforms.get();
+        assert_eq!(transforms, 4 * steps, "uncached: {}", 5 * steps);
+    }
+
+    #[test]
+    fn step_accumulator_follows_wall_clock_and_caps_backlog() {
+        let mut clock = StepAccumulator::new(0.002, 0.12, 8);
+        // 60 fps：每帧一块
+        let per_frame: Vec<usize> = (0..6).map(|_| clock.steps(1.0 / 60.0)).collect();
+        assert_eq!(per_frame.iter().sum::<usize>(), 6);
+        // 30 fps：每帧两块，模拟时间与真实时间同步
+        assert_eq!(clock.steps(1.0 / 30.0), 2);
+        // 高帧率：几帧才推进一块
+        let fast: usize = (0..4).map(|_| clock.steps(1.0 / 240.0)).sum();
+        assert_eq!(fast, 1);
+        // 卡顿一秒：只推进上限块数，积压被丢弃
+        assert_eq!(clock.steps(1.0), 8);
+        assert_eq!(clock.steps(0.0), 0);
+    }
+
+    #[test]
+    fn froude_number_uses_the_wetted_length() {
+        let stone = StoneProperties::from_outline(&slab_outline(0.02), 0.01, 2700.0).unwrap();
+        let system = CustomSettings::new(9.81, stone, Rng::new(2));
+        let at = |y: f64, pitch: f64| StoneInfo {
+            position: Vector2D::new(0.0, y),
+            velocity: Vector2D::new(-4.0, -0.5),
+            angle: Vector2D::new(pitch, 0.0),
+            angle_velocity: Vector2D::new(0.0, 0.0),
+        };
+
+        // 平放、一半没入：湿长度为整条弦长 6 cm
+        let flat = at(0.0, 0.0);
+        assert!((system.wetted_length(&flat) - 0.06).abs() < 1e-9);
+        assert!((system.froude_number(&flat) - 4.0 / (9.81f64 * 0.06).sqrt()).abs() < 1e-9);
+
+        // 前缘抬起 90°：沿弦向只有下半截没入
+        assert!((system.wetted_length(&at(0.0, std::f64::consts::FRAC_PI_2)) - 0.03).abs() < 1e-9);
+
+        // 离开水面为 0，且查询不改变运行状态
+        assert_eq!(system.froude_number(&at(1.0, 0.0)), 0.0);
+        assert!(system.current_submerged_regions.is_empty());
+        assert_eq!(system.phase, Phase::Flying);
+    }
+
+    #[test]
+    fn far_wall_stops_or_reflects_the_stone_at_contact() {
+        let blueprint = slab_blueprint(0.02, "wall");
+        let y0 = StoneInfo {
+            position: Vector2D::new(0.0, 1.0),
+            velocity: Vector2D::new(5.0, 0.0),
+            angle: Vector2D::new(0.0, 0.0),
+            angle_velocity: Vector2D::new(0.0, 0.0),
+        };
+        let run = |mode: WallMode| {
+            let mut rng = Rng::new(3);
+            let stone = StoneProperties::new(&blueprint, &mut rng).unwrap();
+            let mut system = CustomSettingsBuilder::new().wall(0.5, mode).rng(rng.fork()).build(stone);
+            let mut solver = RungeKuttaSolver::new(0.0, y0.clone());
+            let (trajectory, summary) = simulate(&mut system, &mut solver, 0.01, 40, 1);
+            (system, solver, trajectory, summary)
+        };
+
+        // 停止：前端恰好停在墙面上 (粗步长也能精确定位)，之后不再推进
+        let (system, solver, trajectory, summary) = run(WallMode::Stop);
+        assert!(summary.reached_wall);
+        let gap = system.wall_gap(&solver.state).unwrap();
+        assert!((0.0..1e-9).contains(&gap), "gap {}", gap);
+        assert!(trajectory.len() < 40);
+
+        // 反弹：水平速度反向，之后始终在墙的这一侧
+        let (system, solver, trajectory, summary) = run(WallMode::Bounce);
+        assert!(!summary.reached_wall);
+        assert!((solver.state.velocity.x + 5.0).abs() < 1e-12);
+        assert!(trajectory.iter().all(|s| system.wall_gap(&s.state).unwrap() < 1e-9));
+    }
+
+    #[test]
+    fn peak_acceleration_stays_within_a_plausible_envelope() {
+        let blueprint = slab_blueprint(0.02, "peak");
+        let y0 = StoneInfo {
+            position: Vector2D::new(0.0, 0.05),
+            velocity: Vector2D::new(15.0, -1.0),
+            angle: Vector2D::new(0.35, 0.0),
+            angle_velocity: Vector2D::new(0.0, 15.0),
+        };
+        let (trajectory, summary) = run_headless_seeded(&blueprint, y0, 9.81, 20_000, 1, None).unwrap();
+        assert!(summary.skips > 0);
+        // 入水时水动力明显大于重力，但默认步长下不应出现刚性瞬态
+        assert!(summary.peak_g > 1.0 && summary.peak_g < STIFF_ENTRY_G, "peak {} g", summary.peak_g);
+        // 帧里记录的是到该时刻为止的峰值：单调不减，最后一帧等于总结
+        assert!(trajectory.windows(2).all(|w| w[1].peak_g >= w[0].peak_g));
+        assert!(trajectory.last().unwrap().peak_g <= summary.peak_g);
+        assert_eq!(trajectory[0].peak_g, 0.0);
+    }
+
+    #[test]
+    fn headless_progress_callback_runs_every_interval() {
+        let blueprint = slab_blueprint(0.02, "progress");
+        // 高处平抛，2500 步内不会落水
+        let y0 = StoneInfo {
+            position: Vector2D::new(0.0, 100.0),
+            velocity: Vector2D::new(5.0, 0.0),
+            angle: Vector2D::new(0.0, 0.0),
+            angle_velocity: Vector2D::new(0.0, 0.0),
+        };
+        let mut calls = Vec::new();
+        let mut record = |step: usize, state: &StoneInfo| calls.push((step, state.position.x));
+        let (with, _) = run_headless_seeded(&blueprint, y0.clone(), 9.81, 2500, 1, Some(&mut record)).unwrap();
+        let steps: Vec<usize> = calls.iter().map(|&(step, _)| step).collect();
+        assert_eq!(steps, vec![0, PROGRESS_INTERVAL, 2 * PROGRESS_INTERVAL]);
+        assert!(calls[2].1 > calls[1].1);
+
+        // 回调不影响结果
+        let (without, _) = run_headless_seeded(&blueprint, y0, 9.81, 2500, 1, None).unwrap();
+        assert_eq!(with.last().unwrap().state.position, without.last().unwrap().state.position);
+    }
+
+    #[test]
+    fn rising_stone_leaves_water_once_fully_out() {
+        // 细长石片，自转 90° 后竖直，再大角度俯仰：
+        // 粗略的最低点估计 (y - d_max * sin(pitch)) 仍在水下，但实际轮廓已完全出水
+        let blueprint = slab_blueprint(0.01, "thin");
+        let mut rng = Rng::new(5);
+        let stone = StoneProperties::new(&blueprint, &mut rng).unwrap();
+        let mut system = CustomSettings::new(9.81, stone, rng.fork());
+        system.phase = Phase::Bouncing;
+
+        let state = StoneInfo {
+            position: Vector2D::new(0.0, 0.02),
+            velocity: Vector2D::new(5.0, 0.5),
+            angle: Vector2D::new(1.4, std::f64::consts::FRAC_PI_2),
+            angle_velocity: Vector2D::new(0.0, 0.0),
+        };
+        assert!(state.position.y - system.stone.d_max.sqrt() * state.angle.x.sin() < 0.0);
+
+        system.update_submerged_area(&state);
+        system.update_phase(&state);
+        assert_eq!(system.phase, Phase::Flying);
+    }
+
+    #[test]
+    fn slow_grazing_entry_sinks_instead_of_skipping() {
+        let blueprint = slab_blueprint(0.02, "graze");
+        let mut rng = Rng::new(5);
+        let stone = StoneProperties::new(&blueprint, &mut rng).unwrap();
+        let mut system = CustomSettings::new(9.81, stone, rng.fork());
+
+        // 石片刚贴到水面 (下半部分没入)，水平速度很大但法向速度只有 0.1 m/s
+        let entry = |vy: f64| StoneInfo {
+            position: Vector2D::new(0.0, 0.0),
+            velocity: Vector2D::new(4.0, vy),
+            angle: Vector2D::new(0.0, 0.0),
+            angle_velocity: Vector2D::new(0.0, 20.0),
+        };
+        assert!((system.impact_normal_speed(&entry(-0.1)) - 0.1).abs() < 1e-12);
+
+        system.update_phase(&entry(-0.1));
+        assert_eq!(system.phase, Phase::Sinking);
+
+        // 足够快的入水正常弹跳
+        system.phase = Phase::Flying;
+        system.update_phase(&entry(-2.0));
+        assert_eq!(system.phase, Phase::Bouncing);
+
+        // 阈值可调：降低后同样的掠射也能弹起
+        system.phase = Phase::Flying;
+        system.min_skip_speed = 0.05;
+        system.update_phase(&entry(-0.1));
+        assert_eq!(system.phase, Phase::Bouncing);
+    }
+
+    #[test]
+    fn spin_coupling_conserves_angular_momentum() {
+        let blueprint = slab_blueprint(0.02, "couple");
+        let mut rng = Rng::new(5);
+        let stone = StoneProperties::new(&blueprint, &mut rng).unwrap();
+        let mut system = CustomSettings::new(9.81, stone, rng.fork());
+
+        // 抬头入水：后缘先触水，触水区域偏在质心一侧
+        let entry = StoneInfo {
+            position: Vector2D::new(0.0, 0.005),
+            velocity: Vector2D::new(6.0, -2.0),
+            angle: Vector2D::new(0.4, 0.0),
+            angle_velocity: Vector2D::new(8.0, 20.0),
+        };
+        system.update_submerged_area(&entry);
+        assert!(!system.current_submerged_regions.is_empty());
+
+        // 默认关闭：状态不变
+        let mut off = entry.clone();
+        system.apply_spin_coupling(&mut off);
+        assert_eq!(off.angle_velocity, entry.angle_velocity);
+
+        let mut on = entry.clone();
+        system.spin_coupling = 0.5;
+        system.apply_spin_coupling(&mut on);
+        let (ix, iy) = (system.stone.inertia_tensor_x, system.stone.inertia_tensor_y);
+        let momentum = |s: &StoneInfo| ix * s.angle_velocity.x + iy * s.angle_velocity.y;
+        assert!(on.angle_velocity.x < entry.angle_velocity.x);
+        assert!(on.angle_velocity.y > entry.angle_velocity.y);
+        assert!((momentum(&on) - momentum(&entry)).abs() < 1e-15);
+    }
+
+    #[test]
+    fn skips_are_segmented_between_bounces() {
+        use Phase::*;
+        let mut trajectory = vec![
+            stamp(0.0, 1.0, -1.0, Flying),   // 抛出段 (不计)
+            stamp(1.0, 0.0, -1.0, Bouncing),
+            stamp(2.0, 0.1, 1.0, Flying),    // 第 1 跳
+            stamp(3.0, 0.5, 0.0, Flying),
+            stamp(4.0, 0.0, -1.0, Bouncing),
+            stamp(5.0, 0.1, 1.0, Flying),    // 第 2 跳 (最后一段)
+            stamp(5.5, 0.2, -1.0, Flying),
+            stamp(6.0, -0.2, -1.0, Sinking),
+        ];
+        trajectory[1].froude = 2.5;
+        trajectory[4].froude = 1.5;
+
+        let arcs = segment_skips(&trajectory);
+        let shape: Vec<_> = arcs.iter().map(|a| (a.apex, a.distance, a.complete)).collect();
+        assert_eq!(shape, vec![(0.5, 2.0, true), (0.2, 1.0, false)]);
+
+        // 能量：第 1 跳之前从 2.0 (触水前) 降到 1.1 (起跳)；
+        // 第 2 跳起跳 (1.1) 比触水前 (1.0) 还高，损失为负，即能量反常增加
+        let close = |a: f64, b: f64| (a - b).abs() < 1e-12;
+        assert!(close(arcs[0].energy_start, 1.1) && close(arcs[0].energy_end, 1.0) && close(arcs[0].energy_loss, 0.9));
+        assert!(close(arcs[1].energy_end, 1.2) && close(arcs[1].energy_loss, -0.1));
+        // 每跳记录起跳前那次触水的弗劳德数
+        assert_eq!((arcs[0].froude, arcs[1].froude), (2.5, 1.5));
+
+        let csv = skips_to_csv(&arcs);
+        assert!(csv.starts_with("skip, apex, distance, energy_start, energy_end, energy_loss, froude, complete\n"));
+        assert_eq!(csv.lines().count(), 3);
+
+        // 结束回看：最后一次入水 (下标 4) 前 0.5 帧时间起，到沉没为止
+        let timed: Vec<Stamp> = trajectory.iter().enumerate()
+            .map(|(i, s)| Stamp { t: i as f64 * 0.1, ..s.clone() })
+            .collect();
+        assert_eq!(last_bounce_window(&timed, 0.25), Some((2, 7)));
+        assert_eq!(last_bounce_window(&timed[..1], 0.25), None);
+
+        // 渲染器标注：每段的最高点与沉没位置
+        let markers = trajectory_markers(&trajectory);
+        assert_eq!(markers.apexes, vec![Vector2D::new(3.0, 0.5), Vector2D::new(5.5, 0.2)]);
+        assert_eq!(markers.sink, Some(Vector2D::new(6.0, -0.2)));
+        assert_eq!(markers.range, 6.0);
+    }
+
+    #[test]
+    fn clipping_keeps_the_submerged_half() {
+        let square = vec![
+            Vector2D::new(0.0, -0.5),
+            Vector2D::new(1.0, -0.5),
+            Vector2D::new(1.0, 0.5),
+            Vector2D::new(0.0, 0.5),
+        ];
+        let regions = clip_polygon_below_line(&square, 0.0);
+        assert_eq!(regions.len(), 1);
+        let clipped = &regions[0];
+
+        let expected = [
+            Vector2D::new(0.0, 0.0),
+            Vector2D::new(0.0, -0.5),
+            Vector2D::new(1.0, -0.5),
+            Vector2D::new(1.0, 0.0),
+        ];
+        assert_eq!(clipped.len(), expected.len());
+        for (p, e) in clipped.iter().zip(&expected) {
+            assert!(p.approx_eq(*e, 1e-12), "{:?} != {:?}", p, e);
+        }
+    }
+
+    #[test]
+    fn contact_point_is_the_lowest_outline_point() {
+        let outline = slab_outline(0.02);
+        // 前倾 30°：最低点是前缘下角，质心仍高于它
+        let stone = StoneInfo {
+            position: Vector2D::new(1.0, 0.02),
+            velocity: Vector2D::new(5.0, -1.0),
+            angle: Vector2D::new(-30f64.to_radians(), 0.0),
+            angle_velocity: Vector2D::new(0.0, 0.0),
+        };
+        let contact = contact_point(&outline, &stone).unwrap();
+        let world = outline_in_world(&outline, &stone);
+        assert!(world.iter().all(|p| p.y >= contact.y));
+        assert!(contact.x > stone.position.x && contact.y < 0.0 && stone.position.y > 0.0);
+        assert!(contact_point(&[], &stone).is_none());
+    }
+
+    #[test]
+    fn clipping_keeps_fully_submerged_and_drops_dry_polygons() {
+        let triangle = vec![
+            Vector2D::new(0.0, -1.0),
+            Vector2D::new(2.0, -1.0),
+            Vector2D::new(1.0, -0.2),
+        ];
+        let regions = clip_polygon_below_line(&triangle, 0.0);
+        assert_eq!(regions.len(), 1);
+        assert!((polygon_area(&regions[0]) - polygon_area(&triangle)).abs() < 1e-12);
+        for (p, e) in regions[0].iter().zip(&triangle) {
+            assert!(p.approx_eq(*e, 1e-12));
+        }
+
+        let dry: Vec<Vector2D> = triangle.iter().map(|p| Vector2D::new(p.x, p.y + 2.0)).collect();
+        assert!(clip_polygon_below_line(&dry, 0.0).is_empty());
+    }
+
+    #[test]
+    fn clipping_handles_vertices_and_edges_on_the_line() {
+        // 顶点恰好在水面上，其余在水下：不产生重复点
+        let triangle = vec![
+            Vector2D::new(0.0, 0.0),
+            Vector2D::new(1.0, -1.0),
+            Vector2D::new(-1.0, -1.0),
+        ];
+        let regions = clip_polygon_below_line(&triangle, 0.0);
+        assert_eq!(regions.len(), 1);
+        assert_eq!(regions[0].len(), 3);
+        for (p, e) in regions[0].iter().zip(&triangle) {
+            assert!(p.approx_eq(*e, 1e-12), "{:?} != {:?}", p, e);
+        }
+
+        // 只有一个顶点碰到水面、其余在水面以上：没有浸没区域
+        let touching = vec![
+            Vector2D::new(0.0, 0.0),
+            Vector2D::new(1.0, 1.0),
+            Vector2D::new(-1.0, 1.0),
+        ];
+        assert!(clip_polygon_below_line(&touching, 0.0).is_empty());
+
+        // 水平边恰好在水面上：整个正方形都在水下，水线边不计入湿周
+        let square = vec![
+            Vector2D::new(0.0, -1.0),
+            Vector2D::new(1.0, -1.0),
+            Vector2D::new(1.0, 0.0),
+            Vector2D::new(0.0, 0.0),
+        ];
+        let regions = clip_polygon_below_line(&square, 0.0);
+        assert_eq!(regions.len(), 1);
+        assert_eq!(regions[0].len(), 4);
+        assert!((polygon_area(&regions[0]) - 1.0).abs() < 1e-12);
+        let expected = [
+            Vector2D::new(0.0, 0.0),
+            Vector2D::new(0.0, -1.0),
+            Vector2D::new(1.0, -1.0),
+            Vector2D::new(1.0, 0.0),
+        ];
+        for (p, e) in regions[0].iter().zip(&expected) {
+            assert!(p.approx_eq(*e, 1e-12), "{:?} != {:?}", p, e);
+        }
+        assert!((wetted_perimeter(&regions, 0.0, 0.0) - 3.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn wetted_perimeter_of_half_submerged_unit_square() {
+        let square = vec![
+            Vector2D::new(0.0, -0.5),
+            Vector2D::new(1.0, -0.5),
+            Vector2D::new(1.0, 0.5),
+            Vector2D::new(0.0, 0.5),
+        ];
+        let clipped = clip_polygon_below_surface(&square, 0.0, 0.0);
+
+        // 底边 1 + 两条侧边各 0.5，水线不计入
+        assert!((wetted_perimeter(&clipped, 0.0, 0.0) - 2.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn w_shape_dips_into_two_separate_regions() {
+        // W 形：两个尖角伸到水下 (y = -1)，中间的尖角和两侧都在水面以上
+        let w = vec![
+            Vector2D::new(0.0, 2.0),
+            Vector2D::new(1.0, -1.0),
+            Vector2D::new(2.0, 1.0),
+            Vector2D::new(3.0, -1.0),
+            Vector2D::new(4.0, 2.0),
+        ];
+        let regions = clip_polygon_below_surface(&w, 0.0, 0.0);
+        assert_eq!(regions.len(), 2);
+
+        // 每个尖角在水下是底边宽度为 w、高 1 的三角形
+        // 左尖角：边 (0,2)-(1,-1) 与 (1,-1)-(2,1) 的水面交点为 x = 2/3 与 x = 1.5
+        let left = polygon_area(&regions[0]);
+        let right = polygon_area(&regions[1]);
+        assert!((left - 0.5 * (1.5 - 2.0 / 3.0)).abs() < 1e-12);
+        assert!((right - 0.5 * (10.0 / 3.0 - 2.5)).abs() < 1e-12);
+        assert!((regions_area(&regions) - (left + right)).abs() < 1e-12);
+
+        // 压力中心按面积加权，落在两个区域之间
+        let center = pressure_center(&regions);
+        assert!(center.x > 1.0 && center.x < 3.0);
+        assert!(center.y < 0.0);
+    }
+
+    #[test]
+    fn adaptive_steps_follow_the_phase_and_keep_scoring() {
+        let blueprint = slab_blueprint(0.01, "adaptive");
+        let y0 = StoneInfo {
+            position: Vector2D::new(0.0, 0.05),
+            velocity: Vector2D::new(15.0, -1.0),
+            angle: Vector2D::new(0.35, 0.0),
+            angle_velocity: Vector2D::new(0.0, 15.0),
+        };
+        let run = |adaptive: bool| {
+            let mut rng = Rng::new(5);
+            let stone = StoneProperties::new(&blueprint, &mut rng).unwrap();
+            let mut system = CustomSettings::new(9.81, stone, rng.fork());
+            let mut solver = RungeKuttaSolver::new(0.0, y0.clone());
+            if adaptive {
+                simulate_adaptive(&mut system, &mut solver, 40_000, 1)
+            } else {
+                let dt = system.dt_bouncing;
+                simulate(&mut system, &mut solver, dt, 80_000, 1)
+            }
+        };
+
+        // 1. 每帧之间的步长等于前一帧相位的推荐步长 (入水当步已用细步长)
+        let (trajectory, summary) = run(true);
+        let defaults = (DEFAULT_DT_FLYING, DEFAULT_DT_BOUNCING);
+        let mut saw = (false, false);
+        for pair in trajectory.windows(2) {
+            let dt = pair[1].t - pair[0].t;
+            let expected = if pair[1].phase == Phase::Flying && pair[0].phase == Phase::Flying {
+                saw.0 = true;
+                defaults.0
+            } else if pair[1].phase == Phase::Bouncing {
+                saw.1 = true;
+                defaults.1
+            } else {
+                continue;
+            };
+            assert!((dt - expected).abs() < 1e-12, "step {} at t = {}", dt, pair[0].t);
+        }
+        assert!(saw.0 && saw.1);
+
+        // 飞行段机械能守恒，每次触水都耗散能量
+        for arc in segment_skips(&trajectory) {
+            assert!((arc.energy_end - arc.energy_start).abs() < 1e-9 * arc.energy_start.abs().max(1.0));
+            assert!(arc.energy_loss > 0.0);
+        }
+
+        // 2. 计分与全程细步长的结果一致 (飞行段只受重力，粗步长的 RK4 是精确的)
+        let (_, fine) = run(false);
+        assert!(summary.skips > 0);
+        assert_eq!(summary.skips, fine.skips);
+        assert!((summary.air_time - fine.air_time).abs() < 2.0 * DEFAULT_DT_FLYING * summary.skips as f64);
+        assert!((summary.distance - fine.distance).abs() < 0.05 * fine.distance);
+    }
+
+    #[test]
+    fn submerged_stone_at_rest_floats_or_sinks_by_density() {
+        let outline = slab_outline(0.01);
+        // 完全浸没 (上表面在水面下 1.5 cm)，静止
+        let y0 = StoneInfo {
+            position: Vector2D::new(0.0, -0.02),
+            velocity: Vector2D::new(0.0, 0.0),
+            angle: Vector2D::new(0.0, 0.0),
+            angle_velocity: Vector2D::new(0.0, 0.0),
+        };
+        let drop = |density: f64, buoyancy: bool| {
+            let stone = StoneProperties::from_outline(&outline, 0.01, density).unwrap();
+            let mut system = CustomSettings::new(9.81, stone, Rng::new(1)).with_initial_state(&y0);
+            system.buoyancy = buoyancy;
+            assert_eq!(system.phase, Phase::Bouncing);
+            let mut solver = RungeKuttaSolver::new(0.0, y0.clone());
+            simulate_adaptive(&mut system, &mut solver, 10_000, 1);
+            (system.phase, solver.state.position.y)
+        };
+
+        // 1. 水面以上的石片仍从 Flying 开始
+        let stone = StoneProperties::from_outline(&outline, 0.01, 2500.0).unwrap();
+        let above = StoneInfo { position: Vector2D::new(0.0, 0.05), ..y0.clone() };
+        assert_eq!(CustomSettings::new(9.81, stone, Rng::new(1)).with_initial_state(&above).phase, Phase::Flying);
+
+        // 2. 比水重的石片下沉
+        let (phase, _) = drop(2500.0, true);
+        assert_eq!(phase, Phase::Sinking);
+
+        // 3. 比水轻的石片上浮，上表面到达水面后漂浮而不沉没
+        let (phase, y) = drop(500.0, true);
+        assert_ne!(phase, Phase::Sinking);
+        assert!(y > -0.01, "y = {}", y);
+
+        // 4. 不计浮力时静止的石片在水中失速，直接沉没
+        let (phase, _) = drop(500.0, false);
+        assert_eq!(phase, Phase::Sinking);
+    }
+
+    #[test]
+    fn equilibrium_pose_balances_buoyancy_and_torque() {
+        let settings = |outline: &[Vector2D], density: f64| {
+            let stone = StoneProperties::from_outline(outline, 0.01, density).unwrap();
+            CustomSettings::new(9.81, stone, Rng::new(1))
+        };
+        let plank = slab_outline(0.01);
+        let square = [
+            Vector2D::new(-0.01, -0.01),
+            Vector2D::new(0.01, -0.01),
+            Vector2D::new(0.01, 0.01),
+            Vector2D::new(-0.01, 0.01),
+        ];
+
+        // 1. 扁平木板平躺，吃水 = 厚度 * 密度比
+        let system = settings(&plank, 500.0);
+        let (pitch, draft) = system.equilibrium_pose().unwrap();
+        assert!(pitch.abs() < 1e-6, "pitch {}", pitch);
+        assert!((draft - 0.01 * 500.0 / system.rho).abs() < 1e-9, "draft {}", draft);
+
+        // 2. 密度为水一半的方块以对角线竖直漂浮，恰好一半浸没
+        let system = settings(&square, 0.5 * water_density(DEFAULT_WATER_TEMP_C));
+        let (pitch, draft) = system.equilibrium_pose().unwrap();
+        assert!((pitch.abs() - PI / 4.0).abs() < 1e-6, "pitch {}", pitch);
+        assert!((draft - 0.01 * 2f64.sqrt()).abs() < 1e-9, "draft {}", draft);
+
+        // 3. 比水重的石片没有漂浮姿态
+        assert!(settings(&plank, 2700.0).equilibrium_pose().is_none());
+
+        // 4. 静置入水：从平衡姿态静止释放 (计浮力)，石片留在原处
+        let mut system = settings(&plank, 500.0);
+        system.buoyancy = true;
+        let y0 = system.rest_state().unwrap();
+        let mut system = system.with_initial_state(&y0);
+        let mut solver = RungeKuttaSolver::new(0.0, y0.clone());
+        // 初始时刻合力为零；其余水动力项使它缓慢漂移，但始终漂浮在平衡位置附近
+        assert!(system.deriv_at(0.0, &y0).velocity.length() < 1e-9);
+        simulate_adaptive(&mut system, &mut solver, 2_000, 1);
+        assert_ne!(system.phase, Phase::Sinking);
+        assert!((solver.state.position.y - y0.position.y).abs() < 0.005, "y = {}", solver.state.position.y);
+        assert!(solver.state.angle.x.abs() < 0.01);
+    }
+
+    #[test]
+    fn trajectory_distance_aligns_times_and_uses_the_overlap() {
+        // 匀速直线运动 x = t，按给定时刻记录，y 方向整体平移 dy
+        let line = |times: &[f64], dy: f64| -> Vec<Stamp> {
+            times.iter().map(|&t| Stamp {
+                t,
+                state: StoneInfo {
+                    position: Vector2D::new(t, dy),
+                    velocity: Vector2D::new(1.0, 0.0),
+                    angle: Vector2D::new(0.0, 0.0),
+                    angle_velocity: Vector2D::new(0.0, 0.0),
+                },
+                energy: 0.0,
+                phase: Phase::Flying,
+                wetted_perimeter: 0.0,
+                froude: 0.0,
+                peak_g: 0.0,
+                forces: None,
+                submerged: Vec::new(),
+            }).collect()
+        };
+        let coarse: Vec<f64> = (0..=10).map(|i| i as f64 * 0.1).collect();
+        let fine: Vec<f64> = (0..=70).map(|i| i as f64 * 0.01 + 0.3).collect(); // 0.3 ~ 1.0
+
+        // 1. 同一运动，不同记录步长：插值后完全一致
+        assert!(trajectory_distance(&line(&coarse, 0.0), &line(&fine, 0.0)) < 1e-12);
+        // 2. 整体平移 0.2 m：RMS 差就是 0.2 (只比较重叠的 0.3 ~ 1.0 s)
+        let d = trajectory_distance(&line(&coarse, 0.0), &line(&fine, 0.2));
+        assert!((d - 0.2).abs() < 1e-12);
+        // 3. 没有重叠
+        assert!(trajectory_distance(&line(&[0.0, 0.1], 0.0), &line(&[0.5, 0.6], 0.0)).is_nan());
+        assert!(trajectory_distance(&[], &line(&coarse, 0.0)).is_nan());
+    }
+
+    #[test]
+    fn streak_multiplier_grows_with_consecutive_skips() {
+        let mut system = CustomSettings::new(9.81, StoneProperties::default(), Rng::new(1));
+        let state = stamp(0.0, 0.0, 0.0, Phase::Flying).state;
+        let mut summary = SimSummary::new(&state);
+        let v = state.velocity;
+        // 依次把相位切到 `phase` 并记录一步
+        let step = |summary: &mut SimSummary, system: &mut CustomSettings, phase: Phase, dt: f64| {
+            let before = system.phase;
+            system.phase = phase;
+            summary.record(before, v, system, &state, dt);
+        };
+
+        // 触水 -> 第 1 跳，飞行 1 s (x1.0) -> 触水 -> 第 2 跳，飞行 1 s (x1.1)
+        for _ in 0..2 {
+            step(&mut summary, &mut system, Phase::Bouncing, 0.01);
+            step(&mut summary, &mut system, Phase::Flying, 0.5);
+            step(&mut summary, &mut system, Phase::Flying, 0.5);
+        }
+        assert_eq!(summary.skips, 2);
+        assert!((summary.air_time - 2.0).abs() < 1e-12);
+        assert!((summary.streak_score - 2.1).abs() < 1e-12);
+        assert_eq!(ScoreMode::Streak.score(&summary), summary.streak_score);
+        assert!((summary.streak_multiplier() - 1.1).abs() < 1e-12);
+
+        // 沉没后倍率归 1，已得的分数保留
+        step(&mut summary, &mut system, Phase::Sinking, 0.01);
+        assert_eq!(summary.streak_multiplier(), 1.0);
+        assert!((summary.streak_score - 2.1).abs() < 1e-12);
+    }
+
+    #[test]
+    fn incidence_angle_is_measured_from_the_surface() {
+        assert!((incidence_angle(Vector2D::new(1.0, -1.0), 0.0) - 45.0).abs() < 1e-12);
+        assert!((incidence_angle(Vector2D::new(-3.0, -3.0), 0.0) - 45.0).abs() < 1e-12);
+        // 沿倾斜水面运动：夹角为 0
+        let slope = 10f64.to_radians().tan();
+        assert!(incidence_angle(Vector2D::new(2.0, 2.0 * slope), slope).abs() < 1e-12);
+
+        // 冲量模型：首次触水时记录入水角，之后的弹跳不覆盖
+        let stone = StoneProperties::from_outline(&slab_outline(0.01), 0.01, 2500.0).unwrap();
+        let mut system = CustomSettings::new(9.81, stone, Rng::new(1));
+        system.bounce_model = BounceModel::Impulse;
+        let mut solver = RungeKuttaSolver::new(0.0, StoneInfo {
+            position: Vector2D::new(0.0, 0.006),
+            velocity: Vector2D::new(10.0, -10.0 * 15f64.to_radians().tan()),
+            angle: Vector2D::new(0.0, 0.0),
+            angle_velocity: Vector2D::new(0.0, 0.0),
+        });
+        let (_, summary) = simulate(&mut system, &mut solver, 0.0005, 200, 1);
+        let angle = summary.impact_angle.expect("should have touched the water");
+        // 下落途中重力使入水角略大于抛出角
+        assert!(angle > 15.0 && angle < 15.3, "angle = {}", angle);
+    }
+
+    #[test]
+    fn spin_lift_rewards_a_fast_spin() {
+        let outline = slab_outline(0.02);
+        // 15 m/s、0.35 rad 攻角的正常打法 (与黄金轨迹相同)：不开耦合时只弹一次，
+        // 离弹跳次数的临界点足够远，附加升力的效果不会被一次弹跳的得失掩盖
+        let skips = |spin: f64, coeff: f64| {
+            let y0 = StoneInfo {
+                position: Vector2D::new(0.0, 0.05),
+                velocity: Vector2D::new(15.0, -1.0),
+                angle: Vector2D::new(0.35, 0.0),
+                angle_velocity: Vector2D::new(0.0, spin),
+            };
+            let stone = StoneProperties::from_outline(&outline, 0.01, 2700.0).unwrap();
+            let mut system = CustomSettingsBuilder::new().spin_lift_coeff(coeff).rng(Rng::new(1)).build(stone).with_initial_state(&y0);
+            let mut solver = RungeKuttaSolver::new(0.0, y0);
+            simulate_adaptive(&mut system, &mut solver, 80_000, 1).1.skips
+        };
+        // 开启耦合后高速自转的石片弹得明显更多；不自转时耦合不起作用
+        assert!(skips(40.0, 1.0) >= skips(0.0, 1.0) + 2, "{} vs {}", skips(40.0, 1.0), skips(0.0, 1.0));
+        assert_eq!(skips(0.0, 1.0), skips(0.0, 0.0));
+        // 关闭耦合时同样的自转没有这份附加升力
+        assert!(skips(40.0, 1.0) >= skips(40.0, 0.0) + 2, "{} vs {}", skips(40.0, 1.0), skips(40.0, 0.0));
+    }
+
+    #[test]
+    fn bobbing_float_settles_and_ends_the_run() {
+        // 1. 滚动窗口：原地小幅振荡满足判定，匀速移动或关闭判定时不满足
+        let state = |x: f64, vx: f64| StoneInfo {
+            position: Vector2D::new(x, 0.0),
+            velocity: Vector2D::new(vx, 0.0),
+            angle: Vector2D::new(0.0, 0.0),
+            angle_velocity: Vector2D::new(0.0, 0.0),
+        };
+        let criteria = SettleCriteria::default();
+        let settles_at = |criteria: SettleCriteria, path: &dyn Fn(f64) -> StoneInfo| {
+            let mut tracker = SettleTracker::new(criteria);
+            (0..1000).map(|i| i as f64 * 0.01).find(|&t| tracker.push(t, &path(t)))
+        };
+        let t = settles_at(criteria, &|t| state(100.0 + 0.001 * (7.0 * t).sin(), 0.007 * (7.0 * t).cos())).unwrap();
+        assert!((t - criteria.window).abs() < 1e-9, "t = {}", t);
+        assert_eq!(settles_at(criteria, &|t| state(0.01 * t, 0.01)), None);
+        assert_eq!(settles_at(SettleCriteria { window: 0.0, ..criteria }, &|_| state(0.0, 0.0)), None);
+
+        // 2. 从平衡位置向下压一点释放的漂浮木板：上下晃动后停稳，不会一直跑到步数上限
+        let plank = slab_outline(0.01);
+        let stone = StoneProperties::from_outline(&plank, 0.01, 500.0).unwrap();
+        let mut system = CustomSettings::new(9.81, stone, Rng::new(1));
+        system.buoyancy = true;
+        let rest = system.rest_state().unwrap();
+        let float = |gravity: f64, dy: f64| {
+            let mut y0 = rest.clone();
+            y0.position.y += dy;
+            let mut system = CustomSettings::new(gravity, system.stone.clone(), Rng::new(1));
+            system.buoyancy = true;
+            system.set_initial_phase(&y0);
+            let mut solver = RungeKuttaSolver::new(0.0, y0);
+            simulate_adaptive(&mut system, &mut solver, 200_000, 1).1.end_reason
+        };
+        assert_eq!(float(9.81, -0.001), SimEndReason::Settled);
+        // 3. 完全不受力、一动不动地浮在水面上：在滚动窗口填满之前就满足 "几乎不动"，
+        // 同样算停稳而不是卡住
+        assert_eq!(float(0.0, 0.0), SimEndReason::Settled);
     }
 
-    area.abs() * 0.5
+    #[test]
+    fn sinking_stone_comes_to_rest_on_the_bottom() {
+        let outline = slab_outline(0.01);
+        let y0 = StoneInfo {
+            position: Vector2D::new(0.0, 0.05),
+            velocity: Vector2D::new(0.5, -3.0),
+            angle: Vector2D::new(0.1, 0.0),
+            angle_velocity: Vector2D::new(0.0, 0.0),
+        };
+        let run = |depth: f64| {
+            let stone = StoneProperties::from_outline(&outline, 0.01, 2700.0).unwrap();
+            let mut system = CustomSettingsBuilder::new().water_depth(depth).rng(Rng::new(1)).build(stone).with_initial_state(&y0);
+            let mut solver = RungeKuttaSolver::new(0.0, y0.clone());
+            let (trajectory, summary) = simulate_adaptive(&mut system, &mut solver, 200_000, 1);
+            (system, solver, trajectory, summary)
+        };
+
+        // 无底：一开始沉没模拟即结束
+        let (_, shallow_solver, _, summary) = run(0.0);
+        assert_eq!(summary.end_reason, SimEndReason::Sank);
+
+        // 0.3 m 深：继续下沉，轮廓最低点恰好停在池底，速度归零
+        let (system, solver, trajectory, summary) = run(0.3);
+        assert_eq!(summary.end_reason, SimEndReason::HitBottom);
+        assert_eq!(summary.final_phase, Phase::Sinking);
+        let gap = system.bottom_gap(&solver.state).unwrap();
+        assert!((0.0..1e-9).contains(&gap), "gap {}", gap);
+        assert_eq!(solver.state.velocity.length(), 0.0);
+        assert!(solver.t > shallow_solver.t);
+        assert!(trajectory.iter().all(|s| s.state.position.y > -0.31));
+        // 下沉过程不计入水平跨度
+        assert!(summary.distance < solver.state.position.x);
+    }
+
+    #[test]
+    fn phase_events_log_every_transition() {
+        let outline = slab_outline(0.02);
+        let y0 = StoneInfo {
+            position: Vector2D::new(0.0, 0.05),
+            velocity: Vector2D::new(15.0, -1.0),
+            angle: Vector2D::new(0.35, 0.0),
+            angle_velocity: Vector2D::new(0.0, 15.0),
+        };
+        let run = |model: BounceModel| {
+            let stone = StoneProperties::from_outline(&outline, 0.01, 2700.0).unwrap();
+            let mut system = CustomSettingsBuilder::new().bounce_model(model).rng(Rng::new(1)).build(stone).with_initial_state(&y0);
+            let mut solver = RungeKuttaSolver::new(0.0, y0.clone());
+            simulate(&mut system, &mut solver, 0.0005, 20_000, 1)
+        };
+        let count = |events: &[PhaseEvent], from: Phase, to: Phase| events.iter().filter(|e| e.from == from && e.to == to).count();
+
+        for model in [BounceModel::Continuous, BounceModel::Impulse] {
+            let (trajectory, summary) = run(model);
+            let events = &summary.events;
+            assert!(summary.skips > 0);
+            assert_eq!(count(events, Phase::Bouncing, Phase::Flying), summary.skips as usize, "{:?}", model);
+            assert_eq!(events[0].from, Phase::Flying);
+            assert_eq!(events.last().unwrap().to, Phase::Sinking);
+            assert!(events.windows(2).all(|w| w[0].t <= w[1].t && w[0].to == w[1].from));
+
+            // 事件记录的是判定相位时的步首状态，与同一时刻的轨迹帧一致
+            // (冲量反弹的帧始终是 Flying，看不到中间的 Bouncing)
+            for event in events {
+                let stamp = trajectory.iter().find(|s| s.t == event.t).unwrap();
+                assert_eq!(stamp.state.position, event.position);
+                assert!(stamp.phase == event.from || model == BounceModel::Impulse);
+            }
+        }
+    }
+
+    #[test]
+    fn end_reason_tells_why_the_run_stopped() {
+        let outline = slab_outline(0.01);
+        let run = |gravity: f64, (y, vx, vy): (f64, f64, f64), dt: f64, max_steps: usize| {
+            let stone = StoneProperties::from_outline(&outline, 0.01, 2500.0).unwrap();
+            let y0 = StoneInfo {
+                position: Vector2D::new(0.0, y),
+                velocity: Vector2D::new(vx, vy),
+                angle: Vector2D::new(0.0, 0.0),
+                angle_velocity: Vector2D::new(0.0, 0.0),
+            };
+            let mut system = CustomSettings::new(gravity, stone, Rng::new(1)).with_initial_state(&y0);
+            let mut solver = RungeKuttaSolver::new(0.0, y0);
+            simulate(&mut system, &mut solver, dt, max_steps, 1).1.end_reason
+        };
+
+        // 没有水平速度，直接落水沉没
+        assert_eq!(run(9.81, (0.05, 0.0, -3.0), 0.0005, 20_000), SimEndReason::Sank);
+        // 步数不够：与沉没区分开
+        assert_eq!(run(9.81, (1.0, 5.0, 0.0), 0.001, 10), SimEndReason::MaxStepsReached);
+        // 无重力时一直飞走，或停在空中不动
+        assert_eq!(run(0.0, (1.0, 100.0, 0.0), 0.1, 1_000), SimEndReason::LeftBounds);
+        assert_eq!(run(0.0, (1.0, 0.0, 0.0), 0.01, 1_000), SimEndReason::Stuck);
+        assert!(SimEndReason::Sank.is_finished() && !SimEndReason::MaxStepsReached.is_finished());
+    }
+
+    #[test]
+    fn restitution_decay_shortens_skips_until_the_stone_sinks() {
+        let stone = StoneProperties::from_outline(&slab_outline(0.01), 0.01, 2500.0).unwrap();
+        let run = |decay: f64, curve: DecayCurve| {
+            let mut system = CustomSettingsBuilder::new()
+                .bounce_model(BounceModel::Impulse)
+                .restitution(0.8)
+                .tangent_friction(0.02)
+                .restitution_decay(decay, curve)
+                .build(stone.clone());
+            let mut solver = RungeKuttaSolver::new(0.0, StoneInfo {
+                position: Vector2D::new(0.0, 0.1),
+                velocity: Vector2D::new(10.0, -2.0),
+                angle: Vector2D::new(0.0, 0.0),
+                angle_velocity: Vector2D::new(0.0, 0.0),
+            });
+            let (trajectory, summary) = simulate(&mut system, &mut solver, 0.0005, 200_000, 1);
+            (segment_skips(&trajectory), summary, system.bounce_count)
+        };
+
+        for curve in [DecayCurve::Geometric, DecayCurve::Linear] {
+            let (arcs, summary, bounces) = run(0.15, curve);
+            assert_eq!(summary.final_phase, Phase::Sinking, "{:?}", curve);
+            let complete: Vec<f64> = arcs.iter().filter(|a| a.complete).map(|a| a.distance).collect();
+            assert!(complete.len() >= 2, "{:?}: {:?}", curve, complete);
+            assert!(complete.windows(2).all(|w| w[1] < w[0]), "{:?}: {:?}", curve, complete);
+            assert!(bounces > 0);
+        }
+
+        // 衰减让石片更早沉没
+        let (without, _, _) = run(0.0, DecayCurve::Geometric);
+        let (with, _, _) = run(0.15, DecayCurve::Geometric);
+        assert!(with.len() < without.len(), "{} vs {}", with.len(), without.len());
+
+        // 衰减趋于 0 时连续地趋于不衰减的结果 (判据在 decay = 0 处没有跳变)
+        for curve in [DecayCurve::Geometric, DecayCurve::Linear] {
+            let (tiny, _, tiny_bounces) = run(1e-9, curve);
+            let (_, _, bounces) = run(0.0, curve);
+            assert_eq!((tiny.len(), tiny_bounces), (without.len(), bounces), "{:?}", curve);
+        }
+    }
+
+    #[test]
+    fn impulse_skips_do_not_depend_on_the_launch_direction() {
+        // 对称的石片向 +x 和 -x 镜像抛出：弹跳次数相同，落点关于起点对称
+        let stone = StoneProperties::from_outline(&slab_outline(0.01), 0.01, 2500.0).unwrap();
+        let run = |vx: f64| {
+            let mut system = CustomSettingsBuilder::new()
+                .bounce_model(BounceModel::Impulse)
+                .restitution(0.8)
+                .tangent_friction(0.02)
+                .build(stone.clone());
+            let mut solver = RungeKuttaSolver::new(0.0, StoneInfo {
+                position: Vector2D::new(0.0, 0.1),
+                velocity: Vector2D::new(vx, -2.0),
+                angle: Vector2D::new(0.0, 0.0),
+                angle_velocity: Vector2D::new(0.0, 0.0),
+            });
+            let (trajectory, summary) = simulate(&mut system, &mut solver, 0.0005, 200_000, 1);
+            (summary.skips, system.bounce_count, trajectory.last().unwrap().state.position.x)
+        };
+
+        let (forward, forward_bounces, forward_x) = run(10.0);
+        let (backward, backward_bounces, backward_x) = run(-10.0);
+        assert!(forward > 1, "{}", forward);
+        assert_eq!((forward, forward_bounces), (backward, backward_bounces));
+        assert!((forward_x + backward_x).abs() < 1e-6, "{} vs {}", forward_x, backward_x);
+    }
+
+    #[test]
+    fn phase_entry_uses_the_true_lowest_outline_point() {
+        // 不对称的石片：前端细长，后端短而厚；机头朝下时最低点是前端尖
+        let stone = StoneProperties::from_outline(&[
+            Vector2D::new(-0.02, -0.008),
+            Vector2D::new(0.07, -0.002),
+            Vector2D::new(0.07, 0.002),
+            Vector2D::new(-0.02, 0.008),
+        ], 0.01, 2500.0).unwrap();
+        let mut system = CustomSettings::new(9.81, stone, Rng::new(1));
+        let pitch: f64 = -0.1;
+        let state = StoneInfo {
+            position: Vector2D::new(0.0, 0.002),
+            velocity: Vector2D::new(5.0, -1.0),
+            angle: Vector2D::new(pitch, 0.0),
+            angle_velocity: Vector2D::new(0.0, 0.0),
+        };
+
+        // 按最大半径估计的最低点仍在水面以上，真实轮廓已经触水
+        let estimate = state.position.y - system.stone.d_max.sqrt() * pitch.sin();
+        assert!(estimate > 0.0, "{}", estimate);
+        let lowest = system.contact_point(&state);
+        assert!(lowest.y < 0.0 && lowest.x > 0.0, "{:?}", lowest);
+        let outline = system.outline_to_world(&state);
+        assert!(outline.iter().all(|p| p.y >= lowest.y));
+
+        system.update_phase(&state);
+        assert_eq!(system.phase, Phase::Bouncing);
+        assert!(!system.current_submerged_regions.is_empty());
+    }
+
+    #[test]
+    fn submerged_fraction_is_a_pure_query() {
+        let stone = StoneProperties::from_outline(&slab_outline(0.01), 0.01, 2500.0).unwrap();
+        let system = CustomSettings::new(9.81, stone, Rng::new(1));
+        let at = |y: f64, pitch: f64| StoneInfo {
+            position: Vector2D::new(0.0, y),
+            velocity: Vector2D::new(0.0, 0.0),
+            angle: Vector2D::new(pitch, 0.0),
+            angle_velocity: Vector2D::new(0.0, 0.0),
+        };
+
+        assert_eq!(system.submerged_fraction(&at(0.1, 0.0)), 0.0);
+        assert!((system.submerged_fraction(&at(-0.1, 0.0)) - 1.0).abs() < 1e-12);
+        assert!((system.submerged_fraction(&at(0.0, 0.0)) - 0.5).abs() < 1e-12);
+        // 质心在水面上，任意俯仰角下对称的矩形都是一半浸没
+        assert!((system.submerged_fraction(&at(0.0, 0.4)) - 0.5).abs() < 1e-12);
+
+        // 不影响运行状态
+        assert_eq!(system.Sim, 0.01);
+        assert!(system.current_submerged_regions.is_empty());
+    }
+
+    #[test]
+    fn force_regions_follow_the_accepted_step() {
+        let stone = StoneProperties::from_outline(&slab_outline(0.01), 0.01, 2500.0).unwrap();
+        // 一半浸在水中、水平滑行的石片
+        let y0 = StoneInfo {
+            position: Vector2D::new(0.0, 0.0),
+            velocity: Vector2D::new(5.0, -0.5),
+            angle: Vector2D::new(0.1, 0.0),
+            angle_velocity: Vector2D::new(0.0, 0.0),
+        };
+        let mut system = CustomSettings::new(9.81, stone, Rng::new(1)).with_initial_state(&y0);
+        let mut solver = RungeKuttaSolver::new(0.0, y0);
+        assert!(system.force_regions().is_empty());
+
+        assert!(advance(&mut system, &mut solver, 1e-4));
+        let regions = system.force_regions();
+        assert!(!regions.is_empty());
+        // 最后一次导数求值接近步末状态：面积与按步末状态重新裁剪的结果相近
+        let expected = system.submerged_fraction(&solver.state) * system.stone.area;
+        assert!((regions_area(&regions) - expected).abs() < 0.01 * expected);
+        assert_eq!(Stamp::capture(&system, &solver).submerged.len(), regions.len());
+
+        // 离开水面后的一步不再有受力多边形
+        system.phase = Phase::Flying;
+        solver.state.position.y = 1.0;
+        assert!(advance(&mut system, &mut solver, 1e-4));
+        assert!(system.force_regions().is_empty());
+    }
 }
@@ -0,0 +1,319 @@
+// 职责：冻结/恢复一次模拟的完整可积分状态，用于快速存档与调试。
+// 快照包含求解器时间与状态、相位、可调参数以及所有运行状态 (浸没多边形、随机数源、阵风分量)，
+// 恢复后继续积分与原运行逐位一致。
+// 不包含石片本身 (StoneProperties)：恢复时必须使用同一块石片构建的 CustomSettings。
+// 与 run.json 相同，文件格式是手写的扁平 JSON 对象。
+
+use std::fs;
+
+use crate::basic_structs::Vector2D;
+use crate::physics::parameters::{CustomSettings, GustMode, Phase, Ripple};
+use crate::physics::simulation::StoneInfo;
+use crate::rng::Rng;
+use crate::run_record::{parse_numbers, split_object, SettingsSnapshot};
+use crate::solver2::{RkMethod, RungeKuttaSolver};
+
+/// 默认快速存档文件路径 (相对于工作目录)
+pub const SNAPSHOT_FILE: &str = "quicksave.json";
+
+/// 模拟在某一时刻的完整状态
+pub struct SimSnapshot {
+    pub t: f64,
+    pub state: StoneInfo,
+    pub phase: Phase,
+    pub method: RkMethod,
+    pub settings: SettingsSnapshot,
+
+    // 运行状态
+    pub submerged_area: f64,
+    pub wetted_perimeter: f64,
+    pub submerged_regions: Vec<Vec<Vector2D>>,
+    pub angular_clamp_warned: bool,
+    pub bounce_count: u32,
+    pub bouncing_entry_t: Option<f64>,
+    pub reached_wall: bool,
+    pub hit_bottom: bool,
+    pub ripples: Vec<Ripple>,
+    pub ripple_offset: f64,
+    pub rng_state: u64,
+    pub(crate) gust_modes: Vec<GustMode>,
+}
+
+impl CustomSettings {
+    /// 记录当前的完整模拟状态 (连同求解器的时间、状态与积分方法)
+    pub fn snapshot(&self, solver: &RungeKuttaSolver<StoneInfo>) -> SimSnapshot {
+        SimSnapshot {
+            t: solver.t,
+            state: solver.state.clone(),
+            phase: self.phase,
+            method: solver.method,
+            settings: SettingsSnapshot::capture(self),
+            submerged_area: self.Sim,
+            wetted_perimeter: self.wetted_perimeter,
+            submerged_regions: self.current_submerged_regions.clone(),
+            angular_clamp_warned: self.angular_clamp_warned,
+            bounce_count: self.bounce_count,
+            bouncing_entry_t: self.bouncing_entry_t,
+            reached_wall: self.reached_wall,
+            hit_bottom: self.hit_bottom,
+            ripples: self.ripples.clone(),
+            ripple_offset: self.ripple_offset,
+            rng_state: self.rng.state(),
+            gust_modes: self.gust_modes.clone(),
+        }
+    }
+
+    /// 从快照恢复模拟状态与求解器；性能统计 (导数求值次数) 保持不变
+    pub fn restore(&mut self, snapshot: &SimSnapshot, solver: &mut RungeKuttaSolver<StoneInfo>) {
+        snapshot.settings.apply(self);
+        self.phase = snapshot.phase;
+        self.Sim = snapshot.submerged_area;
+        self.wetted_perimeter = snapshot.wetted_perimeter;
+        self.current_submerged_regions = snapshot.submerged_regions.clone();
+        self.angular_clamp_warned = snapshot.angular_clamp_warned;
+        self.bounce_count = snapshot.bounce_count;
+        self.bouncing_entry_t = snapshot.bouncing_entry_t;
+        self.reached_wall = snapshot.reached_wall;
+        self.hit_bottom = snapshot.hit_bottom;
+        self.ripples = snapshot.ripples.clone();
+        self.ripple_offset = snapshot.ripple_offset;
+        self.rng = Rng::new(snapshot.rng_state);
+        self.gust_modes = snapshot.gust_modes.clone();
+        *self.outline_cache.borrow_mut() = None;
+
+        solver.t = snapshot.t;
+        solver.state = snapshot.state.clone();
+        solver.method = snapshot.method;
+        solver.error = None;
+    }
+}
+
+impl SimSnapshot {
+    /// 写入文件；失败时只打印警告
+    pub fn save(&self, path: &str) {
+        if let Err(e) = fs::write(path, self.to_json()) {
+            println!("Warning: 无法写入快照 {}: {}", path, e);
+        }
+    }
+
+    /// 从文件读取；返回可读的错误信息
+    pub fn load(path: &str) -> Result<Self, String> {
+        let text = fs::read_to_string(path).map_err(|e| format!("无法读取 {}: {}", path, e))?;
+        parse_snapshot(&text).ok_or_else(|| format!("{} 格式错误", path))
+    }
+
+    fn to_json(&self) -> String {
+        let y = &self.state;
+        // 浸没区域展平存储：所有点依次排列，另记每个区域的点数
+        let points: Vec<String> = self.submerged_regions.iter().flatten()
+            .map(|p| format!("{}, {}", p.x, p.y))
+            .collect();
+        let counts: Vec<String> = self.submerged_regions.iter().map(|r| r.len().to_string()).collect();
+        let ripples: Vec<String> = self.ripples.iter()
+            .map(|r| format!("{}, {}, {}", r.x0, r.t0, r.amplitude))
+            .collect();
+        let gusts: Vec<String> = self.gust_modes.iter()
+            .map(|m| format!("{}, {}, {}, {}", m.freq_ratio, m.phase_x, m.phase_y, m.weight))
+            .collect();
+
+        let mut fields = vec![
+            format!("\"t\": {}", self.t),
+            format!("\"state\": [{}, {}, {}, {}, {}, {}, {}, {}]",
+                    y.position.x, y.position.y, y.velocity.x, y.velocity.y,
+                    y.angle.x, y.angle.y, y.angle_velocity.x, y.angle_velocity.y),
+            format!("\"phase\": \"{:?}\"", self.phase),
+            format!("\"method\": \"{}\"", self.method.name()),
+            format!("\"submerged_area\": {}", self.submerged_area),
+            format!("\"wetted_perimeter\": {}", self.wetted_perimeter),
+            format!("\"submerged_points\": [{}]", points.join(", ")),
+            format!("\"submerged_counts\": [{}]", counts.join(", ")),
+            format!("\"angular_clamp_warned\": {}", self.angular_clamp_warned),
+            format!("\"bounce_count\": {}", self.bounce_count),
+            format!("\"bouncing_entry_t\": {}", self.bouncing_entry_t.map_or("null".to_string(), |t| t.to_string())),
+            format!("\"reached_wall\": {}", self.reached_wall),
+            format!("\"hit_bottom\": {}", self.hit_bottom),
+            format!("\"ripples\": [{}]", ripples.join(", ")),
+            format!("\"ripple_offset\": {}", self.ripple_offset),
+            format!("\"rng_state\": {}", self.rng_state),
+            format!("\"gust_modes\": [{}]", gusts.join(", ")),
+        ];
+        fields.extend(self.settings.json_fields());
+        format!("{{\n  {}\n}}\n", fields.join(",\n  "))
+    }
+}
+
+fn parse_snapshot(text: &str) -> Option<SimSnapshot> {
+    let fields = split_object(text)?;
+    let get = |key: &str| fields.iter().find(|(k, _)| k == key).map(|(_, v)| v.as_str());
+    let num = |key: &str| get(key)?.parse::<f64>().ok().filter(|x| x.is_finite());
+
+    // 1. 求解器状态
+    let y = parse_numbers(get("state")?)?;
+    if y.len() != 8 { return None; }
+    let state = StoneInfo {
+        position: Vector2D::new(y[0], y[1]),
+        velocity: Vector2D::new(y[2], y[3]),
+        angle: Vector2D::new(y[4], y[5]),
+        angle_velocity: Vector2D::new(y[6], y[7]),
+    };
+    let phase = match get("phase")?.trim_matches('"') {
+        "Flying" => Phase::Flying,
+        "Bouncing" => Phase::Bouncing,
+        "Sinking" => Phase::Sinking,
+        _ => return None,
+    };
+    let method = match get("method")?.trim_matches('"') {
+        "Euler" => RkMethod::Euler,
+        "Midpoint" => RkMethod::Midpoint,
+        "RK4" => RkMethod::Rk4,
+        _ => return None,
+    };
+
+    // 2. 运行状态
+    // 旧快照只有一个浸没多边形 ("submerged_polygon")，读作单个区域
+    let (coords, counts) = match get("submerged_points") {
+        Some(points) => (parse_numbers(points)?, Some(parse_numbers(get("submerged_counts")?)?)),
+        None => (parse_numbers(get("submerged_polygon")?)?, None),
+    };
+    if coords.len() % 2 != 0 { return None; }
+    let points: Vec<Vector2D> = coords.chunks(2).map(|c| Vector2D::new(c[0], c[1])).collect();
+    let counts = counts.unwrap_or_else(|| if points.is_empty() { Vec::new() } else { vec![points.len() as f64] });
+    let mut submerged_regions = Vec::new();
+    let mut rest = points.as_slice();
+    for count in counts {
+        let count = count as usize;
+        if count > rest.len() { return None; }
+        let (region, tail) = rest.split_at(count);
+        submerged_regions.push(region.to_vec());
+        rest = tail;
+    }
+    if !rest.is_empty() { return None; }
+
+    let ripples = parse_numbers(get("ripples")?)?;
+    if ripples.len() % 3 != 0 { return None; }
+    let ripples = ripples.chunks(3)
+        .map(|c| Ripple { x0: c[0], t0: c[1], amplitude: c[2] })
+        .collect();
+
+    let gusts = parse_numbers(get("gust_modes")?)?;
+    if gusts.len() % 4 != 0 { return None; }
+    let gust_modes = gusts.chunks(4)
+        .map(|c| GustMode { freq_ratio: c[0], phase_x: c[1], phase_y: c[2], weight: c[3] })
+        .collect();
+
+    Some(SimSnapshot {
+        t: num("t")?,
+        state,
+        phase,
+        method,
+        settings: SettingsSnapshot::from_fields(&fields)?,
+        submerged_area: num("submerged_area")?,
+        wetted_perimeter: num("wetted_perimeter")?,
+        submerged_regions,
+        angular_clamp_warned: get("angular_clamp_warned")?.parse().ok()?,
+        // 加入恢复系数衰减之前的快照没有弹跳次数，按 0 读取
+        bounce_count: match get("bounce_count") {
+            Some(v) => v.parse().ok()?,
+            None => 0,
+        },
+        bouncing_entry_t: match get("bouncing_entry_t")? {
+            "null" => None,
+            v => Some(v.parse().ok().filter(|t: &f64| t.is_finite())?),
+        },
+        reached_wall: get("reached_wall")?.parse().ok()?,
+        hit_bottom: get("hit_bottom")?.parse().ok()?,
+        ripples,
+        ripple_offset: num("ripple_offset")?,
+        rng_state: get("rng_state")?.parse().ok()?,
+        gust_modes,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physics::simulation::advance;
+    use crate::stone_phy::{slab_blueprint, StoneProperties};
+
+    fn settings(seed: u64) -> CustomSettings {
+        let blueprint = slab_blueprint(0.02, "slab");
+        // 碰撞网格固定用种子 1，只让受力模型的随机数源随 seed 变化
+        let stone = StoneProperties::new(&blueprint, &mut Rng::new(1)).unwrap();
+        CustomSettings::new(9.81, stone, Rng::new(seed))
+    }
+
+    #[test]
+    fn restoring_mid_bounce_continues_identically() {
+        let dt = 0.0005;
+        let mut system = settings(42);
+        system.gust_strength = 3.0;
+        let mut solver = RungeKuttaSolver::new(0.0, StoneInfo {
+            position: Vector2D::new(0.0, 0.05),
+            velocity: Vector2D::new(8.0, -1.0),
+            angle: Vector2D::new(0.1, 0.0),
+            angle_velocity: Vector2D::new(0.0, 30.0),
+        });
+
+        // 1. 推进到入水后若干步
+        let mut steps_in_water = 0;
+        while steps_in_water < 10 {
+            assert!(advance(&mut system, &mut solver, dt));
+            if system.phase == Phase::Bouncing { steps_in_water += 1; }
+        }
+        let text = system.snapshot(&solver).to_json();
+
+        // 2. 原运行继续
+        let mut expected = Vec::new();
+        for _ in 0..400 {
+            if !advance(&mut system, &mut solver, dt) { break; }
+            expected.push((solver.t, solver.state.position, solver.state.angle_velocity, system.phase));
+        }
+
+        // 3. 另一组参数与随机数源，从快照 (经过 JSON) 恢复后继续
+        let mut restored = settings(7);
+        let mut restored_solver = RungeKuttaSolver::new(0.0, StoneInfo {
+            position: Vector2D::new(0.0, 0.0),
+            velocity: Vector2D::new(0.0, 0.0),
+            angle: Vector2D::new(0.0, 0.0),
+            angle_velocity: Vector2D::new(0.0, 0.0),
+        });
+        restored.restore(&parse_snapshot(&text).unwrap(), &mut restored_solver);
+        assert_eq!(restored.phase, Phase::Bouncing);
+
+        let mut actual = Vec::new();
+        for _ in 0..400 {
+            if !advance(&mut restored, &mut restored_solver, dt) { break; }
+            actual.push((restored_solver.t, restored_solver.state.position,
+                         restored_solver.state.angle_velocity, restored.phase));
+        }
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn older_snapshots_read_the_single_submerged_polygon() {
+        let mut system = settings(1);
+        let mut solver = RungeKuttaSolver::new(0.0, StoneInfo {
+            position: Vector2D::new(0.0, 0.05),
+            velocity: Vector2D::new(8.0, -1.0),
+            angle: Vector2D::new(0.1, 0.0),
+            angle_velocity: Vector2D::new(0.0, 30.0),
+        });
+        while system.phase != Phase::Bouncing {
+            assert!(advance(&mut system, &mut solver, 0.0005));
+        }
+        let snapshot = system.snapshot(&solver);
+        assert_eq!(snapshot.submerged_regions.len(), 1);
+
+        // 旧格式：没有 submerged_counts，区域存为 "submerged_polygon"
+        let text: String = snapshot.to_json().lines()
+            .filter(|line| !line.contains("\"submerged_counts\""))
+            .map(|line| line.replace("\"submerged_points\"", "\"submerged_polygon\"") + "\n")
+            .collect();
+        let old = parse_snapshot(&text).unwrap();
+        assert_eq!(old.submerged_regions, snapshot.submerged_regions);
+
+        // 更早的快照也没有 bounce_count
+        let text: String = text.lines().filter(|line| !line.contains("\"bounce_count\"")).map(|line| line.to_string() + "\n").collect();
+        assert_eq!(parse_snapshot(&text).unwrap().bounce_count, 0);
+    }
+}
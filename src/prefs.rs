@@ -0,0 +1,65 @@
+// 职责：编辑器偏好 (prefs.json)。开始模拟、重新开始与关闭窗口时保存上次使用的输入框内容与设置
+// (包括网格点数与弹跳模型)，下次打开编辑器时恢复，迭代设计时不必重新输入。
+// 与 run.json 相同，格式是手写的扁平 JSON 对象；值一律按字符串 (输入框原文) 保存。
+// 读取时不做校验，由编辑器逐字段检查：缺失或格式错误的字段保留默认值。
+
+use std::fs;
+
+use crate::run_record::split_object;
+
+/// 偏好文件路径 (相对于工作目录)
+pub const PREFS_FILE: &str = "prefs.json";
+
+/// 读取到的偏好 (键, 值)
+pub struct Prefs {
+    fields: Vec<(String, String)>,
+}
+
+impl Prefs {
+    /// 从文件读取；文件缺失或损坏时返回空偏好 (全部使用默认值)
+    pub fn load(path: &str) -> Self {
+        Self::parse(&fs::read_to_string(path).unwrap_or_default())
+    }
+
+    pub(crate) fn parse(text: &str) -> Self {
+        Self { fields: split_object(text).unwrap_or_default() }
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.fields.iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.trim_matches('"'))
+    }
+
+    /// 写入文件；失败时只打印警告
+    pub fn save(path: &str, fields: &[(&str, String)]) {
+        if let Err(e) = fs::write(path, to_json(fields)) {
+            println!("Warning: 无法写入偏好文件 {}: {}", path, e);
+        }
+    }
+}
+
+fn to_json(fields: &[(&str, String)]) -> String {
+    let rows: Vec<String> = fields.iter()
+        .map(|(k, v)| format!("\"{}\": \"{}\"", k, v.replace('"', "")))
+        .collect();
+    format!("{{\n  {}\n}}\n", rows.join(",\n  "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prefs_round_trip_and_tolerate_bad_files() {
+        let fields = [("pos_y", "0.35".to_string()), ("gravity_preset", "Moon".to_string())];
+        let prefs = Prefs::parse(&to_json(&fields));
+        assert_eq!(prefs.get("pos_y"), Some("0.35"));
+        assert_eq!(prefs.get("gravity_preset"), Some("Moon"));
+        assert_eq!(prefs.get("missing"), None);
+
+        // 缺失或损坏的文件得到空偏好
+        assert!(Prefs::load("/nonexistent/prefs.json").fields.is_empty());
+        assert!(Prefs::parse("{\"pos_y\": ").get("pos_y").is_none());
+    }
+}
@@ -1,32 +1,291 @@
 // 职责：接收2D物理模拟的状态，并在XY平面上将其可视化。
 
+use std::collections::VecDeque;
+use std::fs;
+use std::path::Path;
+
 use macroquad::prelude::*;
-use crate::physics::simulation::StoneInfo; // 状态向量
+use crate::physics::simulation::{clip_polygon_below_surface, contact_point, last_bounce_window, trajectory_markers, Stamp, StoneInfo, STIFF_ENTRY_G}; // 状态向量
 use crate::basic_structs::Vector2D;
-use crate::stone_phy::StoneProperties; // 物理属性结构体
+use crate::stone_phy::{triangulate_polygon, StoneProperties}; // 物理属性结构体
+use crate::physics::parameters::{CustomSettings, GravityPreset, Phase, Ripple};
+use crate::rng::Rng;
+use crate::ui::{game_ui_scale, AngleUnit};
+
+/// 默认缩放：每米对应的像素数 (编辑器画布与渲染器共用)
+/// 1 米 = 8000 像素，即 1 cm = 80 像素，一块 12 cm 的石片约占 1000 像素
+pub const PIXELS_PER_METER: f64 = 8000.0;
+
+/// 方向键平移视图的默认速度 (像素/秒)
+pub const DEFAULT_KEY_PAN_SPEED: f32 = 600.0;
+
+/// 结束回看：从最后一次入水前这么多秒开始循环播放
+const LAST_BOUNCE_LEAD_TIME: f64 = 0.3;
+/// 结束回看的播放速度倍率
+const LAST_BOUNCE_SPEED: f32 = 0.25;
+
+/// 自转小窗中拖尾覆盖的时长 (s)
+const SPIN_TRAIL_TIME: f64 = 0.05;
+/// 自转小窗的最大缩放 (像素/米)，避免极小的石片把缩放放大到失控
+const SPIN_PREVIEW_MAX_SCALE: f64 = 40000.0;
+
+/// 录制帧的输出目录
+const RECORD_DIR: &str = "frames";
+
+/// 单帧截图 (S 键) 的文件名前缀，保存在工作目录
+const FIGURE_PREFIX: &str = "figure";
+
+/// 每次入水溅起的粒子数
+const SPLASH_PARTICLES: usize = 24;
+/// 粒子寿命 (s，按真实帧时间计)
+const PARTICLE_LIFETIME: f32 = 0.8;
+/// 粒子受到的微小重力 (m/s^2)，只用于视觉效果
+const PARTICLE_GRAVITY: f64 = 2.0;
+
+/// 慢动作默认参数：入水/出水前后多少帧内减速，以及最低播放速度倍率
+const DEFAULT_SLOW_MOTION_WINDOW: usize = 40;
+const DEFAULT_SLOW_MOTION_FACTOR: f32 = 0.25;
+
+/// 最多同时叠加显示的对比轨迹数
+const MAX_OVERLAYS: usize = 3;
+/// 对比轨迹依次使用的颜色 (与石片轨迹的颜色区分开)
+const OVERLAY_COLORS: [Color; 3] = [ORANGE, MAGENTA, WHITE];
+
+/// 训练模式参考轨迹的颜色 (半透明，画在实时轨迹后面)
+const GUIDE_COLOR: Color = Color::new(0.3, 0.9, 1.0, 0.35);
+
+/// 轨迹模式最多保留的历史发射次数 (超出时丢弃最早的一次)
+const MAX_TRACES: usize = 40;
+/// 历史轨迹的不透明度
+const TRACE_ALPHA: f32 = 0.2;
+
+/// 各条轨迹依次使用的颜色
+const TRACK_COLORS: [Color; 4] = [YELLOW, SKYBLUE, PINK, LIME];
+
+/// 一块石片的全部历史数据
+struct Track {
+    /// 存储历史帧 (模拟时间、状态、总机械能)，用于绘制轨迹
+    /// 设置了 max_trajectory_len 时是环形缓冲区，只保留最近的若干帧
+    trajectory: VecDeque<Stamp>,
+    /// 已从缓冲区前端丢弃的帧数：trajectory[0] 是第 first_frame 帧
+    first_frame: usize,
+    /// 全部帧的完整记录 (不受 max_trajectory_len 限制，第 i 项即第 i 帧)，
+    /// 用于计分、保存回放与幽灵轨迹；不含只用于显示的受力分解与浸没多边形
+    history: Vec<Stamp>,
+    /// 每帧轮廓最低点 (接触点) 的世界坐标，与 trajectory 一一对应，添加帧时计算
+    contact_path: VecDeque<Vector2D>,
+    /// 石片的物理和几何属性 (质心系)
+    stone_props: StoneProperties,
+    /// 轮廓的三角剖分 (outline_com 的下标)，添加轨迹时计算一次
+    fill_triangles: Vec<[usize; 3]>,
+    color: Color,
+    /// 轨迹中的速度范围 (m/s)，添加帧时增量更新，用于热力图归一化
+    speed_min: f64,
+    speed_max: f64,
+}
+
+impl Track {
+    /// 当前帧对应的缓冲区下标 (已沉没的石片停在最后一帧，已丢弃的帧停在最早保留的一帧)
+    fn frame_index(&self, current_frame: usize) -> usize {
+        current_frame.saturating_sub(self.first_frame).min(self.trajectory.len().saturating_sub(1))
+    }
+
+    /// 第 `frame` 帧在缓冲区中的下标；已丢弃或尚未记录时返回 None
+    fn buffer_index(&self, frame: usize) -> Option<usize> {
+        frame.checked_sub(self.first_frame).filter(|&i| i < self.trajectory.len())
+    }
+
+    /// 记录过的总帧数 (含已丢弃的)
+    fn total_frames(&self) -> usize {
+        self.first_frame + self.trajectory.len()
+    }
+
+    /// 第 i 帧速度在 [speed_min, speed_max] 中的归一化位置
+    fn normalized_speed(&self, i: usize) -> f64 {
+        let range = self.speed_max - self.speed_min;
+        if range < 1e-9 { return 0.0; }
+        (self.trajectory[i].state.velocity.length() - self.speed_min) / range
+    }
+}
+
+/// 叠加显示的对比轨迹 (例如同一初始条件换一种积分器)，只画路径
+struct Overlay {
+    label: String,
+    trajectory: Vec<Stamp>,
+    color: Color,
+}
+
+/// 溅水粒子 (世界坐标)，纯视觉效果，不参与物理
+struct Particle {
+    position: Vector2D,
+    velocity: Vector2D,
+    /// 剩余寿命 (s)，降到 0 时移除
+    lifetime: f32,
+}
+
+/// 从一帧中取出要绘制的数值 (曲线面板使用)
+type StampValue = fn(&Stamp) -> f64;
+
+/// 石片填充色：按当前相位区分
+fn phase_fill_color(phase: Phase) -> Color {
+    match phase {
+        Phase::Flying => Color::new(0.53, 0.81, 0.92, 0.6),
+        Phase::Bouncing => Color::new(0.85, 0.95, 1.0, 0.8),
+        Phase::Sinking => Color::new(0.1, 0.2, 0.5, 0.6),
+    }
+}
+
+/// 水下深度 (m) 达到该值时着色最暗
+const DEPTH_SHADE_RANGE: f64 = 0.05;
+
+/// 水下部分的颜色：刚入水时接近浪花白，随深度渐变为深蓝
+fn depth_shade(depth: f64) -> Color {
+    let k = (depth / DEPTH_SHADE_RANGE).clamp(0.0, 1.0) as f32;
+    lerp_color(Color::new(0.6, 0.85, 1.0, 0.85), Color::new(0.05, 0.1, 0.35, 0.9), k)
+}
+
+/// 刻度标签：小数位数随网格间距 (m) 变化，刚好能区分相邻两条网格线
+fn tick_label(value: f64, step: f64) -> String {
+    let decimals = (-step.log10()).ceil().clamp(0.0, 4.0) as usize;
+    // 避免出现 "-0.00"
+    let value = if value.abs() < 0.5 * step { 0.0 } else { value };
+    format!("{:.*}", decimals, value)
+}
+
+/// 轨迹线的着色方式 (H 键循环切换)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PathColoring {
+    /// 每条轨迹一种纯色
+    Track,
+    /// 按速度大小着色 (见 speed_colormap)
+    Speed,
+    /// 按相位着色 (见 phase_path_color)
+    Phase,
+}
+
+impl PathColoring {
+    fn next(self) -> Self {
+        match self {
+            PathColoring::Track => PathColoring::Speed,
+            PathColoring::Speed => PathColoring::Phase,
+            PathColoring::Phase => PathColoring::Track,
+        }
+    }
+}
+
+/// 沉没后轨迹线经过这么多帧逐渐变暗
+const SINK_FADE_FRAMES: f32 = 60.0;
+
+/// 按相位着色时轨迹线的颜色：飞行为黄色，触水为青色，沉没为深蓝
+fn phase_path_color(phase: Phase) -> Color {
+    match phase {
+        Phase::Flying => Color::new(1.0, 0.85, 0.2, 1.0),
+        Phase::Bouncing => Color::new(0.2, 0.9, 1.0, 1.0),
+        Phase::Sinking => Color::new(0.1, 0.15, 0.35, 1.0),
+    }
+}
+
+/// 两种颜色按 t (0 ~ 1) 线性插值
+fn lerp_color(a: Color, b: Color, t: f32) -> Color {
+    let t = t.clamp(0.0, 1.0);
+    Color::new(a.r + (b.r - a.r) * t, a.g + (b.g - a.g) * t, a.b + (b.b - a.b) * t, a.a + (b.a - a.a) * t)
+}
+
+/// 简单色图：0 (慢) 为蓝色，1 (快) 为红色
+fn speed_colormap(t: f64) -> Color {
+    let t = t.clamp(0.0, 1.0) as f32;
+    Color::new(t, 0.2, 1.0 - t, 1.0)
+}
 
 /// # 2D 模拟渲染器
 ///
 /// 负责在 2D (XY) 平面中绘制模拟。
 /// 假定物理世界中 Y 轴向上，X 轴向右。
+/// 可以同时绘制多块石片，第 0 块为主石片 (信息面板和自转小窗显示它)。
 pub struct SimulationRenderer {
-    /// 存储所有历史帧，用于绘制轨迹
-    trajectory: Vec<StoneInfo>,
-    /// 石片的物理和几何属性 (质心系)
-    stone_props: StoneProperties,
-
+    tracks: Vec<Track>,
+    /// 对比轨迹，超过 MAX_OVERLAYS 时丢弃最早的一条
+    overlays: Vec<Overlay>,
+    overlay_count: usize,
+    /// 训练模式的参考轨迹 (标签, 轨迹)：编辑器中搜索到的最佳发射
+    guide: Option<(String, Vec<Stamp>)>,
+    /// 轨迹模式 (T 键)：每次发射结束时保留主石片的完整轨迹，淡色叠加显示，X 键清空
+    trace_mode: bool,
+    traces: Vec<Vec<Stamp>>,
+
+    /// 当前帧号 (从第一帧起计数，不受环形缓冲区丢弃的影响)
     current_frame: usize,
+    /// 每条轨迹最多保留的帧数 (None 为不限)；超出时丢弃最早的帧，轨迹显示为拖尾
+    max_trajectory_len: Option<usize>,
     is_playing: bool,
+    /// 倒放 (V 键)：播放时逐帧后退到最早保留的帧；倒放期间溅水粒子与涟漪冻结
+    reverse_playback: bool,
+    /// 播放速度 (每个渲染帧推进的轨迹帧数)
+    pub playback_speed: f32,
+    // 小数帧累加器，播放速度 < 1 时若干渲染帧才推进一帧
+    frame_accumulator: f32,
+    /// 慢动作：距 Flying <-> Bouncing 切换不超过 window 帧时减速到 factor 倍，
+    /// 离切换越远越接近原速；window = 0 关闭
+    slow_motion_window: usize,
+    slow_motion_factor: f32,
+    /// 结束回看：在 (起始帧, 结束帧) 之间慢速循环播放主石片的最后一次入水，Enter 或 R 退出
+    last_bounce_loop: Option<(usize, usize)>,
+    /// 轨迹线着色方式 (H 键在纯色 / 速度 / 相位之间切换)，默认纯色
+    path_coloring: PathColoring,
+    /// 石片填充绘制 (F 键切换为只画轮廓)
+    filled: bool,
+    /// 高度/速度曲线面板 (G 键切换)
+    show_graph: bool,
+    show_markers: bool, // 弧段最高点与沉没位置的标注 (M 键)
+    /// 接触点 (轮廓最低点) 的轨迹 (L 键)：质心仍在水面以上时石片为何已经触水
+    show_contact_path: bool,
+
+    /// 水面: y = water_level + slope * x (由 set_water_surface 从物理参数读取)
+    water_level: f64,
+    slope: f64,
+    /// 对岸墙壁的 x (m)，没有墙壁时为 None (由 set_water_surface 读取)
+    wall_x: Option<f64>,
+    /// 池底高度 (m)，没有池底时为 None
+    bottom_y: Option<f64>,
+    /// 当前的涟漪及其对应的模拟时间 (由 set_ripples 每帧更新)
+    ripples: Vec<Ripple>,
+    ripple_time: f64,
+    /// 信息面板中显示的重力来源，例如 "Moon (1.62 m/s^2)"
+    gravity_label: String,
+    /// 信息面板中角度与角速度的单位 (由 set_angle_unit 从编辑器读取)
+    angle_unit: AngleUnit,
+    /// 测量工具 (D 键)：左键依次点两个点，显示两点间的距离与连线角度；第三次点击开始新的测量
+    measuring: bool,
+    measure_points: Vec<Vector2D>,
 
     /// 视觉缩放比例 (像素/米)
     scale: f64,
+    /// 创建时的缩放比例，Home 键恢复到它
+    initial_scale: f64,
     /// 世界坐标 (0, 0) 在屏幕上的像素位置
     world_origin_on_screen: Vec2,
 
     // 摄像机平移状态
+    /// 拖动平移使用的鼠标键 (默认中键，左键留给按钮等界面交互)
+    pub pan_button: MouseButton,
+    /// 方向键平移速度 (像素/秒)；按屏幕像素计，对应的世界速度为 pan_speed / scale，任意缩放下手感一致
+    pub key_pan_speed: f32,
     is_panning: bool,
     last_mouse_pos: Vec2,
     pub should_restart: bool,
+    /// 请求用相同初始条件重新模拟 (主循环处理后调用 clear_trajectories)
+    pub should_replay: bool,
+
+    // 录制状态 (C 键开关)
+    is_recording: bool,
+    record_index: usize,
+    // S 键请求保存单帧截图 (在所有 UI 绘制完成后执行)
+    figure_requested: bool,
+
+    /// 入水 (Flying -> Bouncing) 时溅起的粒子
+    particles: Vec<Particle>,
+    /// 粒子方向/速度的随机源 (固定种子，录制的画面可复现)
+    particle_rng: Rng,
 }
 
 impl SimulationRenderer {
@@ -35,27 +294,184 @@ impl SimulationRenderer {
     /// - `stone_props`: 从 stone_factory 计算得出的石片物理属性。
     /// - `scale`: 初始缩放比例 (例如: 1000.0 像素/米)
     pub fn new(stone_props: StoneProperties, scale: f64) -> Self {
-        Self {
-            trajectory: Vec::new(),
-            stone_props,
+        let mut renderer = Self {
+            tracks: Vec::new(),
+            overlays: Vec::new(),
+            overlay_count: 0,
+            guide: None,
+            trace_mode: false,
+            traces: Vec::new(),
             current_frame: 0,
+            max_trajectory_len: None,
             is_playing: true, // 默认自动播放
+            reverse_playback: false,
+            playback_speed: 1.0,
+            frame_accumulator: 0.0,
+            slow_motion_window: DEFAULT_SLOW_MOTION_WINDOW,
+            slow_motion_factor: DEFAULT_SLOW_MOTION_FACTOR,
+            last_bounce_loop: None,
+            path_coloring: PathColoring::Track,
+            filled: true,
+            show_graph: false,
+            show_markers: true,
+            show_contact_path: true,
+            water_level: 0.0,
+            slope: 0.0,
+            wall_x: None,
+            bottom_y: None,
+            ripples: Vec::new(),
+            ripple_time: 0.0,
+            gravity_label: String::new(),
+            angle_unit: AngleUnit::Degrees,
+            measuring: false,
+            measure_points: Vec::new(),
             scale,
+            initial_scale: scale,
             // 默认将 (0,0) 放在屏幕左 1/4, 垂直 3/4 的位置
             world_origin_on_screen: vec2(screen_width() / 4.0, screen_height() * 0.75),
+            pan_button: MouseButton::Middle,
+            key_pan_speed: DEFAULT_KEY_PAN_SPEED,
             is_panning: false,
             last_mouse_pos: Vec2::ZERO,
             should_restart: false,
+            should_replay: false,
+            is_recording: false,
+            record_index: 0,
+            figure_requested: false,
+            particles: Vec::new(),
+            particle_rng: Rng::new(0x5A1A54),
+        };
+        renderer.add_track(stone_props);
+        renderer
+    }
+
+    /// 添加一块参与比赛的石片，返回其轨迹编号
+    pub fn add_track(&mut self, stone_props: StoneProperties) -> usize {
+        let color = TRACK_COLORS[self.tracks.len() % TRACK_COLORS.len()];
+        self.tracks.push(Track {
+            trajectory: VecDeque::new(),
+            first_frame: 0,
+            history: Vec::new(),
+            contact_path: VecDeque::new(),
+            fill_triangles: triangulate_polygon(&stone_props.outline_com),
+            stone_props,
+            color,
+            speed_min: f64::MAX,
+            speed_max: 0.0,
+        });
+        self.tracks.len() - 1
+    }
+
+    /// 从物理参数读取水面高度和坡度，保证绘制与物理一致
+    pub fn set_water_surface(&mut self, settings: &CustomSettings) {
+        self.water_level = settings.water_level;
+        self.slope = settings.slope;
+        self.wall_x = settings.wall_x;
+        self.bottom_y = settings.bottom_y();
+    }
+
+    /// 更新要绘制的涟漪 (所有石片激起的) 与当前模拟时间
+    pub fn set_ripples(&mut self, ripples: Vec<Ripple>, t: f64) {
+        if self.reverse_playback { return; } // 倒放时冻结
+        self.ripples = ripples;
+        self.ripple_time = t;
+    }
+
+    /// 设置信息面板中角度与角速度的单位
+    pub fn set_angle_unit(&mut self, unit: AngleUnit) {
+        self.angle_unit = unit;
+    }
+
+    /// 设置信息面板中显示的重力来源
+    pub fn set_gravity_label(&mut self, gravity: &GravityPreset) {
+        self.gravity_label = format!("{} ({} m/s^2)", gravity.name(), gravity.value());
+    }
+
+    /// 设置慢动作窗口 (帧) 与最低速度倍率，window = 0 关闭慢动作
+    pub fn set_slow_motion(&mut self, window: usize, factor: f32) {
+        self.slow_motion_window = window;
+        self.slow_motion_factor = factor.clamp(0.01, 1.0);
+    }
+
+    /// 每条轨迹最多保留的帧数，None 为不限 (默认)
+    /// 用于很长的实验：绘制开销不再随时间增长；已有的多余帧立即丢弃
+    pub fn set_max_trajectory_len(&mut self, max_len: Option<usize>) {
+        self.max_trajectory_len = max_len.map(|n| n.max(1));
+        for track in self.tracks.iter_mut() {
+            Self::trim_track(track, self.max_trajectory_len);
         }
     }
 
-    /// (主循环调用) 添加一个新的状态帧
-    pub fn add_state(&mut self, state: StoneInfo) {
-        self.trajectory.push(state);
+    // 丢弃超出上限的最早帧 (速度范围保留历史极值)
+    fn trim_track(track: &mut Track, max_len: Option<usize>) {
+        let Some(max_len) = max_len else { return; };
+        while track.trajectory.len() > max_len {
+            track.trajectory.pop_front();
+            track.contact_path.pop_front();
+            track.first_frame += 1;
+        }
+    }
+
+    /// 叠加一条带标签的对比轨迹 (颜色轮换)
+    pub fn add_overlay(&mut self, label: String, trajectory: Vec<Stamp>) {
+        if self.overlays.len() >= MAX_OVERLAYS {
+            self.overlays.remove(0);
+        }
+        let color = OVERLAY_COLORS[self.overlay_count % OVERLAY_COLORS.len()];
+        self.overlay_count += 1;
+        self.overlays.push(Overlay { label, trajectory, color });
+    }
+
+    /// 设置训练模式的参考轨迹 (重放时保留)
+    pub fn set_guide(&mut self, label: String, trajectory: Vec<Stamp>) {
+        self.guide = Some((label, trajectory));
+    }
+
+    /// (主循环在一次发射结束时调用) 轨迹模式下保留主石片的完整轨迹
+    pub fn record_trace(&mut self) {
+        let Some(primary) = self.tracks.first().filter(|t| self.trace_mode && !t.history.is_empty()) else {
+            return;
+        };
+        let trajectory = primary.history.clone();
+        if self.traces.len() >= MAX_TRACES {
+            self.traces.remove(0);
+        }
+        self.traces.push(trajectory);
+    }
+
+    /// 取出轨迹模式的开关与历史轨迹 (每次发射都会新建渲染器，主循环借此在发射之间保留它们)
+    pub fn take_traces(&mut self) -> (bool, Vec<Vec<Stamp>>) {
+        (self.trace_mode, std::mem::take(&mut self.traces))
+    }
+
+    /// 恢复 take_traces 取出的轨迹模式与历史轨迹
+    pub fn set_traces(&mut self, trace_mode: bool, traces: Vec<Vec<Stamp>>) {
+        self.trace_mode = trace_mode;
+        self.traces = traces;
+    }
+
+    /// 第 `track` 块石片使用的颜色
+    pub fn track_color(&self, track: usize) -> Color {
+        self.tracks[track].color
+    }
+
+    /// (主循环调用) 给第 `track` 块石片添加一个新的状态帧
+    /// `stamp.t` 为模拟时间 (s)，显示的时间直接取自这里
+    pub fn add_state(&mut self, track: usize, stamp: Stamp) {
+        let t = &mut self.tracks[track];
+        let speed = stamp.state.velocity.length();
+        t.speed_min = t.speed_min.min(speed);
+        t.speed_max = t.speed_max.max(speed);
+        let contact = contact_point(&t.stone_props.outline_com, &stamp.state).unwrap_or(stamp.state.position);
+        t.contact_path.push_back(contact);
+        t.history.push(stamp.without_display_data());
+        t.trajectory.push_back(stamp);
+        Self::trim_track(t, self.max_trajectory_len);
     }
 
     /// (主循环调用) 更新并绘制当前帧
-    pub fn draw_and_update(&mut self) {
+    /// `ghost`: 之前最佳一次的轨迹，以半透明灰色画在实时轨迹后面
+    pub fn draw_and_update(&mut self, ghost: Option<&[Stamp]>) {
         clear_background(Color::from_rgba(10, 20, 35, 100)); // 深蓝色背景
 
         // 1. (新) 处理摄像机控制 (平移和缩放)
@@ -65,40 +481,78 @@ impl SimulationRenderer {
         self.draw_grid_and_axes();
         self.draw_water_surface(); // 绘制 Y=0 的水面
 
-        // 3. 绘制动态元素 (轨迹, 石块)
-        if !self.trajectory.is_empty() {
-            // 绘制轨迹线
-            self.draw_trajectory_path();
-
-            // 获取当前帧 (如果暂停则固定, 播放则推进)
-            let state_to_draw = &self.trajectory[self.current_frame];
-            self.draw_stone(state_to_draw);
+        // 3. 绘制动态元素 (幽灵轨迹, 对比轨迹, 轨迹, 石块)
+        self.draw_traces();
+        self.draw_guide();
+        if let Some(ghost) = ghost {
+            self.draw_ghost(ghost);
+        }
+        self.draw_overlays();
+        if self.has_trajectory() {
+            // 标注需要连续切片 (只有设置了 max_trajectory_len、缓冲区回绕时才会真正移动数据)
+            if self.show_markers {
+                for track in self.tracks.iter_mut() {
+                    track.trajectory.make_contiguous();
+                }
+            }
+            for track in self.tracks.iter().filter(|t| !t.trajectory.is_empty()) {
+                // 绘制轨迹线 (以及接触点的淡色轨迹)
+                if self.show_contact_path {
+                    self.draw_contact_path(track);
+                }
+                self.draw_trajectory_path(track, track.color);
+                if self.show_markers {
+                    self.draw_markers(track);
+                }
+
+                // 获取当前帧 (如果暂停则固定, 播放则推进)
+                let stamp = &track.trajectory[track.frame_index(self.current_frame)];
+                self.draw_stone(track, stamp);
+            }
 
-            self.draw_rotation_preview(state_to_draw);
+            if let Some(primary) = self.tracks.first() && !primary.trajectory.is_empty() {
+                self.draw_rotation_preview(primary, primary.frame_index(self.current_frame));
+            }
 
-            if self.is_playing && self.current_frame < self.trajectory.len() - 1 {
-                self.current_frame += 1;
+            if self.is_playing {
+                self.advance_playback();
             }
         } else {
             draw_text(
                 "等待模拟数据...",
-                screen_width() / 2.0 - 150.0,
+                screen_width() / 2.0 - 150.0 * game_ui_scale(),
                 screen_height() / 2.0,
-                30.0,
+                30.0 * game_ui_scale(),
                 WHITE,
             );
         }
 
+        // 溅水粒子 (暂停或倒放时冻结)
+        if self.is_playing && !self.reverse_playback {
+            self.update_particles(get_frame_time());
+        }
+        self.draw_particles();
+        self.draw_measurement();
+
         // 4. 绘制 UI 信息
+        if self.path_coloring == PathColoring::Phase {
+            self.draw_phase_legend();
+        }
         self.draw_info_panel();
+        if self.show_graph {
+            self.draw_graph_panel();
+        }
     }
 
     // --- 核心绘制函数 ---
 
     /// 绘制石片
-    fn draw_stone(&self, state: &StoneInfo) {
+    fn draw_stone(&self, track: &Track, stamp: &Stamp) {
+        let state = &stamp.state;
+        let color = track.color;
+
         // 1. 获取石片的基准形状 (位于质心系, 0,0)
-        let base_outline = &self.stone_props.outline_com;
+        let base_outline = &track.stone_props.outline_com;
         if base_outline.is_empty() { return; }
 
         // 2. 获取当前状态
@@ -108,56 +562,116 @@ impl SimulationRenderer {
         let sin_a = tilt_angle.sin();
 
         // 3. 转换并绘制轮廓
-        let screen_points: Vec<Vec2> = base_outline.iter().map(|&local_point| {
+        let world_points: Vec<Vector2D> = base_outline.iter().map(|&local_point| {
             // 3a. 应用旋转 (绕质心)
             let rotated_x = local_point.x * cos_a - local_point.y * sin_a;
             let rotated_y = local_point.x * sin_a + local_point.y * cos_a;
 
             // 3b. 应用平移 (到世界坐标)
-            let world_point = Vector2D {
+            Vector2D {
                 x: rotated_x + world_pos.x,
                 y: rotated_y + world_pos.y,
-            };
-
-            // 3c. 转换到屏幕坐标
-            self.world_to_screen(world_point)
+            }
         }).collect();
+        // 3c. 转换到屏幕坐标
+        let screen_points: Vec<Vec2> = world_points.iter().map(|&p| self.world_to_screen(p)).collect();
+
+        // 4. 按相位填充 (飞行为天蓝色，弹跳为浪花白)
+        if self.filled {
+            let fill = phase_fill_color(stamp.phase);
+            for t in &track.fill_triangles {
+                draw_triangle(screen_points[t[0]], screen_points[t[1]], screen_points[t[2]], fill);
+            }
+            // 4b. 水下部分按深度着色 (越深越暗)，水上部分保持原色
+            if stamp.phase != Phase::Flying {
+                self.draw_underwater_shading(&world_points);
+            }
+        }
 
-        // 4. 绘制轮廓线
+        // 5. 水动力实际作用的浸没多边形 (与物理使用的完全一致，而非按当前帧重新裁剪)
+        for region in &stamp.submerged {
+            for (i, &p) in region.iter().enumerate() {
+                let (p1, p2) = (self.world_to_screen(p), self.world_to_screen(region[(i + 1) % region.len()]));
+                draw_line(p1.x, p1.y, p2.x, p2.y, 1.5, SKYBLUE);
+            }
+        }
+
+        // 6. 绘制轮廓线
         if screen_points.len() > 2 {
             for i in 0..screen_points.len() {
                 let p1 = screen_points[i];
                 let p2 = screen_points[(i + 1) % screen_points.len()];
-                draw_line(p1.x, p1.y, p2.x, p2.y, 2.0, YELLOW);
+                draw_line(p1.x, p1.y, p2.x, p2.y, 2.0, color);
             }
         }
 
-        // 5. 绘制石片质心
+        // 7. 绘制石片质心
         let com_screen = self.world_to_screen(world_pos);
         draw_circle(com_screen.x, com_screen.y, 3.0, RED);
     }
 
-    /// 绘制一个显示“自转”的俯视小窗
-    fn draw_rotation_preview(&self, state: &StoneInfo) {
+    /// 把轮廓在水面以下的部分画成逐顶点的深度渐变
+    /// 用与物理相同的裁剪函数切出水下区域；裁剪的是画出的侧视轮廓，保证渐变与轮廓线对齐
+    fn draw_underwater_shading(&self, world_points: &[Vector2D]) {
+        for region in clip_polygon_below_surface(world_points, self.water_level, self.slope) {
+            let vertices: Vec<Vertex> = region.iter().map(|&p| {
+                let depth = self.water_level + self.slope * p.x - p.y;
+                let screen = self.world_to_screen(p);
+                Vertex::new(screen.x, screen.y, 0.0, 0.0, 0.0, depth_shade(depth))
+            }).collect();
+            let indices: Vec<u16> = triangulate_polygon(&region).iter()
+                .flat_map(|t| t.map(|i| i as u16))
+                .collect();
+            draw_mesh(&Mesh { vertices, indices, texture: None });
+        }
+    }
+
+    /// 测量工具：两点连线与标注 (世界坐标保存，随平移缩放移动)；只点了一个点时连到鼠标位置
+    fn draw_measurement(&self) {
+        if !self.measuring { return; }
+        let sc = game_ui_scale();
+        draw_text("MEASURE: click two points (D: exit)", screen_width() / 2.0 - 170.0 * sc, 85.0 * sc, 24.0 * sc, YELLOW);
+
+        let Some(&start) = self.measure_points.first() else { return; };
+        let end = self.measure_points.get(1).copied()
+            .unwrap_or_else(|| self.screen_to_world(mouse_position().into()));
+        let (a, b) = (self.world_to_screen(start), self.world_to_screen(end));
+        draw_line(a.x, a.y, b.x, b.y, 2.0, YELLOW);
+        draw_circle(a.x, a.y, 4.0, YELLOW);
+        draw_circle(b.x, b.y, 4.0, YELLOW);
+
+        let d = end - start;
+        let unit = self.angle_unit;
+        let label = format!("{:.4} m  (dx {:.4}, dy {:.4})  angle {} {}",
+                            d.length(), d.x, d.y, unit.format(unit.in_unit(d.y.atan2(d.x))), unit.name());
+        let mid = (a + b) * 0.5;
+        draw_text(&label, mid.x + 8.0, mid.y - 8.0, 22.0 * sc, YELLOW);
+    }
+
+    /// 绘制一个显示“自转”的俯视小窗：轮廓、指示当前转角的刻度箭头、近期转动的拖尾以及转速
+    fn draw_rotation_preview(&self, track: &Track, index: usize) {
+        let stone_props = &track.stone_props;
+        let state = &track.trajectory[index].state;
         // 1. 定义小窗的位置和大小
-        let rect = Rect::new(20.0, 100.0, 200.0, 200.0);
+        let sc = game_ui_scale();
+        let rect = Rect::new(20.0 * sc, 100.0 * sc, 200.0 * sc, 200.0 * sc);
         let center = rect.center();
 
         // 2. 绘制边框和标题
         draw_rectangle_lines(rect.x, rect.y, rect.w, rect.h, 2.0, GRAY);
-        draw_text("Spin (Top-Down)", rect.x + 30.0, rect.y + 30.0, 20.0, WHITE);
+        draw_text("Spin (Top-Down)", rect.x + 30.0 * sc, rect.y + 30.0 * sc, 20.0 * sc, WHITE);
 
         // 3. 获取石片数据
-        let base_outline = &self.stone_props.outline_com;
+        let base_outline = &stone_props.outline_com;
         if base_outline.is_empty() { return; }
 
         // d_max 是距离的平方, 我们需要半径
-        let max_radius = self.stone_props.d_max.sqrt();
+        let max_radius = stone_props.d_max.sqrt();
         if max_radius < 1e-9 { return; } // 避免除以零
 
         // 4. 计算此小窗的本地缩放
-        // (让石片占满 80% 的窗口, 留出边距)
-        let local_scale = (rect.w * 0.8 / 2.0) as f64 / max_radius;
+        // (让石片占满 80% 的窗口, 留出边距；极小的石片按上限缩放)
+        let local_scale = ((rect.w * 0.8 / 2.0) as f64 / max_radius).min(SPIN_PREVIEW_MAX_SCALE);
 
         // 5. 获取当前自转角度
         let spin_angle = state.angle.y; // 这对应于你的 "angle.y"
@@ -185,31 +699,382 @@ impl SimulationRenderer {
                 draw_line(p1.x, p1.y, p2.x, p2.y, 1.0, YELLOW);
             }
         }
+
+        // 8. 拖尾：最近 SPIN_TRAIL_TIME 秒内 (最多半圈) 刻度尖端的位置，越早越淡
+        let tick_len = (max_radius * local_scale) as f32 * 1.1;
+        let tip = |angle: f64| vec2(center.x + tick_len * angle.cos() as f32, center.y - tick_len * angle.sin() as f32);
+        let now = track.trajectory[index].t;
+        let mut trail: Vec<(Vec2, f64)> = Vec::new();
+        for i in (0..index).rev() {
+            let stamp = &track.trajectory[i];
+            if now - stamp.t > SPIN_TRAIL_TIME || (spin_angle - stamp.state.angle.y).abs() > std::f64::consts::PI { break; }
+            trail.push((tip(stamp.state.angle.y), now - stamp.t));
+        }
+        let mut previous = tip(spin_angle);
+        for (point, age) in trail {
+            let alpha = 0.5 * (1.0 - age / SPIN_TRAIL_TIME) as f32;
+            draw_line(previous.x, previous.y, point.x, point.y, 2.0, Color::new(1.0, 0.6, 0.2, alpha));
+            previous = point;
+        }
+
+        // 9. 刻度箭头：从中心指向当前转角 angle.y
+        let head = tip(spin_angle);
+        draw_line(center.x, center.y, head.x, head.y, 2.0, ORANGE);
+        draw_circle(head.x, head.y, 4.0, ORANGE);
+
+        // 10. 转速 (rev/s，逆时针为正)
+        let rev_per_sec = state.angle_velocity.y / std::f64::consts::TAU;
+        draw_text(&format!("{:+.1} rev/s", rev_per_sec), rect.x + 10.0 * sc, rect.y + rect.h - 10.0 * sc, 20.0 * sc, WHITE);
+    }
+
+    /// 接触点轨迹：每帧轮廓最低点的连线，以细的淡色虚线画到当前帧
+    fn draw_contact_path(&self, track: &Track) {
+        let end_index = (self.current_frame + 1).saturating_sub(track.first_frame).min(track.contact_path.len());
+        let color = Color { a: 0.35, ..track.color };
+        for i in 0..end_index.saturating_sub(1) {
+            // 隔段绘制成虚线，与质心轨迹区分
+            if i % 4 >= 2 { continue; }
+            let p1 = self.world_to_screen(track.contact_path[i]);
+            let p2 = self.world_to_screen(track.contact_path[i + 1]);
+            draw_line(p1.x, p1.y, p2.x, p2.y, 1.0, color);
+        }
     }
 
     /// 绘制轨迹（所有历史位置）
-    fn draw_trajectory_path(&self) {
-        if self.trajectory.len() < 2 { return; }
+    fn draw_trajectory_path(&self, track: &Track, color: Color) {
+        let trajectory = &track.trajectory;
+        if trajectory.len() < 2 { return; }
 
-        // 只绘制到当前帧
-        let end_index = (self.current_frame + 1).min(self.trajectory.len());
+        // 只绘制到当前帧 (缓冲区下标)
+        let end_index = (self.current_frame + 1).saturating_sub(track.first_frame).min(trajectory.len());
+        let mut sunk_frames = 0.0; // 已沉没的帧数 (相位着色时用于逐渐变暗)
         for i in 0..(end_index.saturating_sub(1)) {
-            let p1 = self.world_to_screen(self.trajectory[i].position);
-            let p2 = self.world_to_screen(self.trajectory[i + 1].position);
-            draw_line(p1.x, p1.y, p2.x, p2.y, 1.0, YELLOW);
+            let p1 = self.world_to_screen(trajectory[i].state.position);
+            let p2 = self.world_to_screen(trajectory[i + 1].state.position);
+            let segment_color = match self.path_coloring {
+                PathColoring::Track => color,
+                PathColoring::Speed => speed_colormap(track.normalized_speed(i)),
+                PathColoring::Phase => {
+                    // 相位切换处取两端颜色的平均，沉没段从触水色渐变到深色
+                    let (a, b) = (trajectory[i].phase, trajectory[i + 1].phase);
+                    if b == Phase::Sinking {
+                        sunk_frames += 1.0;
+                        lerp_color(phase_path_color(Phase::Bouncing), phase_path_color(Phase::Sinking),
+                                   sunk_frames / SINK_FADE_FRAMES)
+                    } else {
+                        lerp_color(phase_path_color(a), phase_path_color(b), 0.5)
+                    }
+                }
+            };
+            draw_line(p1.x, p1.y, p2.x, p2.y, 1.0, segment_color);
+        }
+    }
+
+    /// 已播放部分的弧段最高点 (三角) 与沉没位置 (叉) 及其标签
+    /// 标签跟随世界坐标，平移缩放时与轨迹一起移动
+    fn draw_markers(&self, track: &Track) {
+        let (trajectory, _) = track.trajectory.as_slices();
+        let end_index = (self.current_frame + 1).saturating_sub(track.first_frame).min(trajectory.len());
+        let markers = trajectory_markers(&trajectory[..end_index]);
+
+        for (i, apex) in markers.apexes.iter().enumerate() {
+            let p = self.world_to_screen(*apex);
+            draw_triangle(vec2(p.x, p.y - 4.0), vec2(p.x - 6.0, p.y - 14.0), vec2(p.x + 6.0, p.y - 14.0), track.color);
+            draw_text(&format!("apex {}: {:.2} m", i + 1, apex.y), p.x + 8.0, p.y - 10.0, 18.0, track.color);
+        }
+        if let Some(sink) = markers.sink {
+            let p = self.world_to_screen(sink);
+            draw_line(p.x - 7.0, p.y - 7.0, p.x + 7.0, p.y + 7.0, 2.0, track.color);
+            draw_line(p.x - 7.0, p.y + 7.0, p.x + 7.0, p.y - 7.0, 2.0, track.color);
+            draw_text(&format!("range: {:.1} m", markers.range), p.x + 10.0, p.y + 20.0, 18.0, track.color);
+        }
+    }
+
+    /// 相位着色的图例 (右下角)
+    fn draw_phase_legend(&self) {
+        let sc = game_ui_scale();
+        let x = screen_width() - 170.0 * sc;
+        let mut y = screen_height() - 130.0 * sc;
+        draw_rectangle(x - 10.0 * sc, y - 25.0 * sc, 165.0 * sc, 95.0 * sc, Color::new(0.0, 0.0, 0.0, 0.5));
+        for (phase, label) in [(Phase::Flying, "Flying"), (Phase::Bouncing, "Bouncing"), (Phase::Sinking, "Sinking")] {
+            draw_line(x, y - 6.0 * sc, x + 40.0 * sc, y - 6.0 * sc, 3.0, phase_path_color(phase));
+            draw_text(label, x + 50.0 * sc, y, 20.0 * sc, WHITE);
+            y += 25.0 * sc;
+        }
+    }
+
+    /// 绘制幽灵轨迹：整条路径加上与当前帧同一时刻的位置
+    /// (幽灵轨迹取自 trajectory() 的完整记录，第 i 项即第 i 帧，与 current_frame 直接对应)
+    fn draw_ghost(&self, ghost: &[Stamp]) {
+        let color = Color::new(0.7, 0.7, 0.7, 0.35);
+        for w in ghost.windows(2) {
+            let p1 = self.world_to_screen(w[0].state.position);
+            let p2 = self.world_to_screen(w[1].state.position);
+            draw_line(p1.x, p1.y, p2.x, p2.y, 2.0, color);
+        }
+
+        let frame = self.current_frame.min(ghost.len().saturating_sub(1));
+        if let Some(stamp) = ghost.get(frame) {
+            let pos = self.world_to_screen(stamp.state.position);
+            draw_circle(pos.x, pos.y, 6.0, color);
+        }
+    }
+
+    /// 绘制训练模式的参考轨迹：粗的半透明路径，加上与当前模拟时间同一时刻的位置
+    fn draw_guide(&self) {
+        let Some((label, guide)) = &self.guide else { return; };
+        self.draw_stamp_path(guide, 6.0, GUIDE_COLOR);
+
+        // 参考轨迹是按步长抽样的，按时间而不是帧号对齐
+        let t = self.current_time();
+        let index = guide.partition_point(|stamp| stamp.t <= t).saturating_sub(1);
+        if let Some(stamp) = guide.get(index) {
+            let pos = self.world_to_screen(stamp.state.position);
+            draw_circle(pos.x, pos.y, 8.0, GUIDE_COLOR);
+        }
+        let sc = game_ui_scale();
+        draw_text(&format!("GUIDE: {}", label), 20.0 * sc, screen_height() - (90.0 + 22.0 * (self.overlays.len() + 1) as f32) * sc,
+                  20.0 * sc, Color { a: 1.0, ..GUIDE_COLOR });
+    }
+
+    /// 把一条完整轨迹画成折线 (跳过发散产生的非有限点)
+    fn draw_stamp_path(&self, trajectory: &[Stamp], thickness: f32, color: Color) {
+        let points: Vec<Vec2> = trajectory.iter()
+            .map(|stamp| stamp.state.position)
+            .filter(|p| p.x.is_finite() && p.y.is_finite())
+            .map(|p| self.world_to_screen(p))
+            .collect();
+        for w in points.windows(2) {
+            draw_line(w[0].x, w[0].y, w[1].x, w[1].y, thickness, color);
+        }
+    }
+
+    /// 轨迹模式：以主石片的颜色淡色绘制历史发射的路径，当前发射照常绘制在上面
+    fn draw_traces(&self) {
+        if !self.trace_mode { return; }
+        let Some(primary) = self.tracks.first() else { return; };
+        let color = Color { a: TRACE_ALPHA, ..primary.color };
+        for trace in &self.traces {
+            self.draw_stamp_path(trace, 1.5, color);
+        }
+        let sc = game_ui_scale();
+        draw_text(&format!("TRACE MODE: {} runs | X: clear", self.traces.len()),
+                  screen_width() / 2.0 - 120.0 * sc, 90.0 * sc, 22.0 * sc, primary.color);
+    }
+
+    /// 绘制对比轨迹的路径，并在左下角列出标签
+    fn draw_overlays(&self) {
+        for overlay in &self.overlays {
+            self.draw_stamp_path(&overlay.trajectory, 1.5, overlay.color);
+        }
+
+        let sc = game_ui_scale();
+        let mut y = screen_height() - (90.0 + 22.0 * self.overlays.len() as f32) * sc;
+        for overlay in &self.overlays {
+            draw_text(&format!("-- {}", overlay.label), 20.0 * sc, y, 20.0 * sc, overlay.color);
+            y += 22.0 * sc;
+        }
+    }
+
+    // --- 播放速度 ---
+
+    /// 按当前播放速度推进若干帧 (可能为 0 帧)
+    fn advance_playback(&mut self) {
+        if let Some((start, end)) = self.last_bounce_loop {
+            self.frame_accumulator += self.playback_speed * LAST_BOUNCE_SPEED;
+            while self.frame_accumulator >= 1.0 {
+                self.frame_accumulator -= 1.0;
+                if self.current_frame >= end {
+                    self.current_frame = start;
+                    self.particles.clear();
+                } else {
+                    self.current_frame += 1;
+                    self.spawn_splashes();
+                }
+            }
+            return;
+        }
+
+        if self.reverse_playback {
+            // 倒放：后退到最早保留的帧为止，不生成溅水粒子
+            let first = self.tracks.iter().map(|t| t.first_frame).min().unwrap_or(0);
+            self.frame_accumulator += self.current_playback_speed();
+            while self.frame_accumulator >= 1.0 && self.current_frame > first {
+                self.frame_accumulator -= 1.0;
+                self.current_frame -= 1;
+            }
+            if self.current_frame <= first {
+                self.current_frame = first;
+                self.frame_accumulator = 0.0;
+            }
+            return;
+        }
+
+        self.frame_accumulator += self.current_playback_speed();
+        while self.frame_accumulator >= 1.0 && self.current_frame + 1 < self.trajectory_len() {
+            self.frame_accumulator -= 1.0;
+            self.current_frame += 1;
+            self.spawn_splashes();
+        }
+        // 追上实时模拟后不再累积，避免新数据到来时一次跳过多帧
+        if self.current_frame + 1 >= self.trajectory_len() {
+            self.frame_accumulator = 0.0;
+        }
+    }
+
+    /// 游戏结束时调用：回到主石片最后一次入水前不久，慢速循环播放到沉没，直到按 Enter (跳到末尾) 或 R
+    /// 主石片从未入水时不做任何事
+    pub fn start_last_bounce_loop(&mut self) {
+        let Some(track) = self.tracks.first_mut() else { return; };
+        let first_frame = track.first_frame;
+        let Some((start, end)) = last_bounce_window(track.trajectory.make_contiguous(), LAST_BOUNCE_LEAD_TIME) else { return; };
+        self.last_bounce_loop = Some((first_frame + start, first_frame + end));
+        self.current_frame = first_frame + start;
+        self.frame_accumulator = 0.0;
+        self.is_playing = true;
+        self.reverse_playback = false;
+        self.particles.clear();
+    }
+
+    /// 退出结束回看并跳到最后一帧
+    fn exit_last_bounce_loop(&mut self) {
+        if self.last_bounce_loop.take().is_some() {
+            self.current_frame = self.trajectory_len().saturating_sub(1);
+            self.frame_accumulator = 0.0;
+        }
+    }
+
+    /// 当前的实际播放速度：靠近入水/出水时线性减速到 slow_motion_factor
+    fn current_playback_speed(&self) -> f32 {
+        let Some(distance) = self.frames_to_nearest_impact() else { return self.playback_speed; };
+        let ramp = distance as f32 / self.slow_motion_window as f32;
+        let factor = self.slow_motion_factor;
+        self.playback_speed * (factor + (1.0 - factor) * ramp)
+    }
+
+    /// 主石片当前帧到窗口内最近一次 Flying <-> Bouncing 切换的帧数
+    /// 长时间的抛物线飞行中窗口内没有切换，返回 None (原速播放)
+    fn frames_to_nearest_impact(&self) -> Option<usize> {
+        let window = self.slow_motion_window;
+        let primary = self.tracks.first()?;
+        let trajectory = &primary.trajectory;
+        if window == 0 || trajectory.len() < 2 { return None; }
+
+        let frame = primary.frame_index(self.current_frame);
+        let lo = frame.saturating_sub(window).max(1);
+        let hi = (frame + window).min(trajectory.len() - 1);
+        (lo..=hi)
+            .filter(|&i| matches!(
+                (trajectory[i - 1].phase, trajectory[i].phase),
+                (Phase::Flying, Phase::Bouncing) | (Phase::Bouncing, Phase::Flying)
+            ))
+            .map(|i| i.abs_diff(frame))
+            .min()
+    }
+
+    // --- 溅水粒子 ---
+
+    /// 当前帧刚从 Flying 进入 Bouncing 的石片，在其入水点溅起粒子
+    fn spawn_splashes(&mut self) {
+        let frame = self.current_frame;
+        let contacts: Vec<(Vector2D, f64)> = self.tracks.iter()
+            .filter_map(|t| Some((t, t.buffer_index(frame)?)))
+            .filter(|&(t, i)| i >= 1 && t.trajectory[i - 1].phase == Phase::Flying
+                && t.trajectory[i].phase == Phase::Bouncing)
+            .map(|(t, i)| {
+                let state = &t.trajectory[i].state;
+                let x = state.position.x;
+                (Vector2D::new(x, self.water_level + self.slope * x), state.velocity.x)
+            })
+            .collect();
+
+        for (contact, vx) in contacts {
+            for _ in 0..SPLASH_PARTICLES {
+                // 向上散开，略微顺着石片前进方向
+                let angle = self.particle_rng.range(0.15, std::f64::consts::PI - 0.15);
+                let speed = self.particle_rng.range(0.1, 0.5);
+                let velocity = Vector2D::new(angle.cos() * speed + 0.05 * vx, angle.sin() * speed);
+                let lifetime = PARTICLE_LIFETIME * self.particle_rng.range(0.5, 1.0) as f32;
+                self.particles.push(Particle { position: contact, velocity, lifetime });
+            }
+        }
+    }
+
+    /// 按真实帧时间推进粒子，移除寿命耗尽的粒子
+    fn update_particles(&mut self, dt: f32) {
+        let dt_world = dt as f64;
+        for p in self.particles.iter_mut() {
+            p.velocity.y -= PARTICLE_GRAVITY * dt_world;
+            p.position = p.position + p.velocity * dt_world;
+            p.lifetime -= dt;
+        }
+        self.particles.retain(|p| p.lifetime > 0.0);
+    }
+
+    /// 绘制粒子，随剩余寿命淡出
+    fn draw_particles(&self) {
+        for p in &self.particles {
+            let pos = self.world_to_screen(p.position);
+            let alpha = (p.lifetime / PARTICLE_LIFETIME).clamp(0.0, 1.0);
+            draw_circle(pos.x, pos.y, 2.5, Color::new(0.8, 0.9, 1.0, alpha));
         }
     }
 
     /// 绘制 Y=0 的水面线
     fn draw_water_surface(&self) {
-        let water_y_screen = self.world_to_screen(Vector2D::new(0.0, 0.0)).y;
+        // 屏幕左右边缘处的水面点
+        let surface_at = |screen_x: f32| {
+            let x = self.screen_to_world(vec2(screen_x, 0.0)).x;
+            self.world_to_screen(Vector2D::new(x, self.water_level + self.slope * x))
+        };
+        let left = surface_at(0.0);
+        let right = surface_at(screen_width());
+        let bottom = screen_height().max(left.y).max(right.y);
+
+        // 绘制水面；有涟漪时按像素步进采样起伏后的水面
+        if self.ripples.is_empty() {
+            draw_line(left.x, left.y, right.x, right.y, 2.0, BLUE);
+        } else {
+            let step = 4.0;
+            let wavy_at = |screen_x: f32| {
+                let x = self.screen_to_world(vec2(screen_x, 0.0)).x;
+                let ripple: f64 = self.ripples.iter().map(|r| r.height(x, self.ripple_time)).sum();
+                self.world_to_screen(Vector2D::new(x, self.water_level + self.slope * x + ripple))
+            };
+            let mut prev = wavy_at(0.0);
+            let mut sx = step;
+            while sx <= screen_width() + step {
+                let next = wavy_at(sx);
+                draw_line(prev.x, prev.y, next.x, next.y, 2.0, BLUE);
+                prev = next;
+                sx += step;
+            }
+        }
+        // 绘制水下区域 (填充，水面倾斜时用两个三角形)
+        let fill = Color::new(0.0, 0.2, 0.5, 0.3);
+        draw_triangle(left, right, vec2(right.x, bottom), fill);
+        draw_triangle(left, vec2(right.x, bottom), vec2(left.x, bottom), fill);
 
-        // 绘制水面
-        draw_line(0.0, water_y_screen, screen_width(), water_y_screen, 2.0, BLUE);
-        // 绘制水下区域 (填充)
-        draw_rectangle(0.0, water_y_screen, screen_width(), screen_height() - water_y_screen, Color::new(0.0, 0.2, 0.5, 0.3));
+        let label = if self.slope == 0.0 {
+            format!("Water (Y={})", self.water_level)
+        } else {
+            format!("Water (Y={} + {}X)", self.water_level, self.slope)
+        };
+        draw_text(&label, 20.0, left.y + 30.0, 20.0, LIGHTGRAY);
+
+        // 池底：水平线贯穿整个屏幕
+        if let Some(bottom_y) = self.bottom_y {
+            let y = self.world_to_screen(Vector2D::new(0.0, bottom_y)).y;
+            draw_line(0.0, y, screen_width(), y, 3.0, BROWN);
+            draw_text(&format!("Bottom (Y={})", bottom_y), 20.0, y + 24.0, 20.0, BROWN);
+        }
 
-        draw_text("Water (Y=0)", 20.0, water_y_screen + 30.0, 20.0, LIGHTGRAY);
+        // 对岸墙壁：竖直线贯穿整个屏幕
+        if let Some(wall_x) = self.wall_x {
+            let x = self.world_to_screen(Vector2D::new(wall_x, 0.0)).x;
+            draw_line(x, 0.0, x, screen_height(), 4.0, BROWN);
+            draw_text("Far shore", x + 8.0, 30.0, 20.0, BROWN);
+        }
     }
 
     /// 绘制背景网格和 X/Y 轴
@@ -240,87 +1105,216 @@ impl SimulationRenderer {
 
         draw_text("X (m)", screen_width() - 50.0, oy - 10.0, 20.0, RED);
         draw_text("Y (m)", ox + 10.0, 30.0, 20.0, GREEN);
+
+        self.draw_tick_labels(grid_spacing);
+    }
+
+    /// 在坐标轴旁标出每条网格线的世界坐标 (m)
+    /// 坐标轴移出屏幕时标签贴在屏幕边缘；与前一个标签重叠的跳过
+    fn draw_tick_labels(&self, grid_spacing: f32) {
+        let font_size = 14.0;
+        let color = Color::new(1.0, 1.0, 1.0, 0.5);
+        let step = grid_spacing as f64 / self.scale; // 相邻网格线的世界距离
+        let ox = self.world_origin_on_screen.x;
+        let oy = self.world_origin_on_screen.y;
+
+        // 第一条可见网格线 (与原点对齐)
+        let first = |origin: f32| origin - (origin / grid_spacing).floor() * grid_spacing;
+
+        // X 轴：标签在轴线下方
+        let label_y = (oy + 16.0).clamp(16.0, screen_height() - 4.0);
+        let mut last_end = f32::NEG_INFINITY;
+        let mut x = first(ox);
+        while x < screen_width() {
+            let text = tick_label(self.screen_to_world(vec2(x, oy)).x, step);
+            let width = measure_text(&text, None, font_size as u16, 1.0).width;
+            let left = x + 3.0;
+            if left > last_end + 6.0 {
+                draw_text(&text, left, label_y, font_size, color);
+                last_end = left + width;
+            }
+            x += grid_spacing;
+        }
+
+        // Y 轴：标签在轴线右侧 (自上而下，跳过与上一个标签重叠的)
+        let label_x = (ox + 4.0).clamp(4.0, screen_width() - 60.0);
+        let mut last_bottom = f32::NEG_INFINITY;
+        let mut y = first(oy);
+        while y < screen_height() {
+            let text = tick_label(self.screen_to_world(vec2(ox, y)).y, step);
+            let baseline = y - 3.0;
+            if baseline - font_size > last_bottom + 2.0 {
+                draw_text(&text, label_x, baseline, font_size, color);
+                last_bottom = baseline;
+            }
+            y += grid_spacing;
+        }
+    }
+
+    /// 绘制左下角的曲线面板：主石片的高度 (绿) 与速度 (橙) 随帧变化
+    /// 两条曲线各自按数据范围自动缩放，竖线标出当前帧
+    fn draw_graph_panel(&self) {
+        let Some(primary) = self.tracks.first() else { return; };
+        let n = primary.trajectory.len();
+        if n < 2 { return; }
+
+        let sc = game_ui_scale();
+        let rect = Rect::new(20.0 * sc, screen_height() - 270.0 * sc, 420.0 * sc, 190.0 * sc);
+        draw_rectangle(rect.x, rect.y, rect.w, rect.h, Color::from_rgba(0, 0, 0, 160));
+        draw_rectangle_lines(rect.x, rect.y, rect.w, rect.h, 2.0, GRAY);
+
+        let plot = Rect::new(rect.x + 10.0 * sc, rect.y + 30.0 * sc, rect.w - 20.0 * sc, rect.h - 40.0 * sc);
+        let series: [(Color, &str, StampValue); 2] = [
+            (GREEN, "height (m)", |s| s.state.position.y),
+            (ORANGE, "speed (m/s)", |s| s.state.velocity.length()),
+        ];
+
+        // 每个像素最多取一个样本
+        let step = (n / plot.w as usize).max(1);
+        for (k, (color, label, value)) in series.iter().enumerate() {
+            let (lo, hi) = primary.trajectory.iter()
+                .map(value)
+                .fold((f64::MAX, f64::MIN), |(lo, hi), v| (lo.min(v), hi.max(v)));
+            let range = (hi - lo).max(1e-9);
+
+            let to_screen = |i: usize| {
+                let x = plot.x + plot.w * i as f32 / (n - 1) as f32;
+                let y = plot.y + plot.h * (1.0 - ((value(&primary.trajectory[i]) - lo) / range) as f32);
+                vec2(x, y)
+            };
+            let mut prev = to_screen(0);
+            for i in (step..n).step_by(step).chain(std::iter::once(n - 1)) {
+                let p = to_screen(i);
+                draw_line(prev.x, prev.y, p.x, p.y, 1.5, *color);
+                prev = p;
+            }
+
+            draw_text(&format!("{} [{:.2}, {:.2}]", label, lo, hi),
+                      rect.x + (10.0 + 205.0 * k as f32) * sc, rect.y + 20.0 * sc, 16.0 * sc, *color);
+        }
+
+        // 当前帧
+        let frame = primary.frame_index(self.current_frame);
+        let cursor_x = plot.x + plot.w * frame as f32 / (n - 1) as f32;
+        draw_line(cursor_x, plot.y, cursor_x, plot.y + plot.h, 1.0, WHITE);
     }
 
     /// 绘制右侧的信息面板
     fn draw_info_panel(&mut self) {
-        let info_x = screen_width() - 300.0;
-        let info_y = 20.0;
-        let line_height = 25.0;
+        let sc = game_ui_scale();
+        let info_x = screen_width() - 300.0 * sc;
+        let info_y = 20.0 * sc;
+        let line_height = 25.0 * sc;
 
-        if !self.trajectory.is_empty() {
+        if let Some(primary) = self.tracks.first() && !primary.trajectory.is_empty() {
             draw_text(
-                &format!("F: {}/{}", self.current_frame + 1, self.trajectory.len()),
-                info_x, info_y, 20.0, WHITE,
+                &format!("F: {}/{}", self.current_frame + 1, self.trajectory_len()),
+                info_x, info_y, 20.0 * sc, WHITE,
             );
 
-            // 确保我们不会越界
-            if self.current_frame < self.trajectory.len() {
-                let state = &self.trajectory[self.current_frame];
+            // 确保我们不会越界 (主石片已沉没或该帧已被环形缓冲区丢弃时不显示)
+            if let Some(i) = primary.buffer_index(self.current_frame) {
+                let stamp = &primary.trajectory[i];
+                let state = &stamp.state;
                 draw_text(
-                    &format!("time: {:.3} s", self.current_frame as f64 * (1.0/60.0)), // 假设为 60fps
-                    info_x, info_y + line_height * 1.0, 20.0, WHITE,
+                    &format!("time: {:.3} s", self.current_time()), // 模拟时间
+                    info_x, info_y + line_height * 1.0, 20.0 * sc, WHITE,
                 );
                 draw_text(
                     &format!("location (x, y): ({:.2}, {:.2}) m", state.position.x, state.position.y),
-                    info_x, info_y + line_height * 2.0, 20.0, WHITE,
+                    info_x, info_y + line_height * 2.0, 20.0 * sc, WHITE,
                 );
                 draw_text(
                     &format!("velocity (x, y): ({:.2}, {:.2}) m/s", state.velocity.x, state.velocity.y),
-                    info_x, info_y + line_height * 3.0, 20.0, WHITE,
+                    info_x, info_y + line_height * 3.0, 20.0 * sc, WHITE,
+                );
+                // 积分中的角度会累积，显示时归一化到 [-180, 180)；单位与编辑器一致
+                let angle = state.angle.wrap_angles();
+                let unit = self.angle_unit;
+                draw_text(
+                    &format!("angle (x, y): ({}, {}) {}",
+                             unit.format(unit.in_unit(angle.x)), unit.format(unit.in_unit(angle.y)), unit.name()),
+                    info_x, info_y + line_height * 4.0, 20.0 * sc, WHITE,
                 );
                 draw_text(
-                    &format!("angle (x, y): ({:.1}, {:.1}) deg",
-                             state.angle.x.to_degrees(), state.angle.y.to_degrees()),
-                    info_x, info_y + line_height * 4.0, 20.0, WHITE,
+                    &format!("angle velocity (x, y): ({}, {}) {}/s",
+                             unit.format(unit.in_unit(state.angle_velocity.x)),
+                             unit.format(unit.in_unit(state.angle_velocity.y)), unit.name()),
+                    info_x, info_y + line_height * 5.0, 20.0 * sc, WHITE,
                 );
                 draw_text(
-                    &format!("angle velocity (x, y): ({:.1}, {:.1}) r/s",
-                             state.angle_velocity.x, state.angle_velocity.y),
-                    info_x, info_y + line_height * 5.0, 20.0, WHITE,
+                    &format!("energy: {:.4} J", stamp.energy),
+                    info_x, info_y + line_height * 6.0, 20.0 * sc, WHITE,
                 );
+                draw_text(
+                    &format!("wetted perimeter: {:.4} m   Fr: {:.2}", stamp.wetted_perimeter, stamp.froude),
+                    info_x, info_y + line_height * 8.0, 20.0 * sc, WHITE,
+                );
+                draw_text(
+                    &format!("gravity: {}", self.gravity_label),
+                    info_x, info_y + line_height * 7.0, 20.0 * sc, WHITE,
+                );
+                draw_text(
+                    &format!("phase: {:?}   peak accel: {:.1} g", stamp.phase, stamp.peak_g),
+                    info_x, info_y + line_height * 9.0, 20.0 * sc,
+                    if stamp.peak_g > STIFF_ENTRY_G { RED } else { WHITE },
+                );
+
+                // 浸没时各水动力分量的大小及占比，最大的一项高亮 (便于判断哪个系数起主导作用)
+                if let Some(forces) = stamp.forces {
+                    let magnitudes = forces.magnitudes();
+                    let dominant = magnitudes.iter().map(|(_, m)| *m).fold(0.0, f64::max);
+                    let sum: f64 = magnitudes.iter().map(|(_, m)| *m).sum();
+                    for (i, (name, magnitude)) in magnitudes.iter().enumerate() {
+                        let share = if sum > 0.0 { 100.0 * magnitude / sum } else { 0.0 };
+                        draw_text(
+                            &format!("  {}: {:.3} N ({:.0}%)", name, magnitude, share),
+                            info_x, info_y + line_height * (10.0 + i as f32), 20.0 * sc,
+                            if *magnitude == dominant && dominant > 0.0 { YELLOW } else { LIGHTGRAY },
+                        );
+                    }
+                }
             }
         }
 
+        let speed = self.current_playback_speed();
+        if self.last_bounce_loop.is_some() {
+            draw_text(&format!("LAST BOUNCE x{:.2} | Enter: skip to end", self.playback_speed * LAST_BOUNCE_SPEED),
+                      screen_width() / 2.0 - 160.0 * sc, 60.0 * sc, 24.0 * sc, SKYBLUE);
+        } else if self.is_playing && self.reverse_playback {
+            draw_text(&format!("REVERSE x{:.2}", speed), screen_width() / 2.0 - 60.0 * sc, 60.0 * sc, 24.0 * sc, ORANGE);
+        } else if self.is_playing && speed < self.playback_speed {
+            draw_text(&format!("SLOW x{:.2}", speed), screen_width() / 2.0 - 40.0 * sc, 60.0 * sc, 24.0 * sc, SKYBLUE);
+        }
+
+        if self.is_recording {
+            draw_circle(screen_width() / 2.0 - 40.0 * sc, 25.0 * sc, 8.0 * sc, RED);
+            draw_text("REC", screen_width() / 2.0 - 25.0 * sc, 32.0 * sc, 24.0 * sc, RED);
+        }
+
         draw_text(
-            "SPACE: Play/Pause | R: Reset",
-            20.0, screen_height() - 30.0, 20.0, GRAY,
+            "SPACE: Play/Pause | V: Reverse | R: Reset | C: Record | S: Save frame | H: Path colors | F: Fill | G: Graph | M: Markers | T: Trace mode | L: Contact path | Z: Fit | D: Measure",
+            20.0 * sc, screen_height() - 30.0 * sc, 20.0 * sc, GRAY,
         );
         draw_text(
-            "rool: scaling | Left mouse button drag: Pan",
-            250.0, screen_height() - 30.0, 20.0, GRAY,
+            &format!("rool: scaling | {} mouse button drag / arrows: Pan | Q/Home: Reset view | I: Compare integrator", pan_button_name(self.pan_button)),
+            20.0 * sc, screen_height() - 10.0 * sc, 20.0 * sc, GRAY,
         );
-        let btn_w = 120.0;
-        let btn_h = 40.0;
+        let btn_w = 120.0 * sc;
+        let btn_h = 40.0 * sc;
         // 放在右下角
-        let btn_x = screen_width() - 150.0;
-        let btn_y = screen_height() - 60.0;
-        let btn_rect = Rect::new(btn_x, btn_y, btn_w, btn_h);
-
-        // 检测鼠标悬停
-        let mouse_pos = mouse_position();
-        let mouse_point = vec2(mouse_pos.0, mouse_pos.1);
-        let is_hover = btn_rect.contains(mouse_point);
-
-        // 绘制按钮背景
-        let btn_color = if is_hover { LIGHTGRAY } else { DARKGRAY };
-        draw_rectangle(btn_x, btn_y, btn_w, btn_h, btn_color);
-        draw_rectangle_lines(btn_x, btn_y, btn_w, btn_h, 2.0, WHITE);
-
-        // 绘制按钮文字
-        let text = "Restart";
-        let font_size = 20;
-        // 简单居中计算
-        let text_dims = measure_text(text, None, font_size, 1.0);
-        let text_x = btn_x + (btn_w - text_dims.width) / 2.0;
-        let text_y = btn_y + (btn_h + text_dims.height) / 2.0 - 2.0; // 微调垂直居中
-
-        draw_text(text, text_x, text_y, font_size as f32, WHITE);
-
-        // 检测点击
-        if is_mouse_button_pressed(MouseButton::Left) && is_hover {
+        let btn_x = screen_width() - 150.0 * sc;
+        let btn_y = screen_height() - 60.0 * sc;
+
+        // Restart: 回到编辑器
+        if draw_button("Restart", Rect::new(btn_x, btn_y, btn_w, btn_h)) {
             self.should_restart = true;
         }
+        // Replay Same: 用相同石片和当前参数从头重新模拟
+        let replay_w = 140.0 * sc;
+        if draw_button("Replay Same", Rect::new(btn_x - replay_w - 10.0 * sc, btn_y, replay_w, btn_h)) {
+            self.should_replay = true;
+        }
     }
 
     // --- 坐标 & 控制 ---
@@ -367,11 +1361,11 @@ impl SimulationRenderer {
 
         // --- 平移 (鼠标中键) ---
         let mouse_pos = mouse_position().into();
-        if is_mouse_button_pressed(MouseButton::Left) {
+        if is_mouse_button_pressed(self.pan_button) {
             self.is_panning = true;
             self.last_mouse_pos = mouse_pos;
         }
-        if is_mouse_button_released(MouseButton::Left) {
+        if is_mouse_button_released(self.pan_button) {
             self.is_panning = false;
         }
         if self.is_panning {
@@ -379,6 +1373,16 @@ impl SimulationRenderer {
             self.world_origin_on_screen += delta;
             self.last_mouse_pos = mouse_pos;
         }
+
+        // --- 平移 (方向键，按住持续移动；方向为视野移动方向，画面反向移动) ---
+        let mut dir = vec2(0.0, 0.0);
+        if is_key_down(KeyCode::Left) { dir.x -= 1.0; }
+        if is_key_down(KeyCode::Right) { dir.x += 1.0; }
+        if is_key_down(KeyCode::Up) { dir.y -= 1.0; }
+        if is_key_down(KeyCode::Down) { dir.y += 1.0; }
+        if dir != Vec2::ZERO {
+            self.world_origin_on_screen -= dir.normalize() * self.key_pan_speed * get_frame_time();
+        }
     }
 
     // --- 公共控制 API ---
@@ -388,33 +1392,274 @@ impl SimulationRenderer {
         if is_key_pressed(KeyCode::Space) {
             self.toggle_play();
         }
+        if is_key_pressed(KeyCode::V) {
+            self.toggle_reverse();
+        }
         if is_key_pressed(KeyCode::R) {
             self.reset();
         }
-        if is_key_pressed(KeyCode::Q) {
+        if is_key_pressed(KeyCode::Enter) {
+            self.exit_last_bounce_loop();
+        }
+        if is_key_pressed(KeyCode::Q) || is_key_pressed(KeyCode::Home) {
             self.reset_view();
         }
+        if is_key_pressed(KeyCode::C) {
+            self.toggle_recording();
+        }
+        if is_key_pressed(KeyCode::H) {
+            self.path_coloring = self.path_coloring.next();
+        }
+        if is_key_pressed(KeyCode::F) {
+            self.filled = !self.filled;
+        }
+        if is_key_pressed(KeyCode::G) {
+            self.show_graph = !self.show_graph;
+        }
+        if is_key_pressed(KeyCode::M) {
+            self.show_markers = !self.show_markers;
+        }
+        if is_key_pressed(KeyCode::T) {
+            self.trace_mode = !self.trace_mode;
+        }
+        if is_key_pressed(KeyCode::X) {
+            self.traces.clear();
+        }
+        if is_key_pressed(KeyCode::L) {
+            self.show_contact_path = !self.show_contact_path;
+        }
+        if is_key_pressed(KeyCode::Z) {
+            self.fit_trajectory_to_view();
+        }
+        if is_key_pressed(KeyCode::S) {
+            self.figure_requested = true;
+        }
+        if is_key_pressed(KeyCode::D) {
+            self.measuring = !self.measuring;
+            self.measure_points.clear();
+        }
+        if self.measuring && is_mouse_button_pressed(MouseButton::Left) {
+            if self.measure_points.len() >= 2 {
+                self.measure_points.clear();
+            }
+            let point = self.screen_to_world(mouse_position().into());
+            self.measure_points.push(point);
+        }
+    }
+
+    /// 开始/停止录制；开始时确保输出目录存在
+    pub fn toggle_recording(&mut self) {
+        if self.is_recording {
+            self.is_recording = false;
+            println!("录制结束，共 {} 帧，保存在 {}/", self.record_index, RECORD_DIR);
+            return;
+        }
+
+        match fs::create_dir_all(RECORD_DIR) {
+            Ok(()) => {
+                self.is_recording = true;
+                println!("开始录制到 {}/", RECORD_DIR);
+            }
+            Err(e) => println!("Warning: 无法创建录制目录 {}: {}", RECORD_DIR, e),
+        }
+    }
+
+    /// (主循环调用，在 next_frame 之前) 录制中则保存当前画面
+    /// 按过 S 键时另外保存一张单帧截图
+    pub fn capture_if_recording(&mut self) {
+        if self.figure_requested {
+            self.figure_requested = false;
+            self.save_figure();
+        }
+        if !self.is_recording || !self.has_trajectory() { return; }
+
+        // 写入失败 (目录被删除、磁盘已满等) 时停止录制，不再逐帧重复报错
+        if let Err(e) = self.capture_frame(RECORD_DIR, self.record_index) {
+            println!("Warning: {}，录制已停止", e);
+            self.is_recording = false;
+            return;
+        }
+        self.record_index += 1;
+    }
+
+    /// 把当前屏幕保存为 `dir/frame_00000.png`
+    /// 文件名补零，便于外部工具按顺序合成 GIF/MP4
+    pub fn capture_frame(&self, dir: &str, index: usize) -> Result<(), String> {
+        if !Path::new(dir).is_dir() {
+            return Err(format!("录制目录 {} 不存在，无法保存第 {} 帧", dir, index));
+        }
+
+        let path = Path::new(dir).join(format!("frame_{:05}.png", index));
+        write_png(&get_screen_data(), &path.to_string_lossy())
+    }
+
+    /// 把当前屏幕 (含叠加层与 UI，窗口原生分辨率) 保存为单张 PNG
+    /// 文件名包含当前帧号与时间戳：figure_f00042_1700000000.png
+    fn save_figure(&self) {
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        let path = format!("{}_f{:05}_{}.png", FIGURE_PREFIX, self.current_frame, timestamp);
+        let screen = get_screen_data();
+        match write_png(&screen, &path) {
+            Ok(()) => println!("已保存截图 {} ({}x{})", path, screen.width, screen.height),
+            Err(e) => println!("Warning: {}", e),
+        }
     }
 
     pub fn toggle_play(&mut self) {
         self.is_playing = !self.is_playing;
     }
 
+    /// 切换播放方向；结束回看期间不可倒放
+    pub fn toggle_reverse(&mut self) {
+        if self.last_bounce_loop.is_some() { return; }
+        self.reverse_playback = !self.reverse_playback;
+        self.frame_accumulator = 0.0;
+    }
+
     pub fn reset(&mut self) {
         self.current_frame = 0;
+        self.reverse_playback = false;
+        self.frame_accumulator = 0.0;
         self.is_playing = true; // 重置后自动播放
+        self.particles.clear();
+        self.last_bounce_loop = None;
+    }
+
+    /// 清空所有轨迹 (保留石片、颜色和视角)，用于重新模拟
+    pub fn clear_trajectories(&mut self) {
+        for track in self.tracks.iter_mut() {
+            track.trajectory.clear();
+            track.history.clear();
+            track.contact_path.clear();
+            track.first_frame = 0;
+            track.speed_min = f64::MAX;
+            track.speed_max = 0.0;
+        }
+        self.should_replay = false;
+        self.reset();
+    }
+
+    /// 当前帧对应的模拟时间 (s)，取自主石片的时间戳；没有数据时为 0
+    pub fn current_time(&self) -> f64 {
+        self.current_stamp().map(|stamp| stamp.t).unwrap_or(0.0)
+    }
+
+    /// 当前帧主石片的状态；没有数据时为 None
+    pub fn current_state(&self) -> Option<&StoneInfo> {
+        self.current_stamp().map(|stamp| &stamp.state)
+    }
+
+    fn current_stamp(&self) -> Option<&Stamp> {
+        let primary = self.tracks.first()?;
+        primary.trajectory.get(primary.frame_index(self.current_frame))
+    }
+
+    /// 石片 (轨迹) 条数
+    pub fn track_count(&self) -> usize {
+        self.tracks.len()
+    }
+
+    /// 第 `track` 块石片的全部帧 (不受 max_trajectory_len 限制，不含受力分解与浸没多边形)
+    /// 计分、保存回放与幽灵轨迹使用；第 i 项即第 i 帧，可直接用 current_frame 索引
+    pub fn trajectory(&self, track: usize) -> &[Stamp] {
+        &self.tracks[track].history
+    }
+
+    /// 第 `track` 块石片绘制用缓冲区中保留的帧 (设置了 max_trajectory_len 时只有最近的部分)
+    pub fn trajectory_iter(&self, track: usize) -> impl DoubleEndedIterator<Item = &Stamp> + ExactSizeIterator {
+        self.tracks[track].trajectory.iter()
     }
 
+    /// 最长一条轨迹记录过的总帧数 (含环形缓冲区已丢弃的帧)
     pub fn trajectory_len(&self) -> usize {
-        self.trajectory.len()
+        self.tracks.iter().map(Track::total_frames).max().unwrap_or(0)
     }
 
     pub fn has_trajectory(&self) -> bool {
-        !self.trajectory.is_empty()
+        self.tracks.iter().any(|t| !t.trajectory.is_empty())
+    }
+
+    /// 缩放并平移视图，使所有轨迹 (加边距) 完整显示在窗口内
+    /// 轨迹为空或只有一个点时保持视图不变
+    pub fn fit_trajectory_to_view(&mut self) {
+        let mut points = self.tracks.iter()
+            .flat_map(|t| t.trajectory.iter())
+            .map(|stamp| stamp.state.position);
+        let Some(first) = points.next() else { return; };
+
+        // 1. 所有位置的 AABB
+        let (mut min, mut max) = (first, first);
+        for p in points {
+            min = Vector2D::new(min.x.min(p.x), min.y.min(p.y));
+            max = Vector2D::new(max.x.max(p.x), max.y.max(p.y));
+        }
+        let width = max.x - min.x;
+        let height = max.y - min.y;
+        if width < 1e-9 && height < 1e-9 { return; }
+
+        // 2. 取两个方向中较小的缩放，四周各留 10% 边距
+        let margin = 0.1;
+        let usable_w = screen_width() as f64 * (1.0 - 2.0 * margin);
+        let usable_h = screen_height() as f64 * (1.0 - 2.0 * margin);
+        let scale_x = if width > 1e-9 { usable_w / width } else { f64::MAX };
+        let scale_y = if height > 1e-9 { usable_h / height } else { f64::MAX };
+        self.scale = scale_x.min(scale_y);
+
+        // 3. AABB 中心对准屏幕中心
+        let center = Vector2D::new(0.5 * (min.x + max.x), 0.5 * (min.y + max.y));
+        self.world_origin_on_screen = vec2(
+            screen_width() / 2.0 - (center.x * self.scale) as f32,
+            screen_height() / 2.0 + (center.y * self.scale) as f32, // Y 轴反转
+        );
     }
 
+    /// 恢复创建时的缩放与默认原点 (Q / Home 键)
     pub fn reset_view(&mut self) {
-        self.scale = 8000.0; // 恢复默认缩放
+        self.scale = self.initial_scale;
         self.world_origin_on_screen = vec2(screen_width() / 4.0, screen_height() * 0.75);
     }
-}
\ No newline at end of file
+}
+
+/// 把屏幕图像保存为 PNG；与 Image::export_png 相同，但写入失败时返回错误而不是 panic
+fn write_png(screen: &Image, path: &str) -> Result<(), String> {
+    // 屏幕数据从下往上逐行存放，保存前上下翻转
+    let row = screen.width as usize * 4;
+    let bytes: Vec<u8> = screen.bytes.chunks_exact(row).rev().flatten().copied().collect();
+    ::image::save_buffer(path, &bytes, screen.width as u32, screen.height as u32, ::image::ColorType::Rgba8)
+        .map_err(|e| format!("无法写入 {}: {}", path, e))
+}
+
+/// 提示文字中的鼠标键名称
+fn pan_button_name(button: MouseButton) -> &'static str {
+    match button {
+        MouseButton::Left => "Left",
+        MouseButton::Right => "Right",
+        MouseButton::Middle => "Middle",
+        _ => "Other",
+    }
+}
+
+/// 绘制一个带悬停效果的按钮，返回本帧是否被点击
+fn draw_button(text: &str, rect: Rect) -> bool {
+    // 检测鼠标悬停
+    let mouse_pos = mouse_position();
+    let is_hover = rect.contains(vec2(mouse_pos.0, mouse_pos.1));
+
+    // 绘制按钮背景
+    let btn_color = if is_hover { LIGHTGRAY } else { DARKGRAY };
+    draw_rectangle(rect.x, rect.y, rect.w, rect.h, btn_color);
+    draw_rectangle_lines(rect.x, rect.y, rect.w, rect.h, 2.0, WHITE);
+
+    // 绘制按钮文字 (简单居中计算，字号随界面缩放)
+    let font_size = (20.0 * game_ui_scale()) as u16;
+    let text_dims = measure_text(text, None, font_size, 1.0);
+    let text_x = rect.x + (rect.w - text_dims.width) / 2.0;
+    let text_y = rect.y + (rect.h + text_dims.height) / 2.0 - 2.0; // 微调垂直居中
+    draw_text(text, text_x, text_y, font_size as f32, WHITE);
+
+    // 检测点击
+    is_mouse_button_pressed(MouseButton::Left) && is_hover
+}
@@ -0,0 +1,220 @@
+// 职责：完整回放文件 (.dapreplay)。在 run.json 的全部输入 (蓝图、初始状态、物理参数、种子)
+// 之外，可选地附带模拟得到的轨迹，作为分享与交换结果的统一格式。
+// 读取时可以按输入重新模拟，也可以直接播放保存的轨迹。
+// 文件带版本号：新增字段时提高 REPLAY_VERSION，读取旧版本时为缺少的字段补默认值。
+// 版本 1 只含最初的物理参数；版本 2 加入了此后新增的参数 (水深、墙壁、自转升力、恢复系数衰减等)。
+// 与 run.json 相同，格式是手写的扁平 JSON 对象。
+
+use std::fs;
+
+use crate::basic_structs::Vector2D;
+use crate::physics::parameters::Phase;
+use crate::physics::simulation::{Stamp, StoneInfo};
+use crate::run_record::{parse_numbers, split_object, RunRecord};
+
+/// 每局结束时自动保存的回放文件 (相对于工作目录)
+pub const REPLAY_FILE: &str = "last.dapreplay";
+/// 回放文件扩展名，`--replay` 按扩展名区分回放文件与 run.json
+pub const REPLAY_EXTENSION: &str = ".dapreplay";
+/// 当前写入的格式版本
+pub const REPLAY_VERSION: u32 = 2;
+const FORMAT_TAG: &str = "dapreplay";
+
+/// 每帧保存的数值个数：t, 状态 (8), 能量, 相位, 湿周
+/// 受力分解、弗劳德数与浸没多边形只用于显示，不保存
+const STAMP_FIELDS: usize = 12;
+
+/// 一次模拟的输入以及 (可选的) 结果轨迹
+pub struct Replay {
+    /// 文件的格式版本 (读取时为文件中的值，新建时为 REPLAY_VERSION)
+    pub version: u32,
+    pub record: RunRecord,
+    pub trajectory: Option<Vec<Stamp>>,
+}
+
+impl Replay {
+    pub fn new(record: RunRecord, trajectory: Option<Vec<Stamp>>) -> Self {
+        Self { version: REPLAY_VERSION, record, trajectory }
+    }
+
+    fn to_json(&self) -> String {
+        let mut fields = vec![
+            format!("\"format\": \"{}\"", FORMAT_TAG),
+            format!("\"version\": {}", REPLAY_VERSION),
+        ];
+        fields.extend(self.record.json_fields());
+        if let Some(trajectory) = &self.trajectory {
+            let values: Vec<String> = trajectory.iter()
+                .flat_map(stamp_values)
+                .map(|v| v.to_string())
+                .collect();
+            fields.push(format!("\"trajectory\": [{}]", values.join(", ")));
+        }
+        format!("{{\n  {}\n}}\n", fields.join(",\n  "))
+    }
+}
+
+/// 写入回放文件；返回可读的错误信息
+pub fn save_replay(path: &str, replay: &Replay) -> Result<(), String> {
+    fs::write(path, replay.to_json()).map_err(|e| format!("无法写入回放文件 {}: {}", path, e))
+}
+
+/// 读取回放文件；比本程序更新的版本和格式错误返回可读的错误信息
+pub fn load_replay(path: &str) -> Result<Replay, String> {
+    let text = fs::read_to_string(path).map_err(|e| format!("无法读取 {}: {}", path, e))?;
+    parse_replay(&text).map_err(|e| format!("{}: {}", path, e))
+}
+
+fn parse_replay(text: &str) -> Result<Replay, String> {
+    let fields = split_object(text).ok_or("不是 JSON 对象")?;
+    let get = |key: &str| fields.iter().find(|(k, _)| k == key).map(|(_, v)| v.as_str());
+
+    // 1. 格式与版本
+    if get("format").map(|f| f.trim_matches('"')) != Some(FORMAT_TAG) {
+        return Err("不是回放文件".to_string());
+    }
+    let version: u32 = get("version").and_then(|v| v.parse().ok()).ok_or("缺少版本号")?;
+    if version > REPLAY_VERSION {
+        return Err(format!("文件版本 {} 比本程序支持的 {} 新", version, REPLAY_VERSION));
+    }
+
+    // 2. 输入 (与 run.json 相同的字段)
+    let record = RunRecord::from_fields(&fields).ok_or("输入参数格式错误")?;
+
+    // 3. 轨迹 (可选)
+    let trajectory = match get("trajectory") {
+        Some(value) => {
+            let values = parse_numbers(value).ok_or("轨迹格式错误")?;
+            if values.len() % STAMP_FIELDS != 0 {
+                return Err("轨迹长度错误".to_string());
+            }
+            Some(values.chunks(STAMP_FIELDS).map(stamp_from_values).collect::<Option<Vec<_>>>()
+                .ok_or("轨迹中的相位无效")?)
+        }
+        None => None,
+    };
+
+    Ok(Replay { version, record, trajectory })
+}
+
+fn stamp_values(stamp: &Stamp) -> [f64; STAMP_FIELDS] {
+    let s = &stamp.state;
+    let phase = match stamp.phase {
+        Phase::Flying => 0.0,
+        Phase::Bouncing => 1.0,
+        Phase::Sinking => 2.0,
+    };
+    [stamp.t,
+     s.position.x, s.position.y, s.velocity.x, s.velocity.y,
+     s.angle.x, s.angle.y, s.angle_velocity.x, s.angle_velocity.y,
+     stamp.energy, phase, stamp.wetted_perimeter]
+}
+
+fn stamp_from_values(v: &[f64]) -> Option<Stamp> {
+    let phase = match v[10] as u8 {
+        0 => Phase::Flying,
+        1 => Phase::Bouncing,
+        2 => Phase::Sinking,
+        _ => return None,
+    };
+    Some(Stamp {
+        t: v[0],
+        state: StoneInfo {
+            position: Vector2D::new(v[1], v[2]),
+            velocity: Vector2D::new(v[3], v[4]),
+            angle: Vector2D::new(v[5], v[6]),
+            angle_velocity: Vector2D::new(v[7], v[8]),
+        },
+        energy: v[9],
+        phase,
+        wetted_perimeter: v[11],
+        froude: 0.0,
+        peak_g: 0.0,
+        forces: None,
+        submerged: Vec::new(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physics::parameters::CustomSettings;
+    use crate::physics::simulation::{simulate, DEFAULT_SAMPLE_STRIDE};
+    use crate::rng::Rng;
+    use crate::run_record::SettingsSnapshot;
+    use crate::solver2::RungeKuttaSolver;
+    use crate::stone_editor::StoneBlueprint;
+    use crate::stone_phy::{StoneProperties, DEFAULT_MESH_POINTS};
+
+    #[test]
+    fn replay_round_trips_inputs_and_trajectory() {
+        let blueprint = StoneBlueprint {
+            points: vec![
+                Vector2D::new(-0.03, -0.02),
+                Vector2D::new(0.03, -0.02),
+                Vector2D::new(0.03, 0.02),
+                Vector2D::new(-0.03, 0.02),
+            ],
+            thickness: 0.01,
+            name: "rect".to_string(),
+            density_fn: None,
+        };
+        let y0 = StoneInfo {
+            position: Vector2D::new(0.0, 0.05),
+            velocity: Vector2D::new(12.0, -1.0),
+            angle: Vector2D::new(0.3, 0.0),
+            angle_velocity: Vector2D::new(0.0, 20.0),
+        };
+        let mut rng = Rng::new(5);
+        let stone = StoneProperties::new(&blueprint, &mut rng).unwrap();
+        let mut system = CustomSettings::new(9.81, stone, rng.fork()).with_initial_state(&y0);
+        let settings = SettingsSnapshot::capture(&system);
+        let mut solver = RungeKuttaSolver::new(0.0, y0.clone());
+        let (trajectory, _) = simulate(&mut system, &mut solver, 0.001, 2_000, DEFAULT_SAMPLE_STRIDE);
+
+        let replay = Replay::new(RunRecord { blueprint, y0, seed: 5, mesh_points: DEFAULT_MESH_POINTS, settings }, Some(trajectory.clone()));
+        let parsed = parse_replay(&replay.to_json()).unwrap();
+        assert_eq!(parsed.version, REPLAY_VERSION);
+        assert_eq!(parsed.record.blueprint.points, replay.record.blueprint.points);
+        assert_eq!(parsed.record.seed, 5);
+        let stored = parsed.trajectory.unwrap();
+        assert_eq!(stored.len(), trajectory.len());
+        for (a, b) in stored.iter().zip(&trajectory) {
+            assert_eq!((a.t, a.phase, a.energy), (b.t, b.phase, b.energy));
+            assert_eq!(a.state.position, b.state.position);
+            assert_eq!(a.state.angle_velocity, b.state.angle_velocity);
+        }
+
+        // 不带轨迹的回放；更新版本的文件被拒绝
+        let inputs_only = Replay::new(parsed.record, None);
+        assert!(parse_replay(&inputs_only.to_json()).unwrap().trajectory.is_none());
+        let future = inputs_only.to_json().replace(&format!("\"version\": {}", REPLAY_VERSION), "\"version\": 99");
+        assert!(parse_replay(&future).is_err());
+    }
+
+    #[test]
+    fn first_version_files_still_load_with_defaults() {
+        // 第一版格式写出的文件 (此后新增的参数都不在其中)
+        let replay = parse_replay(include_str!("../tests/golden/v1.dapreplay")).unwrap();
+        assert_eq!(replay.version, 1);
+        assert_eq!(replay.record.blueprint.name, "rect");
+        assert_eq!(replay.record.mesh_points, DEFAULT_MESH_POINTS);
+        assert_eq!(replay.trajectory.map(|t| t.len()), Some(3));
+
+        let settings = &replay.record.settings;
+        let defaults = SettingsSnapshot::capture(&CustomSettings::new(9.81, StoneProperties::default(), Rng::new(1)));
+        assert_eq!(settings.gravity, 9.81);
+        assert_eq!(settings.restitution, 0.6);
+        assert_eq!(settings.water_depth, defaults.water_depth);
+        assert_eq!(settings.spin_lift_coeff, defaults.spin_lift_coeff);
+        assert_eq!(settings.force_ramp_time, defaults.force_ramp_time);
+        assert_eq!(settings.damping_quad, defaults.damping_quad);
+        assert_eq!(settings.restitution_decay, defaults.restitution_decay);
+        assert_eq!(settings.settle, defaults.settle);
+        assert!(settings.wall_x.is_none());
+
+        // 存在但格式错误的字段仍然拒绝
+        let broken = include_str!("../tests/golden/v1.dapreplay").replace("\"Cl\": 0.2", "\"Cl\": oops");
+        assert!(parse_replay(&broken).is_err());
+    }
+}
@@ -0,0 +1,47 @@
+// 职责：提供可复现的伪随机数 (SplitMix64)。
+// 不使用 macroquad::rand，保证相同种子在任何平台上得到逐位相同的序列，
+// 便于回归测试和轨迹对比。
+
+/// 默认种子 (主程序使用)
+pub const DEFAULT_SEED: u64 = 0x5EED_0000_D0A9_0001;
+
+/// SplitMix64 伪随机数生成器
+#[derive(Clone, Debug)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    /// 生成下一个 64 位随机数
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// 生成 [0, 1) 内的均匀分布浮点数 (取高 53 位)
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 * (1.0 / (1u64 << 53) as f64)
+    }
+
+    /// 生成 [lo, hi) 内的均匀分布浮点数
+    pub fn range(&mut self, lo: f64, hi: f64) -> f64 {
+        lo + (hi - lo) * self.next_f64()
+    }
+
+    /// 当前内部状态；`Rng::new(state)` 可从该值恢复出完全相同的后续序列
+    pub fn state(&self) -> u64 {
+        self.state
+    }
+
+    /// 派生一个独立的子生成器 (例如给受力模型单独使用)
+    pub fn fork(&mut self) -> Self {
+        Self::new(self.next_u64())
+    }
+}
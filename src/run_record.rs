@@ -0,0 +1,485 @@
+// 职责：把一次模拟的全部输入 (蓝图、初始状态、物理参数、种子) 保存为 run.json，
+// 并能从该文件重新读取，配合 `--replay run.json` 跳过编辑器直接复现。
+// 与排行榜相同，格式是手写的扁平 JSON 对象，不引入额外依赖。
+
+use std::fs;
+
+use crate::basic_structs::Vector2D;
+use crate::physics::parameters::{BounceModel, CustomSettings, CustomSettingsBuilder, DecayCurve, SettleCriteria, WallMode};
+use crate::physics::simulation::StoneInfo;
+use crate::stone_editor::StoneBlueprint;
+use crate::stone_phy::{StoneProperties, DEFAULT_MESH_POINTS};
+
+/// 默认记录文件路径 (相对于工作目录)
+pub const RUN_FILE: &str = "run.json";
+
+/// 可复现一次模拟所需的全部输入
+/// 注意：蓝图的 density_fn 是函数指针，无法保存，回放时按均匀密度处理
+pub struct RunRecord {
+    pub blueprint: StoneBlueprint,
+    pub y0: StoneInfo,
+    pub seed: u64,
+    pub mesh_points: usize, // 碰撞网格采样点数 (旧文件没有该字段时取默认值)
+    pub settings: SettingsSnapshot,
+}
+
+/// CustomSettings 中可调参数的快照 (不含运行状态)
+#[allow(non_snake_case)]
+pub struct SettingsSnapshot {
+    pub gravity: f64,
+    pub rho: f64,
+    pub Cl: f64,
+    pub Cf: f64,
+    pub beta: f64,
+    pub gyro_coeff: f64,
+    pub spin_lift_coeff: f64,
+    pub added_mass_coeff: f64,
+    pub damping_quad: f64,
+    pub damping_lin: f64,
+    pub tension_coeff: f64,
+    pub wave_damping_coeff: f64,
+    pub horizontal_resist_coeff: f64,
+    pub max_pitch_rate: f64,
+    pub max_spin_rate: f64,
+    pub water_level: f64,
+    pub slope: f64,
+    pub water_depth: f64,
+    pub wall_x: Option<f64>,
+    pub wall_mode: WallMode,
+    pub water_temp_c: f64,
+    pub use_reynolds_drag: bool,
+    pub nu: f64,
+    pub distributed_forces: bool,
+    pub buoyancy: bool,
+    pub air_drag: bool,
+    pub rho_air: f64,
+    pub Cd_air: f64,
+    pub gust_strength: f64,
+    pub gust_frequency: f64,
+    pub min_skip_speed: f64,
+    pub force_ramp_time: f64,
+    pub spin_coupling: f64,
+    pub settle: SettleCriteria,
+    pub ripple_amplitude: f64,
+    pub ripple_feedback: bool,
+    pub dt_flying: f64,
+    pub dt_bouncing: f64,
+    pub bounce_model: BounceModel,
+    pub restitution: f64,
+    pub tangent_friction: f64,
+    pub restitution_decay: f64,
+    pub restitution_decay_curve: DecayCurve,
+}
+
+impl SettingsSnapshot {
+    pub fn capture(s: &CustomSettings) -> Self {
+        Self {
+            gravity: s.gravity,
+            rho: s.rho,
+            Cl: s.Cl,
+            Cf: s.Cf,
+            beta: s.beta,
+            gyro_coeff: s.gyro_coeff,
+            spin_lift_coeff: s.spin_lift_coeff,
+            added_mass_coeff: s.added_mass_coeff,
+            damping_quad: s.damping_quad,
+            damping_lin: s.damping_lin,
+            tension_coeff: s.tension_coeff,
+            wave_damping_coeff: s.wave_damping_coeff,
+            horizontal_resist_coeff: s.horizontal_resist_coeff,
+            max_pitch_rate: s.max_pitch_rate,
+            max_spin_rate: s.max_spin_rate,
+            water_level: s.water_level,
+            slope: s.slope,
+            water_depth: s.water_depth,
+            wall_x: s.wall_x,
+            wall_mode: s.wall_mode,
+            water_temp_c: s.water_temp_c,
+            use_reynolds_drag: s.use_reynolds_drag,
+            nu: s.nu,
+            distributed_forces: s.distributed_forces,
+            buoyancy: s.buoyancy,
+            air_drag: s.air_drag,
+            rho_air: s.rho_air,
+            Cd_air: s.Cd_air,
+            gust_strength: s.gust_strength,
+            gust_frequency: s.gust_frequency,
+            min_skip_speed: s.min_skip_speed,
+            force_ramp_time: s.force_ramp_time,
+            spin_coupling: s.spin_coupling,
+            settle: s.settle,
+            ripple_amplitude: s.ripple_amplitude,
+            ripple_feedback: s.ripple_feedback,
+            dt_flying: s.dt_flying,
+            dt_bouncing: s.dt_bouncing,
+            bounce_model: s.bounce_model,
+            restitution: s.restitution,
+            tangent_friction: s.tangent_friction,
+            restitution_decay: s.restitution_decay,
+            restitution_decay_curve: s.restitution_decay_curve,
+        }
+    }
+
+    pub fn apply(&self, s: &mut CustomSettings) {
+        s.gravity = self.gravity;
+        s.rho = self.rho;
+        s.Cl = self.Cl;
+        s.Cf = self.Cf;
+        s.beta = self.beta;
+        s.gyro_coeff = self.gyro_coeff;
+        s.spin_lift_coeff = self.spin_lift_coeff;
+        s.added_mass_coeff = self.added_mass_coeff;
+        s.damping_quad = self.damping_quad;
+        s.damping_lin = self.damping_lin;
+        s.tension_coeff = self.tension_coeff;
+        s.wave_damping_coeff = self.wave_damping_coeff;
+        s.horizontal_resist_coeff = self.horizontal_resist_coeff;
+        s.max_pitch_rate = self.max_pitch_rate;
+        s.max_spin_rate = self.max_spin_rate;
+        s.water_level = self.water_level;
+        s.slope = self.slope;
+        s.water_depth = self.water_depth;
+        s.wall_x = self.wall_x;
+        s.wall_mode = self.wall_mode;
+        s.water_temp_c = self.water_temp_c;
+        s.use_reynolds_drag = self.use_reynolds_drag;
+        s.nu = self.nu;
+        s.distributed_forces = self.distributed_forces;
+        s.buoyancy = self.buoyancy;
+        s.air_drag = self.air_drag;
+        s.rho_air = self.rho_air;
+        s.Cd_air = self.Cd_air;
+        s.gust_strength = self.gust_strength;
+        s.gust_frequency = self.gust_frequency;
+        s.min_skip_speed = self.min_skip_speed;
+        s.force_ramp_time = self.force_ramp_time;
+        s.spin_coupling = self.spin_coupling;
+        s.settle = self.settle;
+        s.ripple_amplitude = self.ripple_amplitude;
+        s.ripple_feedback = self.ripple_feedback;
+        s.dt_flying = self.dt_flying;
+        s.dt_bouncing = self.dt_bouncing;
+        s.bounce_model = self.bounce_model;
+        s.restitution = self.restitution;
+        s.tangent_friction = self.tangent_friction;
+        s.restitution_decay = self.restitution_decay;
+        s.restitution_decay_curve = self.restitution_decay_curve;
+    }
+
+    /// 序列化为 "键": 值 形式的 JSON 字段 (不含外层花括号)
+    pub(crate) fn json_fields(&self) -> Vec<String> {
+        let bounce_model = match self.bounce_model {
+            BounceModel::Continuous => "Continuous",
+            BounceModel::Impulse => "Impulse",
+        };
+        vec![
+            format!("\"gravity\": {}", self.gravity),
+            format!("\"rho\": {}", self.rho),
+            format!("\"Cl\": {}", self.Cl),
+            format!("\"Cf\": {}", self.Cf),
+            format!("\"beta\": {}", self.beta),
+            format!("\"gyro_coeff\": {}", self.gyro_coeff),
+            format!("\"spin_lift_coeff\": {}", self.spin_lift_coeff),
+            format!("\"added_mass_coeff\": {}", self.added_mass_coeff),
+            format!("\"damping_quad\": {}", self.damping_quad),
+            format!("\"damping_lin\": {}", self.damping_lin),
+            format!("\"tension_coeff\": {}", self.tension_coeff),
+            format!("\"wave_damping_coeff\": {}", self.wave_damping_coeff),
+            format!("\"horizontal_resist_coeff\": {}", self.horizontal_resist_coeff),
+            format!("\"max_pitch_rate\": {}", self.max_pitch_rate),
+            format!("\"max_spin_rate\": {}", self.max_spin_rate),
+            format!("\"water_level\": {}", self.water_level),
+            format!("\"slope\": {}", self.slope),
+            format!("\"water_depth\": {}", self.water_depth),
+            format!("\"wall_x\": {}", self.wall_x.map_or("null".to_string(), |x| x.to_string())),
+            format!("\"wall_mode\": \"{:?}\"", self.wall_mode),
+            format!("\"water_temp_c\": {}", self.water_temp_c),
+            format!("\"use_reynolds_drag\": {}", self.use_reynolds_drag),
+            format!("\"nu\": {}", self.nu),
+            format!("\"distributed_forces\": {}", self.distributed_forces),
+            format!("\"buoyancy\": {}", self.buoyancy),
+            format!("\"air_drag\": {}", self.air_drag),
+            format!("\"rho_air\": {}", self.rho_air),
+            format!("\"Cd_air\": {}", self.Cd_air),
+            format!("\"gust_strength\": {}", self.gust_strength),
+            format!("\"gust_frequency\": {}", self.gust_frequency),
+            format!("\"min_skip_speed\": {}", self.min_skip_speed),
+            format!("\"force_ramp_time\": {}", self.force_ramp_time),
+            format!("\"spin_coupling\": {}", self.spin_coupling),
+            format!("\"settle_window\": {}", self.settle.window),
+            format!("\"settle_variance\": {}", self.settle.max_variance),
+            format!("\"settle_speed\": {}", self.settle.max_speed),
+            format!("\"ripple_amplitude\": {}", self.ripple_amplitude),
+            format!("\"ripple_feedback\": {}", self.ripple_feedback),
+            format!("\"dt_flying\": {}", self.dt_flying),
+            format!("\"dt_bouncing\": {}", self.dt_bouncing),
+            format!("\"bounce_model\": \"{}\"", bounce_model),
+            format!("\"restitution\": {}", self.restitution),
+            format!("\"tangent_friction\": {}", self.tangent_friction),
+            format!("\"restitution_decay\": {}", self.restitution_decay),
+            format!("\"restitution_decay_curve\": \"{:?}\"", self.restitution_decay_curve),
+        ]
+    }
+
+    /// 从 split_object 拆出的字段中读取；格式错误的字段返回 None
+    /// 缺少的字段 (旧版本文件中还没有的参数) 取 CustomSettings 的默认值
+    pub(crate) fn from_fields(fields: &[(String, String)]) -> Option<Self> {
+        let d = SettingsSnapshot::capture(&CustomSettingsBuilder::new().build(StoneProperties::default()));
+        let get = |key: &str| fields.iter().find(|(k, _)| k == key).map(|(_, v)| v.as_str());
+        let num = |key: &str, default: f64| match get(key) {
+            Some(v) => v.parse::<f64>().ok().filter(|x| x.is_finite()),
+            None => Some(default),
+        };
+        let flag = |key: &str, default: bool| match get(key) {
+            Some(v) => v.parse().ok(),
+            None => Some(default),
+        };
+        let word = |key: &str| get(key).map(|v| v.trim_matches('"'));
+
+        let bounce_model = match word("bounce_model") {
+            None => d.bounce_model,
+            Some("Continuous") => BounceModel::Continuous,
+            Some("Impulse") => BounceModel::Impulse,
+            Some(_) => return None,
+        };
+        let wall_mode = match word("wall_mode") {
+            None => d.wall_mode,
+            Some("Stop") => WallMode::Stop,
+            Some("Bounce") => WallMode::Bounce,
+            Some(_) => return None,
+        };
+        let restitution_decay_curve = match word("restitution_decay_curve") {
+            None => d.restitution_decay_curve,
+            Some("Geometric") => DecayCurve::Geometric,
+            Some("Linear") => DecayCurve::Linear,
+            Some(_) => return None,
+        };
+        Some(SettingsSnapshot {
+            gravity: num("gravity", d.gravity)?,
+            rho: num("rho", d.rho)?,
+            Cl: num("Cl", d.Cl)?,
+            Cf: num("Cf", d.Cf)?,
+            beta: num("beta", d.beta)?,
+            gyro_coeff: num("gyro_coeff", d.gyro_coeff)?,
+            spin_lift_coeff: num("spin_lift_coeff", d.spin_lift_coeff)?,
+            added_mass_coeff: num("added_mass_coeff", d.added_mass_coeff)?,
+            damping_quad: num("damping_quad", d.damping_quad)?,
+            damping_lin: num("damping_lin", d.damping_lin)?,
+            tension_coeff: num("tension_coeff", d.tension_coeff)?,
+            wave_damping_coeff: num("wave_damping_coeff", d.wave_damping_coeff)?,
+            horizontal_resist_coeff: num("horizontal_resist_coeff", d.horizontal_resist_coeff)?,
+            max_pitch_rate: num("max_pitch_rate", d.max_pitch_rate)?,
+            max_spin_rate: num("max_spin_rate", d.max_spin_rate)?,
+            water_level: num("water_level", d.water_level)?,
+            slope: num("slope", d.slope)?,
+            water_depth: num("water_depth", d.water_depth).filter(|&depth| depth >= 0.0)?,
+            wall_x: match get("wall_x") {
+                None | Some("null") => None,
+                Some(_) => Some(num("wall_x", 0.0)?),
+            },
+            wall_mode,
+            water_temp_c: num("water_temp_c", d.water_temp_c)?,
+            use_reynolds_drag: flag("use_reynolds_drag", d.use_reynolds_drag)?,
+            nu: num("nu", d.nu)?,
+            distributed_forces: flag("distributed_forces", d.distributed_forces)?,
+            buoyancy: flag("buoyancy", d.buoyancy)?,
+            air_drag: flag("air_drag", d.air_drag)?,
+            rho_air: num("rho_air", d.rho_air)?,
+            Cd_air: num("Cd_air", d.Cd_air)?,
+            gust_strength: num("gust_strength", d.gust_strength)?,
+            gust_frequency: num("gust_frequency", d.gust_frequency)?,
+            min_skip_speed: num("min_skip_speed", d.min_skip_speed)?,
+            force_ramp_time: num("force_ramp_time", d.force_ramp_time).filter(|&t| t >= 0.0)?,
+            spin_coupling: num("spin_coupling", d.spin_coupling)?,
+            settle: SettleCriteria {
+                window: num("settle_window", d.settle.window)?,
+                max_variance: num("settle_variance", d.settle.max_variance)?,
+                max_speed: num("settle_speed", d.settle.max_speed)?,
+            },
+            ripple_amplitude: num("ripple_amplitude", d.ripple_amplitude)?,
+            ripple_feedback: flag("ripple_feedback", d.ripple_feedback)?,
+            dt_flying: num("dt_flying", d.dt_flying).filter(|&dt| dt > 0.0)?,
+            dt_bouncing: num("dt_bouncing", d.dt_bouncing).filter(|&dt| dt > 0.0)?,
+            bounce_model,
+            restitution: num("restitution", d.restitution)?,
+            tangent_friction: num("tangent_friction", d.tangent_friction)?,
+            restitution_decay: num("restitution_decay", d.restitution_decay)?,
+            restitution_decay_curve,
+        })
+    }
+}
+
+impl RunRecord {
+    /// 写入文件；失败时只打印警告
+    pub fn save(&self, path: &str) {
+        if let Err(e) = fs::write(path, self.to_json()) {
+            println!("Warning: 无法写入运行记录 {}: {}", path, e);
+        }
+    }
+
+    /// 从文件读取；返回可读的错误信息
+    pub fn load(path: &str) -> Result<Self, String> {
+        let text = fs::read_to_string(path).map_err(|e| format!("无法读取 {}: {}", path, e))?;
+        parse_record(&text).ok_or_else(|| format!("{} 格式错误", path))
+    }
+
+    fn to_json(&self) -> String {
+        format!("{{\n  {}\n}}\n", self.json_fields().join(",\n  "))
+    }
+
+    /// 序列化为 "键": 值 形式的 JSON 字段 (不含外层花括号)，回放文件在此基础上追加字段
+    pub(crate) fn json_fields(&self) -> Vec<String> {
+        let points: Vec<String> = self.blueprint.points.iter()
+            .map(|p| format!("{}, {}", p.x, p.y))
+            .collect();
+        let y0 = &self.y0;
+
+        let mut fields = vec![
+            format!("\"name\": \"{}\"", self.blueprint.name.replace('"', "")),
+            format!("\"seed\": {}", self.seed),
+            format!("\"mesh_points\": {}", self.mesh_points),
+            format!("\"thickness\": {}", self.blueprint.thickness),
+            format!("\"points\": [{}]", points.join(", ")),
+            format!("\"y0\": [{}, {}, {}, {}, {}, {}, {}, {}]",
+                    y0.position.x, y0.position.y, y0.velocity.x, y0.velocity.y,
+                    y0.angle.x, y0.angle.y, y0.angle_velocity.x, y0.angle_velocity.y),
+        ];
+        fields.extend(self.settings.json_fields());
+        fields
+    }
+}
+
+// 把扁平 JSON 对象拆成 (键, 原始值文本)，只在括号深度为 0 且不在字符串内时按逗号切分
+pub(crate) fn split_object(text: &str) -> Option<Vec<(String, String)>> {
+    let body = text.trim().strip_prefix('{')?.strip_suffix('}')?;
+
+    let mut parts = Vec::new();
+    let mut depth = 0;
+    let mut in_string = false;
+    let mut start = 0;
+    for (i, c) in body.char_indices() {
+        match c {
+            '"' => in_string = !in_string,
+            '[' if !in_string => depth += 1,
+            ']' if !in_string => depth -= 1,
+            ',' if !in_string && depth == 0 => {
+                parts.push(&body[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(&body[start..]);
+
+    parts.into_iter()
+        .filter(|p| !p.trim().is_empty())
+        .map(|p| {
+            let (key, value) = p.split_once(':')?;
+            Some((key.trim().trim_matches('"').to_string(), value.trim().to_string()))
+        })
+        .collect()
+}
+
+pub(crate) fn parse_numbers(value: &str) -> Option<Vec<f64>> {
+    let inner = value.strip_prefix('[')?.strip_suffix(']')?.trim();
+    if inner.is_empty() { return Some(Vec::new()); }
+    inner.split(',')
+        .map(|v| v.trim().parse::<f64>().ok().filter(|x| x.is_finite()))
+        .collect()
+}
+
+fn parse_record(text: &str) -> Option<RunRecord> {
+    RunRecord::from_fields(&split_object(text)?)
+}
+
+impl RunRecord {
+    /// 从 split_object 拆出的字段中读取；缺少或格式错误的字段返回 None
+    pub(crate) fn from_fields(fields: &[(String, String)]) -> Option<Self> {
+        let get = |key: &str| fields.iter().find(|(k, _)| k == key).map(|(_, v)| v.as_str());
+        let num = |key: &str| get(key)?.parse::<f64>().ok().filter(|x| x.is_finite());
+
+        // 1. 蓝图
+        let coords = parse_numbers(get("points")?)?;
+        if coords.len() % 2 != 0 { return None; }
+        let points = coords.chunks(2).map(|c| Vector2D::new(c[0], c[1])).collect();
+        let blueprint = StoneBlueprint {
+            points,
+            thickness: num("thickness")?,
+            name: get("name")?.trim_matches('"').to_string(),
+            density_fn: None,
+        };
+
+        // 2. 初始状态
+        let y = parse_numbers(get("y0")?)?;
+        if y.len() != 8 { return None; }
+        let y0 = StoneInfo {
+            position: Vector2D::new(y[0], y[1]),
+            velocity: Vector2D::new(y[2], y[3]),
+            angle: Vector2D::new(y[4], y[5]),
+            angle_velocity: Vector2D::new(y[6], y[7]),
+        };
+
+        // 3. 参数
+        let settings = SettingsSnapshot::from_fields(fields)?;
+
+        Some(RunRecord {
+            blueprint,
+            y0,
+            seed: get("seed")?.parse().ok()?,
+            mesh_points: match get("mesh_points") {
+                Some(v) => v.parse().ok().filter(|&n| n > 0)?,
+                None => DEFAULT_MESH_POINTS,
+            },
+            settings,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::Rng;
+    use crate::stone_phy::StoneProperties;
+
+    #[test]
+    fn record_round_trips_through_json() {
+        let blueprint = StoneBlueprint {
+            points: vec![
+                Vector2D::new(-0.03, -0.02),
+                Vector2D::new(0.03, -0.02),
+                Vector2D::new(0.0, 0.04),
+            ],
+            thickness: 0.012,
+            name: "tri".to_string(),
+            density_fn: None,
+        };
+        let mut rng = Rng::new(11);
+        let stone = StoneProperties::new(&blueprint, &mut rng).unwrap();
+        let mut settings = CustomSettings::new(3.71, stone, rng.fork());
+        settings.slope = 0.05;
+        settings.bounce_model = BounceModel::Impulse;
+
+        let record = RunRecord {
+            blueprint,
+            y0: StoneInfo {
+                position: Vector2D::new(0.0, 1.0),
+                velocity: Vector2D::new(12.5, -0.3),
+                angle: Vector2D::new(0.2, 0.0),
+                angle_velocity: Vector2D::new(0.0, 40.0),
+            },
+            seed: 11,
+            mesh_points: 5000,
+            settings: SettingsSnapshot::capture(&settings),
+        };
+
+        let parsed = parse_record(&record.to_json()).unwrap();
+        assert_eq!(parsed.blueprint.points, record.blueprint.points);
+        assert_eq!(parsed.blueprint.name, "tri");
+        assert_eq!(parsed.y0.velocity, record.y0.velocity);
+        assert_eq!(parsed.seed, 11);
+        assert_eq!(parsed.mesh_points, 5000);
+        assert_eq!(parsed.settings.gravity, 3.71);
+        assert_eq!(parsed.settings.slope, 0.05);
+        assert_eq!(parsed.settings.bounce_model, BounceModel::Impulse);
+    }
+}
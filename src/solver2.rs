@@ -5,6 +5,11 @@ pub trait VectorSpace: Sized + Clone {
 
     // 标量乘法: self * scalar
     fn scale(&self, scalar: f64) -> Self;
+
+    // 所有分量是否有限 (非 NaN/Inf)，求解器据此检测发散；默认视为有限
+    fn is_finite(&self) -> bool {
+        true
+    }
 }
 
 // 为 Vec<f64> 实现这个特征 (动态维度)
@@ -19,6 +24,10 @@ impl VectorSpace for Vec<f64> {
     fn scale(&self, scalar: f64) -> Self {
         self.iter().map(|x| x * scalar).collect()
     }
+
+    fn is_finite(&self) -> bool {
+        self.iter().all(|x| x.is_finite())
+    }
 }
 
 // T 代表状态类型，它必须满足 VectorSpace 特征
@@ -28,9 +37,62 @@ pub trait OdeSystem<T: VectorSpace> {
     fn derivatives(&self, t: f64, y: &T) -> T;
 }
 
+/// 把闭包 `|t, y| dy/dt` 包装成 OdeSystem，简单的方程不必单独定义结构体
+/// 例：`FnOdeSystem(|_t, y: &Vec<f64>| vec![-y[0]])`
+pub struct FnOdeSystem<F>(pub F);
+
+impl<T: VectorSpace, F: Fn(f64, &T) -> T> OdeSystem<T> for FnOdeSystem<F> {
+    fn derivatives(&self, t: f64, y: &T) -> T {
+        (self.0)(t, y)
+    }
+}
+
+/// 显式 Runge-Kutta 方法 (运行时可切换，用于比较积分精度)
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum RkMethod {
+    Euler,    // 一阶
+    Midpoint, // 二阶
+    Rk4,      // 四阶 (默认)
+}
+
+impl RkMethod {
+    /// 循环切换到下一种方法
+    pub fn next(self) -> Self {
+        match self {
+            RkMethod::Euler => RkMethod::Midpoint,
+            RkMethod::Midpoint => RkMethod::Rk4,
+            RkMethod::Rk4 => RkMethod::Euler,
+        }
+    }
+
+    /// 每步的导数求值次数
+    pub fn stages(self) -> u64 {
+        match self {
+            RkMethod::Euler => 1,
+            RkMethod::Midpoint => 2,
+            RkMethod::Rk4 => 4,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            RkMethod::Euler => "Euler",
+            RkMethod::Midpoint => "Midpoint",
+            RkMethod::Rk4 => "RK4",
+        }
+    }
+}
+
 pub struct RungeKuttaSolver<T> {
     pub t: f64,
     pub state: T, // 泛型状态，不再是硬编码的数组或 Vec
+    pub method: RkMethod,
+    /// 累计的导数求值次数 (性能统计)
+    pub derivative_calls: u64,
+    /// 减半步长重试后状态仍然非有限时的错误信息；出现后 step 不再推进
+    pub error: Option<String>,
+    /// 状态变为非有限值、回滚并以 dt/2 重试的次数 (是否报告由调用方决定)
+    pub halved_steps: u64,
 }
 
 impl<T: VectorSpace> RungeKuttaSolver<T> {
@@ -39,12 +101,78 @@ impl<T: VectorSpace> RungeKuttaSolver<T> {
         Self {
             t: t0,
             state: y0,
+            method: RkMethod::Rk4,
+            derivative_calls: 0,
+            error: None,
+            halved_steps: 0,
         }
     }
 
-    // 核心：泛型 RK4 步进
+    // 原地重置时间与状态 (重放时复用同一个求解器)
+    // 积分方法保持不变，导数求值计数清零，使统计只针对新的一次运行
+    pub fn reset(&mut self, t0: f64, y0: T) {
+        self.t = t0;
+        self.state = y0;
+        self.derivative_calls = 0;
+        self.error = None;
+        self.halved_steps = 0;
+    }
+
+    // 核心：泛型步进 (按 method 选择积分方法)
     // S 是实现了 OdeSystem<T> 的物理系统
+    // 步后状态出现 NaN/Inf 时回滚，以 dt/2 走两步重试；仍失败则回滚并记录 error
     pub fn step<S: OdeSystem<T>>(&mut self, system: &S, dt: f64) {
+        if self.error.is_some() { return; }
+
+        let (t0, y0) = (self.t, self.state.clone());
+        self.step_once(system, dt);
+        if self.state.is_finite() { return; }
+
+        // 1. 回滚，减半步长重试
+        self.halved_steps += 1;
+        self.t = t0;
+        self.state = y0.clone();
+        self.step_once(system, 0.5 * dt);
+        if self.state.is_finite() {
+            self.step_once(system, 0.5 * dt);
+        }
+        if self.state.is_finite() { return; }
+
+        // 2. 仍然发散：停在发散前的状态
+        let message = format!("state became non-finite at t={} (retried with dt={})", t0, 0.5 * dt);
+        self.t = t0;
+        self.state = y0;
+        self.error = Some(message);
+    }
+
+    // 按 method 走一步，不做检查
+    fn step_once<S: OdeSystem<T>>(&mut self, system: &S, dt: f64) {
+        match self.method {
+            RkMethod::Euler => {
+                // y_{n+1} = y + dt * f(t, y)
+                let k1 = system.derivatives(self.t, &self.state);
+                self.state = self.state.add(&k1.scale(dt));
+                self.t += dt;
+                self.derivative_calls += RkMethod::Euler.stages();
+            }
+            RkMethod::Midpoint => {
+                // y_{n+1} = y + dt * f(t + dt/2, y + k1 * dt/2)
+                let k1 = system.derivatives(self.t, &self.state);
+                let mid = self.state.add(&k1.scale(0.5 * dt));
+                let k2 = system.derivatives(self.t + 0.5 * dt, &mid);
+                self.state = self.state.add(&k2.scale(dt));
+                self.t += dt;
+                self.derivative_calls += RkMethod::Midpoint.stages();
+            }
+            RkMethod::Rk4 => {
+                self.step_with_stages(system, dt);
+            }
+        }
+    }
+
+    // 与 RK4 的 step 相同 (忽略 method)，但返回四个阶段的导数 [k1, k2, k3, k4]
+    // 用于诊断一步之内导数的变化 (例如弹跳阶段)，也可用于 Hermite 稠密输出
+    pub fn step_with_stages<S: OdeSystem<T>>(&mut self, system: &S, dt: f64) -> [T; 4] {
         let y = &self.state;
         let t = self.t;
 
@@ -74,5 +202,67 @@ impl<T: VectorSpace> RungeKuttaSolver<T> {
 
         self.state = y.add(&delta);
         self.t += dt;
+        self.derivative_calls += RkMethod::Rk4.stages();
+
+        [k1, k2, k3, k4]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    // dy/dt = 1；第 `poisoned` 次 (从 1 开始) 及之后的求值返回 NaN，直到 `until` 为止
+    struct Flaky {
+        calls: Cell<u64>,
+        poisoned: u64,
+        until: u64,
     }
-}
\ No newline at end of file
+
+    impl OdeSystem<Vec<f64>> for Flaky {
+        fn derivatives(&self, _t: f64, _y: &Vec<f64>) -> Vec<f64> {
+            let n = self.calls.get() + 1;
+            self.calls.set(n);
+            if (self.poisoned..=self.until).contains(&n) { vec![f64::NAN] } else { vec![1.0] }
+        }
+    }
+
+    #[test]
+    fn non_finite_step_is_retried_with_half_dt() {
+        // 第一步的 k2 是 NaN，减半重试成功
+        let system = Flaky { calls: Cell::new(0), poisoned: 2, until: 2 };
+        let mut solver = RungeKuttaSolver::new(0.0, vec![0.0]);
+        solver.step(&system, 0.1);
+        assert!(solver.error.is_none());
+        assert_eq!(solver.halved_steps, 1);
+        assert!((solver.t - 0.1).abs() < 1e-15);
+        assert!((solver.state[0] - 0.1).abs() < 1e-15);
+    }
+
+    #[test]
+    fn closure_system_integrates_exponential_decay() {
+        // dy/dt = -y，y(1) = e^-1
+        let system = FnOdeSystem(|_t: f64, y: &Vec<f64>| vec![-y[0]]);
+        let mut solver = RungeKuttaSolver::new(0.0, vec![1.0]);
+        for _ in 0..100 {
+            solver.step(&system, 0.01);
+        }
+        assert!((solver.state[0] - (-1.0f64).exp()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn persistent_non_finite_state_aborts_and_rolls_back() {
+        let system = Flaky { calls: Cell::new(0), poisoned: 5, until: u64::MAX };
+        let mut solver = RungeKuttaSolver::new(0.0, vec![0.0]);
+        solver.step(&system, 0.1); // 正常
+        solver.step(&system, 0.1); // 发散
+        assert!(solver.error.is_some());
+        assert!((solver.t - 0.1).abs() < 1e-15);
+        assert!((solver.state[0] - 0.1).abs() < 1e-15);
+
+        // 出错后不再推进
+        solver.step(&system, 0.1);
+        assert!((solver.t - 0.1).abs() < 1e-15);
+    }
+}
@@ -1,12 +1,61 @@
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+
 use macroquad::prelude::*;
-use crate::basic_structs::Vector2D; // [修正] 不再需要 Vector3D
-use crate::bezier::BezierInfo;
-use crate::physics::simulation::StoneInfo; // 假设 StoneInfo 现在使用 2D 向量
+use crate::basic_structs::{polygon_area, Vector2D}; // [修正] 不再需要 Vector3D
+use crate::bezier::{mirror_handle, BezierInfo};
+use crate::stone_phy::{ensure_ccw, export_svg, find_self_intersections, repair_polygon, ShapeReport, StoneError, SELF_INTERSECTION_LIMIT};
+use crate::physics::simulation::{simulate, ScoreMode, SimSummary, StoneInfo}; // 假设 StoneInfo 现在使用 2D 向量
+use crate::physics::parameters::{BounceModel, CustomSettingsBuilder, GravityPreset, DEFAULT_WATER_TEMP_C};
+use crate::prefs::{Prefs, PREFS_FILE};
+use crate::print::PIXELS_PER_METER;
+use crate::rng::{Rng, DEFAULT_SEED};
+use crate::solver2::RungeKuttaSolver;
+use crate::stone_phy::{StoneProperties, DEFAULT_MESH_POINTS, DENSITY_SLATE, PREVIEW_MESH_POINTS, PROPERTIES_FILE, SVG_FILE};
+use crate::training::{find_optimal_launch, TrainingSearch, TrainingTarget, Water};
+use crate::ui::{button, button_colored, slider, ui_scale, AngleUnit};
+
+/// 首尾点距离小于该值 (m) 时视为已闭合，直接把终点吸附到起点
+pub const CLOSE_LOOP_THRESHOLD: f64 = 0.02;
+
+/// 画布缩放范围 (相对 PIXELS_PER_METER 的倍数)
+const MIN_CANVAS_ZOOM: f64 = 0.25;
+const MAX_CANVAS_ZOOM: f64 = 4.0;
+
+/// 预测轨迹 (初始条件界面) 使用的粗时间步长、步数上限与记录间隔
+/// 只用于预览，步数上限保证每次重算都足够快
+const PREDICTION_DT: f64 = 0.002;
+const PREDICTION_MAX_STEPS: usize = 2500;
+const PREDICTION_STRIDE: usize = 5;
+/// 输入停止变化多久 (s) 后才重算预测轨迹
+const PREDICTION_DEBOUNCE: f64 = 0.25;
+/// 侧边预览中初始翻滚动画的循环周期 (s)
+const PREVIEW_TUMBLE_LOOP: f64 = 2.0;
+
+/// 绘制模式下比例尺的候选长度 (cm)，取屏幕上最接近 RULER_TARGET_PX 的一个
+const RULER_LENGTHS_CM: [f64; 7] = [0.5, 1.0, 2.0, 5.0, 10.0, 20.0, 50.0];
+const RULER_TARGET_PX: f64 = 200.0;
+
+/// 手绘平滑：强度为 1 时的平滑迭代次数，以及 Catmull-Rom 重采样时每段的细分数
+const SMOOTHING_MAX_ITERATIONS: usize = 20;
+const CATMULL_ROM_SUBDIVISIONS: usize = 8;
+
+/// 参数化预设形状的采样点数 (正多边形除外)
+const PRESET_SEGMENTS: usize = 64;
+
+/// 保存到偏好文件的输入框 (键即输入框 ID，读取时按 validate_input 校验)
+const PREF_INPUTS: [&str; 15] = [
+    "thickness", "pos_x", "pos_y", "vel_x", "vel_y", "ang", "ang_vel", "pitch_vel",
+    "water_level", "slope", "water_temp", "gravity", "preset_a", "preset_b", "preset_n",
+];
+
+/// 导入轮廓时读取的 CSV 文件 (相对于工作目录)，每行一个世界坐标点 "x, y" (m)
+pub const OUTLINE_CSV: &str = "outline.csv";
 
 // 对应 UML 中的编辑状态
 #[derive(PartialEq, Clone, Copy)]
 pub enum EditorMode {
     Menu,
+    Presets, // 参数化预设形状
     BezierDrawing,
     FreehandDrawing,
     Preview,
@@ -14,11 +63,57 @@ pub enum EditorMode {
     Finished,
 }
 
+/// 预设形状
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PresetKind {
+    Circle,
+    Ellipse,
+    Polygon,
+    Teardrop,
+}
+
+impl PresetKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            PresetKind::Circle => "Circle",
+            PresetKind::Ellipse => "Ellipse",
+            PresetKind::Polygon => "N-gon",
+            PresetKind::Teardrop => "Teardrop",
+        }
+    }
+}
+
+/// 预设形状的尺寸 (m)
+/// 圆：半径 a；椭圆：半轴 a (x) 与 b (y)；正多边形：外接圆半径 a，边数 sides；
+/// 水滴形：长度 2a、最大宽度约 b 的 "x = a cos t, y = b sin t sin(t/2)"
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PresetParams {
+    pub a: f64,
+    pub b: f64,
+    pub sides: usize,
+}
+
 // 用于存储编辑器产生的数据
+#[derive(Clone)]
 pub struct StoneBlueprint {
     pub points: Vec<Vector2D>, // 最终的轮廓点 (f64)
     pub thickness: f64,        // 厚度
     pub name: String,
+    /// 径向密度分布 ρ(r) (kg/m^3)，r 为到几何质心的距离 (m)
+    /// None 表示均匀密度 (板岩)
+    pub density_fn: Option<fn(f64) -> f64>,
+}
+
+/// 后台线程中进行的训练搜索 (T 键)，编辑器每帧读取它的消息
+struct TrainingJob {
+    key: String, // 开始搜索时的输入 (见 training_inputs_key)
+    updates: Receiver<TrainingUpdate>,
+    runs: (usize, usize), // 最近一次报告的 (已评估次数, 上限)
+}
+
+enum TrainingUpdate {
+    Progress(usize, usize),
+    Done(Box<Result<TrainingTarget, StoneError>>),
 }
 
 // [修改] 辅助结构, 从 Vec3Input 变为 Vec2Input
@@ -41,57 +136,217 @@ pub struct StoneEditor {
     pub mode: EditorMode,
     pub thickness_input: String,
 
+    /// 闭合判定阈值 (m)，默认 CLOSE_LOOP_THRESHOLD
+    pub close_threshold: f64,
+
+    /// 画布缩放 (像素/米)，绘制与预览时用滚轮调整
+    pub canvas_scale: f64,
+    /// 画布平移：世界原点相对屏幕中心的偏移 (px)，按住鼠标中键拖动
+    canvas_pan: Vec2,
+    canvas_pan_anchor: Option<Vec2>, // 拖动中上一帧的鼠标位置
+
     // 统一的文本输入状态
     active_input_id: Option<String>,
+    cursor: TextCursor,
 
     // 贝塞尔模式数据
     bezier_control_points: Vec<Vector2D>,
+    bezier_piecewise: bool, // 分段三次曲线 (S 键切换)
+    bezier_c1: bool,        // 分段时自动镜像手柄保证 C1 连续 (M 键切换)
 
     // 手绘模式数据
     freehand_points: Vec<Vector2D>,
+    // 绘制中实时显示的面积 (m^2，自动闭合后的形状) 及其对应的点列状态，状态不变时不重算
+    live_area: f64,
+    live_area_key: String,
+    freehand_smoothing: f32, // 手绘平滑强度 (0 ~ 1)，0 为保留原始笔画
 
     // 预览和状态管理
     previous_mode: EditorMode,
     preview_points: Vec<Vector2D>,
-    self_intersection_warning: bool,
+    // 自相交的线段下标对 (预览中高亮显示)
+    self_intersections: Vec<(usize, usize)>,
+    // 形状评估 (预览中显示，仅供参考)
+    shape_report: Option<ShapeReport>,
+    // 预览轮廓的物理属性 (与模拟开始时使用相同的种子构建)，以及导出结果提示
+    properties: Option<StoneProperties>,
+    properties_status: Option<String>,
+    // 静水 (按输入的水温) 中的平衡漂浮姿态 (俯仰角, 吃水深度)，比水重时为 None；
+    // 以及计算时使用的水温，水温输入改变后重新求解
+    floating_pose: Option<(f64, f64)>,
+    floating_pose_temp: f64,
+    // 物理构建失败时返回预览并显示的错误
+    build_error: Option<StoneError>,
+    // 导入轮廓失败时在菜单中显示的错误
+    import_error: Option<String>,
 
     // 阶段性存储
     blueprint_buffer: Option<StoneBlueprint>,
 
+    // 对比模式：菜单中可分别保存设计 A 与 B，然后从相同初始条件同时模拟
+    design_a: Option<StoneBlueprint>,
+    design_b: Option<StoneBlueprint>,
+    editing_slot_b: bool, // 当前绘制的石片存入 B 槽 (确认后回到菜单)
+    comparing: bool,      // 初始条件界面是为对比运行设置的
+
+    // 发射前的预测轨迹 (初始条件界面的侧边预览)
+    predicted_path: Vec<Vector2D>,
+    predicted_summary: Option<SimSummary>,
+    predicted_submersion: f64, // 初始状态的浸没比例 (0 ~ 1)
+    prediction_key: String,             // 上次检测到的输入组合，变化即需要重算
+    prediction_changed_at: Option<f64>, // 输入最近一次变化的时刻 (防抖)
+
+    // 训练模式 (T 键)：当前石片的最佳发射，作为参考轨迹显示在预览和模拟中
+    // training_key 为搜索时除攻角和速度以外的输入组合，与当前输入 (inputs_key) 不同时参考轨迹失效
+    pub training: Option<TrainingTarget>,
+    training_key: String,
+    inputs_key: String,                // 当前输入的训练键，输入变化时更新 (见 update_predicted_path)
+    training_job: Option<TrainingJob>, // 进行中的搜索
+    training_status: Option<String>,   // 搜索失败的原因
+
     // [修改] y0 (初始条件) 的输入
     y0_position: Vec2Input, // 变为 2D
     y0_velocity: Vec2Input, // 变为 2D
     y0_angle: String,         // 变为 1D
     y0_angular_velocity: String, // 变为 1D
+    y0_pitch_rate: String,       // 俯仰翻滚角速度，写入 angle_velocity.x
+    /// 角度输入的单位 (攻角为 unit，两个角速度为 unit/s)，渲染器的信息面板同样使用它
+    pub angle_unit: AngleUnit,
+    // 最近一次切换单位前后的角度输入文本；未编辑就切换回去时恢复原文本，来回切换不产生舍入误差
+    angle_unit_memo: Option<([String; 3], [String; 3])>,
+    water_level_input: String,
+    slope_input: String,
+    water_temp_input: String,
+    gravity_input: String, // 自定义重力 (选择 Custom 时使用)
+
+    // 预设形状的尺寸输入 (cm) 与边数
+    preset_a_input: String,
+    preset_b_input: String,
+    preset_sides_input: String,
+
+    /// 水面高度 (m) 与坡度 (dy/dx)，在 START 时从输入框解析
+    pub water_level: f64,
+    pub slope: f64,
+    /// 水温 (°C)，在 START 时从输入框解析
+    pub water_temp_c: f64,
+
+    /// 重力预设，默认地球；Custom 的数值在 START 时从输入框解析
+    pub gravity: GravityPreset,
+    custom_gravity_selected: bool,
+
+    /// 计分方式，在初始条件界面切换；由 main 在多局之间保持
+    pub score_mode: ScoreMode,
+
+    /// 弹跳模型 (模拟中按 B 切换)；与输入一起保存在偏好文件中，由 main 读取和写回
+    pub bounce_model: BounceModel,
+
+    /// 石片与受力模型的随机数种子 (命令行 `--seed`)；预测轨迹与训练搜索使用同一种子
+    pub seed: u64,
+
+    /// 开始模拟时碰撞网格的采样点数 (预览界面 N 键切换)；预览本身固定用 PREVIEW_MESH_POINTS
+    pub mesh_points: usize,
 
     // 最终生成的蓝图
     pub result: Option<(StoneBlueprint, StoneInfo)>,
+    /// 对比运行时的设计 B (与 result 使用相同的初始条件)
+    pub compare_result: Option<StoneBlueprint>,
+}
+
+/// 内置默认参数，不读取偏好文件 (见 new)
+impl Default for StoneEditor {
+    fn default() -> Self {
+        Self::with_defaults()
+    }
 }
 
 impl StoneEditor {
+    /// 默认参数，再用偏好文件 (上次使用的输入) 覆盖
     pub fn new() -> Self {
+        let mut editor = Self::with_defaults();
+        editor.apply_prefs(&Prefs::load(PREFS_FILE));
+        editor
+    }
+
+    fn with_defaults() -> Self {
         Self {
             mode: EditorMode::Menu,
             thickness_input: "1.0".to_string(),
+            close_threshold: CLOSE_LOOP_THRESHOLD,
+            canvas_scale: PIXELS_PER_METER,
+            canvas_pan: Vec2::ZERO,
+            canvas_pan_anchor: None,
             active_input_id: None,
+            cursor: TextCursor::new(),
             bezier_control_points: Vec::new(),
+            bezier_piecewise: false,
+            bezier_c1: true,
             freehand_points: Vec::new(),
+            freehand_smoothing: 0.3,
+            live_area: 0.0,
+            live_area_key: String::new(),
             previous_mode: EditorMode::Menu,
             preview_points: Vec::new(),
-            self_intersection_warning: false,
+            self_intersections: Vec::new(),
+            shape_report: None,
+            properties: None,
+            properties_status: None,
+            floating_pose: None,
+            floating_pose_temp: DEFAULT_WATER_TEMP_C,
+            build_error: None,
+            import_error: None,
             blueprint_buffer: None,
+            design_a: None,
+            design_b: None,
+            editing_slot_b: false,
+            comparing: false,
+            predicted_path: Vec::new(),
+            predicted_summary: None,
+            predicted_submersion: 0.0,
+            prediction_key: String::new(),
+            prediction_changed_at: None,
+            training: None,
+            training_key: String::new(),
+            inputs_key: String::new(),
+            training_job: None,
+            training_status: None,
             // [修改] y0 默认值
             y0_position: Vec2Input::new("0.0", "0.2"),    // 变为 2D
             y0_velocity: Vec2Input::new("10.0", "0.0"),  // 变为 2D
-            y0_angle: "5.0".to_string(),                 // 变为 1D (5 度攻角)
-            y0_angular_velocity: "15.0".to_string(),     // 变为 1D (绕 Z 轴旋转)
+            y0_angle: "5.0".to_string(),                 // 5° 攻角
+            // 绕 Z 轴自转 15 rad/s (与改为角度单位之前的默认值完全相同)
+            y0_angular_velocity: format!("{}", 15.0_f64.to_degrees()),
+            y0_pitch_rate: "0.0".to_string(),            // 默认不翻滚
+            angle_unit: AngleUnit::Degrees,
+            angle_unit_memo: None,
+            water_level_input: "0.0".to_string(),
+            slope_input: "0.0".to_string(),
+            water_level: 0.0,
+            slope: 0.0,
+            water_temp_input: format!("{}", DEFAULT_WATER_TEMP_C),
+            water_temp_c: DEFAULT_WATER_TEMP_C,
+            gravity_input: "9.81".to_string(),
+            preset_a_input: "4.0".to_string(),
+            preset_b_input: "1.5".to_string(),
+            preset_sides_input: "6".to_string(),
+            gravity: GravityPreset::Earth,
+            custom_gravity_selected: false,
+            score_mode: ScoreMode::AirTime,
+            bounce_model: BounceModel::Continuous,
+            seed: DEFAULT_SEED,
+            mesh_points: DEFAULT_MESH_POINTS,
 
             result: None,
+            compare_result: None,
         }
     }
 
     pub async fn run(&mut self) {
         loop {
+            // 关闭窗口：保存偏好后返回 (没有结果，main 随之退出)
+            if is_quit_requested() {
+                self.save_prefs();
+                break;
+            }
             clear_background(BLACK);
 
             // 统一的点击和键盘输入处理
@@ -106,6 +361,7 @@ impl StoneEditor {
             // 3. 绘制当前状态的 UI (UI 内部会覆盖 active_input_id)
             match self.mode {
                 EditorMode::Menu => self.draw_menu(),
+                EditorMode::Presets => self.draw_presets(),
                 EditorMode::BezierDrawing => self.update_bezier(),
                 EditorMode::FreehandDrawing => self.update_freehand(),
                 EditorMode::Preview => self.draw_preview(),
@@ -113,85 +369,310 @@ impl StoneEditor {
                 EditorMode::Finished => break,
             }
 
-            // 绘制通用的 UI (比如厚度输入)
+            // 绘制通用的 UI (比如厚度输入) 与比例尺
             if self.mode == EditorMode::BezierDrawing || self.mode == EditorMode::FreehandDrawing {
                 self.draw_common_ui();
+                self.draw_ruler();
+            }
+
+            // 绘制与预览时可缩放画布
+            if matches!(self.mode, EditorMode::BezierDrawing | EditorMode::FreehandDrawing | EditorMode::Preview) {
+                self.handle_canvas_zoom();
             }
 
             next_frame().await
         }
     }
 
+    // 滚轮缩放画布 (以鼠标为中心)、中键拖动平移、Home 复位，并在右上角显示当前比例
+    // 已画的点以米为单位保存，缩放和平移只改变显示位置 (与渲染器的 handle_view_controls 一致)
+    fn handle_canvas_zoom(&mut self) {
+        let mouse = Vec2::from(mouse_position());
+        let scroll = mouse_wheel().1;
+        if scroll.abs() > 0.1 {
+            let factor = if scroll > 0.0 { 1.1 } else { 1.0 / 1.1 };
+            let scale = (self.canvas_scale * factor)
+                .clamp(MIN_CANVAS_ZOOM * PIXELS_PER_METER, MAX_CANVAS_ZOOM * PIXELS_PER_METER);
+            self.canvas_pan = zoom_pan_about(screen_center(), mouse, self.canvas_scale, scale, self.canvas_pan);
+            self.canvas_scale = scale;
+        }
+
+        // 平移 (鼠标中键)
+        if is_mouse_button_pressed(MouseButton::Middle) {
+            self.canvas_pan_anchor = Some(mouse);
+        }
+        if is_mouse_button_released(MouseButton::Middle) {
+            self.canvas_pan_anchor = None;
+        }
+        if let Some(anchor) = self.canvas_pan_anchor {
+            self.canvas_pan += mouse - anchor;
+            self.canvas_pan_anchor = Some(mouse);
+        }
+
+        if self.active_input_id.is_none() && is_key_pressed(KeyCode::Home) {
+            self.canvas_scale = PIXELS_PER_METER;
+            self.canvas_pan = Vec2::ZERO;
+        }
+
+        let text = format!("Scroll: Zoom | Middle drag: Pan | Home: Reset | 1 cm = {:.0} px", self.canvas_scale * 0.01);
+        let dims = measure_text(&text, None, 30, 1.0);
+        draw_text(&text, screen_width() - dims.width - 20.0, 40.0, 30.0, GRAY);
+    }
+
+    // 比例尺：一段标注长度的参考线段，以及当前形状包围盒的尺寸 (cm)
+    // 画布缩放只改变显示大小，参考长度随缩放自动换成合适的整数值
+    fn draw_ruler(&self) {
+        let x = 20.0;
+        let y = screen_height() - 330.0;
+
+        // 1. 参考线段与两端刻度
+        let length_cm = ruler_length_cm(self.canvas_scale);
+        let length_px = (length_cm * 0.01 * self.canvas_scale) as f32;
+        draw_line(x, y, x + length_px, y, 3.0, WHITE);
+        for tick_x in [x, x + length_px] {
+            draw_line(tick_x, y - 8.0, tick_x, y + 8.0, 2.0, WHITE);
+        }
+        draw_text(&format!("{} cm", length_cm), x + length_px + 10.0, y + 8.0, 28.0, WHITE);
+
+        // 2. 当前形状的包围盒 (贝塞尔模式按曲线本身计算)
+        let points = match self.mode {
+            EditorMode::BezierDrawing if self.bezier_control_points.len() > 1 =>
+                self.build_bezier("temp", self.bezier_control_points.clone()).get_polyline_points().clone(),
+            EditorMode::FreehandDrawing => self.freehand_points.clone(),
+            _ => Vec::new(),
+        };
+        if let Some((min, max)) = bounding_box(&points) {
+            let (a, b) = (world_to_screen(min, self.canvas_scale, self.canvas_pan), world_to_screen(max, self.canvas_scale, self.canvas_pan));
+            draw_rectangle_lines(a.x, b.y, b.x - a.x, a.y - b.y, 1.0, Color::new(0.5, 0.5, 0.5, 0.6));
+            draw_text(&format!("Size: {:.1} x {:.1} cm", (max.x - min.x) * 100.0, (max.y - min.y) * 100.0),
+                      x, y - 25.0, 28.0, LIGHTGRAY);
+        }
+    }
+
     // 键盘输入处理
     fn handle_keyboard_input(&mut self) {
-        if self.active_input_id.is_none() { return; } // 没有激活的输入框
+        let Some(id) = self.active_input_id.clone() else { return; }; // 没有激活的输入框
+
+        // 1. 获取当前激活的 &mut String (光标暂时取出，避免同时借用)
+        let mut cursor = std::mem::replace(&mut self.cursor, TextCursor::new());
+        let mut submitted = false;
 
-        // 1. 获取当前激活的 &mut String
-        let s_mut_option: Option<&mut String> = match self.active_input_id.as_deref() {
-            Some("thickness") => Some(&mut self.thickness_input),
+        // 2. 将键盘事件写入 (切换输入框时光标移到末尾)
+        if let Some(s_mut) = self.input_mut(&id) {
+            cursor.sync(&Some(id.clone()), s_mut);
+            submitted = read_numeric_chars(s_mut, &mut cursor.pos);
+        }
+        self.cursor = cursor;
+        if submitted {
+            self.active_input_id = None; // 回车键取消焦点
+        }
+    }
+
+    // 输入框 ID 对应的文本
+    fn input_mut(&mut self, id: &str) -> Option<&mut String> {
+        match id {
+            "thickness" => Some(&mut self.thickness_input),
             // [修改] 适配 2D
             // Position
-            Some("pos_x") => Some(&mut self.y0_position.x),
-            Some("pos_y") => Some(&mut self.y0_position.y),
+            "pos_x" => Some(&mut self.y0_position.x),
+            "pos_y" => Some(&mut self.y0_position.y),
             // Velocity
-            Some("vel_x") => Some(&mut self.y0_velocity.x),
-            Some("vel_y") => Some(&mut self.y0_velocity.y),
+            "vel_x" => Some(&mut self.y0_velocity.x),
+            "vel_y" => Some(&mut self.y0_velocity.y),
             // Angle (1D)
-            Some("ang") => Some(&mut self.y0_angle),
+            "ang" => Some(&mut self.y0_angle),
             // Angular Velocity (1D)
-            Some("ang_vel") => Some(&mut self.y0_angular_velocity),
+            "ang_vel" => Some(&mut self.y0_angular_velocity),
+            "pitch_vel" => Some(&mut self.y0_pitch_rate),
+            // 水面
+            "water_level" => Some(&mut self.water_level_input),
+            "slope" => Some(&mut self.slope_input),
+            "water_temp" => Some(&mut self.water_temp_input),
+            "gravity" => Some(&mut self.gravity_input),
+            "preset_a" => Some(&mut self.preset_a_input),
+            "preset_b" => Some(&mut self.preset_b_input),
+            "preset_n" => Some(&mut self.preset_sides_input),
             _ => None,
-        };
+        }
+    }
 
-        // 2. 将键盘事件写入
-        if let Some(s_mut) = s_mut_option {
-            while let Some(c) = get_char_pressed() {
-                match c {
-                    '\u{0008}' => { s_mut.pop(); }, // 退格键
-                    '\r' | '\n' => { self.active_input_id = None; break; }, // 回车键取消焦点
-                    // 允许数字、小数点和负号
-                    c if c.is_digit(10) || c == '.' || (c == '-' && s_mut.is_empty()) => {
-                        s_mut.push(c);
-                    },
-                    _ => {}
+    // 输入框 ID 对应的文本 (只读)
+    fn input(&self, id: &str) -> Option<&String> {
+        match id {
+            "thickness" => Some(&self.thickness_input),
+            "pos_x" => Some(&self.y0_position.x),
+            "pos_y" => Some(&self.y0_position.y),
+            "vel_x" => Some(&self.y0_velocity.x),
+            "vel_y" => Some(&self.y0_velocity.y),
+            "ang" => Some(&self.y0_angle),
+            "ang_vel" => Some(&self.y0_angular_velocity),
+            "pitch_vel" => Some(&self.y0_pitch_rate),
+            "water_level" => Some(&self.water_level_input),
+            "slope" => Some(&self.slope_input),
+            "water_temp" => Some(&self.water_temp_input),
+            "gravity" => Some(&self.gravity_input),
+            "preset_a" => Some(&self.preset_a_input),
+            "preset_b" => Some(&self.preset_b_input),
+            "preset_n" => Some(&self.preset_sides_input),
+            _ => None,
+        }
+    }
+
+    // 用偏好覆盖默认值；缺失或无效的字段保留默认值
+    fn apply_prefs(&mut self, prefs: &Prefs) {
+        // 没有 angle_unit 的旧偏好文件：攻角为度，两个角速度为 rad/s
+        let stored_unit = prefs.get("angle_unit").and_then(AngleUnit::parse);
+        if let Some(unit) = stored_unit {
+            self.angle_unit = unit;
+        }
+        let unit = self.angle_unit;
+        for id in PREF_INPUTS {
+            if let Some(value) = prefs.get(id).filter(|v| validate_input(id, v).is_ok())
+                && let Some(text) = self.input_mut(id) {
+                *text = value.trim().to_string();
+                if stored_unit.is_none() && (id == "ang_vel" || id == "pitch_vel") {
+                    *text = unit.convert_text(text, AngleUnit::Radians);
                 }
             }
         }
+        match prefs.get("gravity_preset") {
+            Some("Earth") => self.gravity = GravityPreset::Earth,
+            Some("Moon") => self.gravity = GravityPreset::Moon,
+            Some("Mars") => self.gravity = GravityPreset::Mars,
+            Some("Custom") => self.custom_gravity_selected = true,
+            _ => {}
+        }
+        match prefs.get("bounce_model") {
+            Some("Continuous") => self.bounce_model = BounceModel::Continuous,
+            Some("Impulse") => self.bounce_model = BounceModel::Impulse,
+            _ => {}
+        }
+        if let Some(n) = prefs.get("mesh_points").and_then(|v| v.parse::<usize>().ok())
+            && MESH_POINT_OPTIONS.contains(&n) {
+            self.mesh_points = n;
+        }
+        if let Some(s) = prefs.get("freehand_smoothing").and_then(|v| v.parse::<f32>().ok())
+            && (0.0..=1.0).contains(&s) {
+            self.freehand_smoothing = s;
+        }
+    }
+
+    /// 保存当前输入与设置，下次打开编辑器时恢复 (开始模拟、重新开始与退出时调用)
+    pub fn save_prefs(&self) {
+        Prefs::save(PREFS_FILE, &self.pref_fields());
+    }
+
+    // 当前输入与设置，写入偏好文件
+    fn pref_fields(&self) -> Vec<(&'static str, String)> {
+        let mut fields: Vec<(&'static str, String)> = PREF_INPUTS.iter()
+            .filter_map(|&id| Some((id, self.input(id)?.clone())))
+            .collect();
+        let gravity = if self.custom_gravity_selected { "Custom" } else { self.gravity.name() };
+        fields.push(("gravity_preset", gravity.to_string()));
+        fields.push(("angle_unit", self.angle_unit.name().to_string()));
+        fields.push(("bounce_model", format!("{:?}", self.bounce_model)));
+        fields.push(("mesh_points", self.mesh_points.to_string()));
+        fields.push(("freehand_smoothing", self.freehand_smoothing.to_string()));
+        fields
     }
 
     // 绘制菜单
     fn draw_menu(&mut self) {
-        let title_font_size = 120.0;
+        // 布局按设计高度绘制，再整体乘以 ui_scale，矮窗口中按钮也不会超出屏幕
+        let sc = ui_scale();
+        let title_font_size = 120.0 * sc;
         let title_text = "STONE GENERATOR";
         let text_dims = measure_text(title_text, None, title_font_size as u16, 1.0);
 
         draw_text(
             title_text,
             screen_width() / 2.0 - text_dims.width / 2.0,
-            400.0,
+            400.0 * sc,
             title_font_size,
             WHITE,
         );
 
-        let btn_width = 500.0;
-        let btn_height = 75.0;
+        let btn_width = 500.0 * sc;
+        let btn_height = 75.0 * sc;
         let btn_x = screen_width() / 2.0 - btn_width / 2.0;
-        let btn_y1 = 1000.0;
+        let font_size = (48.0 * sc) as u16;
+        let note_size = 32.0 * sc;
+        let text_y = btn_height - 20.0 * sc; // 按钮内文字基线
+
+        let btn_y0 = 900.0 * sc;
+        let btn_rect0 = Rect::new(btn_x, btn_y0, btn_width, btn_height);
+
+        draw_rectangle_lines(btn_rect0.x, btn_rect0.y, btn_rect0.w, btn_rect0.h, 4.0, GRAY);
+        draw_text_ex("Presets", btn_rect0.x + 20.0, btn_rect0.y + text_y,
+                     TextParams { font_size, ..Default::default() });
+
+        let btn_y1 = 1000.0 * sc;
         let btn_rect1 = Rect::new(btn_x, btn_y1, btn_width, btn_height);
 
         draw_rectangle_lines(btn_rect1.x, btn_rect1.y, btn_rect1.w, btn_rect1.h, 4.0, GRAY);
-        draw_text_ex("Mode: Bezier Curve", btn_rect1.x + 20.0, btn_rect1.y + btn_rect1.h - 20.0,
-                     TextParams { font_size: 48, ..Default::default() });
+        draw_text_ex("Mode: Bezier Curve", btn_rect1.x + 20.0, btn_rect1.y + text_y,
+                     TextParams { font_size, ..Default::default() });
 
-        let btn_y2 = 1100.0;
+        let btn_y2 = 1100.0 * sc;
         let btn_rect2 = Rect::new(btn_x, btn_y2, btn_width, btn_height);
 
         draw_rectangle_lines(btn_rect2.x, btn_rect2.y, btn_rect2.w, btn_rect2.h, 4.0, GRAY);
-        draw_text_ex("Mode: Freehand Draw", btn_rect2.x + 20.0, btn_rect2.y + btn_rect2.h - 20.0,
-                     TextParams { font_size: 48, ..Default::default() });
+        draw_text_ex("Mode: Freehand Draw", btn_rect2.x + 20.0, btn_rect2.y + text_y,
+                     TextParams { font_size, ..Default::default() });
+
+        let btn_y3 = 1200.0 * sc;
+        let btn_rect3 = Rect::new(btn_x, btn_y3, btn_width, btn_height);
+
+        draw_rectangle_lines(btn_rect3.x, btn_rect3.y, btn_rect3.w, btn_rect3.h, 4.0, GRAY);
+        draw_text_ex("Import Outline (CSV)", btn_rect3.x + 20.0, btn_rect3.y + text_y,
+                     TextParams { font_size, ..Default::default() });
+        draw_text(&format!("reads {}: one \"x, y\" point (m) per line", OUTLINE_CSV),
+                  btn_x, btn_y3 + btn_height + 40.0 * sc, note_size, GRAY);
+
+        if let Some(err) = &self.import_error {
+            draw_text(err, btn_x, btn_y3 + btn_height + 85.0 * sc, note_size, RED);
+        }
+
+        // 设计槽：决定下一次绘制存入 A 还是 B；两者都有时可以对比运行
+        let btn_y4 = 1400.0 * sc;
+        let btn_rect4 = Rect::new(btn_x, btn_y4, btn_width, btn_height);
+        draw_rectangle_lines(btn_rect4.x, btn_rect4.y, btn_rect4.w, btn_rect4.h, 4.0,
+                             if self.editing_slot_b { ORANGE } else { GRAY });
+        let slot_text = if self.editing_slot_b { "Drawing: Design B" } else { "Drawing: Design A" };
+        draw_text_ex(slot_text, btn_rect4.x + 20.0, btn_rect4.y + text_y,
+                     TextParams { font_size, ..Default::default() });
+        let slot_state = |slot: &Option<StoneBlueprint>| if slot.is_some() { "set" } else { "empty" };
+        draw_text(&format!("A: {}   B: {}   (click to switch slot)", slot_state(&self.design_a), slot_state(&self.design_b)),
+                  btn_x, btn_y4 + btn_height + 40.0 * sc, note_size, GRAY);
+
+        let can_compare = self.design_a.is_some() && self.design_b.is_some();
+        let btn_rect5 = Rect::new(btn_x, btn_y4 + 150.0 * sc, btn_width, btn_height);
+        if can_compare {
+            draw_rectangle(btn_rect5.x, btn_rect5.y, btn_rect5.w, btn_rect5.h, DARKGREEN);
+            draw_text_ex("Compare Run (A vs B)", btn_rect5.x + 20.0, btn_rect5.y + text_y,
+                         TextParams { font_size, ..Default::default() });
+        }
 
         if is_mouse_button_pressed(MouseButton::Left) {
             let (mx, my) = mouse_position();
+            if btn_rect0.contains(vec2(mx, my)) {
+                self.mode = EditorMode::Presets;
+                self.active_input_id = None;
+            }
+            if btn_rect3.contains(vec2(mx, my)) {
+                self.import_outline();
+                self.active_input_id = None;
+            }
+            if btn_rect4.contains(vec2(mx, my)) {
+                self.editing_slot_b = !self.editing_slot_b;
+            }
+            if can_compare && btn_rect5.contains(vec2(mx, my)) {
+                self.blueprint_buffer = self.design_a.clone();
+                self.comparing = true;
+                self.mode = EditorMode::SetInitialConditions;
+                self.active_input_id = None;
+                self.prediction_key.clear();
+            }
             if btn_rect1.contains(vec2(mx, my)) {
                 self.mode = EditorMode::BezierDrawing;
                 self.bezier_control_points.clear();
@@ -205,97 +686,214 @@ impl StoneEditor {
         }
     }
 
+    // 预设形状子菜单：尺寸输入 + 每种形状一个按钮，生成的轮廓直接进入预览
+    fn draw_presets(&mut self) {
+        let sc = ui_scale();
+        let font_size = (48.0 * sc) as u16;
+        let start_x = 100.0 * sc;
+        let label_w = 400.0 * sc;
+        let input_w = 200.0 * sc;
+        let input_h = 70.0 * sc;
+        let row_height = 100.0 * sc;
+
+        let title_text = "PRESETS";
+        let text_dims = measure_text(title_text, None, (60.0 * sc) as u16, 1.0);
+        draw_text(title_text, screen_width() / 2.0 - text_dims.width / 2.0, 80.0 * sc, 60.0 * sc, WHITE);
+
+        // 1. 尺寸输入
+        let rows = [
+            ("Size a (cm)", "preset_a", self.preset_a_input.clone()),
+            ("Size b (cm)", "preset_b", self.preset_b_input.clone()),
+            ("Sides (n-gon)", "preset_n", self.preset_sides_input.clone()),
+            ("Thickness (cm)", "thickness", self.thickness_input.clone()),
+        ];
+        let mut y = 150.0 * sc;
+        let mut clicked_id = None;
+        for (label, id, text) in &rows {
+            let id = draw_f64_input_row(label, text, id, y, start_x, label_w, input_w, input_h, font_size,
+                                        &self.active_input_id, self.cursor.pos);
+            if id.is_some() { clicked_id = id; }
+            y += row_height;
+        }
+        if let Some(id) = clicked_id {
+            self.active_input_id = Some(id);
+        }
+        draw_text("a: radius / half-length, b: half-width (ellipse, teardrop)", start_x, y + 20.0 * sc, 32.0 * sc, GRAY);
+
+        // 2. 形状按钮
+        let params = rows.iter().all(|(_, id, text)| validate_input(id, text).is_ok()).then(|| PresetParams {
+            a: validate_input("preset_a", &self.preset_a_input).unwrap_or(0.0) / 100.0,
+            b: validate_input("preset_b", &self.preset_b_input).unwrap_or(0.0) / 100.0,
+            sides: validate_input("preset_n", &self.preset_sides_input).unwrap_or(0.0) as usize,
+        });
+
+        let kinds = [PresetKind::Circle, PresetKind::Ellipse, PresetKind::Polygon, PresetKind::Teardrop];
+        let btn_w = 300.0 * sc;
+        let btn_h = 75.0 * sc;
+        let btn_y = y + 80.0 * sc;
+        for (i, kind) in kinds.iter().enumerate() {
+            let rect = Rect::new(start_x + i as f32 * (btn_w + 30.0 * sc), btn_y, btn_w, btn_h);
+            let color = if params.is_some() { DARKGREEN } else { DARKGRAY };
+            if button_colored(rect, kind.name(), font_size, color)
+                && let Some(params) = params {
+                let mut points = generate_preset(*kind, &params);
+                close_loop(&mut points, self.close_threshold);
+                ensure_ccw(&mut points);
+                self.set_preview_points(points);
+                self.previous_mode = EditorMode::Presets;
+                self.mode = EditorMode::Preview;
+                self.active_input_id = None;
+            }
+        }
+
+        let btn_back_rect = Rect::new(50.0, screen_height() - 120.0 * sc, 400.0 * sc, btn_h);
+        if button(btn_back_rect, "Back to Menu", font_size) {
+            self.mode = EditorMode::Menu;
+            self.active_input_id = None;
+        }
+    }
+
     // 通用 UI, 只绘制厚度
     fn draw_common_ui(&mut self) {
-        let font_size = 48.0;
-        let base_y = screen_height() - 250.0;
-        let padding = 30.0;
-        let control_height = 70.0;
+        let sc = ui_scale();
+        let font_size = 48.0 * sc;
+        let base_y = screen_height() - 250.0 * sc;
+        let padding = 30.0 * sc;
+        let control_height = 70.0 * sc;
 
-        draw_text("Inputs:", 50.0, base_y - 70.0, font_size + 10.0, GRAY);
+        draw_text("Inputs:", 50.0, base_y - 70.0 * sc, font_size + 10.0 * sc, GRAY);
 
         // --- 厚度输入行 ---
-        draw_text_ex("Thickness:", 50.0, base_y + control_height - 20.0,
+        draw_text_ex("Thickness:", 50.0, base_y + control_height - 20.0 * sc,
                      TextParams { font_size: font_size as u16, ..Default::default() });
 
-        let input_width = 200.0;
-        let input_x = 50.0 + 300.0;
+        let input_width = 200.0 * sc;
+        let input_x = 50.0 + 300.0 * sc;
         let input_rect = Rect::new(input_x, base_y, input_width, control_height);
 
+        let thickness_check = validate_input("thickness", &self.thickness_input);
+
         // [FIX] 调用新的自由函数
         if draw_text_input_box(
             &self.thickness_input,
             input_rect,
             "thickness",
             &self.active_input_id,
-            font_size as u16
+            font_size as u16,
+            thickness_check.is_err(),
+            self.cursor.pos
         ) {
             self.active_input_id = Some("thickness".to_string());
         }
 
         let cm_label_x = input_x + input_width + padding;
-        draw_text_ex("cm", cm_label_x, base_y + control_height - 20.0,
+        draw_text_ex("cm", cm_label_x, base_y + control_height - 20.0 * sc,
                      TextParams { font_size: font_size as u16, ..Default::default() });
 
-        let btn_width = 400.0;
-        let btn_height = 75.0;
+        if let Err(msg) = thickness_check {
+            draw_text_ex(msg, cm_label_x + 80.0 * sc, base_y + control_height - 20.0 * sc,
+                         TextParams { font_size: (32.0 * sc) as u16, color: RED, ..Default::default() });
+        }
 
-        let btn_finish_rect = Rect::new(screen_width() - 450.0, screen_height() - 120.0, btn_width, btn_height);
-        draw_rectangle(btn_finish_rect.x, btn_finish_rect.y, btn_finish_rect.w, btn_finish_rect.h, DARKGRAY);
-        draw_text_ex("FINISH & BUILD", btn_finish_rect.x + 20.0, btn_finish_rect.y + btn_finish_rect.h - 25.0,
-                     TextParams { font_size: font_size as u16, color: WHITE, ..Default::default() });
+        // 手绘平滑强度滑块 (FINISH 时生效)
+        if self.mode == EditorMode::FreehandDrawing {
+            let slider_x = cm_label_x + 300.0 * sc;
+            draw_text_ex(&format!("Smoothing: {:.0}%", self.freehand_smoothing * 100.0), slider_x, base_y - 15.0 * sc,
+                         TextParams { font_size: (32.0 * sc) as u16, ..Default::default() });
+            let slider_rect = Rect::new(slider_x, base_y, 400.0 * sc, control_height);
+            self.freehand_smoothing = slider(slider_rect, self.freehand_smoothing);
+        }
 
-        let btn_back_rect = Rect::new(50.0, screen_height() - 120.0, btn_width, btn_height);
-        draw_rectangle(btn_back_rect.x, btn_back_rect.y, btn_back_rect.w, btn_back_rect.h, DARKGRAY);
-        draw_text_ex("Back to Menu", btn_back_rect.x + 20.0, btn_back_rect.y + btn_back_rect.h - 25.0,
-                     TextParams { font_size: font_size as u16, color: WHITE, ..Default::default() });
+        let btn_width = 400.0 * sc;
+        let btn_height = 75.0 * sc;
 
-        if is_mouse_button_pressed(MouseButton::Left) {
-            let (mx, my) = mouse_position();
-            let mouse_pos = vec2(mx, my);
+        let btn_finish_rect = Rect::new(screen_width() - btn_width - 50.0, screen_height() - 120.0 * sc, btn_width, btn_height);
+        let btn_back_rect = Rect::new(50.0, screen_height() - 120.0 * sc, btn_width, btn_height);
 
-            if btn_finish_rect.contains(mouse_pos) {
-                self.finalize_stone();
-                self.active_input_id = None;
-            }
-            if btn_back_rect.contains(mouse_pos) {
-                self.mode = EditorMode::Menu;
-                self.active_input_id = None;
-            }
+        // 厚度无效时不允许进入预览
+        if button(btn_finish_rect, "FINISH & BUILD", font_size as u16) && thickness_check.is_ok() {
+            self.finalize_stone();
+            self.active_input_id = None;
+        }
+        if button(btn_back_rect, "Back to Menu", font_size as u16) {
+            self.mode = EditorMode::Menu;
+            self.active_input_id = None;
         }
     }
 
     // 贝塞尔模式
     fn update_bezier(&mut self) {
-        draw_text("Click to add control points.", 20.0, 30.0, 40.0, WHITE);
+        draw_text("Click to add control points.  +/-: elevate/reduce degree", 20.0, 30.0, 40.0, WHITE);
+        let mode_text = if self.bezier_piecewise {
+            format!("S: piecewise cubic (on)   M: C1 mirror ({})", if self.bezier_c1 { "on" } else { "off" })
+        } else {
+            "S: piecewise cubic (off)".to_string()
+        };
+        draw_text(&mode_text, 20.0, 70.0, 30.0, GRAY);
+
+        if self.active_input_id.is_none() {
+            if is_key_pressed(KeyCode::S) { self.bezier_piecewise = !self.bezier_piecewise; }
+            if is_key_pressed(KeyCode::M) { self.bezier_c1 = !self.bezier_c1; }
+        }
+
+        // 升阶/降阶：在不重画的情况下增减控制点 (只适用于单条曲线)
+        if self.active_input_id.is_none() && !self.bezier_piecewise && self.bezier_control_points.len() > 1 {
+            let elevate = is_key_pressed(KeyCode::Equal) || is_key_pressed(KeyCode::KpAdd);
+            let reduce = is_key_pressed(KeyCode::Minus) || is_key_pressed(KeyCode::KpSubtract);
+            if elevate || reduce {
+                let info = BezierInfo::new("temp".to_string(), self.bezier_control_points.clone());
+                let changed = if elevate { info.elevate_degree() } else { info.reduce_degree() };
+                self.bezier_control_points = changed.control_points().to_vec();
+            }
+        }
 
         if is_mouse_button_pressed(MouseButton::Left) {
             let (mx, my) = mouse_position();
             // 避免点击 UI 区域
-            if my < screen_height() - 300.0 {
-                let world_pos = screen_to_world(mx, my);
+            if my < screen_height() - 300.0 * ui_scale() {
+                let world_pos = screen_to_world(mx, my, self.canvas_scale, self.canvas_pan);
                 self.bezier_control_points.push(world_pos);
+
+                // 分段模式：每点满一段 (第 4, 7, 10... 个点) 时，自动镜像出下一段的第一个手柄
+                let n = self.bezier_control_points.len();
+                if self.bezier_piecewise && self.bezier_c1 && n >= 4 && (n - 1).is_multiple_of(3) {
+                    let handle = mirror_handle(self.bezier_control_points[n - 2], world_pos);
+                    self.bezier_control_points.push(handle);
+                }
             }
         }
 
         for (i, p) in self.bezier_control_points.iter().enumerate() {
-            let screen_pos = world_to_screen(*p);
-            draw_circle(screen_pos.x, screen_pos.y, 10.0, RED);
+            let screen_pos = world_to_screen(*p, self.canvas_scale, self.canvas_pan);
+            // 分段模式下段与段的连接点用橙色标出
+            let is_joint = self.bezier_piecewise && i > 0 && i.is_multiple_of(3);
+            draw_circle(screen_pos.x, screen_pos.y, 10.0, if is_joint { ORANGE } else { RED });
             if i > 0 {
-                let prev = world_to_screen(self.bezier_control_points[i - 1]);
+                let prev = world_to_screen(self.bezier_control_points[i - 1], self.canvas_scale, self.canvas_pan);
                 draw_line(prev.x, prev.y, screen_pos.x, screen_pos.y, 2.0, DARKGRAY);
             }
         }
 
         if self.bezier_control_points.len() > 1 {
-            let info = BezierInfo::new("temp".to_string(), self.bezier_control_points.clone());
+            let info = self.build_bezier("temp", self.bezier_control_points.clone());
             let curve_points = info.get_polyline_points();
             for i in 0..curve_points.len() - 1 {
-                let p1 = world_to_screen(curve_points[i]);
-                let p2 = world_to_screen(curve_points[i+1]);
+                let p1 = world_to_screen(curve_points[i], self.canvas_scale, self.canvas_pan);
+                let p2 = world_to_screen(curve_points[i+1], self.canvas_scale, self.canvas_pan);
                 draw_line(p1.x, p1.y, p2.x, p2.y, 4.0, YELLOW);
             }
         }
+
+        self.draw_live_readout();
+    }
+
+    // 按当前模式构建单条或分段贝塞尔曲线
+    fn build_bezier(&self, name: &str, points: Vec<Vector2D>) -> BezierInfo {
+        if self.bezier_piecewise {
+            BezierInfo::new_piecewise(name.to_string(), points)
+        } else {
+            BezierInfo::new(name.to_string(), points)
+        }
     }
 
     // 手绘模式
@@ -304,11 +902,13 @@ impl StoneEditor {
 
         if is_mouse_button_down(MouseButton::Left) {
             let (mx, my) = mouse_position();
-            if my < screen_height() - 300.0 {
-                let world_pos = screen_to_world(mx, my);
+            if my < screen_height() - 300.0 * ui_scale() {
+                let world_pos = screen_to_world(mx, my, self.canvas_scale, self.canvas_pan);
                 if let Some(last) = self.freehand_points.last() {
+                    // 相邻采样点至少相隔 5 像素 (换算成米)
+                    let min_dist = 5.0 / self.canvas_scale;
                     let dist_sq = (last.x - world_pos.x).powi(2) + (last.y - world_pos.y).powi(2);
-                    if dist_sq > 5.0 * 5.0 {
+                    if dist_sq > min_dist * min_dist {
                         self.freehand_points.push(world_pos);
                     }
                 } else {
@@ -318,17 +918,49 @@ impl StoneEditor {
         }
 
         for i in 0..self.freehand_points.len().saturating_sub(1) {
-            let p1 = world_to_screen(self.freehand_points[i]);
-            let p2 = world_to_screen(self.freehand_points[i+1]);
+            let p1 = world_to_screen(self.freehand_points[i], self.canvas_scale, self.canvas_pan);
+            let p2 = world_to_screen(self.freehand_points[i+1], self.canvas_scale, self.canvas_pan);
             draw_line(p1.x, p1.y, p2.x, p2.y, 4.0, GREEN);
         }
+
+        self.draw_live_readout();
+    }
+
+    // 绘制中的实时面积与质量 (当前厚度、板岩密度)，点列变化时才重算
+    fn draw_live_readout(&mut self) {
+        let bezier = self.mode == EditorMode::BezierDrawing;
+        let points = if bezier { &self.bezier_control_points } else { &self.freehand_points };
+        let key = format!("{} {} {:?} {}", bezier, points.len(), points.last(), self.bezier_piecewise);
+        if key != self.live_area_key {
+            self.live_area = self.closed_drawing_area();
+            self.live_area_key = key;
+        }
+
+        let mut text = format!("area: {:.2} cm^2", self.live_area * 1.0e4);
+        if let Ok(thickness_cm) = validate_input("thickness", &self.thickness_input) {
+            let mass = self.live_area * thickness_cm / 100.0 * DENSITY_SLATE;
+            text += &format!("   mass: {:.1} g", mass * 1000.0);
+        }
+        draw_text(&text, 20.0, 110.0, 30.0, WHITE);
+    }
+
+    // 当前笔画/控制点按 FINISH 时的方式自动闭合后的面积 (m^2)；不足三个点时为 0
+    // 手绘平滑只在 FINISH 时进行，几乎不改变面积，这里不做
+    fn closed_drawing_area(&self) -> f64 {
+        let bezier = self.mode == EditorMode::BezierDrawing;
+        let mut points = if bezier { self.bezier_control_points.clone() } else { self.freehand_points.clone() };
+        if points.len() < 3 { return 0.0; }
+        close_loop(&mut points, self.close_threshold);
+        let outline = if bezier { self.build_bezier("live", points).polyline_points } else { points };
+        polygon_area(&outline)
     }
 
     // 预览绘制
     fn draw_preview(&mut self) {
-        let font_size = 48.0;
+        let sc = ui_scale();
+        let font_size = 48.0 * sc;
         let screen_points: Vec<Vec2> = self.preview_points.iter()
-            .map(|p| world_to_screen(*p))
+            .map(|p| world_to_screen(*p, self.canvas_scale, self.canvas_pan))
             .collect();
 
         if screen_points.len() > 1 {
@@ -340,82 +972,145 @@ impl StoneEditor {
             if let (Some(first), Some(last)) = (screen_points.first(), screen_points.last()) {
                 draw_line(first.x, first.y, last.x, last.y, 4.0, GREEN);
             }
+
+            // 高亮相交的线段
+            for &(i, j) in &self.self_intersections {
+                for k in [i, j] {
+                    if let (Some(p1), Some(p2)) = (screen_points.get(k), screen_points.get(k + 1)) {
+                        draw_line(p1.x, p1.y, p2.x, p2.y, 6.0, RED);
+                    }
+                }
+            }
         }
 
         let title_text = "PREVIEW";
-        let text_dims = measure_text(title_text, None, 60, 1.0);
-        draw_text(title_text, screen_width() / 2.0 - text_dims.width / 2.0, 80.0, 60.0, WHITE);
-
-        if self.self_intersection_warning {
-            let warn_text = "Warning: Shape self-intersects!";
-            let warn_text_2 = "This may cause physics issues.";
-            let warn_dims = measure_text(warn_text, None, 40, 1.0);
-            let warn_dims_2 = measure_text(warn_text_2, None, 40, 1.0);
-            draw_text(warn_text, screen_width() / 2.0 - warn_dims.width / 2.0, 150.0, 40.0, RED);
-            draw_text(warn_text_2, screen_width() / 2.0 - warn_dims_2.width / 2.0, 200.0, 40.0, RED);
-            let warn_text_3 = "Multiple regions are not supported.";
-            let warn_dims_3 = measure_text(warn_text_3, None, 40, 1.0);
-            draw_text(warn_text_3, screen_width() / 2.0 - warn_dims_3.width / 2.0, 250.0, 40.0, RED);
-        }
-
-        let btn_width = 400.0;
-        let btn_height = 75.0;
-
-        let btn_confirm_rect = Rect::new(screen_width() - 450.0, screen_height() - 120.0, btn_width, btn_height);
-        draw_rectangle(btn_confirm_rect.x, btn_confirm_rect.y, btn_confirm_rect.w, btn_confirm_rect.h, DARKGREEN);
-        draw_text_ex("CONFIRM", btn_confirm_rect.x + 20.0, btn_confirm_rect.y + btn_confirm_rect.h - 25.0,
-                     TextParams { font_size: font_size as u16, color: WHITE, ..Default::default() });
-
-        let btn_back_rect = Rect::new(50.0, screen_height() - 120.0, btn_width, btn_height);
-        draw_rectangle(btn_back_rect.x, btn_back_rect.y, btn_back_rect.w, btn_back_rect.h, DARKGRAY);
-        draw_text_ex("Go Back (Edit)", btn_back_rect.x + 20.0, btn_back_rect.y + btn_back_rect.h - 25.0,
-                     TextParams { font_size: font_size as u16, color: WHITE, ..Default::default() });
+        let text_dims = measure_text(title_text, None, (60.0 * sc) as u16, 1.0);
+        draw_text(title_text, screen_width() / 2.0 - text_dims.width / 2.0, 80.0 * sc, 60.0 * sc, WHITE);
 
-        if is_mouse_button_pressed(MouseButton::Left) {
-            let (mx, my) = mouse_position();
-            let mouse_pos = vec2(mx, my);
+        let warn_size = 40.0 * sc;
+        let centered = |text: &str, y: f32| {
+            let dims = measure_text(text, None, warn_size as u16, 1.0);
+            draw_text(text, screen_width() / 2.0 - dims.width / 2.0, y, warn_size, RED);
+        };
+        if let Some(err) = self.build_error {
+            centered(&format!("Cannot simulate: {}", err), 310.0 * sc);
+        }
+
+        if self.self_intersections.len() >= SELF_INTERSECTION_LIMIT {
+            centered("Warning: Shape self-intersects!", 150.0 * sc);
+            centered("This may cause physics issues.", 200.0 * sc);
+            centered("Multiple regions are not supported.", 250.0 * sc);
+        }
 
-            if btn_confirm_rect.contains(mouse_pos) {
-                let thickness_cm: f64 = self.thickness_input.parse().unwrap_or(1.0);
-                let thickness_meters = thickness_cm / 100.0;
+        self.draw_properties_panel();
+
+        // 形状评估 (仅提示，不阻止继续)
+        if let Some(report) = self.shape_report {
+            let report_x = 50.0;
+            let report_y = screen_height() - 300.0 * sc;
+            draw_text("Shape report", report_x, report_y, 36.0 * sc, GRAY);
+            draw_text(&format!("area: {:.2} cm^2   aspect: {:.2}   d_max / mean r: {:.2}",
+                               report.area * 1.0e4, report.aspect_ratio, report.radius_ratio),
+                      report_x, report_y + 40.0 * sc, 32.0 * sc, WHITE);
+            draw_text(report.verdict(), report_x, report_y + 80.0 * sc, 32.0 * sc, YELLOW);
+        }
 
-                self.blueprint_buffer = Some(StoneBlueprint {
-                    points: self.preview_points.clone(),
-                    thickness: thickness_meters,
-                    name: "CustomStone".to_string(),
-                });
+        let btn_width = 400.0 * sc;
+        let btn_height = 75.0 * sc;
+
+        let btn_confirm_rect = Rect::new(screen_width() - btn_width - 50.0, screen_height() - 120.0 * sc, btn_width, btn_height);
+        let btn_back_rect = Rect::new(50.0, screen_height() - 120.0 * sc, btn_width, btn_height);
+
+        if button_colored(btn_confirm_rect, "CONFIRM", font_size as u16, DARKGREEN)
+            && let Ok(thickness_cm) = validate_input("thickness", &self.thickness_input) {
+            let thickness_meters = thickness_cm / 100.0;
+
+            let blueprint = StoneBlueprint {
+                points: self.preview_points.clone(),
+                thickness: thickness_meters,
+                name: if self.editing_slot_b { "CustomStone B" } else { "CustomStone" }.to_string(),
+                density_fn: None,
+            };
+            self.active_input_id = None;
+            self.build_error = None;
+
+            if self.editing_slot_b {
+                // B 槽只保存设计，回到菜单
+                self.design_b = Some(blueprint);
+                self.editing_slot_b = false;
+                self.mode = EditorMode::Menu;
+            } else {
+                self.design_a = Some(blueprint.clone());
+                self.blueprint_buffer = Some(blueprint);
+                self.comparing = false;
                 self.mode = EditorMode::SetInitialConditions;
-                self.active_input_id = None;
+                self.prediction_key.clear(); // 新蓝图，重新预测
             }
+        }
 
-            if btn_back_rect.contains(mouse_pos) {
-                self.preview_points.clear();
-                self.self_intersection_warning = false;
-                self.mode = self.previous_mode;
-                self.active_input_id = None;
-            }
+        // 自相交时提供修复：剪掉交叉绕出的小环，保留最大的简单多边形并重新检测
+        let btn_fix_rect = Rect::new(screen_width() / 2.0 - btn_width / 2.0, screen_height() - 120.0 * sc, btn_width, btn_height);
+        if !self.self_intersections.is_empty()
+            && button_colored(btn_fix_rect, "Fix shape", font_size as u16, ORANGE) {
+            let mut repaired = repair_polygon(&self.preview_points);
+            ensure_ccw(&mut repaired);
+            self.build_error = None;
+            self.set_preview_points(repaired);
+        }
+
+        // 导出轮廓 SVG (厚度取输入框，无效时按 0 记录)，结果显示在物理属性面板下方
+        let btn_svg_rect = Rect::new(screen_width() - btn_width - 50.0, screen_height() - 210.0 * sc, btn_width, btn_height);
+        if button(btn_svg_rect, "Export SVG", font_size as u16) {
+            let thickness = validate_input("thickness", &self.thickness_input).map_or(0.0, |cm| cm / 100.0);
+            self.properties_status = Some(match export_svg(&self.preview_points, thickness, SVG_FILE) {
+                Ok(()) => format!("Saved {}", SVG_FILE),
+                Err(e) => e,
+            });
+        }
+
+        if button(btn_back_rect, "Go Back (Edit)", font_size as u16) {
+            self.preview_points.clear();
+            self.self_intersections.clear();
+            self.shape_report = None;
+            self.properties = None;
+            self.build_error = None;
+            self.mode = self.previous_mode;
+            self.active_input_id = None;
         }
     }
 
     // 初始条件设置 UI
     fn draw_initial_conditions_ui(&mut self) {
-        let font_size = 48.0;
-        let title_font_size = 60.0;
-        let start_y = 150.0;
-        let row_height = 100.0;
-        let input_w = 200.0;
-        let input_h = 70.0;
-        let label_w = 400.0;
-        let col_w = 250.0;
-        let start_x = 100.0;
+        let sc = ui_scale();
+        let font_size = 48.0 * sc;
+        let title_font_size = 60.0 * sc;
+        let start_y = 150.0 * sc;
+        let row_height = 90.0 * sc;
+        let input_w = 200.0 * sc;
+        let input_h = 70.0 * sc;
+        let label_w = 400.0 * sc;
+        let col_w = 250.0 * sc;
+        let start_x = 100.0 * sc;
+
+        // 快捷键微调发射参数 (写回输入框，输入框仍是唯一的数据来源)
+        self.handle_quick_launch_keys();
+
+        // T 键：训练模式，在后台搜索当前石片的最佳发射
+        if self.active_input_id.is_none() && is_key_pressed(KeyCode::T) {
+            self.start_training();
+        }
+        self.poll_training();
+
+        // 输入变化后 (防抖) 重算预测轨迹
+        self.update_predicted_path();
 
         // 侧边预览窗口
         self.draw_side_screen_preview();
 
         // 标题
-        let title_text = "SET INITIAL CONDITIONS (2D)";
+        let title_text = if self.comparing { "COMPARE RUN: A vs B" } else { "SET INITIAL CONDITIONS (2D)" };
         let text_dims = measure_text(title_text, None, title_font_size as u16, 1.0);
-        draw_text(title_text, screen_width() / 2.0 - text_dims.width / 2.0, 80.0, title_font_size, WHITE);
+        draw_text(title_text, screen_width() / 2.0 - text_dims.width / 2.0, 80.0 * sc, title_font_size, WHITE);
 
         let mut y = start_y;
 
@@ -430,7 +1125,7 @@ impl StoneEditor {
             "pos",
             y,
             start_x, label_w, col_w, input_w, input_h, font_size as u16,
-            &self.active_input_id
+            &self.active_input_id, self.cursor.pos
         );
         if id.is_some() { clicked_id = id; }
         y += row_height;
@@ -442,33 +1137,86 @@ impl StoneEditor {
             "vel",
             y,
             start_x, label_w, col_w, input_w, input_h, font_size as u16,
-            &self.active_input_id
+            &self.active_input_id, self.cursor.pos
         );
         if id.is_some() { clicked_id = id; }
         y += row_height;
 
         // --- 3. Angle (deg) ---
         let id = draw_f64_input_row(
-            "Angle (deg)",
+            &format!("Angle ({})", self.angle_unit.name()),
             &self.y0_angle,
             "ang",
             y,
             start_x, label_w, input_w, input_h, font_size as u16,
-            &self.active_input_id
+            &self.active_input_id, self.cursor.pos
         );
         if id.is_some() { clicked_id = id; }
         y += row_height;
 
         // --- 4. Angular Velocity (rad/s) ---
         let id = draw_f64_input_row(
-            "Ang. Vel (rad/s)",
+            &format!("Ang. Vel ({}/s)", self.angle_unit.name()),
             &self.y0_angular_velocity,
             "ang_vel",
             y,
             start_x, label_w, input_w, input_h, font_size as u16,
-            &self.active_input_id
+            &self.active_input_id, self.cursor.pos
+        );
+        if id.is_some() { clicked_id = id; }
+        y += row_height;
+
+        // --- 4b. Pitch rate (rad/s)：石片绕俯仰轴的初始翻滚 ---
+        let id = draw_f64_input_row(
+            &format!("Pitch rate ({}/s)", self.angle_unit.name()),
+            &self.y0_pitch_rate,
+            "pitch_vel",
+            y,
+            start_x, label_w, input_w, input_h, font_size as u16,
+            &self.active_input_id, self.cursor.pos
+        );
+        if id.is_some() { clicked_id = id; }
+        y += row_height;
+
+        // --- 5. Water level (m) ---
+        let id = draw_f64_input_row(
+            "Water level (m)",
+            &self.water_level_input,
+            "water_level",
+            y,
+            start_x, label_w, input_w, input_h, font_size as u16,
+            &self.active_input_id, self.cursor.pos
+        );
+        if id.is_some() { clicked_id = id; }
+        y += row_height;
+
+        // --- 6. Surface slope (dy/dx) ---
+        let id = draw_f64_input_row(
+            "Slope (dy/dx)",
+            &self.slope_input,
+            "slope",
+            y,
+            start_x, label_w, input_w, input_h, font_size as u16,
+            &self.active_input_id, self.cursor.pos
+        );
+        if id.is_some() { clicked_id = id; }
+        y += row_height;
+
+        // --- 7. Water temperature (C) ---
+        let id = draw_f64_input_row(
+            "Water temp (C)",
+            &self.water_temp_input,
+            "water_temp",
+            y,
+            start_x, label_w, input_w, input_h, font_size as u16,
+            &self.active_input_id, self.cursor.pos
         );
         if id.is_some() { clicked_id = id; }
+        y += row_height;
+
+        // --- 8. Gravity preset ---
+        let id = self.draw_gravity_row(y, start_x, label_w, input_w, input_h, font_size as u16);
+        if id.is_some() { clicked_id = id; }
 
         // --- [FIX] 在所有绘制完成后才更新 self ---
         if let Some(id_str) = clicked_id {
@@ -476,33 +1224,38 @@ impl StoneEditor {
         }
 
         // --- 按钮 ---
-        let btn_width = 500.0;
-        let btn_height = 75.0;
-
-        let btn_start_rect = Rect::new(screen_width() - (btn_width + 50.0), screen_height() - 120.0, btn_width, btn_height);
-        draw_rectangle(btn_start_rect.x, btn_start_rect.y, btn_start_rect.w, btn_start_rect.h, DARKGREEN);
-        draw_text_ex("START SIMULATION", btn_start_rect.x + 20.0, btn_start_rect.y + btn_start_rect.h - 25.0,
-                     TextParams { font_size: font_size as u16, color: WHITE, ..Default::default() });
-
-        let btn_back_rect = Rect::new(50.0, screen_height() - 120.0, btn_width, btn_height);
-        draw_rectangle(btn_back_rect.x, btn_back_rect.y, btn_back_rect.w, btn_back_rect.h, DARKGRAY);
-        draw_text_ex("Back (Preview)", btn_back_rect.x + 20.0, btn_back_rect.y + btn_back_rect.h - 25.0,
-                     TextParams { font_size: font_size as u16, color: WHITE, ..Default::default() });
+        let btn_width = 500.0 * sc;
+        let btn_height = 75.0 * sc;
+        let all_valid = self.initial_conditions_valid();
+
+        let btn_start_rect = Rect::new(screen_width() - (btn_width + 50.0), screen_height() - 120.0 * sc, btn_width, btn_height);
+        let btn_back_rect = Rect::new(50.0, screen_height() - 120.0 * sc, btn_width, btn_height);
+
+        // 存在无效输入时不允许开始模拟
+        let start_color = if all_valid { DARKGREEN } else { DARKGRAY };
+        if button_colored(btn_start_rect, "START SIMULATION", font_size as u16, start_color) && all_valid {
+            self.finish_and_build_y0();
+            self.active_input_id = None;
+        }
 
-        // --- 点击检测 ---
-        if is_mouse_button_pressed(MouseButton::Left) {
-            let (mx, my) = mouse_position();
-            let mouse_pos = vec2(mx, my);
+        // 计分方式 (Start 与 Back 之间)
+        let score_rect = Rect::new(screen_width() / 2.0 - 200.0 * sc, screen_height() - 120.0 * sc, 400.0 * sc, btn_height);
+        if button(score_rect, &format!("Score: {}", self.score_mode.name()), (font_size * 0.75) as u16) {
+            self.score_mode = self.score_mode.next();
+        }
 
-            if btn_start_rect.contains(mouse_pos) {
-                self.finish_and_build_y0();
-                self.active_input_id = None;
-            }
+        // 角度单位 (侧边预览上方)
+        let unit_rect = Rect::new(screen_width() - 550.0 * sc, 60.0 * sc, 500.0 * sc, 70.0 * sc);
+        if button(unit_rect, &format!("Angle unit: {}", self.angle_unit.name()), (font_size * 0.75) as u16) {
+            self.toggle_angle_unit();
+        }
 
-            if btn_back_rect.contains(mouse_pos) {
-                self.mode = EditorMode::Preview;
-                self.active_input_id = None;
-            }
+        let back_label = if self.comparing { "Back (Menu)" } else { "Back (Preview)" };
+        if button(btn_back_rect, back_label, font_size as u16) {
+            // 对比运行从菜单进入，返回菜单
+            self.mode = if self.comparing { EditorMode::Menu } else { EditorMode::Preview };
+            self.comparing = false;
+            self.active_input_id = None;
         }
     }
 
@@ -518,91 +1271,126 @@ impl StoneEditor {
 
         if points_to_process.len() < 2 { return; }
 
-        let first = points_to_process.first().unwrap();
-        let last = points_to_process.last().unwrap();
-        let dist_sq = (first.x - last.x).powi(2) + (first.y - last.y).powi(2);
-
-        if dist_sq > (0.02 * 0.02) {
-            points_to_process.push(*first);
-        }
+        // 在细分之前闭合原始点列，贝塞尔曲线本身会平滑地回到起点
+        // (首尾点严格相同，预览和自相交检测都依赖这一点)
+        close_loop(&mut points_to_process, self.close_threshold);
 
-        let final_points = match self.mode {
+        let mut final_points = match self.mode {
             EditorMode::BezierDrawing => {
-                let info = BezierInfo::new("final".to_string(), points_to_process);
+                let info = self.build_bezier("final", points_to_process);
                 info.polyline_points
             },
-            EditorMode::FreehandDrawing => points_to_process,
+            // 手绘笔画按滑块强度平滑；平滑引入新的自相交时保留原始笔画
+            EditorMode::FreehandDrawing => {
+                let smoothed = smooth_closed_stroke(&points_to_process, self.freehand_smoothing as f64);
+                if find_self_intersections(&smoothed).len() > find_self_intersections(&points_to_process).len() {
+                    points_to_process
+                } else {
+                    smoothed
+                }
+            },
             _ => Vec::new(),
         };
 
         if final_points.is_empty() { return; }
 
-        let intersection_count = self.count_self_intersections(&final_points);
-        self.self_intersection_warning = intersection_count >= 2;
-
-        self.preview_points = final_points;
+        // 手绘可能是顺时针，统一为逆时针后再检测/构建
+        ensure_ccw(&mut final_points);
+        self.set_preview_points(final_points);
         self.mode = EditorMode::Preview;
     }
 
-    // --- 几何辅助函数 (保持 &self) ---
-
-    // 计算自相交次数
-    fn count_self_intersections(&self, points: &[Vector2D]) -> usize {
-        if points.len() < 4 { return 0; }
-        let mut intersection_count = 0;
-        let num_segments = points.len() - 1;
-
-        for i in 0..num_segments {
-            let p1 = points[i];
-            let p2 = points[i + 1];
-
-            for j in (i + 2)..num_segments {
-                if i == 0 && j == num_segments - 1 {
-                    continue;
-                }
-                let p3 = points[j];
-                let p4 = points[j + 1];
-                if self.line_segments_intersect(p1, p2, p3, p4) {
-                    intersection_count += 1;
-                }
-            }
-        }
-        intersection_count
+    // 设置预览轮廓，同时更新自相交检测、形状评估与物理属性
+    fn set_preview_points(&mut self, points: Vec<Vector2D>) {
+        self.self_intersections = find_self_intersections(&points);
+        self.shape_report = ShapeReport::from_outline(&points);
+        self.properties = validate_input("thickness", &self.thickness_input).ok().and_then(|thickness_cm| {
+            let blueprint = StoneBlueprint {
+                points: points.clone(),
+                thickness: thickness_cm / 100.0,
+                name: String::new(),
+                density_fn: None,
+            };
+            StoneProperties::with_mesh_points(&blueprint, PREVIEW_MESH_POINTS, &mut Rng::new(self.seed)).ok()
+        });
+        self.properties_status = None;
+        self.preview_points = points;
+        self.update_floating_pose(true);
     }
 
-    // 检查线段相交
-    fn line_segments_intersect(&self, a: Vector2D, b: Vector2D, c: Vector2D, d: Vector2D) -> bool {
-        let o1 = self.orientation(a, b, c);
-        let o2 = self.orientation(a, b, d);
-        let o3 = self.orientation(c, d, a);
-        let o4 = self.orientation(c, d, b);
-        if o1 != o2 && o3 != o4 { return true; }
-        if o1 == 0 && self.on_segment(a, c, b) { return true; }
-        if o2 == 0 && self.on_segment(a, d, b) { return true; }
-        if o3 == 0 && self.on_segment(c, a, d) { return true; }
-        if o4 == 0 && self.on_segment(c, b, d) { return true; }
-        false
+    // 按水温输入 (无效时为默认水温) 求解平衡漂浮姿态；水温没变且不强制时保留上次的结果
+    fn update_floating_pose(&mut self, force: bool) {
+        let temp = validate_input("water_temp", &self.water_temp_input).unwrap_or(DEFAULT_WATER_TEMP_C);
+        if !force && temp == self.floating_pose_temp { return; }
+        self.floating_pose_temp = temp;
+        self.floating_pose = self.properties.as_ref().and_then(|props| {
+            CustomSettingsBuilder::new().water_temperature(temp).build(props.clone()).equilibrium_pose()
+        });
     }
 
-    // 几何方向
-    fn orientation(&self, p: Vector2D, q: Vector2D, r: Vector2D) -> i8 {
-        let val = (q.y - p.y) * (r.x - q.x) - (q.x - p.x) * (r.y - q.y);
-        if val.abs() < 1e-10 { return 0; }
-        if val > 0.0 { 1 } else { 2 }
+    // 预览界面右上角的物理属性面板；E 键导出为 JSON
+    fn draw_properties_panel(&mut self) {
+        self.update_floating_pose(false);
+        let Some(props) = &self.properties else { return; };
+        let x = screen_width() - 600.0;
+        let mut y = 150.0;
+        draw_text("Physical properties", x, y, 36.0, GRAY);
+        for line in props.report_lines() {
+            y += 40.0;
+            draw_text(&line, x, y, 32.0, WHITE);
+        }
+        y += 40.0;
+        let floating = match self.floating_pose {
+            Some((pitch, draft)) => format!("floats at {} C: pitch {:.1} deg, draft {:.2} mm",
+                                            self.floating_pose_temp, pitch.to_degrees(), draft * 1000.0),
+            None => format!("floats at {} C: no (denser than water)", self.floating_pose_temp),
+        };
+        draw_text(&floating, x, y, 32.0, WHITE);
+        y += 50.0;
+        draw_text(&format!("E: export to {}", PROPERTIES_FILE), x, y, 28.0, GRAY);
+        y += 35.0;
+        draw_text(&format!("N: mesh {} pts (preview {})", self.mesh_points, PREVIEW_MESH_POINTS), x, y, 28.0, GRAY);
+        if is_key_pressed(KeyCode::N) {
+            self.mesh_points = next_mesh_points(self.mesh_points);
+        }
+
+        if is_key_pressed(KeyCode::E) {
+            self.properties_status = Some(match props.export_report(PROPERTIES_FILE) {
+                Ok(()) => format!("Saved {}", PROPERTIES_FILE),
+                Err(e) => e,
+            });
+        }
+        if let Some(status) = &self.properties_status {
+            draw_text(status, x, y + 35.0, 28.0, YELLOW);
+        }
     }
 
-    // 检查点是否在线段上
-    fn on_segment(&self, p: Vector2D, q: Vector2D, r: Vector2D) -> bool {
-        q.x <= f64::max(p.x, r.x) && q.x >= f64::min(p.x, r.x) &&
-            q.y <= f64::max(p.y, r.y) && q.y >= f64::min(p.y, r.y)
+    // 从 CSV 导入轮廓，成功时直接进入预览 (返回时回到菜单)
+    fn import_outline(&mut self) {
+        let parsed = std::fs::read_to_string(OUTLINE_CSV)
+            .map_err(|e| format!("Cannot read {}: {}", OUTLINE_CSV, e))
+            .and_then(|text| parse_outline_csv(&text));
+
+        match parsed {
+            Ok(mut points) => {
+                close_loop(&mut points, self.close_threshold);
+                ensure_ccw(&mut points);
+                self.set_preview_points(points);
+                self.import_error = None;
+                self.previous_mode = EditorMode::Menu;
+                self.mode = EditorMode::Preview;
+            }
+            Err(e) => self.import_error = Some(e),
+        }
     }
 
     // [FIX] 绘制侧边预览小屏幕 (改为 &self)
     fn draw_side_screen_preview(&self) {
         // 1. 定义预览区域
-        let rect = Rect::new(screen_width() - 550.0, 150.0, 500.0, 400.0);
+        let sc = ui_scale();
+        let rect = Rect::new(screen_width() - 550.0 * sc, 150.0 * sc, 500.0 * sc, 400.0 * sc);
         let title = "Cross-section Preview";
-        let font_size = 30;
+        let font_size = (30.0 * sc) as u16;
 
         // 绘制边框和标题
         draw_rectangle_lines(rect.x, rect.y, rect.w, rect.h, 2.0, GRAY);
@@ -620,11 +1408,13 @@ impl StoneEditor {
 
         // 4. 解析当前输入值
         let parse = |s: &String| s.parse::<f64>().unwrap_or(0.0);
-        let deg_to_rad = |deg: f64| deg * std::f64::consts::PI / 180.0;
+        let unit = self.angle_unit;
 
         let pos_y = parse(&self.y0_position.y); // Y 坐标 (m)
-        let angle_deg = parse(&self.y0_angle);  // 角度 (deg)
-        let angle_rad = deg_to_rad(angle_deg);  // 角度 (rad)
+        let angle0 = unit.to_radians(parse(&self.y0_angle)); // 初始攻角 (rad)
+        let pitch_rate = parse(&self.y0_pitch_rate); // 翻滚角速度 (unit/s)
+        // 有初始翻滚时按设定的角速度转动，每 PREVIEW_TUMBLE_LOOP 秒回到初始角度
+        let angle_rad = angle0 + unit.to_radians(pitch_rate) * (get_time() % PREVIEW_TUMBLE_LOOP);
 
         // 5. 计算石片在预览中的位置和朝向
         let stone_draw_y = world_y_zero - (pos_y * world_scale) as f32;
@@ -641,19 +1431,326 @@ impl StoneEditor {
         let p2_x = world_center_x + cos_a * (stone_len / 2.0);
         let p2_y = stone_draw_y + sin_a * (stone_len / 2.0);
 
-        // 6. 绘制石片
+        // 6. 绘制石片 (翻滚时先用淡色画出初始朝向)
+        if pitch_rate != 0.0 {
+            let (cos0, sin0) = (angle0.cos() as f32, angle0.sin() as f32);
+            draw_line(world_center_x - cos0 * (stone_len / 2.0), stone_draw_y - sin0 * (stone_len / 2.0),
+                      world_center_x + cos0 * (stone_len / 2.0), stone_draw_y + sin0 * (stone_len / 2.0),
+                      2.0, Color::new(1.0, 1.0, 0.0, 0.3));
+        }
         draw_line(p1_x, p1_y, p2_x, p2_y, 5.0, YELLOW);
 
         // 绘制一个 "前" 标记
         draw_circle(p2_x, p2_y, 6.0, RED);
         draw_text_ex("Front", p2_x, p2_y - 15.0, TextParams { font_size: 20, color: RED, ..Default::default() });
+
+        // 7. 速度箭头与数值 (随快捷键实时更新)
+        let vel = Vector2D::new(parse(&self.y0_velocity.x), parse(&self.y0_velocity.y));
+        let speed = vel.length();
+        if speed > 1e-9 {
+            let arrow_len = (speed as f32 * 8.0).min(rect.w / 2.0 - 20.0);
+            let dir = vel * (1.0 / speed);
+            let tip_x = world_center_x + dir.x as f32 * arrow_len;
+            let tip_y = stone_draw_y - dir.y as f32 * arrow_len;
+            draw_line(world_center_x, stone_draw_y, tip_x, tip_y, 3.0, GREEN);
+            draw_circle(tip_x, tip_y, 4.0, GREEN);
+        }
+        // 8. 预测轨迹：按整条路径的水平跨度单独缩放，起点放在左侧
+        // 训练模式的参考轨迹使用同一缩放，画在预测轨迹下面
+        let training = self.current_training();
+        if let (Some(&start), Some(summary)) = (self.predicted_path.first(), &self.predicted_summary) {
+            let guide = training.map(|t| t.trajectory.as_slice()).unwrap_or_default();
+            let span = self.predicted_path.iter().copied()
+                .chain(guide.iter().map(|s| s.state.position))
+                .map(|p| p.x - start.x).fold(0.0, f64::max);
+            let path_scale = world_scale.min((rect.w as f64 - 40.0) / span.max(1e-6));
+            let water_level = parse(&self.water_level_input);
+            let to_screen = |p: Vector2D| vec2(
+                rect.x + 20.0 + ((p.x - start.x) * path_scale) as f32,
+                world_y_zero - ((p.y - water_level) * path_scale) as f32,
+            );
+            let guide_color = Color::new(0.3, 0.9, 1.0, 0.35);
+            for pair in guide.windows(2) {
+                let (a, b) = (to_screen(pair[0].state.position), to_screen(pair[1].state.position));
+                draw_line(a.x, a.y, b.x, b.y, 5.0, guide_color);
+            }
+            let path_color = Color::new(1.0, 0.63, 0.0, 0.7);
+            for pair in self.predicted_path.windows(2) {
+                let (a, b) = (to_screen(pair[0]), to_screen(pair[1]));
+                draw_line(a.x, a.y, b.x, b.y, 2.0, path_color);
+            }
+            draw_text(&format!("Predicted: {} skips, {:.2} m", summary.skips, summary.distance),
+                      rect.x + 10.0, rect.y + 70.0, 22.0, ORANGE);
+            if let Some(target) = training && summary.skips >= target.summary.skips {
+                draw_text("Matched the guide!", rect.x + rect.w - 190.0, rect.y + 70.0, 22.0, GREEN);
+            }
+        }
+        // 训练模式：参考发射的参数，或提示按 T 搜索
+        let (guide_text, guide_color) = match (training, &self.training, &self.training_status) {
+            _ if let Some(job) = &self.training_job =>
+                (format!("Searching for the best launch... {}/{} runs", job.runs.0, job.runs.1), GRAY),
+            (_, _, Some(error)) => (error.clone(), RED),
+            (Some(target), _, _) => (format!("Guide: {} {} at {:.1} m/s -> {} skips ({} runs)",
+                                             unit.format(unit.in_unit(target.angle_deg.to_radians())), unit.name(),
+                                             target.speed, target.summary.skips, target.runs),
+                                     Color::new(0.3, 0.9, 1.0, 1.0)),
+            (None, Some(_), _) => ("Guide out of date: T to search again".to_string(), GRAY),
+            (None, None, _) => ("T: find the best launch (training guide)".to_string(), GRAY),
+        };
+        draw_text(&guide_text, rect.x + 10.0, rect.y + 120.0, 20.0, guide_color);
+        // 9. 发射时最低点已在水面以下：红色警告 (质心在水面下时不等预测结果)
+        let surface_y = parse(&self.water_level_input) + parse(&self.slope_input) * parse(&self.y0_position.x);
+        if self.predicted_submersion > 0.0 || pos_y <= surface_y {
+            let text = if self.predicted_submersion > 0.0 {
+                format!("Launch below the surface: starts {:.0}% submerged", self.predicted_submersion * 100.0)
+            } else {
+                "Launch below the surface: stone starts in the water".to_string()
+            };
+            draw_text(&text, rect.x + 10.0, rect.y + 95.0, 22.0, RED);
+        }
+
+        let spin = parse(&self.y0_angular_velocity);
+        draw_text(&format!("|v| = {:.2} m/s   spin = {}   pitch = {} {}/s",
+                           speed, unit.format(spin), unit.format(pitch_rate), unit.name()),
+                  rect.x + 10.0, rect.y + rect.h - 40.0, 22.0, WHITE);
+        draw_text("Up/Down: angle  Left/Right: speed  PgUp/PgDn: spin",
+                  rect.x + 10.0, rect.y + rect.h - 12.0, 18.0, GRAY);
     }
 
-    // [修正] 最终构建 y0 (纯 2D)
-    fn finish_and_build_y0(&mut self) {
+    // 快捷发射控制 (没有激活的输入框时生效)
+    // Up/Down: 攻角 ±1°, Left/Right: 速度大小 ±0.5 m/s (方向不变), PageUp/PageDown: 自转 ±1 rad/s
+    // 步长按当前角度单位换算后写回输入框
+    fn handle_quick_launch_keys(&mut self) {
+        if self.active_input_id.is_some() { return; }
+        let parse = |s: &String| s.parse::<f64>().unwrap_or(0.0);
+        let unit = self.angle_unit;
+
+        // 1. 攻角
+        let angle_step = match (is_key_pressed(KeyCode::Up), is_key_pressed(KeyCode::Down)) {
+            (true, false) => 1.0,
+            (false, true) => -1.0,
+            _ => 0.0,
+        };
+        if angle_step != 0.0 {
+            self.y0_angle = unit.format(parse(&self.y0_angle) + unit.in_unit(f64::to_radians(angle_step)));
+        }
+
+        // 2. 速度大小 (保持方向；水平速度必须为正，所以速度不低于 0.5 m/s)
+        let speed_step = match (is_key_pressed(KeyCode::Right), is_key_pressed(KeyCode::Left)) {
+            (true, false) => 0.5,
+            (false, true) => -0.5,
+            _ => 0.0,
+        };
+        if speed_step != 0.0 {
+            let vel = Vector2D::new(parse(&self.y0_velocity.x), parse(&self.y0_velocity.y));
+            let speed = vel.length();
+            let dir = if speed > 1e-9 && vel.x > 0.0 { vel * (1.0 / speed) } else { Vector2D::new(1.0, 0.0) };
+            let new_vel = dir * (speed + speed_step).max(0.5);
+            self.y0_velocity.x = format!("{:.2}", new_vel.x);
+            self.y0_velocity.y = format!("{:.2}", new_vel.y);
+        }
+
+        // 3. 自转角速度
+        let spin_step = match (is_key_pressed(KeyCode::PageUp), is_key_pressed(KeyCode::PageDown)) {
+            (true, false) => 1.0,
+            (false, true) => -1.0,
+            _ => 0.0,
+        };
+        if spin_step != 0.0 {
+            self.y0_angular_velocity = unit.format(parse(&self.y0_angular_velocity) + unit.in_unit(spin_step));
+        }
+    }
+
+    /// 切换角度单位，同时换算三个角度输入框，使它们表示的物理量不变
+    fn toggle_angle_unit(&mut self) {
+        let current = [self.y0_angle.clone(), self.y0_angular_velocity.clone(), self.y0_pitch_rate.clone()];
+        let (from, to) = (self.angle_unit, self.angle_unit.next());
+        let converted = match self.angle_unit_memo.take() {
+            Some((before, after)) if after == current => before,
+            _ => current.clone().map(|text| to.convert_text(&text, from)),
+        };
+        [self.y0_angle, self.y0_angular_velocity, self.y0_pitch_rate] = converted.clone();
+        self.angle_unit_memo = Some((current, converted));
+        self.angle_unit = to;
+    }
+
+    /// 物理构建失败：回到预览界面并显示原因，保留用户的绘制数据
+    pub fn show_build_error(&mut self, blueprint: StoneBlueprint, error: StoneError) {
+        self.set_preview_points(blueprint.points);
+        self.build_error = Some(error);
+        self.result = None;
+        self.compare_result = None;
+        self.mode = EditorMode::Preview;
+        self.active_input_id = None;
+    }
+
+    // 初始条件的所有输入是否都有效
+    fn initial_conditions_valid(&self) -> bool {
+        [
+            ("pos_x", &self.y0_position.x),
+            ("pos_y", &self.y0_position.y),
+            ("vel_x", &self.y0_velocity.x),
+            ("vel_y", &self.y0_velocity.y),
+            ("ang", &self.y0_angle),
+            ("ang_vel", &self.y0_angular_velocity),
+            ("pitch_vel", &self.y0_pitch_rate),
+            ("water_level", &self.water_level_input),
+            ("slope", &self.slope_input),
+            ("water_temp", &self.water_temp_input),
+        ].iter().all(|(id, text)| validate_input(id, text).is_ok())
+            && (!self.custom_gravity_selected || validate_input("gravity", &self.gravity_input).is_ok())
+    }
+
+    // 重力预设行：Earth / Moon / Mars / Custom 按钮，选择 Custom 时显示输入框
+    // 返回被点击的输入框 ID
+    fn draw_gravity_row(&mut self, y: f32, start_x: f32, label_w: f32, input_w: f32, input_h: f32, font_size: u16) -> Option<String> {
+        let text_y_offset = input_h - (input_h - font_size as f32) / 2.0 - 5.0;
+        draw_text_ex("Gravity", start_x, y + text_y_offset,
+                     TextParams { font_size, color: WHITE, ..Default::default() });
+
+        let presets = [GravityPreset::Earth, GravityPreset::Moon, GravityPreset::Mars, GravityPreset::Custom(0.0)];
+        let btn_w = 150.0 * ui_scale();
+        let mut x = start_x + label_w;
+        for preset in presets {
+            let rect = Rect::new(x, y, btn_w - 10.0, input_h);
+            let is_custom = matches!(preset, GravityPreset::Custom(_));
+            let selected = if is_custom { self.custom_gravity_selected } else { !self.custom_gravity_selected && self.gravity == preset };
+
+            draw_rectangle(rect.x, rect.y, rect.w, rect.h, if selected { DARKGREEN } else { DARKGRAY });
+            draw_text_ex(preset.name(), rect.x + 10.0, y + text_y_offset,
+                         TextParams { font_size: font_size * 2 / 3, color: WHITE, ..Default::default() });
+
+            if is_mouse_button_pressed(MouseButton::Left) && rect.contains(mouse_position().into()) {
+                self.custom_gravity_selected = is_custom;
+                if !is_custom { self.gravity = preset; }
+            }
+            x += btn_w;
+        }
+
+        if !self.custom_gravity_selected { return None; }
+
+        let input_rect = Rect::new(x, y, input_w, input_h);
+        let check = validate_input("gravity", &self.gravity_input);
+        if let Err(msg) = check {
+            draw_input_error(msg, input_rect);
+        }
+        if draw_text_input_box(&self.gravity_input, input_rect, "gravity", &self.active_input_id, font_size, check.is_err(), self.cursor.pos) {
+            return Some("gravity".to_string());
+        }
+        None
+    }
+
+    // 输入变化时记下时间，停止变化 PREDICTION_DEBOUNCE 秒后重新预测
+    fn update_predicted_path(&mut self) {
+        let key = [
+            &self.y0_position.x, &self.y0_position.y, &self.y0_velocity.x, &self.y0_velocity.y,
+            &self.y0_angle, &self.y0_angular_velocity, &self.y0_pitch_rate, &self.water_level_input, &self.slope_input,
+            &self.water_temp_input, &self.gravity_input,
+        ].iter().map(|s| s.as_str()).collect::<Vec<_>>().join("|")
+            + &format!("|{}|{}|{}|{}", self.gravity.name(), self.custom_gravity_selected, self.mesh_points, self.seed);
+        if key != self.prediction_key {
+            self.prediction_key = key;
+            self.prediction_changed_at = Some(get_time());
+            self.inputs_key = self.training_inputs_key();
+        }
+
+        let Some(changed_at) = self.prediction_changed_at else { return; };
+        if get_time() - changed_at < PREDICTION_DEBOUNCE { return; }
+        self.prediction_changed_at = None;
+        self.predicted_path.clear();
+        self.predicted_summary = None;
+        self.predicted_submersion = 0.0;
+
+        // 输入无效时不显示预测
+        if !self.initial_conditions_valid() { return; }
+        let Some(blueprint) = &self.blueprint_buffer else { return; };
+
+        let (gravity, water) = self.parsed_environment();
+        if let Some((path, summary, submersion)) = predict_path(blueprint, self.parsed_y0(), gravity, water, self.seed) {
+            self.predicted_path = path;
+            self.predicted_summary = Some(summary);
+            self.predicted_submersion = submersion;
+        }
+    }
+
+    // 由输入框解析重力与水面环境 (无效输入按 0 处理)
+    fn parsed_environment(&self) -> (f64, Water) {
+        let parse = |s: &String| s.parse::<f64>().unwrap_or(0.0);
+        let gravity = if self.custom_gravity_selected { parse(&self.gravity_input) } else { self.gravity.value() };
+        (gravity, (parse(&self.water_level_input), parse(&self.slope_input), parse(&self.water_temp_input)))
+    }
+
+    // 训练结果依赖的输入：石片、环境、种子、发射位置、速度方向与角速度 (攻角和速度大小由用户去模仿)
+    // 格式化整个轮廓，只在输入变化时调用
+    fn training_inputs_key(&self) -> String {
+        let y0 = self.parsed_y0();
+        let shape = self.blueprint_buffer.as_ref()
+            .map(|b| format!("{:?}|{}", b.points, b.thickness))
+            .unwrap_or_default();
+        format!("{}|{:?}|{}|{}|{:?}|{}|{:?}", shape, self.parsed_environment(), self.mesh_points, self.seed,
+                y0.position, y0.velocity.y.atan2(y0.velocity.x), y0.angle_velocity)
+    }
+
+    // T 键：从当前输入出发搜索弹跳次数最多的攻角与速度
+    // 搜索要跑上百次模拟，放在后台线程中进行，界面显示进度 (见 poll_training)
+    fn start_training(&mut self) {
+        if self.training_job.is_some() { return; }
+        if !self.initial_conditions_valid() {
+            self.training_status = Some("Fix the invalid inputs before searching".to_string());
+            return;
+        }
+        let Some(blueprint) = self.blueprint_buffer.clone() else { return; };
+        let (gravity, water) = self.parsed_environment();
+        let (y0, mesh_points, seed) = (self.parsed_y0(), self.mesh_points, self.seed);
+        let search = TrainingSearch::default();
+        let max_runs = search.max_runs();
+
+        let (sender, updates) = mpsc::channel();
+        std::thread::spawn(move || {
+            // 编辑器已关闭时接收端不存在，发送失败直接忽略
+            let mut on_run = |runs: usize, max_runs: usize| { let _ = sender.send(TrainingUpdate::Progress(runs, max_runs)); };
+            let result = find_optimal_launch(&blueprint, mesh_points, &y0, gravity, water, seed, &search, Some(&mut on_run));
+            let _ = sender.send(TrainingUpdate::Done(Box::new(result)));
+        });
+        self.training_job = Some(TrainingJob { key: self.inputs_key.clone(), updates, runs: (0, max_runs) });
+        self.training_status = None;
+    }
+
+    // 读取后台搜索的进度与结果；结果对应开始搜索时的输入
+    fn poll_training(&mut self) {
+        let Some(job) = self.training_job.as_mut() else { return; };
+        let finished = loop {
+            match job.updates.try_recv() {
+                Ok(TrainingUpdate::Progress(runs, max_runs)) => job.runs = (runs, max_runs),
+                Ok(TrainingUpdate::Done(result)) => break Some((*result).map_err(|e| format!("Search failed: {}", e))),
+                Err(TryRecvError::Empty) => break None,
+                Err(TryRecvError::Disconnected) => break Some(Err("Search stopped unexpectedly".to_string())),
+            }
+        };
+        let Some(result) = finished else { return; };
+        let key = std::mem::take(&mut job.key);
+        self.training_job = None;
+        match result {
+            Ok(target) => {
+                self.training = Some(target);
+                self.training_key = key;
+            }
+            Err(e) => {
+                self.training = None;
+                self.training_status = Some(e);
+            }
+        }
+    }
+
+    /// 与当前输入一致的训练结果 (输入变化后为 None)
+    fn current_training(&self) -> Option<&TrainingTarget> {
+        self.training.as_ref().filter(|_| self.training_key == self.inputs_key)
+    }
+
+    // 由输入框解析初始状态 (无效输入按 0 处理)
+    fn parsed_y0(&self) -> StoneInfo {
         // 1. 辅助函数, 解析字符串
         let parse = |s: &String| s.parse::<f64>().unwrap_or(0.0);
-        let deg_to_rad = |deg: f64| deg * std::f64::consts::PI / 180.0;
 
         // 2. [修正] 解析 2D 值
         let pos = Vector2D::new(
@@ -666,26 +1763,54 @@ impl StoneEditor {
         );
 
         // 2D 模拟, 角度和角速度是 f64
-        // (角度输入是 度, 物理计算需要 弧度)
-        let ang = deg_to_rad(parse(&self.y0_angle));
-
-        // (角速度输入已经是 rad/s)
-        let ang_vel = parse(&self.y0_angular_velocity);
+        // (输入按 angle_unit 解释，物理计算需要弧度)
+        let ang = self.angle_unit.to_radians(parse(&self.y0_angle));
+        let ang_vel = self.angle_unit.to_radians(parse(&self.y0_angular_velocity));
+        let pitch_rate = self.angle_unit.to_radians(parse(&self.y0_pitch_rate));
 
         // 3. 创建 y0 StoneInfo (假设 StoneInfo 是 2D 结构)
         //======
         //重点：生成y0
         //======
-        let y0 = StoneInfo {
+        StoneInfo {
             position: pos,
             velocity: vel,
             angle: Vector2D::new(ang, 0.0),
-            angle_velocity: Vector2D::new(0.0,ang_vel),
-        };
+            angle_velocity: Vector2D::new(pitch_rate, ang_vel),
+        }
+    }
+
+    // [修正] 最终构建 y0 (纯 2D)
+    // 调用前已经通过 initial_conditions_valid 校验
+    fn finish_and_build_y0(&mut self) {
+        let parse = |s: &String| s.parse::<f64>().unwrap_or(0.0);
+        let y0 = self.parsed_y0();
+
+        // 水面
+        self.water_level = parse(&self.water_level_input);
+        self.slope = parse(&self.slope_input);
+        self.water_temp_c = parse(&self.water_temp_input);
+
+        // 重力
+        if self.custom_gravity_selected {
+            self.gravity = GravityPreset::Custom(parse(&self.gravity_input));
+        }
+
+        // 记下本次的输入，下次打开编辑器时恢复
+        self.save_prefs();
+
+        // 参考轨迹只在输入与搜索时一致时带入模拟
+        if self.current_training().is_none() {
+            self.training = None;
+        }
 
         // 4. 合并 blueprint 和 y0
         if let Some(blueprint) = self.blueprint_buffer.take() { // .take() 会取出 Some(T), 留下 None
             self.result = Some((blueprint, y0));
+            if self.comparing {
+                self.compare_result = self.design_b.clone();
+                self.comparing = false;
+            }
             self.mode = EditorMode::Finished;
         } else {
             // 这是一个错误状态, 意味着 blueprint_buffer 是 None
@@ -695,53 +1820,294 @@ impl StoneEditor {
     }
 }
 
+/// 预览界面可选的碰撞网格点数：点数越多非解析形状的惯量越准，构建越慢
+const MESH_POINT_OPTIONS: [usize; 4] = [10_000, DEFAULT_MESH_POINTS, 100_000, 250_000];
+
+fn next_mesh_points(current: usize) -> usize {
+    let next = MESH_POINT_OPTIONS.iter().position(|&n| n == current).map_or(0, |i| i + 1);
+    MESH_POINT_OPTIONS[next % MESH_POINT_OPTIONS.len()]
+}
+
+/// 用粗步长无窗口模拟一次，返回质心路径、统计与初始浸没比例 (初始条件界面的预测轨迹)
+/// 碰撞网格固定用 PREVIEW_MESH_POINTS (输入每次变化都要重建石片)，而真实运行用编辑器选择的 mesh_points
+/// (缺省 DEFAULT_MESH_POINTS)；非解析形状的惯量因此与真实运行略有差别，加上粗步长，预测只是近似
+/// `water`: (水面高度 m, 坡度 dy/dx, 水温 °C)；`seed`: 与真实运行相同的随机数种子；石片无法构建时返回 None
+pub(crate) fn predict_path(blueprint: &StoneBlueprint, y0: StoneInfo, gravity: f64,
+                           water: (f64, f64, f64), seed: u64) -> Option<(Vec<Vector2D>, SimSummary, f64)> {
+    let mut rng = Rng::new(seed);
+    let stone = StoneProperties::with_mesh_points(blueprint, PREVIEW_MESH_POINTS, &mut rng).ok()?;
+    let mut system = CustomSettingsBuilder::new()
+        .gravity(gravity)
+        .water_level(water.0)
+        .slope(water.1)
+        .water_temperature(water.2)
+        .rng(rng.fork())
+        .build(stone)
+        .with_initial_state(&y0);
+    let initial_submersion = system.submerged_fraction(&y0);
+
+    let mut solver = RungeKuttaSolver::new(0.0, y0);
+    let (trajectory, summary) = simulate(&mut system, &mut solver, PREDICTION_DT, PREDICTION_MAX_STEPS, PREDICTION_STRIDE);
+    Some((trajectory.iter().map(|s| s.state.position).collect(), summary, initial_submersion))
+}
+
+/// 生成预设形状的轮廓 (以原点为中心，逆时针，不含重复的闭合点)
+pub(crate) fn generate_preset(kind: PresetKind, params: &PresetParams) -> Vec<Vector2D> {
+    let tau = std::f64::consts::TAU;
+    let ring = |n: usize, f: &dyn Fn(f64) -> Vector2D| -> Vec<Vector2D> {
+        (0..n).map(|i| f(tau * i as f64 / n as f64)).collect()
+    };
+    let (a, b) = (params.a, params.b);
+    match kind {
+        PresetKind::Circle => ring(PRESET_SEGMENTS, &|t| Vector2D::new(a * t.cos(), a * t.sin())),
+        PresetKind::Ellipse => ring(PRESET_SEGMENTS, &|t| Vector2D::new(a * t.cos(), b * t.sin())),
+        // 从 -90° - 180°/n 开始，前两个顶点关于 -90° 对称，底边水平
+        PresetKind::Polygon => {
+            let n = params.sides.max(3);
+            let offset = -0.25 * tau - 0.5 * tau / n as f64;
+            ring(n, &|t| Vector2D::new(a * (t + offset).cos(), a * (t + offset).sin()))
+        }
+        // 尖端指向 +x，圆钝的一端在 -x
+        PresetKind::Teardrop => ring(PRESET_SEGMENTS, &|t| Vector2D::new(a * t.cos(), b * t.sin() * (0.5 * t).sin())),
+    }
+}
+
+// 闭合点列：首尾足够接近时把终点吸附到起点，否则追加起点
+fn close_loop(points: &mut Vec<Vector2D>, snap_threshold: f64) {
+    let (Some(&first), Some(&last)) = (points.first(), points.last()) else { return; };
+    let dist_sq = (first.x - last.x).powi(2) + (first.y - last.y).powi(2);
+
+    if dist_sq <= snap_threshold * snap_threshold {
+        if let Some(end) = points.last_mut() {
+            *end = first;
+        }
+    } else {
+        points.push(first);
+    }
+}
+
+/// 平滑闭合的手绘笔画 (首尾点相同)，返回同样首尾闭合的点列；`strength` 为 0 时原样返回
+/// 1. Taubin 平滑 (一次收缩、一次反向膨胀的移动平均)，去掉高频抖动而基本不缩小形状
+/// 2. 以平滑后的点为控制点，闭合 Catmull-Rom 样条按弧长均匀重采样，点数与原始笔画相同
+pub(crate) fn smooth_closed_stroke(points: &[Vector2D], strength: f64) -> Vec<Vector2D> {
+    let iterations = (strength.clamp(0.0, 1.0) * SMOOTHING_MAX_ITERATIONS as f64).round() as usize;
+    // 去掉重复的闭合点后按环处理
+    let mut ring: Vec<Vector2D> = points.to_vec();
+    if ring.len() > 1 && ring.first() == ring.last() {
+        ring.pop();
+    }
+    let n = ring.len();
+    if iterations == 0 || n < 4 {
+        return points.to_vec();
+    }
+
+    // 1. Taubin 平滑
+    let relax = |ring: &[Vector2D], factor: f64| -> Vec<Vector2D> {
+        (0..n).map(|i| {
+            let (prev, next) = (ring[(i + n - 1) % n], ring[(i + 1) % n]);
+            ring[i] + ((prev + next) * 0.5 - ring[i]) * factor
+        }).collect()
+    };
+    for _ in 0..iterations {
+        ring = relax(&ring, 0.5);
+        ring = relax(&ring, -0.53);
+    }
+
+    // 2. 闭合 Catmull-Rom 加密后按弧长均匀重采样
+    let dense: Vec<Vector2D> = (0..n).flat_map(|i| {
+        let (p0, p1, p2, p3) = (ring[(i + n - 1) % n], ring[i], ring[(i + 1) % n], ring[(i + 2) % n]);
+        (0..CATMULL_ROM_SUBDIVISIONS).map(move |k| {
+            let t = k as f64 / CATMULL_ROM_SUBDIVISIONS as f64;
+            let (t2, t3) = (t * t, t * t * t);
+            (p1 * 2.0 + (p2 - p0) * t + (p0 * 2.0 - p1 * 5.0 + p2 * 4.0 - p3) * t2 + (p1 * 3.0 - p0 - p2 * 3.0 + p3) * t3) * 0.5
+        })
+    }).collect();
+    let mut cumulative = vec![0.0];
+    for i in 0..dense.len() {
+        let step = (dense[(i + 1) % dense.len()] - dense[i]).length();
+        cumulative.push(cumulative[i] + step);
+    }
+    let total = cumulative[dense.len()];
+    let mut result = Vec::with_capacity(n + 1);
+    let mut segment = 0;
+    for k in 0..n {
+        let target = total * k as f64 / n as f64;
+        while cumulative[segment + 1] < target {
+            segment += 1;
+        }
+        let span = cumulative[segment + 1] - cumulative[segment];
+        let t = if span > 0.0 { (target - cumulative[segment]) / span } else { 0.0 };
+        let (a, b) = (dense[segment], dense[(segment + 1) % dense.len()]);
+        result.push(a + (b - a) * t);
+    }
+    result.push(result[0]);
+    result
+}
+
+// 解析轮廓 CSV：每行 "x, y"，空行和 # 开头的注释行忽略
+// 失败时返回带行号 (从 1 开始) 的错误信息；至少需要 3 个点
+pub(crate) fn parse_outline_csv(text: &str) -> Result<Vec<Vector2D>, String> {
+    let mut points = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') { continue; }
+
+        let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+        let [x, y] = fields[..] else {
+            return Err(format!("Line {}: expected \"x, y\"", i + 1));
+        };
+        let parse = |v: &str| v.parse::<f64>().ok().filter(|v| v.is_finite());
+        match (parse(x), parse(y)) {
+            (Some(x), Some(y)) => points.push(Vector2D::new(x, y)),
+            _ => return Err(format!("Line {}: invalid number", i + 1)),
+        }
+    }
+
+    if points.len() < 3 {
+        return Err(format!("Need at least 3 points, found {}", points.len()));
+    }
+    Ok(points)
+}
+
 // --- [FIX] UI 辅助绘制函数 (移出 impl 块) ---
 
+// 校验输入框内容：必须是有限数值，厚度、水平速度和重力还必须为正
+// 返回解析后的值或简短的错误提示
+pub(crate) fn validate_input(id: &str, text: &str) -> Result<f64, &'static str> {
+    let value: f64 = text.trim().parse().map_err(|_| "invalid number")?;
+    if !value.is_finite() {
+        return Err("invalid number");
+    }
+    match id {
+        "thickness" | "vel_x" | "gravity" | "preset_a" | "preset_b" if value <= 0.0 => Err("must be > 0"),
+        "spin_lift" | "water_depth" | "gust" if value < 0.0 => Err("must be >= 0"),
+        "preset_n" if value < 3.0 || value.fract() != 0.0 => Err("integer >= 3"),
+        // 液态水范围
+        "water_temp" if !(0.0..=100.0).contains(&value) => Err("must be 0-100"),
+        _ => Ok(value),
+    }
+}
+
+// 输入框中的光标 (字节下标，输入只包含 ASCII)
+// owner 记录光标所属的输入框，焦点切换时光标移到末尾
+pub(crate) struct TextCursor {
+    owner: Option<String>,
+    pub pos: usize,
+}
+
+impl TextCursor {
+    pub(crate) fn new() -> Self {
+        Self { owner: None, pos: 0 }
+    }
+
+    pub(crate) fn sync(&mut self, active_id: &Option<String>, text: &str) {
+        if self.owner != *active_id {
+            self.owner = active_id.clone();
+            self.pos = text.len();
+        }
+        self.pos = self.pos.min(text.len());
+    }
+}
+
+// 将本帧的键盘输入写入数值输入框 (在光标处插入)
+// 支持左右方向键/Home/End 移动光标、Delete 删除、Ctrl+V 粘贴
+// 返回 true 表示按下了回车 (调用方应取消焦点)
+pub(crate) fn read_numeric_chars(s_mut: &mut String, cursor: &mut usize) -> bool {
+    *cursor = (*cursor).min(s_mut.len());
+
+    // 1. 光标移动
+    if is_key_pressed(KeyCode::Left) { *cursor = cursor.saturating_sub(1); }
+    if is_key_pressed(KeyCode::Right) { *cursor = (*cursor + 1).min(s_mut.len()); }
+    if is_key_pressed(KeyCode::Home) { *cursor = 0; }
+    if is_key_pressed(KeyCode::End) { *cursor = s_mut.len(); }
+    if is_key_pressed(KeyCode::Delete) && *cursor < s_mut.len() {
+        s_mut.remove(*cursor);
+    }
+
+    // 2. 粘贴：只接受能整体解析为数值、且插入后仍是合法数值的文本
+    let ctrl = is_key_down(KeyCode::LeftControl) || is_key_down(KeyCode::RightControl)
+        || is_key_down(KeyCode::LeftSuper) || is_key_down(KeyCode::RightSuper);
+    if ctrl && is_key_pressed(KeyCode::V)
+        && let Some(clip) = miniquad::window::clipboard_get() {
+        let pasted = clip.trim();
+        let mut candidate = s_mut.clone();
+        candidate.insert_str(*cursor, pasted);
+        if pasted.parse::<f64>().is_ok() && candidate.parse::<f64>().is_ok() {
+            *s_mut = candidate;
+            *cursor += pasted.len();
+        }
+    }
+
+    // 3. 字符输入
+    while let Some(c) = get_char_pressed() {
+        if ctrl { continue; } // Ctrl 组合键不作为字符输入
+        match c {
+            '\u{0008}' if *cursor > 0 => { // 退格键 (删除光标前的字符)
+                *cursor -= 1;
+                s_mut.remove(*cursor);
+            },
+            '\r' | '\n' => return true, // 回车键
+            // 允许数字、小数点，负号只能在开头
+            c if c.is_ascii_digit() || c == '.' || (c == '-' && *cursor == 0 && !s_mut.starts_with('-')) => {
+                s_mut.insert(*cursor, c);
+                *cursor += 1;
+            },
+            _ => {}
+        }
+    }
+    false
+}
+
 // 绘制一个可点击的文本输入框
 // [FIX] 不再是 &mut self 的方法
 // [FIX] 返回 bool (是否被点击)
-fn draw_text_input_box(
-    text: &String,
+pub(crate) fn draw_text_input_box(
+    text: &str,
     rect: Rect,
     id: &str,
     active_id: &Option<String>,
-    font_size: u16
+    font_size: u16,
+    is_invalid: bool,
+    cursor: usize
 ) -> bool {
     let mut clicked = false;
     // 检查是否被点击
-    if is_mouse_button_pressed(MouseButton::Left) {
-        if rect.contains(mouse_position().into()) {
-            clicked = true;
-        }
+    if is_mouse_button_pressed(MouseButton::Left) && rect.contains(mouse_position().into()) {
+        clicked = true;
     }
 
     let is_active = active_id.as_deref() == Some(id);
 
-    // 绘制框
-    draw_rectangle_lines(rect.x, rect.y, rect.w, rect.h, 2.0,
-                         if is_active { YELLOW } else { GRAY });
+    // 绘制框 (无效输入显示为红色)
+    let border_color = if is_invalid { RED } else if is_active { YELLOW } else { GRAY };
+    draw_rectangle_lines(rect.x, rect.y, rect.w, rect.h, 2.0, border_color);
 
     // 绘制文本
-    let text_to_draw = if is_active {
-        // 添加闪烁的光标 (简单实现)
-        if (get_time() * 2.0).fract() > 0.5 {
-            format!("{}|", text)
-        } else {
-            text.clone()
-        }
-    } else {
-        text.clone()
-    };
-
-    draw_text_ex(&text_to_draw, rect.x + 10.0, rect.y + rect.h - (rect.h - font_size as f32) / 2.0 - 5.0,
+    let text_x = rect.x + 10.0;
+    let baseline = rect.y + rect.h - (rect.h - font_size as f32) / 2.0 - 5.0;
+    draw_text_ex(text, text_x, baseline,
                  TextParams { font_size, color: WHITE, ..Default::default() });
 
+    // 闪烁的光标，画在光标所在的字符之间
+    if is_active && (get_time() * 2.0).fract() > 0.5 {
+        let cursor = cursor.min(text.len());
+        let cursor_x = text_x + measure_text(&text[..cursor], None, font_size, 1.0).width;
+        draw_line(cursor_x, baseline - font_size as f32 * 0.8, cursor_x, baseline + 4.0, 2.0, WHITE);
+    }
+
     clicked // 返回点击状态
 }
 
+// 在输入框下方绘制错误提示
+fn draw_input_error(msg: &str, input_rect: Rect) {
+    draw_text_ex(msg, input_rect.x, input_rect.y + input_rect.h + 24.0,
+                 TextParams { font_size: 24, color: RED, ..Default::default() });
+}
+
 // 绘制一整行 Vec2 输入 (Label + X, Y inputs)
 // [FIX] 不再是 &mut self 的方法
 // [FIX] 返回 Option<String> (被点击的 ID)
+#[allow(clippy::too_many_arguments)]
 fn draw_vec2_input_row(
     label_text: &str,
     input_data: &Vec2Input,
@@ -753,7 +2119,8 @@ fn draw_vec2_input_row(
     input_w: f32,
     input_h: f32,
     font_size: u16,
-    active_id: &Option<String>
+    active_id: &Option<String>,
+    cursor: usize
 ) -> Option<String> {
     let text_y_offset = input_h - (input_h - font_size as f32) / 2.0 - 5.0;
 
@@ -776,7 +2143,11 @@ fn draw_vec2_input_row(
 
         // 输入框
         let input_rect = Rect::new(x + 50.0, y, input_w, input_h);
-        if draw_text_input_box(component_data, input_rect, &component_id, active_id, font_size) {
+        let check = validate_input(&component_id, component_data);
+        if let Err(msg) = check {
+            draw_input_error(msg, input_rect);
+        }
+        if draw_text_input_box(component_data, input_rect, &component_id, active_id, font_size, check.is_err(), cursor) {
             return Some(component_id); // [FIX] 返回被点击的 ID
         }
 
@@ -789,9 +2160,10 @@ fn draw_vec2_input_row(
 // 绘制一整行 f64 (单个) 输入
 // [FIX] 不再是 &mut self 的方法
 // [FIX] 返回 Option<String> (被点击的 ID)
+#[allow(clippy::too_many_arguments)]
 fn draw_f64_input_row(
     label_text: &str,
-    input_data: &String,
+    input_data: &str,
     id: &str,
     y: f32,
     start_x: f32,
@@ -799,7 +2171,8 @@ fn draw_f64_input_row(
     input_w: f32,
     input_h: f32,
     font_size: u16,
-    active_id: &Option<String>
+    active_id: &Option<String>,
+    cursor: usize
 ) -> Option<String> {
     let text_y_offset = input_h - (input_h - font_size as f32) / 2.0 - 5.0;
 
@@ -811,7 +2184,11 @@ fn draw_f64_input_row(
 
     // 2. 绘制输入框
     let input_rect = Rect::new(x, y, input_w, input_h);
-    if draw_text_input_box(input_data, input_rect, id, active_id, font_size) {
+    let check = validate_input(id, input_data);
+    if let Err(msg) = check {
+        draw_input_error(msg, input_rect);
+    }
+    if draw_text_input_box(input_data, input_rect, id, active_id, font_size, check.is_err(), cursor) {
         return Some(id.to_string()); // [FIX] 返回被点击的 ID
     }
 
@@ -819,30 +2196,261 @@ fn draw_f64_input_row(
 }
 
 
-// --- 辅助函数：坐标转换 (保持不变) ---
-// 屏幕坐标 (Top-Left 0,0) -> 物理世界坐标 (Center 0,0, f64, 米)
-fn screen_to_world(mx: f32, my: f32) -> Vector2D {
-    let center_x = screen_width() / 2.0;
-    let center_y = screen_height() / 2.0;
+/// 比例尺的参考长度 (cm)：候选值中在屏幕上最接近 RULER_TARGET_PX 的一个
+/// `scale`: 每米对应的像素数
+fn ruler_length_cm(scale: f64) -> f64 {
+    let px = |cm: f64| cm * 0.01 * scale;
+    RULER_LENGTHS_CM.iter().copied()
+        .min_by(|&a, &b| (px(a) - RULER_TARGET_PX).abs().total_cmp(&(px(b) - RULER_TARGET_PX).abs()))
+        .unwrap_or(1.0)
+}
 
-    // 假设屏幕上的 1000 像素 = 1 米
-    let scale = 8000.0;
+/// 点集的轴对齐包围盒 (min, max)，空点集返回 None
+fn bounding_box(points: &[Vector2D]) -> Option<(Vector2D, Vector2D)> {
+    let first = *points.first()?;
+    Some(points.iter().fold((first, first), |(min, max), p| {
+        (Vector2D::new(min.x.min(p.x), min.y.min(p.y)), Vector2D::new(max.x.max(p.x), max.y.max(p.y)))
+    }))
+}
 
-    Vector2D {
-        x: (mx - center_x) as f64 / scale,
-        y: (center_y - my) as f64 / scale, // Y轴反转, 物理世界Y向上
-    }
+// --- 辅助函数：坐标转换 ---
+// 屏幕坐标 (Top-Left 0,0) -> 物理世界坐标 (Center 0,0, f64, 米)
+// `scale`: 每米对应的像素数 (见 canvas_scale)；`pan`: 世界原点相对屏幕中心的偏移 (见 canvas_pan)
+fn screen_to_world(mx: f32, my: f32, scale: f64, pan: Vec2) -> Vector2D {
+    canvas_to_world(vec2(mx, my), screen_center() + pan, scale)
 }
 
 // 物理世界坐标 (f64, 米) -> 屏幕坐标 (f32)
-fn world_to_screen(v: Vector2D) -> Vec2 {
-    let center_x = screen_width() / 2.0;
-    let center_y = screen_height() / 2.0;
+fn world_to_screen(v: Vector2D, scale: f64, pan: Vec2) -> Vec2 {
+    world_to_canvas(v, screen_center() + pan, scale)
+}
+
+fn screen_center() -> Vec2 {
+    vec2(screen_width() / 2.0, screen_height() / 2.0)
+}
 
-    let scale = 8000.0; // 1 米 对应 1000 像素
+// 以屏幕上 `origin` 处为世界原点的变换 (不依赖窗口，便于测试)
+fn canvas_to_world(screen: Vec2, origin: Vec2, scale: f64) -> Vector2D {
+    Vector2D {
+        x: (screen.x - origin.x) as f64 / scale,
+        y: (origin.y - screen.y) as f64 / scale, // Y轴反转, 物理世界Y向上
+    }
+}
 
+fn world_to_canvas(v: Vector2D, origin: Vec2, scale: f64) -> Vec2 {
     vec2(
-        center_x + (v.x * scale) as f32,
-        center_y - (v.y * scale) as f32, // Y轴反转
+        origin.x + (v.x * scale) as f32,
+        origin.y - (v.y * scale) as f32, // Y轴反转
     )
-}
\ No newline at end of file
+}
+
+// 缩放时保持鼠标下的世界点不动，返回新的平移量
+fn zoom_pan_about(center: Vec2, mouse: Vec2, old_scale: f64, new_scale: f64, pan: Vec2) -> Vec2 {
+    let anchor = canvas_to_world(mouse, center + pan, old_scale);
+    mouse - world_to_canvas(anchor, center, new_scale)
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::basic_structs::polygon_area;
+    use crate::stone_phy::slab_blueprint;
+
+    #[test]
+    fn zooming_keeps_the_point_under_the_mouse_fixed() {
+        let center = vec2(800.0, 600.0);
+        let mouse = vec2(950.0, 420.0);
+        let pan = vec2(-30.0, 12.0);
+        let before = canvas_to_world(mouse, center + pan, PIXELS_PER_METER);
+
+        let new_pan = zoom_pan_about(center, mouse, PIXELS_PER_METER, 2.0 * PIXELS_PER_METER, pan);
+        let after = canvas_to_world(mouse, center + new_pan, 2.0 * PIXELS_PER_METER);
+        assert!((before - after).length() < 1e-6, "{:?} -> {:?}", before, after);
+
+        // 变换互逆：点击位置映射回同一屏幕点
+        let back = world_to_canvas(after, center + new_pan, 2.0 * PIXELS_PER_METER);
+        assert!((back - mouse).length() < 1e-3);
+    }
+
+    #[test]
+    fn presets_match_analytic_areas_and_inertia() {
+        let pi = std::f64::consts::PI;
+        let params = PresetParams { a: 0.04, b: 0.015, sides: 6 };
+        let area = |kind| polygon_area(&generate_preset(kind, &params));
+
+        // 64 段近似圆/椭圆，面积误差约 0.2%
+        assert!((area(PresetKind::Circle) / (pi * 0.04 * 0.04) - 1.0).abs() < 0.005);
+        assert!((area(PresetKind::Ellipse) / (pi * 0.04 * 0.015) - 1.0).abs() < 0.005);
+        // 正六边形面积 (n/2) r^2 sin(2π/n) 为精确值
+        let hexagon = 3.0 * 0.04 * 0.04 * (pi / 3.0).sin();
+        assert!((area(PresetKind::Polygon) - hexagon).abs() < 1e-12);
+        // 底边水平
+        let polygon = generate_preset(PresetKind::Polygon, &params);
+        assert!((polygon[0].y - polygon[1].y).abs() < 1e-12);
+
+        // 水滴形是简单多边形，可以直接构建石片
+        let teardrop = generate_preset(PresetKind::Teardrop, &params);
+        assert!(find_self_intersections(&teardrop).is_empty());
+        assert!(StoneProperties::from_outline(&teardrop, 0.01, 2700.0).is_ok());
+
+        // 椭圆的俯仰惯量与解析解 m b^2 / 4 一致
+        let ellipse = StoneProperties::from_outline(&generate_preset(PresetKind::Ellipse, &params), 0.01, 2700.0).unwrap();
+        let expected = ellipse.mass * 0.015 * 0.015 / 4.0;
+        assert!((ellipse.inertia_tensor_x / expected - 1.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn predicted_path_is_short_and_finite() {
+        let blueprint = slab_blueprint(0.02, "preview");
+        // 编辑器的默认初始条件
+        let y0 = StoneEditor::with_defaults().parsed_y0();
+        assert!((y0.angle_velocity.y - 15.0).abs() < 1e-12, "{}", y0.angle_velocity.y);
+        let (path, summary, submersion) = predict_path(&blueprint, y0.clone(), 9.81, (0.0, 0.0, 20.0), DEFAULT_SEED).unwrap();
+        assert_eq!(submersion, 0.0); // 默认从水面以上抛出
+
+        assert_eq!(path[0], y0.position);
+        assert!(path.len() <= PREDICTION_MAX_STEPS / PREDICTION_STRIDE + 1);
+        assert!(path.iter().all(|p| p.x.is_finite() && p.y.is_finite()));
+        assert!(summary.distance > 0.0);
+    }
+
+    #[test]
+    fn prefs_restore_valid_inputs_and_keep_defaults_otherwise() {
+        let mut editor = StoneEditor::with_defaults();
+        let prefs = Prefs::parse(r#"{"pos_y": "0.35", "vel_x": "-2", "water_temp": "abc", "gravity_preset": "Moon", "mesh_points": "12345"}"#);
+        editor.apply_prefs(&prefs);
+        assert_eq!(editor.y0_position.y, "0.35");
+        // 无效值 (速度必须为正、非数字、不在选项中的网格点数) 保留默认值
+        assert_eq!(editor.y0_velocity.x, "10.0");
+        assert_eq!(editor.water_temp_input, format!("{}", DEFAULT_WATER_TEMP_C));
+        assert_eq!(editor.mesh_points, DEFAULT_MESH_POINTS);
+        assert_eq!(editor.gravity, GravityPreset::Moon);
+
+        // 保存的字段 (包括模拟中切换的弹跳模型) 可以原样读回
+        editor.bounce_model = BounceModel::Impulse;
+        let saved = editor.pref_fields();
+        let mut restored = StoneEditor::with_defaults();
+        let text = format!("{{{}}}", saved.iter().map(|(k, v)| format!("\"{}\": \"{}\"", k, v)).collect::<Vec<_>>().join(", "));
+        restored.apply_prefs(&Prefs::parse(&text));
+        assert_eq!(restored.pref_fields(), saved);
+        assert_eq!(restored.bounce_model, BounceModel::Impulse);
+    }
+
+    #[test]
+    fn angle_unit_toggle_keeps_the_physical_values() {
+        let mut editor = StoneEditor::with_defaults();
+        editor.y0_angle = "30".to_string();
+        editor.y0_angular_velocity = "28.64788975654116".to_string();
+        editor.y0_pitch_rate = "abc".to_string();
+        let degrees = editor.parsed_y0();
+        assert!((degrees.angle.x - 30f64.to_radians()).abs() < 1e-15);
+
+        // 1. 切换到弧度：文本换算，物理量不变；无法解析的文本原样保留
+        editor.toggle_angle_unit();
+        assert_eq!(editor.angle_unit, AngleUnit::Radians);
+        assert_eq!(editor.y0_pitch_rate, "abc");
+        let radians = editor.parsed_y0();
+        assert!((radians.angle.x - degrees.angle.x).abs() < 1e-15);
+        assert!((radians.angle_velocity.y - degrees.angle_velocity.y).abs() < 1e-12);
+
+        // 2. 未编辑就切换回来：逐字恢复
+        editor.toggle_angle_unit();
+        assert_eq!(editor.y0_angle, "30");
+        assert_eq!(editor.y0_angular_velocity, "28.64788975654116");
+
+        // 3. 编辑后再切换：重新换算，换算误差收回到整齐的小数
+        editor.toggle_angle_unit();
+        editor.y0_angle = "0.5".to_string();
+        editor.toggle_angle_unit();
+        assert_eq!(AngleUnit::Radians.convert_text(&editor.y0_angle, AngleUnit::Degrees), "0.5");
+        assert_eq!(AngleUnit::Degrees.convert_text(&AngleUnit::Radians.convert_text("30", AngleUnit::Degrees), AngleUnit::Radians), "30");
+
+        // 4. 旧偏好文件 (没有 angle_unit) 中的角速度是 rad/s
+        let mut legacy = StoneEditor::with_defaults();
+        legacy.apply_prefs(&Prefs::parse(r#"{"ang": "10", "ang_vel": "1", "pitch_vel": "0"}"#));
+        assert_eq!(legacy.y0_angle, "10");
+        assert!((legacy.parsed_y0().angle_velocity.y - 1.0).abs() < 1e-12);
+        let mut current = StoneEditor::with_defaults();
+        current.apply_prefs(&Prefs::parse(r#"{"ang": "0.2", "ang_vel": "1", "angle_unit": "rad"}"#));
+        assert_eq!(current.angle_unit, AngleUnit::Radians);
+        assert_eq!(current.parsed_y0().angle, Vector2D::new(0.2, 0.0));
+    }
+
+    #[test]
+    fn live_area_auto_closes_the_drawing() {
+        let mut editor = StoneEditor::with_defaults();
+        let corners = vec![
+            Vector2D::new(0.0, 0.0),
+            Vector2D::new(0.05, 0.0),
+            Vector2D::new(0.05, 0.05),
+            Vector2D::new(0.0, 0.05),
+        ];
+
+        // 手绘笔画尚未回到起点：按闭合后的正方形计算
+        editor.mode = EditorMode::FreehandDrawing;
+        editor.freehand_points = corners[..2].to_vec();
+        assert_eq!(editor.closed_drawing_area(), 0.0);
+        editor.freehand_points = corners.clone();
+        assert!((editor.closed_drawing_area() - 2.5e-3).abs() < 1e-15);
+
+        // 贝塞尔曲线先闭合控制点再细分，面积在控制多边形之内
+        editor.mode = EditorMode::BezierDrawing;
+        editor.bezier_control_points = corners;
+        let area = editor.closed_drawing_area();
+        assert!(area > 0.0 && area < 2.5e-3, "area {}", area);
+    }
+
+    #[test]
+    fn outline_csv_reports_offending_line() {
+        let points = parse_outline_csv("# outline\n0, 0\n\n1.5, 0\n1, 1\n").unwrap();
+        assert_eq!(points.len(), 3);
+        assert_eq!(points[1], Vector2D::new(1.5, 0.0));
+
+        assert_eq!(parse_outline_csv("0, 0\n1, x\n1, 1\n").unwrap_err(), "Line 2: invalid number");
+        assert_eq!(parse_outline_csv("0, 0\n1, 0, 2\n").unwrap_err(), "Line 2: expected \"x, y\"");
+        assert!(parse_outline_csv("0, 0\n1, 0\n").is_err());
+    }
+
+    #[test]
+    fn ruler_picks_a_readable_length_and_box_measures_the_shape() {
+        // 1 m = 2000 px 时 10 cm 正好 200 px
+        assert_eq!(ruler_length_cm(2000.0), 10.0);
+        // 任何缩放下参考线段都不会过短或过长
+        for zoom in [MIN_CANVAS_ZOOM, 1.0, MAX_CANVAS_ZOOM] {
+            let px = ruler_length_cm(PIXELS_PER_METER * zoom) * 0.01 * PIXELS_PER_METER * zoom;
+            assert!((50.0..=600.0).contains(&px), "zoom {} -> {} px", zoom, px);
+        }
+
+        assert_eq!(bounding_box(&[]), None);
+        let (min, max) = bounding_box(&[Vector2D::new(0.01, -0.02), Vector2D::new(-0.03, 0.04)]).unwrap();
+        assert_eq!((min, max), (Vector2D::new(-0.03, -0.02), Vector2D::new(0.01, 0.04)));
+    }
+
+    #[test]
+    fn smoothing_reduces_jitter_and_keeps_the_shape_closed() {
+        // 半径 4 cm 的圆，每个点沿径向交替抖动 2 mm
+        let mut stroke: Vec<Vector2D> = (0..80).map(|i| {
+            let t = std::f64::consts::TAU * i as f64 / 80.0;
+            let r = 0.04 + if i % 2 == 0 { 0.002 } else { -0.002 };
+            Vector2D::new(r * t.cos(), r * t.sin())
+        }).collect();
+        stroke.push(stroke[0]);
+        // 相邻边方向变化之和：抖动越大越大
+        let turning = |points: &[Vector2D]| -> f64 {
+            points.windows(3).map(|w| {
+                let (a, b) = (w[1] - w[0], w[2] - w[1]);
+                (a.x * b.y - a.y * b.x).atan2(a.x * b.x + a.y * b.y).abs()
+            }).sum()
+        };
+
+        assert_eq!(smooth_closed_stroke(&stroke, 0.0), stroke);
+        let smoothed = smooth_closed_stroke(&stroke, 0.5);
+        assert_eq!(smoothed.len(), stroke.len());
+        assert_eq!(smoothed.first(), smoothed.last());
+        assert!(turning(&smoothed) < 0.5 * turning(&stroke));
+        assert!(find_self_intersections(&smoothed).is_empty());
+        // 整体形状基本不变 (Taubin 平滑几乎不收缩)
+        let area = polygon_area(&smoothed[..smoothed.len() - 1]);
+        let circle = std::f64::consts::PI * 0.04 * 0.04;
+        assert!((area - circle).abs() < 0.05 * circle, "area {} vs {}", area, circle);
+    }
+}
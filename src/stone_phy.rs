@@ -1,11 +1,55 @@
-use crate::basic_structs::Vector2D;
+use crate::basic_structs::{polygon_area, polygon_centroid, signed_polygon_area, Vector2D};
 use crate::stone_editor::StoneBlueprint;
+use crate::rng::{Rng, DEFAULT_SEED};
 
 // --- 常量 ---
-/// 石片碰撞网格的采样点数 (近似)
-const COLLISION_MESH_POINTS: usize = 40000;
+/// 石片碰撞网格的默认采样点数 (近似)
+/// 点数决定非解析形状的惯量精度 (误差约随格距即 1/sqrt(点数) 下降) 与构建时间 (与点数成正比)；
+/// 编辑器预览用 PREVIEW_MESH_POINTS 保持流畅，开始模拟时用完整点数
+pub const DEFAULT_MESH_POINTS: usize = 40000;
+/// 编辑器预览 (物理属性面板、预测轨迹) 使用的采样点数
+/// 比真实运行少得多：非解析形状的惯量误差约为 DEFAULT_MESH_POINTS 时的 4 倍，预测轨迹的落点可能与真实运行不同
+pub const PREVIEW_MESH_POINTS: usize = 2000;
 /// 石头的密度 (假设为板岩, kg/m^3)
-const DENSITY_SLATE: f64 = 2700.0;
+pub const DENSITY_SLATE: f64 = 2700.0;
+/// 解析形状识别的面积相对误差容限
+const SHAPE_FIT_TOLERANCE: f64 = 0.01;
+/// 椭圆识别时轮廓点的径向残差容限
+const ELLIPSE_RADIAL_TOLERANCE: f64 = 0.05;
+
+/// 无法从蓝图构建石片的原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StoneError {
+    /// 轮廓面积接近 0
+    ZeroArea,
+    /// 轮廓点太少，无法构成多边形
+    TooFewPoints,
+    /// 俯仰转动惯量相对石片尺寸接近 0 (细线状轮廓)，受力矩时会任意翻滚
+    DegenerateInertia,
+}
+
+impl std::fmt::Display for StoneError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let msg = match self {
+            StoneError::ZeroArea => "Stone area is near zero.",
+            StoneError::TooFewPoints => "Outline needs at least 3 points.",
+            StoneError::DegenerateInertia => "Stone is too thin: pitch inertia is near zero.",
+        };
+        write!(f, "{}", msg)
+    }
+}
+
+/// 自相交次数达到该值时编辑器给出警告 (仅提示，仍然可以构建和模拟)
+pub const SELF_INTERSECTION_LIMIT: usize = 2;
+
+/// 俯仰回转半径 sqrt(Ix / m) 与特征尺寸 sqrt(面积) 之比的下限
+/// 细长比约 1000:1 的细条在此以下；正常石片在 0.1 以上
+pub const MIN_GYRATION_RATIO: f64 = 0.01;
+
+/// 预览界面导出物理属性报告的文件 (相对于工作目录)
+pub const PROPERTIES_FILE: &str = "stone_properties.json";
+/// 预览界面导出轮廓 SVG 的文件 (相对于工作目录)
+pub const SVG_FILE: &str = "stone.svg";
 
 /// 石片物理属性
 #[derive(Clone)]
@@ -13,10 +57,19 @@ pub struct StoneProperties {
     pub n: usize,// 碰撞点云总点数
     /// 石片总质量 (kg)
     pub mass: f64,
+    /// 石片厚度 (m)，来自蓝图
+    pub thickness: f64,
+
+    /// 石片转动惯量 (I)，薄板近似 (忽略厚度方向的贡献)
+    /// 轮廓点 (x, y) 位于板面内，板面法线即自转轴
+    pub inertia_tensor_x: f64,// 俯仰/翻滚：绕板面内 x 轴，Σ m y²
+    pub inertia_tensor_y: f64,// 自转：绕板面法线，Σ m (x² + y²)
 
-    /// 石片转动惯量 (I)
-    pub inertia_tensor_x: f64,// 方向垂直纸面
-    pub inertia_tensor_y: f64,// 自转转动惯量
+    /// 板面内完整的二阶矩张量与其主轴
+    /// 模拟仍按绘图坐标轴使用上面两个标量 (忽略交叉项)；主轴偏离 x 轴越大，该近似越粗
+    pub inertia_tensor: InertiaTensor2D,
+    pub principal_moments: (f64, f64), // 主惯量 (小, 大)
+    pub principal_angle: f64,          // 小主惯量轴 (长轴方向) 与 x 轴的夹角 (rad)
 
     /// 质心坐标系下的轮廓点 (m)
     pub outline_com: Vec<Vector2D>,
@@ -25,6 +78,11 @@ pub struct StoneProperties {
     pub collision_mesh_com: Vec<Vector2D>,
 
     pub d_max: f64,// 距离质心最远点(用于快速判断是否进水)
+
+    /// 轮廓面积 (m^2)
+    pub area: f64,
+    /// 质心在蓝图坐标系中的位置 (m)
+    pub centroid: Vector2D,
 }
 
 impl StoneProperties {
@@ -33,51 +91,167 @@ impl StoneProperties {
     /// 3. 将轮廓平移到质心系
     /// 4. 生成质心系下的碰撞点云
     /// 5. 计算转动惯量
-    pub fn new(blueprint: &StoneBlueprint) -> Self {
+    ///
+    /// `rng` 用于碰撞网格的随机偏移，相同种子得到相同的石片属性
+    /// 退化的轮廓 (点太少、面积为零、过细) 返回 StoneError；自相交只在编辑器中警告，不阻止构建
+    pub fn new(blueprint: &StoneBlueprint, rng: &mut Rng) -> Result<Self, StoneError> {
+        Self::with_mesh_points(blueprint, DEFAULT_MESH_POINTS, rng)
+    }
+
+    /// 同 new，但指定碰撞网格的目标采样点数 (见 DEFAULT_MESH_POINTS 关于精度与速度的说明)
+    pub fn with_mesh_points(blueprint: &StoneBlueprint, mesh_points: usize, rng: &mut Rng) -> Result<Self, StoneError> {
+        Self::build(&blueprint.points, blueprint.thickness, DENSITY_SLATE, blueprint.density_fn, mesh_points, rng)
+    }
+
+    /// 不经过蓝图，直接由轮廓点 (m)、厚度 (m) 和均匀密度 (kg/m^3) 构建
+    /// 碰撞网格使用固定种子，相同输入得到相同结果
+    pub fn from_outline(points: &[Vector2D], thickness: f64, density: f64) -> Result<Self, StoneError> {
+        Self::build(points, thickness, density, None, DEFAULT_MESH_POINTS, &mut Rng::new(DEFAULT_SEED))
+    }
+
+    /// 石片总质量 (kg)
+    pub fn mass(&self) -> f64 {
+        self.mass
+    }
+
+    /// 绕 z 轴 (板面法线，即自转轴) 的转动惯量 inertia_tensor_y (kg m^2)，Σ m (x² + y²)
+    pub fn moment_z(&self) -> f64 {
+        self.inertia_tensor_y
+    }
+
+    /// 物理属性报告 (预览面板逐行显示)；d_max 内部存的是距离平方，这里换算成距离
+    pub fn report_lines(&self) -> Vec<String> {
+        vec![
+            format!("mass: {:.4} kg", self.mass),
+            format!("area: {:.2} cm^2", self.area * 1.0e4),
+            format!("thickness: {:.2} cm", self.thickness * 100.0),
+            format!("centroid: ({:.4}, {:.4}) m", self.centroid.x, self.centroid.y),
+            format!("I pitch (x): {:.3e} kg m^2", self.inertia_tensor_x),
+            format!("I spin (y): {:.3e} kg m^2", self.inertia_tensor_y),
+            format!("I principal: {:.3e} / {:.3e} kg m^2", self.principal_moments.0, self.principal_moments.1),
+            format!("principal axis: {:.1} deg", self.principal_angle.to_degrees()),
+            format!("d_max: {:.4} m", self.d_max.sqrt()),
+            format!("collision points n: {}", self.n),
+        ]
+    }
+
+    /// 物理属性的 JSON 文本 (SI 单位)
+    pub fn report_json(&self) -> String {
+        let fields = [
+            format!("\"mass\": {}", self.mass),
+            format!("\"area\": {}", self.area),
+            format!("\"thickness\": {}", self.thickness),
+            format!("\"centroid\": [{}, {}]", self.centroid.x, self.centroid.y),
+            format!("\"inertia_tensor_x\": {}", self.inertia_tensor_x),
+            format!("\"inertia_tensor_y\": {}", self.inertia_tensor_y),
+            format!("\"inertia_tensor\": [[{}, {}], [{}, {}]]",
+                    self.inertia_tensor.xx, self.inertia_tensor.xy, self.inertia_tensor.xy, self.inertia_tensor.yy),
+            format!("\"principal_moments\": [{}, {}]", self.principal_moments.0, self.principal_moments.1),
+            format!("\"principal_angle\": {}", self.principal_angle),
+            format!("\"d_max\": {}", self.d_max.sqrt()),
+            format!("\"n\": {}", self.n),
+        ];
+        format!("{{\n  {}\n}}\n", fields.join(",\n  "))
+    }
+
+    /// 导出报告到文件
+    pub fn export_report(&self, path: &str) -> Result<(), String> {
+        std::fs::write(path, self.report_json()).map_err(|e| format!("Cannot write {}: {}", path, e))
+    }
+
+    // `density` 为均匀密度；给出 density_fn 时质量和惯量改用径向密度分布
+    fn build(points: &[Vector2D],
+             thickness: f64,
+             density: f64,
+             density_fn: Option<fn(f64) -> f64>,
+             mesh_points: usize,
+             rng: &mut Rng) -> Result<Self, StoneError> {
+        // 0. 检查轮廓是否有效 (闭合轮廓的首尾重复点不计入)
+        let is_closed = points.len() > 1
+            && points.first().map(|p| (p.x, p.y)) == points.last().map(|p| (p.x, p.y));
+        let distinct_points = if is_closed { points.len() - 1 } else { points.len() };
+        if distinct_points < 3 {
+            return Err(StoneError::TooFewPoints);
+        }
+
         // 1. 计算面积
-        let area = calculate_polygon_area(&blueprint.points);
+        let area = polygon_area(points);
         if area.abs() < 1e-9 {
-            println!("Warning: Stone area is near zero.");
-            return Self::default(); // 返回一个空/默认的石头
+            return Err(StoneError::ZeroArea);
         }
 
         // 2. 计算质心
-        let centroid = calculate_centroid(&blueprint.points);
+        let mut centroid = polygon_centroid(points);
 
         // 3. 计算质量
-        let mass = area.abs() * blueprint.thickness * DENSITY_SLATE;
+        let mut mass = area.abs() * thickness * density;
 
         // 4. 将轮廓平移到质心系
-        let outline_com: Vec<Vector2D> = blueprint.points.iter()
+        let mut outline_com: Vec<Vector2D> = points.iter()
             .map(|p| *p - centroid)
             .collect();
 
         // 5. 生成质心系下的碰撞点云
         //    (我们在已经平移的轮廓内采样)
-        let collision_mesh_com = generate_collision_mesh(&outline_com, COLLISION_MESH_POINTS, area);
+        let mut collision_mesh_com = generate_collision_mesh(&outline_com, mesh_points.max(1), area, rng);
 
         // 6. 计算转动惯量
-        let inertia_tensor_x = calculate_inertia_z(&collision_mesh_com, mass);
-        let inertia_tensor_y = calculate_inertia_y(&collision_mesh_com, mass);
+        let (inertia_tensor_x, inertia_tensor_y, inertia_tensor) = match density_fn {
+            // 非均匀密度：按点加权，质心移到加权平均位置
+            Some(density_fn) => {
+                let volume = area.abs() * thickness;
+                let weighted = calculate_weighted_properties(&collision_mesh_com, volume, density_fn);
+                mass = weighted.mass;
+                for p in outline_com.iter_mut().chain(collision_mesh_com.iter_mut()) {
+                    *p = *p - weighted.com_shift;
+                }
+                centroid = centroid + weighted.com_shift;
+                (weighted.inertia_x, weighted.inertia_y, weighted.tensor)
+            }
+            // 均匀密度：矩形/椭圆直接使用解析解 (轴对齐，无交叉项)，跳过点云求和
+            None => match detect_analytic_shape(&outline_com, area) {
+                Some(shape) => {
+                    let (ix, iy) = shape.inertia(mass);
+                    (ix, iy, InertiaTensor2D { xx: ix, yy: iy - ix, xy: 0.0 })
+                }
+                None => (
+                    calculate_pitch_inertia(&collision_mesh_com, mass),
+                    calculate_spin_inertia(&collision_mesh_com, mass),
+                    calculate_moment_tensor(&collision_mesh_com, mass),
+                ),
+            },
+        };
+
+        // 6.1 惯量退化检查：过小的俯仰惯量会让任意小的力矩产生巨大的角加速度
+        // (只返回错误，由调用方显示，与 ZeroArea 等相同)
+        let gyration_ratio = (inertia_tensor_x / mass).sqrt() / area.abs().sqrt();
+        if gyration_ratio.is_nan() || gyration_ratio < MIN_GYRATION_RATIO {
+            return Err(StoneError::DegenerateInertia);
+        }
 
         let n = collision_mesh_com.len();
 
         let mut d_max = 0.0;
         for point in & collision_mesh_com{
             if point.x * point.x + point.y * point.y >= d_max {d_max = point.x * point.x + point.y * point.y}
-            else{d_max = d_max}
         }
 
         // 7. 返回最终的物理对象
-        Self {
+        Ok(Self {
             n,// 碰撞点云总点数
             mass,// 质量
-            inertia_tensor_x,// 垂直纸面转动惯量
+            thickness,// 厚度
+            inertia_tensor_x,// 俯仰转动惯量
             inertia_tensor_y,// 石片自旋转动惯量
+            inertia_tensor,// 板面内二阶矩张量
+            principal_moments: inertia_tensor.principal_moments(),
+            principal_angle: inertia_tensor.principal_angle(),
             outline_com,// 质心系下石片边界
             collision_mesh_com,// 质心系下所有碰撞点
             d_max,// 距离质心最远点(用于快速判断是否进水)
-        }
+            area,// 轮廓面积
+            centroid,// 蓝图坐标系中的质心
+        })
     }
 }
 
@@ -87,11 +261,17 @@ impl Default for StoneProperties {
         Self {
             n: 0,
             mass: 0.0,
+            thickness: 0.0,
             inertia_tensor_x: 0.0,
             inertia_tensor_y: 0.0,
+            inertia_tensor: InertiaTensor2D::ZERO,
+            principal_moments: (0.0, 0.0),
+            principal_angle: 0.0,
             outline_com: vec![],
             collision_mesh_com: vec![],
             d_max: 0.0,
+            area: 0.0,
+            centroid: Vector2D::new(0.0, 0.0),
         }
     }
 }
@@ -99,57 +279,97 @@ impl Default for StoneProperties {
 
 // --- 几何与物理计算辅助函数 ---
 
-/// 计算多边形面积
-/// 使用 Shoelace (鞋带) 公式
-fn calculate_polygon_area(polygon: &[Vector2D]) -> f64 {
-    if polygon.len() < 3 { return 0.0; }
-
-    let mut area = 0.0;
-    let n = polygon.len();
-    for i in 0..n {
-        let p1 = polygon[i];
-        let p2 = polygon[(i + 1) % n]; // 环绕到第一个点
-        area += p1.x * p2.y - p2.x * p1.y;
+/// 统一为逆时针顶点顺序 (顺时针时反转)
+/// 压力中心、浮心和裁剪结果都假定一致的方向
+pub(crate) fn ensure_ccw(points: &mut [Vector2D]) {
+    if signed_polygon_area(points) < 0.0 {
+        points.reverse();
     }
-
-    // 面积可为负 (取决于顶点顺序), 取 0.5 * 绝对值
-    (area / 2.0).abs()
 }
 
-/// 计算多边形质心 (解析法)
-fn calculate_centroid(polygon: &[Vector2D]) -> Vector2D {
-    let mut centroid_x = 0.0;
-    let mut centroid_y = 0.0;
-    let n = polygon.len();
+/// 轮廓形状评估 (编辑器预览中的提示，不影响模拟)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ShapeReport {
+    /// 面积 (m^2)
+    pub area: f64,
+    /// AABB 长边 / 短边
+    pub aspect_ratio: f64,
+    /// 最远点距离 (d_max) / 轮廓点到质心的平均距离
+    pub radius_ratio: f64,
+}
 
-    // (这个公式要求面积是"有符号"的)
-    let mut signed_area_x2 = 0.0; // 2 * A
+impl ShapeReport {
+    /// 由轮廓计算；退化轮廓返回 None
+    pub fn from_outline(points: &[Vector2D]) -> Option<Self> {
+        let area = polygon_area(points);
+        if points.len() < 3 || area < 1e-12 { return None; }
 
-    for i in 0..n {
-        let p1 = polygon[i];
-        let p2 = polygon[(i + 1) % n];
+        let (min, max) = find_aabb(points);
+        let (w, h) = (max.x - min.x, max.y - min.y);
+        if w.min(h) < 1e-12 { return None; }
 
-        let cross_product = p1.x * p2.y - p2.x * p1.y;
-        signed_area_x2 += cross_product;
+        let centroid = polygon_centroid(points);
+        let distances: Vec<f64> = points.iter().map(|p| (*p - centroid).length()).collect();
+        let mean = distances.iter().sum::<f64>() / distances.len() as f64;
+        let d_max = distances.iter().cloned().fold(0.0, f64::max);
 
-        centroid_x += (p1.x + p2.x) * cross_product;
-        centroid_y += (p1.y + p2.y) * cross_product;
+        Some(Self {
+            area,
+            aspect_ratio: w.max(h) / w.min(h),
+            radius_ratio: if mean > 1e-12 { d_max / mean } else { 1.0 },
+        })
     }
 
-    if signed_area_x2.abs() < 1e-9 {
-        // 如果面积为0 (例如一条直线), 就返回几何中心
-        let mut sum_x = 0.0;
-        let mut sum_y = 0.0;
-        for p in polygon {
-            sum_x += p.x;
-            sum_y += p.y;
+    /// 一句话评价
+    pub fn verdict(&self) -> &'static str {
+        if self.radius_ratio > 1.6 {
+            "irregular & spiky - unstable on impact"
+        } else if self.aspect_ratio >= 2.0 {
+            "flat & wide - good for skipping"
+        } else if self.aspect_ratio < 1.3 {
+            "too round - will dig in and sink"
+        } else {
+            "moderately flat - may skip a few times"
         }
-        return Vector2D::new(sum_x / n as f64, sum_y / n as f64);
     }
+}
+
+/// SVG 导出时轮廓四周留出的边距 (mm)
+const SVG_MARGIN_MM: f64 = 5.0;
+
+/// 轮廓的 SVG 文本：单位为毫米 (1 个 viewBox 单位 = 1 mm)，y 轴翻转为 SVG 的向下方向，
+/// 轮廓为一个 <polygon>，质心用十字标出，厚度写在 <desc> 中
+pub(crate) fn outline_svg(points: &[Vector2D], thickness: f64) -> String {
+    let mm = |p: Vector2D| (p.x * 1000.0, -p.y * 1000.0);
+    let (min, max) = find_aabb(points);
+    let (left, top) = (min.x * 1000.0 - SVG_MARGIN_MM, -max.y * 1000.0 - SVG_MARGIN_MM);
+    let width = (max.x - min.x) * 1000.0 + 2.0 * SVG_MARGIN_MM;
+    let height = (max.y - min.y) * 1000.0 + 2.0 * SVG_MARGIN_MM;
+
+    let coords: Vec<String> = points.iter()
+        .map(|p| { let (x, y) = mm(*p); format!("{:.3},{:.3}", x, y) })
+        .collect();
+    let (cx, cy) = mm(polygon_centroid(points));
+    let arm = 0.05 * width.min(height);
 
-    // (除以 6 * A)
-    let factor = 1.0 / (3.0 * signed_area_x2);
-    Vector2D::new(centroid_x * factor, centroid_y * factor)
+    let mut svg = String::new();
+    svg += "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n";
+    svg += &format!("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{:.3}mm\" height=\"{:.3}mm\" viewBox=\"{:.3} {:.3} {:.3} {:.3}\">\n",
+                    width, height, left, top, width, height);
+    svg += &format!("  <desc>stone outline, thickness {:.2} mm</desc>\n", thickness * 1000.0);
+    svg += &format!("  <polygon points=\"{}\" fill=\"none\" stroke=\"black\" stroke-width=\"0.2\"/>\n", coords.join(" "));
+    svg += &format!("  <path d=\"M {:.3} {:.3} H {:.3} M {:.3} {:.3} V {:.3}\" stroke=\"red\" stroke-width=\"0.2\"/>\n",
+                    cx - arm, cy, cx + arm, cx, cy - arm, cy + arm);
+    svg += "</svg>\n";
+    svg
+}
+
+/// 导出轮廓到 SVG 文件 (文档配图、激光切割实物模型)
+pub fn export_svg(points: &[Vector2D], thickness: f64, path: &str) -> Result<(), String> {
+    if points.len() < 3 {
+        return Err(format!("Cannot write {}: outline has too few points", path));
+    }
+    std::fs::write(path, outline_svg(points, thickness)).map_err(|e| format!("Cannot write {}: {}", path, e))
 }
 
 /// 寻找 AABB
@@ -169,8 +389,170 @@ fn find_aabb(polygon: &[Vector2D]) -> (Vector2D, Vector2D) {
     (Vector2D::new(min_x, min_y), Vector2D::new(max_x, max_y))
 }
 
+/// 查找自相交的线段对
+/// `points` 为首尾相同的闭合点列，线段 i 为 points[i] -> points[i+1]
+/// 返回相交的线段下标对 (i, j)，i < j；相邻线段及首尾线段不计入
+pub(crate) fn find_self_intersections(points: &[Vector2D]) -> Vec<(usize, usize)> {
+    let mut intersections = Vec::new();
+    if points.len() < 4 { return intersections; }
+    let num_segments = points.len() - 1;
+
+    for i in 0..num_segments {
+        let p1 = points[i];
+        let p2 = points[i + 1];
+
+        for j in (i + 2)..num_segments {
+            if i == 0 && j == num_segments - 1 {
+                continue;
+            }
+            let p3 = points[j];
+            let p4 = points[j + 1];
+            if line_segments_intersect(p1, p2, p3, p4) {
+                intersections.push((i, j));
+            }
+        }
+    }
+    intersections
+}
+
+/// 修复自相交的闭合轮廓，得到简单多边形
+/// 每次在第一处交叉点把轮廓拆成两个环，保留面积较大的一个，直到不再自相交
+/// 每次拆分都严格减少顶点数，因此一定终止；返回的点列同样首尾相同
+pub(crate) fn repair_polygon(points: &[Vector2D]) -> Vec<Vector2D> {
+    let mut current = points.to_vec();
+    while let Some(&(i, j)) = find_self_intersections(&current).first() {
+        // 1. 交点 X 位于线段 i 与线段 j 上
+        let x = segment_intersection_point(current[i], current[i + 1], current[j], current[j + 1]);
+
+        // 2. 环 A: X -> i+1 .. j -> X；环 B: 0 .. i -> X -> j+1 .. 末尾
+        let mut inner = vec![x];
+        inner.extend_from_slice(&current[i + 1..=j]);
+        inner.push(x);
+        let mut outer = current[..=i].to_vec();
+        outer.push(x);
+        outer.extend_from_slice(&current[j + 1..]);
+
+        // 3. 保留面积较大的环 (去掉交点处产生的重复点)
+        current = if polygon_area(&inner) > polygon_area(&outer) { inner } else { outer };
+        current.dedup_by(|a, b| (*a - *b).length() < 1e-12);
+        if current.len() < 4 { break; }
+    }
+    current
+}
+
+/// 两条相交线段的交点；共线重叠时取落在另一条线段上的端点
+fn segment_intersection_point(a: Vector2D, b: Vector2D, c: Vector2D, d: Vector2D) -> Vector2D {
+    let (r, s) = (b - a, d - c);
+    let denom = r.x * s.y - r.y * s.x;
+    if denom.abs() > 1e-15 {
+        let t = ((c.x - a.x) * s.y - (c.y - a.y) * s.x) / denom;
+        return a + r * t;
+    }
+    if on_segment(a, c, b) { c } else if on_segment(a, d, b) { d } else { a }
+}
+
+/// 检查线段相交
+fn line_segments_intersect(a: Vector2D, b: Vector2D, c: Vector2D, d: Vector2D) -> bool {
+    let o1 = orientation(a, b, c);
+    let o2 = orientation(a, b, d);
+    let o3 = orientation(c, d, a);
+    let o4 = orientation(c, d, b);
+    if o1 != o2 && o3 != o4 { return true; }
+    if o1 == 0 && on_segment(a, c, b) { return true; }
+    if o2 == 0 && on_segment(a, d, b) { return true; }
+    if o3 == 0 && on_segment(c, a, d) { return true; }
+    if o4 == 0 && on_segment(c, b, d) { return true; }
+    false
+}
+
+/// 几何方向
+fn orientation(p: Vector2D, q: Vector2D, r: Vector2D) -> i8 {
+    let val = (q.y - p.y) * (r.x - q.x) - (q.x - p.x) * (r.y - q.y);
+    if val.abs() < 1e-10 { return 0; }
+    if val > 0.0 { 1 } else { 2 }
+}
+
+/// 检查点是否在线段上
+fn on_segment(p: Vector2D, q: Vector2D, r: Vector2D) -> bool {
+    q.x <= f64::max(p.x, r.x) && q.x >= f64::min(p.x, r.x) &&
+        q.y <= f64::max(p.y, r.y) && q.y >= f64::min(p.y, r.y)
+}
+
+/// 耳切法 (Ear Clipping) 三角剖分简单多边形 (可以是凹多边形)
+/// 返回三角形的顶点下标；闭合点列末尾的重复点会被忽略
+/// 自相交或退化的多边形会尽量剖分，剩余部分直接放弃
+pub(crate) fn triangulate_polygon(polygon: &[Vector2D]) -> Vec<[usize; 3]> {
+    let mut n = polygon.len();
+    if n > 1 && polygon[0].x == polygon[n - 1].x && polygon[0].y == polygon[n - 1].y {
+        n -= 1;
+    }
+    let mut triangles = Vec::new();
+    if n < 3 { return triangles; }
+
+    // 统一按逆时针处理
+    let mut remaining: Vec<usize> = (0..n).collect();
+    if signed_polygon_area(&polygon[..n]) < 0.0 { remaining.reverse(); }
+
+    let cross = |a: Vector2D, b: Vector2D, c: Vector2D| (b.x - a.x) * (c.y - a.y) - (b.y - a.y) * (c.x - a.x);
+
+    while remaining.len() > 3 {
+        let m = remaining.len();
+        let ear = (0..m).find(|&i| {
+            let (ia, ib, ic) = (remaining[(i + m - 1) % m], remaining[i], remaining[(i + 1) % m]);
+            let (a, b, c) = (polygon[ia], polygon[ib], polygon[ic]);
+
+            // 1. 必须是凸顶点
+            if cross(a, b, c) <= 1e-15 { return false; }
+
+            // 2. 三角形内不能包含其他顶点
+            !remaining.iter().any(|&j| {
+                if j == ia || j == ib || j == ic { return false; }
+                let p = polygon[j];
+                cross(a, b, p) >= 0.0 && cross(b, c, p) >= 0.0 && cross(c, a, p) >= 0.0
+            })
+        });
+
+        // 找不到耳朵 (自相交或数值退化)：停止
+        let Some(i) = ear else { break; };
+        triangles.push([remaining[(i + m - 1) % m], remaining[i], remaining[(i + 1) % m]]);
+        remaining.remove(i);
+    }
+
+    if remaining.len() == 3 {
+        triangles.push([remaining[0], remaining[1], remaining[2]]);
+    }
+    triangles
+}
+
+/// 点到边的距离不超过这个值 (m) 时视为落在边界上
+const ON_EDGE_TOLERANCE: f64 = 1e-12;
+
+/// 点是否落在线段 ab 上 (容差 ON_EDGE_TOLERANCE)
+fn is_point_on_segment(point: Vector2D, a: Vector2D, b: Vector2D) -> bool {
+    let ab = b - a;
+    let ap = point - a;
+    let len_sq = ab.length_squared();
+    if len_sq < ON_EDGE_TOLERANCE * ON_EDGE_TOLERANCE {
+        return ap.length() <= ON_EDGE_TOLERANCE;
+    }
+    // 到直线的距离与投影位置
+    let cross = ab.x * ap.y - ab.y * ap.x;
+    let s = ap.dot(ab) / len_sq;
+    cross.abs() <= ON_EDGE_TOLERANCE * len_sq.sqrt() && (0.0..=1.0).contains(&s)
+}
+
 /// 射线法 (Ray Casting) 判断点是否在多边形内
+/// 落在边或顶点上的点一律算作在内：单纯的射线法对边界点的结果取决于边的方向，
+/// 网格点恰好落在边上时会让互为镜像的石片得到略有不同的质量与转动惯量
 fn is_point_in_polygon(point: Vector2D, polygon: &[Vector2D]) -> bool {
+    // 少于 3 个点不构成区域 (同时避免下面 n - 1 下溢)
+    if polygon.len() < 3 { return false; }
+
+    // 边界上的点 (含顶点)
+    if (0..polygon.len()).any(|i| is_point_on_segment(point, polygon[i], polygon[(i + 1) % polygon.len()])) {
+        return true;
+    }
+
     let mut is_inside = false;
     let n = polygon.len();
     let mut j = n - 1; // 最后一个顶点
@@ -192,91 +574,648 @@ fn is_point_in_polygon(point: Vector2D, polygon: &[Vector2D]) -> bool {
 }
 
 /// 生成碰撞点云
-/// 使用网格采样法 (Grid Sampling)
-fn generate_collision_mesh(polygon: &[Vector2D], num_points: usize, polygon_area: f64) -> Vec<Vector2D> {
-    if polygon.is_empty() || polygon_area.abs() < 1e-9 { return Vec::new(); }
+/// 使用网格采样法 (Grid Sampling)，网格整体随机偏移半个格距以内 (分层采样)
+/// 开启 `parallel` 特性时按行并行扫描；各行结果按行号顺序拼接，与串行结果逐位相同
+fn generate_collision_mesh(polygon: &[Vector2D], num_points: usize, polygon_area: f64, rng: &mut Rng) -> Vec<Vector2D> {
+    let Some(grid) = MeshGrid::new(polygon, num_points, polygon_area, rng) else { return Vec::new(); };
+    #[cfg(feature = "parallel")]
+    { grid.scan_parallel(polygon) }
+    #[cfg(not(feature = "parallel"))]
+    { grid.scan_serial(polygon) }
+}
+
+/// 碰撞点云的采样网格 (覆盖 AABB，原点带随机偏移)
+struct MeshGrid {
+    min: Vector2D,
+    max: Vector2D,
+    offset: Vector2D,
+    delta: f64,
+    num_rows: usize,
+    num_cols: usize,
+}
+
+impl MeshGrid {
+    fn new(polygon: &[Vector2D], num_points: usize, polygon_area: f64, rng: &mut Rng) -> Option<Self> {
+        if polygon.is_empty() || polygon_area.abs() < 1e-9 { return None; }
+
+        let (min, max) = find_aabb(polygon);
+        let aabb_width = max.x - min.x;
+        let aabb_height = max.y - min.y;
 
-    let (min, max) = find_aabb(polygon);
-    let aabb_width = max.x - min.x;
-    let aabb_height = max.y - min.y;
+        if aabb_width.abs() < 1e-9 || aabb_height.abs() < 1e-9 { return None; }
 
-    if aabb_width.abs() < 1e-9 || aabb_height.abs() < 1e-9 { return Vec::new(); }
+        // --- 网格计算 ---
+        // 1. 计算每个点代表的面积
+        let area_per_point = polygon_area / (num_points as f64);
+        // 2. 计算网格间距 (delta)
+        let delta = area_per_point.sqrt();
 
-    // --- 网格计算 ---
-    // 1. 计算每个点代表的面积
-    let area_per_point = polygon_area / (num_points as f64);
-    // 2. 计算网格间距 (delta)
-    let delta = area_per_point.sqrt();
+        // 3. 计算需要检查的行列数
+        let num_cols = (aabb_width / delta).ceil() as usize + 1;
+        let num_rows = (aabb_height / delta).ceil() as usize + 1;
 
-    // 3. 计算需要检查的行列数
-    let num_cols = (aabb_width / delta).ceil() as usize + 1;
-    let num_rows = (aabb_height / delta).ceil() as usize + 1;
+        // 网格原点的随机偏移 (由种子决定，可复现)
+        let offset_x = rng.range(0.0, 0.5 * delta);
+        let offset_y = rng.range(0.0, 0.5 * delta);
 
-    let mut mesh = Vec::new();
+        Some(Self { min, max, offset: Vector2D::new(offset_x, offset_y), delta, num_rows, num_cols })
+    }
 
-    // 4. 遍历网格
-    for i in 0..num_rows {
-        let y = min.y + i as f64 * delta;
+    // 4. 第 i 行落在多边形内的网格点
+    fn scan_row(&self, polygon: &[Vector2D], i: usize) -> Vec<Vector2D> {
+        let y = self.min.y + self.offset.y + i as f64 * self.delta;
         // 优化：如果整行都在AABB之外，则跳过
-        if y > max.y { continue; }
+        if y > self.max.y { return Vec::new(); }
 
-        for j in 0..num_cols {
-            let x = min.x + j as f64 * delta;
-            // 优化：如果点在AABB之外，则跳过
-            if x > max.x { continue; }
+        (0..self.num_cols)
+            .map(|j| Vector2D::new(self.min.x + self.offset.x + j as f64 * self.delta, y))
+            // 优化：如果点在AABB之外，则跳过；5. 检查网格点是否在多边形内
+            .filter(|point| point.x <= self.max.x && is_point_in_polygon(*point, polygon))
+            .collect()
+    }
 
-            let point = Vector2D::new(x, y);
+    #[cfg(any(not(feature = "parallel"), test))]
+    fn scan_serial(&self, polygon: &[Vector2D]) -> Vec<Vector2D> {
+        (0..self.num_rows).flat_map(|i| self.scan_row(polygon, i)).collect()
+    }
 
-            // 5. 检查网格点是否在多边形内
-            if is_point_in_polygon(point, polygon) {
-                mesh.push(point);
-            }
-        }
+    #[cfg(feature = "parallel")]
+    fn scan_parallel(&self, polygon: &[Vector2D]) -> Vec<Vector2D> {
+        use rayon::prelude::*;
+        let rows: Vec<Vec<Vector2D>> = (0..self.num_rows).into_par_iter().map(|i| self.scan_row(polygon, i)).collect();
+        rows.concat()
     }
+}
 
-    mesh
+/// 可用解析公式计算转动惯量的简单形状 (轴对齐, 质心系)
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum AnalyticShape {
+    Rectangle { w: f64, h: f64 },
+    Ellipse { a: f64, b: f64 },
 }
 
+impl AnalyticShape {
+    /// 返回 (inertia_tensor_x, inertia_tensor_y)，与点云求和的定义一致：
+    /// x 分量为 Σ m y² (俯仰)，y 分量为 Σ m (x² + y²) (自转)
+    fn inertia(&self, mass: f64) -> (f64, f64) {
+        match *self {
+            AnalyticShape::Rectangle { w, h } => (mass * h * h / 12.0, mass * (w * w + h * h) / 12.0),
+            AnalyticShape::Ellipse { a, b } => (mass * b * b / 4.0, mass * (a * a + b * b) / 4.0),
+        }
+    }
+}
+
+/// 识别轴对齐的矩形或椭圆
+/// `outline`: 质心系下的轮廓点, `area`: 轮廓面积
+fn detect_analytic_shape(outline: &[Vector2D], area: f64) -> Option<AnalyticShape> {
+    if outline.len() < 3 || area.abs() < 1e-9 { return None; }
 
-/// 计算转动惯量 (I_z)
+    let (min, max) = find_aabb(outline);
+    let w = max.x - min.x;
+    let h = max.y - min.y;
+    if w < 1e-9 || h < 1e-9 { return None; }
+
+    // AABB 中心必须与质心重合，否则不是对称形状
+    let center = Vector2D::new(0.5 * (min.x + max.x), 0.5 * (min.y + max.y));
+    if center.length() > SHAPE_FIT_TOLERANCE * w.max(h) { return None; }
+
+    // 矩形：多边形面积几乎填满 AABB
+    let box_area = w * h;
+    if area.abs() / box_area >= 1.0 - SHAPE_FIT_TOLERANCE {
+        return Some(AnalyticShape::Rectangle { w, h });
+    }
+
+    // 椭圆：面积接近 πab 且所有点都在椭圆上
+    let a = 0.5 * w;
+    let b = 0.5 * h;
+    let ellipse_area = std::f64::consts::PI * a * b;
+    if (area.abs() / ellipse_area - 1.0).abs() > SHAPE_FIT_TOLERANCE { return None; }
+
+    let on_ellipse = outline.iter().all(|p| {
+        let r = ((p.x - center.x) / a).powi(2) + ((p.y - center.y) / b).powi(2);
+        (r.sqrt() - 1.0).abs() < ELLIPSE_RADIAL_TOLERANCE
+    });
+    if on_ellipse {
+        Some(AnalyticShape::Ellipse { a, b })
+    } else {
+        None
+    }
+}
+
+/// 计算俯仰转动惯量 (inertia_tensor_x)
 ///
-/// 2D模拟中的转动惯量是绕 Z 轴 (垂直于平面) 的标量。
-/// I_z = Σ (m_i * r_i^2) = Σ m_i * (x_i^2 + y_i^2)
+/// 石片是位于 (x, y) 平面内的薄板，俯仰/翻滚绕板面内的 x 轴进行，
+/// 到该轴的距离只有 y 分量 (薄板近似忽略厚度)：
+/// I_x = Σ m_i * y_i^2
 ///
 /// `mesh_points`: 必须是质心坐标系下的点
 /// `total_mass`: 石片总质量
-fn calculate_inertia_z(mesh_points: &[Vector2D], total_mass: f64) -> f64 {
+fn calculate_pitch_inertia(mesh_points: &[Vector2D], total_mass: f64) -> f64 {
     let n = mesh_points.len();
     if n == 0 { return 0.0; }
 
     // 假设质量均匀分布, 每个采样点的质量
     let mass_per_point = total_mass / (n as f64);
 
-    let mut inertia_sum = 0.0;
-
-    for point in mesh_points {
-        let r_squared = point.x * point.x;
-        inertia_sum += r_squared; // 我们先把 Σ(r_i^2) 加起来
-    }
-
-    // I_z = Σ(m_i * r_i^2) = m_i * Σ(r_i^2)
+    let inertia_sum: f64 = mesh_points.iter().map(|p| p.y * p.y).sum();
     mass_per_point * inertia_sum
 }
 
-fn calculate_inertia_y(mesh_points: &[Vector2D], total_mass: f64) -> f64 {
+/// 计算自转转动惯量 (inertia_tensor_y)
+///
+/// 自转轴是板面法线，到它的距离是点到质心的平面距离 (极惯性矩)：
+/// I_spin = Σ m_i * (x_i^2 + y_i^2)
+fn calculate_spin_inertia(mesh_points: &[Vector2D], total_mass: f64) -> f64 {
     let n = mesh_points.len();
     if n == 0 { return 0.0; }
 
     // 假设质量均匀分布, 每个采样点的质量
     let mass_per_point = total_mass / (n as f64);
 
-    let mut inertia_sum = 0.0;
+    let inertia_sum: f64 = mesh_points.iter().map(|p| p.length_squared()).sum();
+    mass_per_point * inertia_sum
+}
+
+/// 板面内的二阶矩张量 (kg m^2)，质心系、绘图坐标轴：
+/// [[xx, xy], [xy, yy]] = [[Σ m y², -Σ m x y], [-Σ m x y, Σ m x²]]
+/// xx 即俯仰惯量，xx + yy 即自转惯量
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct InertiaTensor2D {
+    pub xx: f64,
+    pub yy: f64,
+    pub xy: f64,
+}
+
+impl InertiaTensor2D {
+    pub const ZERO: Self = Self { xx: 0.0, yy: 0.0, xy: 0.0 };
 
-    for point in mesh_points {
-        let r_squared = point.x * point.x + point.y * point.y;
-        inertia_sum += r_squared; // 我们先把 Σ(r_i^2) 加起来
+    /// 对角化后的主惯量 (小, 大)
+    pub fn principal_moments(&self) -> (f64, f64) {
+        let mean = 0.5 * (self.xx + self.yy);
+        let r = (0.25 * (self.xx - self.yy).powi(2) + self.xy * self.xy).sqrt();
+        (mean - r, mean + r)
     }
 
-    // I_z = Σ(m_i * r_i^2) = m_i * Σ(r_i^2)
-    mass_per_point * inertia_sum
-}
\ No newline at end of file
+    /// 小主惯量对应的主轴与 x 轴的夹角 (rad)，范围 (-π/2, π/2]
+    /// 细长石片的这条轴沿长度方向；轴对齐时为 0
+    pub fn principal_angle(&self) -> f64 {
+        use std::f64::consts::{FRAC_PI_2, PI};
+        // 大主惯量轴的方向，转 90° 得到小主惯量轴
+        let major = 0.5 * (2.0 * self.xy).atan2(self.xx - self.yy);
+        let angle = major + FRAC_PI_2;
+        if angle > FRAC_PI_2 { angle - PI } else { angle }
+    }
+}
+
+/// 由均匀分布的点云计算二阶矩张量
+fn calculate_moment_tensor(mesh_points: &[Vector2D], total_mass: f64) -> InertiaTensor2D {
+    let n = mesh_points.len();
+    if n == 0 { return InertiaTensor2D::ZERO; }
+
+    let mass_per_point = total_mass / (n as f64);
+    let (mut xx, mut yy, mut xy) = (0.0, 0.0, 0.0);
+    for p in mesh_points {
+        xx += p.y * p.y;
+        yy += p.x * p.x;
+        xy -= p.x * p.y;
+    }
+    InertiaTensor2D { xx: xx * mass_per_point, yy: yy * mass_per_point, xy: xy * mass_per_point }
+}
+
+/// 非均匀密度下由点云加权得到的物理量
+struct WeightedProperties {
+    mass: f64,
+    /// 加权质心相对几何质心的偏移
+    com_shift: Vector2D,
+    /// 绕加权质心的转动惯量
+    inertia_x: f64,
+    inertia_y: f64,
+    tensor: InertiaTensor2D,
+}
+
+/// 按径向密度 ρ(r) 对点云加权
+/// 每个采样点代表 volume / n 的体积，其质量为 ρ(r_i) * volume / n
+/// `mesh_points`: 几何质心坐标系下的点
+fn calculate_weighted_properties(mesh_points: &[Vector2D], volume: f64, density_fn: fn(f64) -> f64) -> WeightedProperties {
+    let n = mesh_points.len();
+    if n == 0 {
+        return WeightedProperties { mass: 0.0, com_shift: Vector2D::new(0.0, 0.0), inertia_x: 0.0, inertia_y: 0.0, tensor: InertiaTensor2D::ZERO };
+    }
+
+    let volume_per_point = volume / (n as f64);
+    let masses: Vec<f64> = mesh_points.iter()
+        .map(|p| density_fn(p.length()).max(0.0) * volume_per_point)
+        .collect();
+
+    // 1. 总质量与加权质心
+    let mass: f64 = masses.iter().sum();
+    if mass < 1e-12 {
+        return WeightedProperties { mass: 0.0, com_shift: Vector2D::new(0.0, 0.0), inertia_x: 0.0, inertia_y: 0.0, tensor: InertiaTensor2D::ZERO };
+    }
+    let mut com_shift = Vector2D::new(0.0, 0.0);
+    for (p, m) in mesh_points.iter().zip(&masses) {
+        com_shift = com_shift + *p * *m;
+    }
+    com_shift = com_shift * (1.0 / mass);
+
+    // 2. 绕加权质心的转动惯量 (定义与 calculate_pitch_inertia / calculate_spin_inertia 一致)
+    let mut inertia_x = 0.0;
+    let mut inertia_y = 0.0;
+    let mut tensor = InertiaTensor2D::ZERO;
+    for (p, m) in mesh_points.iter().zip(&masses) {
+        let r = *p - com_shift;
+        inertia_x += m * r.y * r.y;
+        inertia_y += m * (r.x * r.x + r.y * r.y);
+        tensor.yy += m * r.x * r.x;
+        tensor.xy -= m * r.x * r.y;
+    }
+    tensor.xx = inertia_x;
+
+    WeightedProperties { mass, com_shift, inertia_x, inertia_y, tensor }
+}
+
+/// 测试共用的石板：长 6 cm、高 height、以原点为中心的矩形 (逆时针)
+#[cfg(test)]
+pub(crate) fn slab_outline(height: f64) -> Vec<Vector2D> {
+    let (x, y) = (0.03, 0.5 * height);
+    vec![Vector2D::new(-x, -y), Vector2D::new(x, -y), Vector2D::new(x, y), Vector2D::new(-x, y)]
+}
+
+/// 测试共用的石板蓝图 (轮廓见 slab_outline，厚 1 cm，均匀密度)
+#[cfg(test)]
+pub(crate) fn slab_blueprint(height: f64, name: &str) -> StoneBlueprint {
+    StoneBlueprint { points: slab_outline(height), thickness: 0.01, name: name.to_string(), density_fn: None }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn boundary_points_count_as_inside() {
+        let square = [
+            Vector2D::new(0.0, 0.0),
+            Vector2D::new(1.0, 0.0),
+            Vector2D::new(1.0, 1.0),
+            Vector2D::new(0.0, 1.0),
+        ];
+        // 四条边 (射线法单独处理时左右、上下两侧的结果不一致) 与顶点
+        for p in [(0.5, 0.0), (1.0, 0.5), (0.5, 1.0), (0.0, 0.5), (1.0, 1.0), (0.0, 0.0)] {
+            assert!(is_point_in_polygon(Vector2D::new(p.0, p.1), &square), "{:?}", p);
+        }
+        // 紧贴边界的内外两侧
+        assert!(is_point_in_polygon(Vector2D::new(0.5, 1e-9), &square));
+        assert!(is_point_in_polygon(Vector2D::new(1.0 - 1e-9, 0.5), &square));
+        assert!(!is_point_in_polygon(Vector2D::new(0.5, -1e-9), &square));
+        assert!(!is_point_in_polygon(Vector2D::new(1.0 + 1e-9, 0.5), &square));
+        // 边的延长线上不算
+        assert!(!is_point_in_polygon(Vector2D::new(2.0, 0.0), &square));
+    }
+
+    #[test]
+    fn property_report_lists_mass_area_and_centroid() {
+        // 0.1 m x 0.04 m 的矩形，中心在 (0.3, 0.2)，厚 1 cm
+        let outline = vec![
+            Vector2D::new(0.25, 0.18),
+            Vector2D::new(0.35, 0.18),
+            Vector2D::new(0.35, 0.22),
+            Vector2D::new(0.25, 0.22),
+        ];
+        let props = StoneProperties::from_outline(&outline, 0.01, DENSITY_SLATE).unwrap();
+        assert!((props.area - 0.004).abs() < 1e-12);
+        assert!((props.centroid.x - 0.3).abs() < 1e-12);
+        assert!((props.centroid.y - 0.2).abs() < 1e-12);
+
+        let json = props.report_json();
+        assert!(json.contains(&format!("\"mass\": {}", props.mass)));
+        assert!(json.contains("\"centroid\": [0.3"));
+        assert!(json.contains(&format!("\"n\": {}", props.n)));
+        assert_eq!(props.report_lines().len(), 10);
+    }
+
+    #[test]
+    fn svg_export_lists_every_point_in_millimeters() {
+        // 0.1 m x 0.04 m 的矩形，中心在 (0.3, 0.2)
+        let outline = vec![
+            Vector2D::new(0.25, 0.18),
+            Vector2D::new(0.35, 0.18),
+            Vector2D::new(0.35, 0.22),
+            Vector2D::new(0.25, 0.22),
+        ];
+        let svg = outline_svg(&outline, 0.008);
+        assert!(svg.starts_with("<?xml"));
+        assert!(svg.trim_end().ends_with("</svg>"));
+        // 宽高含两侧 5 mm 边距，y 轴翻转
+        assert!(svg.contains("width=\"110.000mm\" height=\"50.000mm\""));
+        assert!(svg.contains("viewBox=\"245.000 -225.000 110.000 50.000\""));
+        assert!(svg.contains("thickness 8.00 mm"));
+
+        let start = svg.find("points=\"").unwrap() + "points=\"".len();
+        let list = &svg[start..start + svg[start..].find('"').unwrap()];
+        assert_eq!(list.split(' ').count(), outline.len());
+        assert!(list.starts_with("250.000,-180.000 "));
+        // 质心十字的中心在 (300, -200)
+        assert!(svg.contains("M 297.500 -200.000 H 302.500 M 300.000 -202.500 V -197.500"));
+        // 每个标签都已闭合
+        assert_eq!(svg.matches('<').count(), svg.matches('>').count());
+
+        assert!(export_svg(&outline[..2], 0.008, "/nonexistent/stone.svg").is_err());
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn parallel_mesh_matches_serial_scan() {
+        // 凹的 L 形，行内点数各不相同
+        let outline = vec![
+            Vector2D::new(0.0, 0.0),
+            Vector2D::new(0.06, 0.0),
+            Vector2D::new(0.06, 0.02),
+            Vector2D::new(0.02, 0.02),
+            Vector2D::new(0.02, 0.05),
+            Vector2D::new(0.0, 0.05),
+        ];
+        let area = polygon_area(&outline);
+        let grid = MeshGrid::new(&outline, DEFAULT_MESH_POINTS, area, &mut Rng::new(DEFAULT_SEED)).unwrap();
+        let (serial, parallel) = (grid.scan_serial(&outline), grid.scan_parallel(&outline));
+        assert_eq!(serial.len(), parallel.len());
+        // 惯量求和与顺序有关的舍入也一致：两者逐点相同
+        let inertia = |mesh: &[Vector2D]| mesh.iter().map(|p| p.length_squared()).sum::<f64>();
+        assert_eq!(inertia(&serial), inertia(&parallel));
+        assert!(serial.iter().zip(&parallel).all(|(a, b)| a.x == b.x && a.y == b.y));
+    }
+
+    fn square(side: f64) -> Vec<Vector2D> {
+        let half = 0.5 * side;
+        vec![
+            Vector2D::new(-half, -half),
+            Vector2D::new(half, -half),
+            Vector2D::new(half, half),
+            Vector2D::new(-half, half),
+        ]
+    }
+
+    #[test]
+    fn square_is_detected_as_rectangle() {
+        let outline = square(0.06);
+        let area = polygon_area(&outline);
+        assert_eq!(
+            detect_analytic_shape(&outline, area),
+            Some(AnalyticShape::Rectangle { w: 0.06, h: 0.06 })
+        );
+    }
+
+    #[test]
+    fn analytic_and_numeric_inertia_agree_for_square() {
+        let outline = square(0.06);
+        let area = polygon_area(&outline);
+        let mass = area * 0.01 * DENSITY_SLATE;
+
+        let mut rng = Rng::new(1);
+        let mesh = generate_collision_mesh(&outline, DEFAULT_MESH_POINTS, area, &mut rng);
+        let numeric = (calculate_pitch_inertia(&mesh, mass), calculate_spin_inertia(&mesh, mass));
+
+        let shape = detect_analytic_shape(&outline, area).unwrap();
+        let analytic = shape.inertia(mass);
+
+        assert!((numeric.0 - analytic.0).abs() / analytic.0 < 0.02);
+        assert!((numeric.1 - analytic.1).abs() / analytic.1 < 0.02);
+    }
+
+    #[test]
+    fn ear_clipping_covers_l_shape() {
+        // L 形 (凹多边形)，面积 = 2x2 - 1x1 = 3
+        let outline = vec![
+            Vector2D::new(0.0, 0.0),
+            Vector2D::new(2.0, 0.0),
+            Vector2D::new(2.0, 1.0),
+            Vector2D::new(1.0, 1.0),
+            Vector2D::new(1.0, 2.0),
+            Vector2D::new(0.0, 2.0),
+        ];
+
+        let triangles = triangulate_polygon(&outline);
+        assert_eq!(triangles.len(), outline.len() - 2);
+
+        let total: f64 = triangles.iter()
+            .map(|t| polygon_area(&[outline[t[0]], outline[t[1]], outline[t[2]]]))
+            .sum();
+        assert!((total - 3.0).abs() < 1e-12);
+
+        // 凹角 (1,1) 处的简单扇形剖分会覆盖 L 形外部，耳切法不会
+        for t in &triangles {
+            let c = (outline[t[0]] + outline[t[1]] + outline[t[2]]) * (1.0 / 3.0);
+            assert!(!(c.x > 1.0 && c.y > 1.0));
+        }
+    }
+
+    #[test]
+    fn inertia_axes_match_rectangle_moments() {
+        // 0.08 x 0.02 的长方形：俯仰惯量只取决于高度 h，自转惯量取决于 w 与 h
+        let (w, h) = (0.08, 0.02);
+        let outline = vec![
+            Vector2D::new(-0.5 * w, -0.5 * h),
+            Vector2D::new(0.5 * w, -0.5 * h),
+            Vector2D::new(0.5 * w, 0.5 * h),
+            Vector2D::new(-0.5 * w, 0.5 * h),
+        ];
+        let area = polygon_area(&outline);
+        let mass = area * 0.01 * DENSITY_SLATE;
+        let pitch = mass * h * h / 12.0;
+        let spin = mass * (w * w + h * h) / 12.0;
+
+        // 解析路径
+        let stone = StoneProperties::from_outline(&outline, 0.01, DENSITY_SLATE).unwrap();
+        assert!((stone.inertia_tensor_x - pitch).abs() < 1e-15);
+        assert!((stone.inertia_tensor_y - spin).abs() < 1e-15);
+
+        // 点云求和路径
+        let mut rng = Rng::new(2);
+        let mesh = generate_collision_mesh(&outline, DEFAULT_MESH_POINTS, area, &mut rng);
+        assert!((calculate_pitch_inertia(&mesh, mass) - pitch).abs() / pitch < 0.05);
+        assert!((calculate_spin_inertia(&mesh, mass) - spin).abs() / spin < 0.02);
+
+        // 均匀密度函数的加权路径
+        let weighted = calculate_weighted_properties(&mesh, area * 0.01, |_| DENSITY_SLATE);
+        assert!((weighted.inertia_x - pitch).abs() / pitch < 0.05);
+        assert!((weighted.inertia_y - spin).abs() / spin < 0.02);
+    }
+
+    #[test]
+    fn from_outline_uses_the_given_density() {
+        let stone = StoneProperties::from_outline(&square(0.1), 0.01, 1000.0).unwrap();
+        assert!((stone.mass() - 0.1).abs() < 1e-12);
+        // 矩形绕板面法线的惯量: m (w^2 + h^2) / 12
+        assert!((stone.moment_z() - 0.1 * 0.02 / 12.0).abs() < 1e-12);
+
+        assert_eq!(StoneProperties::from_outline(&square(0.1)[..2], 0.01, 1000.0).err(), Some(StoneError::TooFewPoints));
+    }
+
+    #[test]
+    fn shape_report_prefers_flat_stones() {
+        let flat = vec![
+            Vector2D::new(-0.04, -0.005),
+            Vector2D::new(0.04, -0.005),
+            Vector2D::new(0.04, 0.005),
+            Vector2D::new(-0.04, 0.005),
+        ];
+        let report = ShapeReport::from_outline(&flat).unwrap();
+        assert!((report.aspect_ratio - 8.0).abs() < 1e-9);
+        assert_eq!(report.verdict(), "flat & wide - good for skipping");
+
+        let round = ShapeReport::from_outline(&square(0.05)).unwrap();
+        assert_eq!(round.verdict(), "too round - will dig in and sink");
+    }
+
+    #[test]
+    fn clockwise_square_is_flipped_to_ccw() {
+        let mut outline = square(1.0);
+        outline.reverse();
+        assert!(signed_polygon_area(&outline) < 0.0);
+
+        ensure_ccw(&mut outline);
+        assert!((signed_polygon_area(&outline) - 1.0).abs() < 1e-12);
+
+        // 已经是逆时针的不变
+        let mut ccw = square(1.0);
+        ensure_ccw(&mut ccw);
+        assert_eq!(ccw, square(1.0));
+    }
+
+    #[test]
+    fn point_in_degenerate_polygon_is_outside() {
+        let point = Vector2D::new(0.0, 0.0);
+        let polygon = square(1.0);
+        for len in 0..3 {
+            assert!(!is_point_in_polygon(point, &polygon[..len]));
+        }
+    }
+
+    #[test]
+    fn sliver_is_rejected_for_degenerate_inertia() {
+        // 10 cm x 0.01 mm 的细条：面积足够，但俯仰惯量几乎为零
+        let sliver = vec![
+            Vector2D::new(-0.05, -0.000005),
+            Vector2D::new(0.05, -0.000005),
+            Vector2D::new(0.05, 0.000005),
+            Vector2D::new(-0.05, 0.000005),
+        ];
+        assert_eq!(StoneProperties::from_outline(&sliver, 0.01, 2700.0).err(),
+                   Some(StoneError::DegenerateInertia));
+        assert!(StoneProperties::from_outline(&square(0.06), 0.01, 2700.0).is_ok());
+    }
+
+    #[test]
+    fn pitch_inertia_converges_with_mesh_points() {
+        // 直角三角形 (数值路径)：绕质心水平轴 Σ m y² = m h² / 18
+        let h = 0.04;
+        let blueprint = StoneBlueprint {
+            points: vec![Vector2D::new(0.0, 0.0), Vector2D::new(0.06, 0.0), Vector2D::new(0.0, h)],
+            thickness: 0.01,
+            name: "triangle".to_string(),
+            density_fn: None,
+        };
+        let error = |mesh_points| {
+            let stone = StoneProperties::with_mesh_points(&blueprint, mesh_points, &mut Rng::new(3)).unwrap();
+            let analytic = stone.mass * h * h / 18.0;
+            (stone.inertia_tensor_x / analytic - 1.0).abs()
+        };
+        let (coarse, fine) = (error(200), error(DEFAULT_MESH_POINTS));
+        assert!(fine < coarse, "coarse {} fine {}", coarse, fine);
+        assert!(fine < 0.01, "fine {}", fine);
+    }
+
+    #[test]
+    fn triangle_uses_numeric_path() {
+        let outline = vec![
+            Vector2D::new(-0.03, -0.02),
+            Vector2D::new(0.03, -0.02),
+            Vector2D::new(0.0, 0.04),
+        ];
+        let area = polygon_area(&outline);
+        assert_eq!(detect_analytic_shape(&outline, area), None);
+    }
+
+    #[test]
+    fn mass_properties_are_deterministic_for_a_seed() {
+        // 三角形走点云求和，非均匀密度时质量也来自点云：两者都依赖网格的随机偏移
+        let blueprint = StoneBlueprint {
+            points: vec![Vector2D::new(-0.03, -0.02), Vector2D::new(0.03, -0.02), Vector2D::new(0.0, 0.04)],
+            thickness: 0.01,
+            name: "seeded".to_string(),
+            density_fn: Some(|r| 2700.0 - 10_000.0 * r),
+        };
+        let build = |seed: u64| StoneProperties::with_mesh_points(&blueprint, 5_000, &mut Rng::new(seed)).unwrap();
+        let key = |s: &StoneProperties| (s.mass, s.inertia_tensor_x, s.inertia_tensor_y, s.centroid);
+
+        // 相同种子逐位相同
+        let (a, b) = (build(7), build(7));
+        assert_eq!(key(&a), key(&b));
+        assert_eq!(a.collision_mesh_com, b.collision_mesh_com);
+
+        // 不同种子的网格不同，但只差采样误差
+        let c = build(8);
+        assert_ne!(a.collision_mesh_com, c.collision_mesh_com);
+        assert!((a.mass / c.mass - 1.0).abs() < 0.01);
+        assert!((a.inertia_tensor_x / c.inertia_tensor_x - 1.0).abs() < 0.02);
+    }
+
+    #[test]
+    fn principal_axes_follow_a_rotated_rectangle() {
+        // 0.08 x 0.02 的长方形绕质心转 30°：长轴方向即小主惯量轴
+        let (w, h) = (0.08, 0.02);
+        let angle = 30f64.to_radians();
+        let (sin, cos) = angle.sin_cos();
+        let corners = [(-0.5 * w, -0.5 * h), (0.5 * w, -0.5 * h), (0.5 * w, 0.5 * h), (-0.5 * w, 0.5 * h)];
+        let rotated: Vec<Vector2D> = corners.iter()
+            .map(|&(x, y)| Vector2D::new(x * cos - y * sin, x * sin + y * cos))
+            .collect();
+        let stone = StoneProperties::from_outline(&rotated, 0.01, DENSITY_SLATE).unwrap();
+        let (minor, major) = stone.principal_moments;
+        assert!((minor - stone.mass * h * h / 12.0).abs() / minor < 0.05);
+        assert!((major - stone.mass * w * w / 12.0).abs() / major < 0.05);
+        assert!((stone.principal_angle - angle).abs() < 1f64.to_radians());
+        // 主惯量之和与坐标轴无关，等于自转惯量
+        assert!((minor + major - stone.inertia_tensor_y).abs() < 1e-12 * stone.inertia_tensor_y.max(1.0));
+
+        // 轴对齐时没有交叉项，主轴就是 x 轴
+        let aligned: Vec<Vector2D> = corners.iter().map(|&(x, y)| Vector2D::new(x, y)).collect();
+        let stone = StoneProperties::from_outline(&aligned, 0.01, DENSITY_SLATE).unwrap();
+        assert_eq!(stone.principal_angle, 0.0);
+        assert!((stone.principal_moments.0 - stone.inertia_tensor_x).abs() < 1e-15);
+    }
+
+    #[test]
+    fn repair_keeps_the_largest_simple_loop() {
+        let v = |x: f64, y: f64| Vector2D::new(x, y);
+        // 2 x 2 的方块，上边绕出一个 0.5 x 0.5 的小环后穿回自身
+        let knotted = vec![
+            v(0.0, 0.0), v(2.0, 0.0), v(2.0, 2.0), v(0.5, 2.0),
+            v(0.5, 2.5), v(1.0, 2.5), v(1.0, 1.8), v(0.0, 1.8), v(0.0, 0.0),
+        ];
+        assert!(!find_self_intersections(&knotted).is_empty());
+        let repaired = repair_polygon(&knotted);
+        assert!(find_self_intersections(&repaired).is_empty());
+        assert_eq!(repaired.first().map(|p| (p.x, p.y)), repaired.last().map(|p| (p.x, p.y)));
+        // 小环被剪掉，主体保留
+        assert!((polygon_area(&repaired) - 3.8).abs() < 1e-12);
+        assert!(StoneProperties::from_outline(&repaired, 0.01, DENSITY_SLATE).is_ok());
+
+        // 8 字形：两半面积相同，保留其中一个三角形
+        let bow_tie = vec![v(0.0, 0.0), v(2.0, 2.0), v(2.0, 0.0), v(0.0, 2.0), v(0.0, 0.0)];
+        let repaired = repair_polygon(&bow_tie);
+        assert!(find_self_intersections(&repaired).is_empty());
+        assert!((polygon_area(&repaired) - 1.0).abs() < 1e-12);
+
+        // 简单多边形原样返回
+        let square = vec![v(0.0, 0.0), v(1.0, 0.0), v(1.0, 1.0), v(0.0, 1.0), v(0.0, 0.0)];
+        assert_eq!(repair_polygon(&square).len(), square.len());
+
+        // 自相交只是警告：不修复也可以构建 (底边被一个向下的凹口穿过两次)
+        let notched = vec![
+            v(0.0, 0.0), v(0.04, 0.0), v(0.04, 0.02), v(0.03, 0.02),
+            v(0.03, -0.01), v(0.01, -0.01), v(0.01, 0.02), v(0.0, 0.02), v(0.0, 0.0),
+        ];
+        assert!(find_self_intersections(&notched).len() >= SELF_INTERSECTION_LIMIT);
+        assert!(StoneProperties::from_outline(&notched, 0.01, DENSITY_SLATE).is_ok());
+    }
+}
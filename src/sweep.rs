@@ -0,0 +1,216 @@
+// 职责：批量参数扫描。固定石片和其余初始条件，让某个发射参数 (攻角、速度或自转)
+// 在区间内按步长变化，每个取值无窗口模拟一次，结果写成 CSV 供分析
+// (例如 "哪个攻角弹跳次数最多")。石片、种子与物理参数取自运行记录，所有运行结果可复现。
+
+use std::fs;
+use std::io::Write;
+
+use crate::basic_structs::Vector2D;
+use crate::physics::parameters::CustomSettings;
+use crate::physics::simulation::{run_headless_with, SimSummary, StoneInfo};
+use crate::rng::Rng;
+use crate::run_record::RunRecord;
+use crate::stone_phy::{StoneError, StoneProperties};
+
+/// 默认输出文件 (相对于工作目录)
+pub const SWEEP_FILE: &str = "sweep.csv";
+
+/// 单次运行的最大步数 (步长按相位选择，与界面相同)
+pub const SWEEP_MAX_STEPS: usize = 20_000;
+
+/// 可扫描的发射参数 (单位与编辑器输入一致)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SweepParam {
+    /// 俯仰角 (deg)
+    Angle,
+    /// 速度大小 (m/s)，方向保持不变
+    Speed,
+    /// 自转角速度 (rad/s)
+    Spin,
+}
+
+impl SweepParam {
+    pub fn name(&self) -> &'static str {
+        match self {
+            SweepParam::Angle => "angle",
+            SweepParam::Speed => "speed",
+            SweepParam::Spin => "spin",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "angle" => Some(SweepParam::Angle),
+            "speed" => Some(SweepParam::Speed),
+            "spin" => Some(SweepParam::Spin),
+            _ => None,
+        }
+    }
+
+    /// 把参数值写入初始状态
+    pub(crate) fn apply(&self, y0: &StoneInfo, value: f64) -> StoneInfo {
+        let mut y = y0.clone();
+        match self {
+            SweepParam::Angle => y.angle.x = value.to_radians(),
+            SweepParam::Speed => {
+                let speed = y.velocity.length();
+                y.velocity = if speed > 1e-12 { y.velocity * (value / speed) } else { Vector2D::new(value, 0.0) };
+            }
+            SweepParam::Spin => y.angle_velocity.y = value,
+        }
+        y
+    }
+}
+
+/// 扫描中的一次运行
+pub struct SweepRow {
+    pub value: f64,
+    pub summary: SimSummary,
+}
+
+/// 区间 [start, end] 内按 step 取值 (含端点)；由下标计算取值，避免步长累加误差
+pub(crate) fn sweep_values(start: f64, end: f64, step: f64) -> Vec<f64> {
+    if step <= 0.0 || !step.is_finite() || end < start {
+        return vec![start];
+    }
+    let count = ((end - start) / step + 1e-9).floor() as usize + 1;
+    (0..count).map(|i| start + step * i as f64).collect()
+}
+
+/// 以运行记录的初始状态为基准，对每个取值无窗口运行一次，返回各次的统计结果
+/// 石片 (种子、网格点数) 与全部物理参数 (水面、弹跳模型、墙壁等) 与记录的那一局相同，只有被扫描的参数不同
+/// `verbose`: 运行中在同一行打印进度 (第几次运行、已积分的步数与石片位置)；为 false 时不输出
+pub fn sweep(record: &RunRecord,
+             param: SweepParam,
+             (start, end, step): (f64, f64, f64),
+             verbose: bool) -> Result<Vec<SweepRow>, StoneError>
+{
+    // 随机数源的用法与模拟时相同：先生成网格，再派生受力模型的随机数源
+    let mut rng = Rng::new(record.seed);
+    let stone = StoneProperties::with_mesh_points(&record.blueprint, record.mesh_points, &mut rng)?;
+    let rng = rng.fork();
+
+    let values = sweep_values(start, end, step);
+    let count = values.len();
+    values.into_iter().enumerate()
+        .map(|(i, value)| {
+            let y0 = param.apply(&record.y0, value);
+            let mut system = CustomSettings::new(record.settings.gravity, stone.clone(), rng.clone());
+            record.settings.apply(&mut system);
+            let mut progress = |steps: usize, state: &StoneInfo| {
+                print!("\r[{}/{}] {} = {}: {} 步, x = {:.3} m   ", i + 1, count, param.name(), value, steps, state.position.x);
+                let _ = std::io::stdout().flush();
+            };
+            let (_, summary) = run_headless_with(&mut system, y0, SWEEP_MAX_STEPS, verbose.then_some(&mut progress as _));
+            if verbose {
+                println!();
+            }
+            Ok(SweepRow { value, summary })
+        })
+        .collect()
+}
+
+/// CSV 文本：param_value, skips, air_time, distance, end
+/// end 为结束原因 (见 SimEndReason::name)；不是 sank / reached_wall / settled 的行结果不完整
+pub fn to_csv(param: SweepParam, rows: &[SweepRow]) -> String {
+    let mut csv = format!("{}, skips, air_time, distance, end\n", param.name());
+    for row in rows {
+        csv += &format!("{}, {}, {}, {}, {}\n", row.value, row.summary.skips, row.summary.air_time, row.summary.distance,
+                        row.summary.end_reason.name());
+    }
+    csv
+}
+
+/// 没有自然结束的运行 (用完步数、跑出范围、停住或发散)
+pub fn unfinished(rows: &[SweepRow]) -> impl Iterator<Item = &SweepRow> {
+    rows.iter().filter(|row| !row.summary.end_reason.is_finished())
+}
+
+/// 写入 CSV 文件
+pub fn write_csv(path: &str, param: SweepParam, rows: &[SweepRow]) -> Result<(), String> {
+    fs::write(path, to_csv(param, rows)).map_err(|e| format!("无法写入 {}: {}", path, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physics::parameters::BounceModel;
+    use crate::physics::simulation::run_headless_seeded;
+    use crate::run_record::SettingsSnapshot;
+    use crate::stone_phy::{slab_blueprint, DEFAULT_MESH_POINTS};
+
+    // 以默认参数 (可由 `adjust` 修改) 记录的一局
+    fn record(adjust: impl FnOnce(&mut CustomSettings)) -> RunRecord {
+        let blueprint = slab_blueprint(0.01, "sweep");
+        let mut rng = Rng::new(7);
+        let stone = StoneProperties::new(&blueprint, &mut rng).unwrap();
+        let mut settings = CustomSettings::new(9.81, stone, rng.fork());
+        adjust(&mut settings);
+        RunRecord {
+            blueprint,
+            y0: StoneInfo {
+                position: Vector2D::new(0.0, 0.05),
+                velocity: Vector2D::new(6.0, -1.0),
+                angle: Vector2D::new(0.0, 0.0),
+                angle_velocity: Vector2D::new(0.0, 15.0),
+            },
+            seed: 7,
+            mesh_points: DEFAULT_MESH_POINTS,
+            settings: SettingsSnapshot::capture(&settings),
+        }
+    }
+
+    #[test]
+    fn angle_sweep_is_deterministic_and_covers_the_range() {
+        let record = record(|_| {});
+        let rows = sweep(&record, SweepParam::Angle, (0.0, 20.0, 10.0), false).unwrap();
+        assert_eq!(rows.iter().map(|r| r.value).collect::<Vec<_>>(), vec![0.0, 10.0, 20.0]);
+
+        let again = sweep(&record, SweepParam::Angle, (0.0, 20.0, 10.0), false).unwrap();
+        assert_eq!(to_csv(SweepParam::Angle, &rows), to_csv(SweepParam::Angle, &again));
+
+        // 默认参数下与普通的无窗口运行相同
+        let y0 = SweepParam::Angle.apply(&record.y0, 10.0);
+        let (_, summary) = run_headless_seeded(&record.blueprint, y0, 9.81, SWEEP_MAX_STEPS, 7, None).unwrap();
+        assert_eq!((rows[1].summary.skips, rows[1].summary.distance), (summary.skips, summary.distance));
+
+        let csv = to_csv(SweepParam::Angle, &rows);
+        assert!(csv.starts_with("angle, skips, air_time, distance, end\n"));
+        for (line, row) in csv.lines().skip(1).zip(&rows) {
+            assert!(line.ends_with(row.summary.end_reason.name()));
+        }
+        assert_eq!(csv.lines().count(), 4);
+    }
+
+    #[test]
+    fn sweep_uses_the_recorded_water_and_bounce_settings() {
+        let range = (10.0, 10.0, 1.0);
+        let plain = sweep(&record(|_| {}), SweepParam::Angle, range, false).unwrap();
+        let adjusted = record(|s| {
+            s.water_level = 0.02;
+            s.bounce_model = BounceModel::Impulse;
+        });
+        let rows = sweep(&adjusted, SweepParam::Angle, range, false).unwrap();
+        assert_ne!(to_csv(SweepParam::Angle, &rows), to_csv(SweepParam::Angle, &plain));
+
+        // 与直接用记录的参数运行相同
+        let mut rng = Rng::new(7);
+        let stone = StoneProperties::new(&adjusted.blueprint, &mut rng).unwrap();
+        let mut system = CustomSettings::new(9.81, stone, rng.fork());
+        adjusted.settings.apply(&mut system);
+        let (_, summary) = run_headless_with(&mut system, SweepParam::Angle.apply(&adjusted.y0, 10.0), SWEEP_MAX_STEPS, None);
+        assert_eq!((rows[0].summary.skips, rows[0].summary.distance), (summary.skips, summary.distance));
+    }
+
+    #[test]
+    fn speed_keeps_direction() {
+        let y0 = StoneInfo {
+            position: Vector2D::new(0.0, 0.0),
+            velocity: Vector2D::new(3.0, -4.0),
+            angle: Vector2D::new(0.0, 0.0),
+            angle_velocity: Vector2D::new(0.0, 0.0),
+        };
+        let y = SweepParam::Speed.apply(&y0, 10.0);
+        assert!(y.velocity.approx_eq(Vector2D::new(6.0, -8.0), 1e-12));
+    }
+}
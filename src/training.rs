@@ -0,0 +1,190 @@
+// 职责：训练模式。为当前石片搜索弹跳次数多的发射参数 (攻角与速度)：
+// 先在网格上粗扫，再从最佳格点出发逐步缩小步长爬山。每次评估都是一次无窗口模拟，
+// 找到的最佳发射及其轨迹以半透明参考线叠加在发射预览和渲染器中，供用户模仿。
+
+use crate::physics::parameters::CustomSettingsBuilder;
+use crate::physics::simulation::{run_headless_with, SimSummary, Stamp, StoneInfo};
+use crate::rng::Rng;
+use crate::stone_editor::StoneBlueprint;
+use crate::stone_phy::{StoneError, StoneProperties};
+use crate::sweep::{sweep_values, SweepParam, SWEEP_MAX_STEPS};
+
+/// 搜索范围与精度
+#[derive(Debug, Clone, PartialEq)]
+pub struct TrainingSearch {
+    pub angles: (f64, f64, f64), // 攻角网格 (起点, 终点, 步长)，单位 deg
+    pub speeds: (f64, f64, f64), // 速度网格 (起点, 终点, 步长)，单位 m/s
+    pub climb_rounds: usize,     // 爬山的轮数 (找不到更好的邻居时步长减半)
+    pub max_steps: usize,        // 每次运行的最大步数
+}
+
+impl Default for TrainingSearch {
+    fn default() -> Self {
+        Self {
+            angles: (0.0, 40.0, 5.0),
+            speeds: (3.0, 21.0, 3.0),
+            climb_rounds: 8,
+            max_steps: SWEEP_MAX_STEPS,
+        }
+    }
+}
+
+impl TrainingSearch {
+    /// 评估次数的上限：网格的格点数加上每轮爬山的四个邻居 (用于显示进度)
+    pub fn max_runs(&self) -> usize {
+        let count = |(start, end, step): (f64, f64, f64)| sweep_values(start, end, step).len();
+        count(self.angles) * count(self.speeds) + 4 * self.climb_rounds
+    }
+}
+
+/// 进度回调：参数为 (已评估的次数, 评估次数上限)
+pub type RunCallback<'a> = &'a mut dyn FnMut(usize, usize);
+
+/// 水面环境 (与预测轨迹相同)：(水面高度 m, 坡度 dy/dx, 水温 °C)
+pub type Water = (f64, f64, f64);
+
+/// 搜索结果：最佳发射参数、对应的初始状态、统计与轨迹
+#[derive(Clone)]
+pub struct TrainingTarget {
+    pub angle_deg: f64,
+    pub speed: f64,
+    pub y0: StoneInfo,
+    pub summary: SimSummary,
+    pub trajectory: Vec<Stamp>,
+    pub runs: usize, // 共评估了多少次发射
+}
+
+/// 先比较弹跳次数，相同时比较水平距离
+fn better(a: &SimSummary, b: &SimSummary) -> bool {
+    (a.skips, a.distance) > (b.skips, b.distance)
+}
+
+/// 为石片搜索最佳发射：base_y0 提供位置、速度方向与自转，攻角和速度大小由搜索决定
+/// 石片只构建一次；每次评估使用相同的随机数源，结果与种子一起可复现
+/// `on_run`: 可选的进度回调，每评估一次发射调用一次
+#[allow(clippy::too_many_arguments)]
+pub fn find_optimal_launch(blueprint: &StoneBlueprint,
+                           mesh_points: usize,
+                           base_y0: &StoneInfo,
+                           gravity: f64,
+                           water: Water,
+                           seed: u64,
+                           search: &TrainingSearch,
+                           mut on_run: Option<RunCallback>) -> Result<TrainingTarget, StoneError>
+{
+    let mut rng = Rng::new(seed);
+    let stone = StoneProperties::with_mesh_points(blueprint, mesh_points, &mut rng)?;
+    let rng = rng.fork();
+
+    let mut runs = 0;
+    let max_runs = search.max_runs();
+    let mut evaluate = |angle: f64, speed: f64| {
+        let y0 = SweepParam::Speed.apply(&SweepParam::Angle.apply(base_y0, angle), speed);
+        let mut system = CustomSettingsBuilder::new()
+            .gravity(gravity)
+            .water_level(water.0)
+            .slope(water.1)
+            .water_temperature(water.2)
+            .rng(rng.clone())
+            .build(stone.clone());
+        let (trajectory, summary) = run_headless_with(&mut system, y0.clone(), search.max_steps, None);
+        runs += 1;
+        if let Some(callback) = on_run.as_mut() {
+            callback(runs, max_runs);
+        }
+        TrainingTarget { angle_deg: angle, speed, y0, summary, trajectory, runs: 0 }
+    };
+
+    // 1. 网格粗扫
+    let mut best: Option<TrainingTarget> = None;
+    for angle in sweep_values(search.angles.0, search.angles.1, search.angles.2) {
+        for speed in sweep_values(search.speeds.0, search.speeds.1, search.speeds.2) {
+            let candidate = evaluate(angle, speed);
+            if best.as_ref().is_none_or(|b| better(&candidate.summary, &b.summary)) {
+                best = Some(candidate);
+            }
+        }
+    }
+    let mut best = best.expect("网格至少有一个取值");
+
+    // 2. 爬山：尝试四个邻居，移动到最好的一个；都不更好时步长减半
+    // (攻角和速度都限制在网格范围内，速度不低于编辑器允许的 0.5 m/s)
+    let (mut angle_step, mut speed_step) = (search.angles.2 / 2.0, search.speeds.2 / 2.0);
+    for _ in 0..search.climb_rounds {
+        let neighbours = [
+            (best.angle_deg + angle_step, best.speed),
+            (best.angle_deg - angle_step, best.speed),
+            (best.angle_deg, best.speed + speed_step),
+            (best.angle_deg, best.speed - speed_step),
+        ];
+        let mut improved = false;
+        for (angle, speed) in neighbours {
+            let angle = angle.clamp(search.angles.0, search.angles.1);
+            let speed = speed.clamp(search.speeds.0, search.speeds.1).max(0.5);
+            if (angle, speed) == (best.angle_deg, best.speed) {
+                continue;
+            }
+            let candidate = evaluate(angle, speed);
+            if better(&candidate.summary, &best.summary) {
+                best = candidate;
+                improved = true;
+            }
+        }
+        if !improved {
+            angle_step /= 2.0;
+            speed_step /= 2.0;
+        }
+    }
+
+    best.runs = runs;
+    Ok(best)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::basic_structs::Vector2D;
+    use crate::physics::parameters::DEFAULT_WATER_TEMP_C;
+    use crate::physics::simulation::run_headless_seeded;
+    use crate::stone_phy::{slab_blueprint, DEFAULT_MESH_POINTS};
+
+    #[test]
+    fn optimal_launch_beats_the_grid_and_replays_exactly() {
+        let blueprint = slab_blueprint(0.01, "training");
+        let base = StoneInfo {
+            position: Vector2D::new(0.0, 0.05),
+            velocity: Vector2D::new(6.0, -1.0),
+            angle: Vector2D::new(0.0, 0.0),
+            angle_velocity: Vector2D::new(0.0, 15.0),
+        };
+        let search = TrainingSearch { angles: (0.0, 20.0, 10.0), speeds: (4.0, 12.0, 4.0), climb_rounds: 2, max_steps: 5_000 };
+
+        let mut reported = Vec::new();
+        let mut on_run = |runs: usize, max_runs: usize| reported.push((runs, max_runs));
+        let target = find_optimal_launch(&blueprint, DEFAULT_MESH_POINTS, &base, 9.81, (0.0, 0.0, DEFAULT_WATER_TEMP_C), 7,
+                                         &search, Some(&mut on_run)).unwrap();
+        assert!(target.runs > 6, "{}", target.runs);
+        assert_eq!(reported.len(), target.runs);
+        assert!(reported.iter().enumerate().all(|(i, &(runs, max_runs))| runs == i + 1 && runs <= max_runs));
+        assert_eq!(search.max_runs(), 3 * 3 + 4 * 2);
+        assert!(target.summary.skips > 0);
+        assert!((target.y0.angle.x - target.angle_deg.to_radians()).abs() < 1e-12);
+        assert!((target.y0.velocity.length() - target.speed).abs() < 1e-9);
+        assert_eq!(target.y0.position, base.position);
+
+        // 不比任何格点差
+        for angle in sweep_values(search.angles.0, search.angles.1, search.angles.2) {
+            for speed in sweep_values(search.speeds.0, search.speeds.1, search.speeds.2) {
+                let y0 = SweepParam::Speed.apply(&SweepParam::Angle.apply(&base, angle), speed);
+                let (_, summary) = run_headless_seeded(&blueprint, y0, 9.81, search.max_steps, 7, None).unwrap();
+                assert!(!better(&summary, &target.summary), "angle {} speed {}", angle, speed);
+            }
+        }
+
+        // 默认水面下与普通的无窗口运行逐位相同，参考轨迹可以原样重放
+        let (trajectory, summary) = run_headless_seeded(&blueprint, target.y0.clone(), 9.81, search.max_steps, 7, None).unwrap();
+        assert_eq!(summary.skips, target.summary.skips);
+        assert_eq!(trajectory.len(), target.trajectory.len());
+        assert_eq!(trajectory.last().map(|s| s.state.position), target.trajectory.last().map(|s| s.state.position));
+    }
+}
@@ -0,0 +1,111 @@
+// 职责：编辑器与渲染器共用的界面小部件。
+// 按钮的绘制与点击检测集中在这里，命中判断 (以及将来与拖动视角的冲突处理) 只需改一处。
+
+use macroquad::prelude::*;
+
+/// 编辑器布局按这个窗口高度 (px) 设计；更矮的窗口按比例缩小
+pub const REFERENCE_HEIGHT: f32 = 1700.0;
+
+/// 当前窗口相对设计高度的缩放 (不放大，最小 0.4)，布局中的像素常量都乘以它
+pub fn ui_scale() -> f32 {
+    (screen_height() / REFERENCE_HEIGHT).clamp(0.4, 1.0)
+}
+
+/// 渲染器与游戏界面 (信息面板、结束画面、暂停菜单) 按这个窗口高度设计；字号比编辑器小，设计高度也低
+pub const GAME_REFERENCE_HEIGHT: f32 = 1000.0;
+
+/// 渲染器与游戏界面的缩放 (不放大，最小 0.6，小字号缩得更小就读不清了)
+pub fn game_ui_scale() -> f32 {
+    (screen_height() / GAME_REFERENCE_HEIGHT).clamp(0.6, 1.0)
+}
+
+/// 本帧是否在 `rect` 内按下了鼠标左键
+pub fn clicked(rect: Rect) -> bool {
+    is_mouse_button_pressed(MouseButton::Left) && rect.contains(mouse_position().into())
+}
+
+/// 灰色背景的按钮，返回本帧是否被点击
+pub fn button(rect: Rect, label: &str, font_size: u16) -> bool {
+    button_colored(rect, label, font_size, DARKGRAY)
+}
+
+/// 指定背景色的按钮 (如确认类按钮用 DARKGREEN)，返回本帧是否被点击
+pub fn button_colored(rect: Rect, label: &str, font_size: u16, color: Color) -> bool {
+    draw_rectangle(rect.x, rect.y, rect.w, rect.h, color);
+    // 文字基线大致垂直居中 (按钮随 ui_scale 缩放时仍然对齐)
+    draw_text_ex(label, rect.x + 20.0, rect.y + (rect.h + font_size as f32 * 0.6) / 2.0,
+                 TextParams { font_size, color: WHITE, ..Default::default() });
+    clicked(rect)
+}
+
+/// 水平滑块：按住鼠标左键拖动设置 0 ~ 1 的值，返回新值
+pub fn slider(rect: Rect, value: f32) -> f32 {
+    draw_rectangle(rect.x, rect.y + rect.h / 2.0 - 3.0, rect.w, 6.0, DARKGRAY);
+    let handle_x = rect.x + rect.w * value.clamp(0.0, 1.0);
+    draw_rectangle(handle_x - 8.0, rect.y, 16.0, rect.h, LIGHTGRAY);
+    if is_mouse_button_down(MouseButton::Left) && rect.contains(mouse_position().into()) {
+        return ((mouse_position().0 - rect.x) / rect.w).clamp(0.0, 1.0);
+    }
+    value
+}
+
+/// 角度与角速度的输入/显示单位 (编辑器与渲染器共用)；物理内部始终使用弧度
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AngleUnit {
+    Degrees,
+    Radians,
+}
+
+impl AngleUnit {
+    pub fn next(self) -> Self {
+        match self {
+            AngleUnit::Degrees => AngleUnit::Radians,
+            AngleUnit::Radians => AngleUnit::Degrees,
+        }
+    }
+
+    /// 单位名称，也用于偏好文件
+    pub fn name(self) -> &'static str {
+        match self {
+            AngleUnit::Degrees => "deg",
+            AngleUnit::Radians => "rad",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        [AngleUnit::Degrees, AngleUnit::Radians].into_iter().find(|u| u.name() == name)
+    }
+
+    /// 以本单位表示的值换算为弧度 (角速度同理：unit/s -> rad/s)
+    pub fn to_radians(self, value: f64) -> f64 {
+        match self {
+            AngleUnit::Degrees => value.to_radians(),
+            AngleUnit::Radians => value,
+        }
+    }
+
+    pub fn in_unit(self, radians: f64) -> f64 {
+        match self {
+            AngleUnit::Degrees => radians.to_degrees(),
+            AngleUnit::Radians => radians,
+        }
+    }
+
+    /// 输入框与信息面板中的显示格式 (弧度的数值小，多保留两位)
+    pub fn format(self, value: f64) -> String {
+        match self {
+            AngleUnit::Degrees => format!("{:.1}", value),
+            AngleUnit::Radians => format!("{:.3}", value),
+        }
+    }
+
+    /// 把输入框文本从 `from` 单位换算到 self；无法解析时原样返回
+    /// 结果保留完整精度，只把换算误差 (如 29.999999999999996) 收回到整齐的小数
+    pub fn convert_text(self, text: &str, from: AngleUnit) -> String {
+        let Ok(value) = text.trim().parse::<f64>() else { return text.to_string(); };
+        let converted = self.in_unit(from.to_radians(value));
+        let rounded = (converted * 1e9).round() / 1e9;
+        let tidy = if (rounded - converted).abs() <= 1e-12 * converted.abs().max(1.0) { rounded } else { converted };
+        format!("{}", tidy)
+    }
+}